@@ -1,4 +1,6 @@
 use core::ffi::CStr;
+#[cfg(feature = "write")]
+use std::vec::Vec;
 
 /// An extension trait for reading data from a slice of bytes.
 ///
@@ -25,15 +27,6 @@ pub trait ByteSliceExt<'a> {
 
     self.read(&mut offset)
   }
-
-  /// Peeks a value of type `R` from the slice at the given offset with the given context.
-  ///
-  /// Returns `None` if the offset is out of bounds or if the value could not be peeked.
-  fn peek_with<R: FromBytes<'a, C>, C>(&self, offset: &usize, ctx: C) -> Option<R> {
-    let mut offset = *offset;
-
-    self.read_with(&mut offset, ctx)
-  }
 }
 
 impl<'a> ByteSliceExt<'a> for &'a [u8] {
@@ -60,7 +53,8 @@ impl<'a> ByteSliceExt<'a> for &'a [u8] {
 ///
 /// The reason behind using `Option` was to try to limit the amount of error types needed and to
 /// generalize a `NotEnoughBytes` error into a `None` return value to allow the consumer to
-/// accurately represent the error.
+/// accurately represent the error.  The heap and table readers do this by converting `None` into a
+/// [ReadError](crate::metadata::errors::ReadError) recording what was being read and where.
 pub trait FromBytes<'a, C = ()>: Sized {
   /// Decodes a value of type `Self` from the given slice of bytes at the given offset with the
   /// given context.
@@ -134,6 +128,20 @@ impl<'a> FromBytes<'a, LengthPrefixed> for &'a CStr {
   }
 }
 
+#[cfg(feature = "write")]
+impl ToBytes<LengthPrefixed> for &CStr {
+  /// Encodes the string and its `nul` terminator padded to a 4-byte boundary, prefixed with the
+  /// padded length.
+  #[inline]
+  fn to_bytes(&self, buf: &mut Vec<u8>, _: LengthPrefixed) {
+    let len = (self.to_bytes_with_nul().len() + 3) & !3;
+
+    buf.write(len as u32);
+    buf.extend_from_slice(self.to_bytes_with_nul());
+    buf.resize(buf.len() + len - self.to_bytes_with_nul().len(), 0);
+  }
+}
+
 /// A context for reading a `&CStr` that is padded to a 4-byte boundary.
 #[derive(Clone, Copy)]
 pub struct FourByteBoundaryPadded;
@@ -153,6 +161,17 @@ impl<'a> FromBytes<'a, FourByteBoundaryPadded> for &'a CStr {
   }
 }
 
+#[cfg(feature = "write")]
+impl ToBytes<FourByteBoundaryPadded> for &CStr {
+  #[inline]
+  fn to_bytes(&self, buf: &mut Vec<u8>, _: FourByteBoundaryPadded) {
+    let len = self.to_bytes_with_nul().len();
+
+    buf.extend_from_slice(self.to_bytes_with_nul());
+    buf.resize(buf.len() + ((len + 3) & !3) - len, 0);
+  }
+}
+
 /// A context for reading compressed length values.
 pub struct CompressedLength;
 
@@ -169,7 +188,7 @@ impl<'a> FromBytes<'a, CompressedLength> for usize {
     }
 
     if val & 0x40 == 0 {
-      let val = val & 0x3f << 8 | *rem.get(1)? as usize;
+      let val = (val & 0x3f) << 8 | *rem.get(1)? as usize;
 
       *offset += 2;
 
@@ -177,7 +196,7 @@ impl<'a> FromBytes<'a, CompressedLength> for usize {
     }
 
     if val & 0x20 == 0 {
-      let val = val & 0x1f << 24;
+      let val = (val & 0x1f) << 24;
       let val = val | (*rem.get(1)? as usize) << 16;
       let val = val | (*rem.get(2)? as usize) << 8;
       let val = val | *rem.get(3)? as usize;
@@ -191,6 +210,59 @@ impl<'a> FromBytes<'a, CompressedLength> for usize {
   }
 }
 
+#[cfg(feature = "write")]
+impl ToBytes<CompressedLength> for usize {
+  /// Encodes a compressed length of up to `0x1FFFFFFF`.
+  ///
+  /// # Panics
+  ///
+  /// Panics if the length is too large to be compressed.
+  #[inline]
+  fn to_bytes(&self, buf: &mut Vec<u8>, _: CompressedLength) {
+    match *self {
+      val @ 0..=0x7f => buf.push(val as u8),
+      val @ 0x80..=0x3fff => buf.extend_from_slice(&(val as u16 | 0x8000).to_be_bytes()),
+      val @ 0x4000..=0x1fff_ffff => {
+        buf.extend_from_slice(&(val as u32 | 0xc000_0000).to_be_bytes())
+      }
+      val => panic!("`{val}` is too large to be a compressed length"),
+    }
+  }
+}
+
+/// An extension trait for writing data to a buffer of bytes.
+///
+/// Primarily uses the [ToBytes] trait to write data to the buffer.
+#[cfg(feature = "write")]
+pub trait ByteVecExt {
+  /// Writes the given value to the end of the buffer.
+  fn write<T: ToBytes>(&mut self, val: T);
+
+  /// Writes the given value to the end of the buffer with the given context.
+  fn write_with<T: ToBytes<C>, C>(&mut self, val: T, ctx: C);
+}
+
+#[cfg(feature = "write")]
+impl ByteVecExt for Vec<u8> {
+  fn write<T: ToBytes>(&mut self, val: T) {
+    val.to_bytes(self, ())
+  }
+
+  fn write_with<T: ToBytes<C>, C>(&mut self, val: T, ctx: C) {
+    val.to_bytes(self, ctx)
+  }
+}
+
+/// A trait that can be used to encode a value into a buffer of bytes.
+///
+/// The inverse of [FromBytes], the `C` type parameter is a context that can be used to provide
+/// additional information to the encoding process.
+#[cfg(feature = "write")]
+pub trait ToBytes<C = ()> {
+  /// Encodes `self` with the given context and appends it to the given buffer.
+  fn to_bytes(&self, buf: &mut Vec<u8>, ctx: C);
+}
+
 /// A trait that can be used to determine the number of bytes necessary to decode a value.
 ///
 /// The `C` type parameter is a context that can be used to provide additional information to the
@@ -215,6 +287,14 @@ macro_rules! int {
         core::mem::size_of::<$int>()
       }
     }
+
+    #[cfg(feature = "write")]
+    impl ToBytes<()> for $int {
+      #[inline]
+      fn to_bytes(&self, buf: &mut Vec<u8>, _: ()) {
+        buf.extend_from_slice(&self.to_le_bytes());
+      }
+    }
   };
 }
 
@@ -259,6 +339,14 @@ macro_rules! bitflags {
       }
     }
 
+    #[cfg(feature = "write")]
+    impl $crate::bytes::ToBytes<()> for $BitFlags {
+      #[inline]
+      fn to_bytes(&self, buf: &mut std::vec::Vec<u8>, _: ()) {
+        self.bits().to_bytes(buf, ())
+      }
+    }
+
     #[cfg(feature = "read")]
     impl $crate::bytes::ByteSize<()> for $BitFlags {
       #[inline]
@@ -274,7 +362,7 @@ pub(crate) use bitflags;
 #[cfg(test)]
 mod tests {
   use super::ByteSliceExt;
-  use crate::bytes::NulTerminated;
+  use crate::bytes::{CompressedLength, NulTerminated};
   use core::ffi::CStr;
 
   #[test]
@@ -298,4 +386,55 @@ mod tests {
     assert_eq!(expected, actual.to_bytes_with_nul());
     assert_eq!(expected.len(), *offset);
   }
+
+  #[test]
+  #[cfg(feature = "write")]
+  fn test_write_compressed_length() {
+    use super::ByteVecExt;
+
+    for len in [0, 0x03, 0x7f, 0x80, 0x2e57, 0x3fff, 0x4000, 0x1fff_ffff] {
+      let mut buf = Vec::new();
+
+      buf.write_with(len, CompressedLength);
+
+      assert_eq!(Some(len), (&buf[..]).read_with(&mut 0, CompressedLength));
+    }
+  }
+
+  #[test]
+  fn test_read_compressed_length() {
+    let cases: [(&[u8], usize); 4] = [
+      (&[0x03], 0x03),
+      (&[0x80, 0x80], 0x80),
+      (&[0xbf, 0xff], 0x3fff),
+      (&[0xc0, 0x00, 0x40, 0x00], 0x4000),
+    ];
+
+    for (bytes, expected) in cases {
+      let offset = &mut 0;
+      let actual = bytes
+        .read_with::<usize, _>(offset, CompressedLength)
+        .unwrap();
+
+      assert_eq!(expected, actual);
+      assert_eq!(bytes.len(), *offset);
+    }
+  }
+
+  #[test]
+  fn test_read_compressed_length_keeps_leading_bits() {
+    // The payload bits of the leading byte must be masked before being shifted into place.
+    let cases: [(&[u8], usize); 4] = [
+      (&[0xbf, 0x00], 0x3f00),
+      (&[0x81, 0x02], 0x0102),
+      (&[0xdf, 0xff, 0xff, 0xff], 0x1fffffff),
+      (&[0xc1, 0x02, 0x03, 0x04], 0x01020304),
+    ];
+
+    for (bytes, expected) in cases {
+      let actual = bytes.read_with::<usize, _>(&mut 0, CompressedLength);
+
+      assert_eq!(Some(expected), actual);
+    }
+  }
 }
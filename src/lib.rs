@@ -5,3 +5,5 @@
 mod bytes;
 pub mod metadata;
 pub mod pe;
+#[cfg(all(feature = "read", feature = "std", feature = "object"))]
+pub mod resolve;
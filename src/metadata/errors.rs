@@ -2,60 +2,308 @@
 #[doc(inline)]
 pub use read::*;
 
+#[cfg(feature = "write")]
+#[doc(inline)]
+pub use write::*;
+
 #[cfg(feature = "read")]
 #[doc(hidden)]
 mod read {
   use crate::metadata::headers::MetadataStreamHeader;
   use core::fmt;
+  use core::str::Utf8Error;
 
   /// An error that occurred while reading the root metadata.
   #[derive(Debug)]
+  #[non_exhaustive]
   pub enum MetadataReadError {
-    /// The bytes supplied do not contain enough data to read the metadata header.
-    NotEnough,
+    /// The metadata header could not be read.
+    Read(ReadError),
     /// The metadata signature was not `0x424A5342`.
     BadSignature(u32),
+    /// The metadata data directory of a PE file points to data outside the file.
+    MissingData {
+      /// The relative virtual address of the metadata.
+      virtual_address: u32,
+      /// The size of the metadata in bytes.
+      size: u32,
+    },
   }
 
   impl fmt::Display for MetadataReadError {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
       match self {
-        Self::NotEnough => write!(f, "Not enough bytes remaining"),
+        Self::Read(err) => err.fmt(f),
         Self::BadSignature(signature) => {
           write!(f, "Expected signature `0x424A5342`, got `{signature}`")
         }
+        Self::MissingData {
+          virtual_address,
+          size,
+        } => write!(
+          f,
+          "The metadata at `{virtual_address:#x}` of size `{size:#x}` is outside the file"
+        ),
       }
     }
   }
 
+  impl From<ReadError> for MetadataReadError {
+    fn from(err: ReadError) -> Self {
+      Self::Read(err)
+    }
+  }
+
   #[cfg(any(feature = "std", test))]
-  impl std::error::Error for MetadataReadError {}
+  impl std::error::Error for MetadataReadError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+      match self {
+        Self::Read(err) => Some(err),
+        Self::BadSignature(_) | Self::MissingData { .. } => None,
+      }
+    }
+  }
 
   /// An error that occurred while reading a metadata stream.
   #[derive(Debug)]
   pub enum MetadataStreamReadError<'a> {
-    /// Not enough bytes remaining to read the stream.
-    NotEnough,
+    /// The stream, or the header describing it, could not be read.
+    Read(ReadError),
     /// The metadata stream header points to data outside the metadata.
     MissingData {
       /// The metadata stream header.
       header: MetadataStreamHeader<'a>,
     },
+    /// A stream that is required is not present in the metadata.
+    MissingStream {
+      /// The name of the stream, such as `#~`.
+      name: &'static str,
+    },
   }
 
   impl<'a> fmt::Display for MetadataStreamReadError<'a> {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
       match self {
-        Self::NotEnough => write!(f, "Not enough bytes remaining"),
+        Self::Read(err) => err.fmt(f),
         Self::MissingData { header } => write!(
           f,
           "The metadata stream header points to data outside the metadata: {:?}",
           header
         ),
+        Self::MissingStream { name } => write!(f, "The metadata has no `{name}` stream"),
       }
     }
   }
 
+  impl<'a> From<ReadError> for MetadataStreamReadError<'a> {
+    fn from(err: ReadError) -> Self {
+      Self::Read(err)
+    }
+  }
+
   #[cfg(any(feature = "std", test))]
-  impl<'a> std::error::Error for MetadataStreamReadError<'a> {}
+  impl<'a> std::error::Error for MetadataStreamReadError<'a> {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+      match self {
+        Self::Read(err) => Some(err),
+        Self::MissingData { .. } | Self::MissingStream { .. } => None,
+      }
+    }
+  }
+
+  /// An error that occurred while reading a value from a heap or table.
+  ///
+  /// Records what was being read, the offset it was read at and why it failed.
+  #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+  pub struct ReadError {
+    location: ReadLocation,
+    offset: usize,
+    kind: ReadErrorKind,
+  }
+
+  impl ReadError {
+    /// Creates a new [ReadError].
+    pub fn new(location: ReadLocation, offset: usize, kind: ReadErrorKind) -> Self {
+      Self {
+        location,
+        offset,
+        kind,
+      }
+    }
+
+    /// Returns what was being read.
+    pub fn location(&self) -> ReadLocation {
+      self.location
+    }
+
+    /// Returns the byte offset the value was read at.
+    ///
+    /// Offsets into heaps are relative to the start of the heap, offsets into tables are relative
+    /// to the start of the `#~` stream and offsets into the metadata root are relative to the start
+    /// of the metadata.
+    pub fn offset(&self) -> usize {
+      self.offset
+    }
+
+    /// Returns why the read failed.
+    pub fn kind(&self) -> ReadErrorKind {
+      self.kind
+    }
+
+    /// Returns this error with the offset moved forward by the given number of bytes.
+    pub(crate) fn offset_by(self, by: usize) -> Self {
+      Self {
+        offset: self.offset.saturating_add(by),
+        ..self
+      }
+    }
+  }
+
+  impl fmt::Display for ReadError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+      write!(
+        f,
+        "Failed to read {} at offset `{:#x}`: {}",
+        self.location, self.offset, self.kind
+      )
+    }
+  }
+
+  #[cfg(any(feature = "std", test))]
+  impl std::error::Error for ReadError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+      match &self.kind {
+        ReadErrorKind::BadUtf8(err) => Some(err),
+        _ => None,
+      }
+    }
+  }
+
+  /// What was being read when a [ReadError] occurred.
+  #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+  #[non_exhaustive]
+  pub enum ReadLocation {
+    /// The metadata root and its stream headers.
+    Root,
+    /// A metadata stream or heap, such as `#Strings`.
+    Stream(&'static str),
+    /// A table of the `#~` stream.
+    Table {
+      /// The name of the table row type, such as `TypeDefRow`.
+      table: &'static str,
+    },
+    /// A row of a table in the `#~` stream.
+    Row {
+      /// The name of the table row type, such as `TypeDefRow`.
+      table: &'static str,
+      /// The 1-based index of the row.
+      row: usize,
+    },
+    /// A column of a row of a table in the `#~` stream.
+    Column {
+      /// The name of the table row type, such as `TypeDefRow`.
+      table: &'static str,
+      /// The 1-based index of the row.
+      row: usize,
+      /// The name of the column, such as `name`.
+      column: &'static str,
+    },
+  }
+
+  impl fmt::Display for ReadLocation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+      match self {
+        Self::Root => write!(f, "the metadata root"),
+        Self::Stream(name) => write!(f, "the `{name}` stream"),
+        Self::Table { table } => write!(f, "the `{table}` table"),
+        Self::Row { table, row } => write!(f, "`{table}` row `{row}`"),
+        Self::Column { table, row, column } => {
+          write!(f, "column `{column}` of `{table}` row `{row}`")
+        }
+      }
+    }
+  }
+
+  /// Why a [ReadError] occurred.
+  #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+  #[non_exhaustive]
+  pub enum ReadErrorKind {
+    /// Not enough bytes remaining to read the value.
+    Truncated,
+    /// A string was not valid UTF-8.
+    BadUtf8(Utf8Error),
+    /// An index or offset was outside the heap or table it refers to.
+    OutOfRange {
+      /// The index or offset.
+      index: usize,
+      /// The number of rows or bytes in the heap or table.
+      len: usize,
+    },
+    /// The bytes did not encode a valid value, such as a coded index with an unknown tag.
+    Invalid,
+  }
+
+  impl fmt::Display for ReadErrorKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+      match self {
+        Self::Truncated => write!(f, "Not enough bytes remaining"),
+        Self::BadUtf8(err) => write!(f, "Invalid UTF-8: {err}"),
+        Self::OutOfRange { index, len } => {
+          write!(f, "Index `{index}` is out of range for length `{len}`")
+        }
+        Self::Invalid => write!(f, "Invalid value"),
+      }
+    }
+  }
+}
+
+#[cfg(feature = "write")]
+#[doc(hidden)]
+mod write {
+  use super::{ReadError, ReadLocation};
+  use core::fmt;
+
+  /// An error that occurred while overwriting a row of a metadata table in place.
+  #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+  #[non_exhaustive]
+  pub enum PatchError {
+    /// The row being overwritten could not be read.
+    Read(ReadError),
+    /// A value does not fit in the current width of its column.
+    ///
+    /// The row is left unchanged.
+    TooWide {
+      /// The column the value was written to.
+      location: ReadLocation,
+      /// The offset of the row, relative to the start of the `#~` stream.
+      offset: usize,
+    },
+  }
+
+  impl fmt::Display for PatchError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+      match self {
+        Self::Read(err) => err.fmt(f),
+        Self::TooWide { location, offset } => write!(
+          f,
+          "The value written to {location} at offset `{offset:#x}` does not fit in the column"
+        ),
+      }
+    }
+  }
+
+  impl From<ReadError> for PatchError {
+    fn from(err: ReadError) -> Self {
+      Self::Read(err)
+    }
+  }
+
+  impl std::error::Error for PatchError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+      match self {
+        Self::Read(err) => Some(err),
+        Self::TooWide { .. } => None,
+      }
+    }
+  }
 }
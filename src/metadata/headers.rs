@@ -3,9 +3,6 @@
 #[cfg(feature = "read")]
 #[doc(inline)]
 pub use read::*;
-#[cfg(feature = "write")]
-#[doc(inline)]
-pub use write::*;
 
 /// The magic signature for the CLI metadata header.
 pub const METADATA_MAGIC: u32 = 0x424A5342;
@@ -30,11 +27,13 @@ pub struct MetadataTablesHeader {
   /// The array containing the values representing the number of rows in a table, indexed by the id
   /// of the table.
   pub rows: [u32; 64],
+  /// The 4 bytes following the row counts if [HeapSizes::EXTRA_DATA] is set, otherwise 0.
+  pub extra_data: u32,
 }
 
 bitflags::bitflags! {
   /// The bit flags indicating which heaps should have 4 bit wide indexes or 2 bit wide indexes.
-  #[derive(Default, Copy, Clone, Debug)]
+  #[derive(Default, Copy, Clone, Debug, PartialEq, Eq)]
   pub struct HeapSizes : u8 {
     /// If set indicates the `#Strings` heap index should be `4` bytes wide, otherwise `2`.
     const WIDE_STRING_HEAP = 0x01;
@@ -42,6 +41,8 @@ bitflags::bitflags! {
     const WIDE_GUID_HEAP = 0x02;
     /// If set indicates the `#Blob` heap index should be `4` bytes wide, otherwise `2`.
     const WIDE_BLOB_HEAP = 0x04;
+    /// If set indicates that 4 bytes of extra data follow the row counts of the tables.
+    const EXTRA_DATA = 0x40;
   }
 }
 
@@ -142,7 +143,7 @@ mod read {
       let _reserved_0 = buf.read(offset)?;
       let major_version = buf.read(offset)?;
       let minor_version = buf.read(offset)?;
-      let heap_sizes = buf.read(offset)?;
+      let heap_sizes: HeapSizes = buf.read(offset)?;
       let _reserved_1 = buf.read(offset)?;
       let valid = buf.read(offset)?;
       let sorted = buf.read(offset)?;
@@ -154,6 +155,11 @@ mod read {
         }
       }
 
+      let extra_data = match heap_sizes.contains(HeapSizes::EXTRA_DATA) {
+        true => buf.read(offset)?,
+        false => 0,
+      };
+
       Some(Self {
         _reserved_0,
         major_version,
@@ -163,17 +169,65 @@ mod read {
         valid,
         sorted,
         rows,
+        extra_data,
       })
     }
   }
 
   impl FromBytes<'_> for HeapSizes {
     fn from_bytes(buf: &[u8], offset: &mut usize, _: ()) -> Option<Self> {
-      Some(Self::from_bits_truncate(buf.read::<u8>(offset)?))
+      // Unknown bits are kept so that the header is written back unchanged.
+      Some(Self::from_bits_retain(buf.read::<u8>(offset)?))
     }
   }
 }
 
 #[cfg(feature = "write")]
 #[doc(hidden)]
-mod write {}
+mod write {
+  use super::{HeapSizes, MetadataHeader, MetadataStreamHeader, MetadataTablesHeader};
+  use crate::bytes::{ByteVecExt, FourByteBoundaryPadded, LengthPrefixed, ToBytes};
+  use std::vec::Vec;
+
+  impl ToBytes for MetadataHeader<'_> {
+    fn to_bytes(&self, buf: &mut Vec<u8>, _: ()) {
+      buf.write(self.signature);
+      buf.write(self.major_version);
+      buf.write(self.minor_version);
+      buf.write(self.reserved);
+      buf.write_with(self.version, LengthPrefixed);
+      buf.write(self.flags);
+      buf.write(self.streams);
+    }
+  }
+
+  impl ToBytes for MetadataStreamHeader<'_> {
+    fn to_bytes(&self, buf: &mut Vec<u8>, _: ()) {
+      buf.write(self.offset);
+      buf.write(self.size);
+      buf.write_with(self.name, FourByteBoundaryPadded);
+    }
+  }
+
+  impl ToBytes for MetadataTablesHeader {
+    fn to_bytes(&self, buf: &mut Vec<u8>, _: ()) {
+      self._reserved_0.to_bytes(buf, ());
+      self.major_version.to_bytes(buf, ());
+      self.minor_version.to_bytes(buf, ());
+      self.heap_sizes.bits().to_bytes(buf, ());
+      self._reserved_1.to_bytes(buf, ());
+      self.valid.to_bytes(buf, ());
+      self.sorted.to_bytes(buf, ());
+
+      for (i, rows) in self.rows.iter().enumerate() {
+        if self.valid & (1 << i) != 0 {
+          rows.to_bytes(buf, ());
+        }
+      }
+
+      if self.heap_sizes.contains(HeapSizes::EXTRA_DATA) {
+        self.extra_data.to_bytes(buf, ());
+      }
+    }
+  }
+}
@@ -0,0 +1,525 @@
+//! Assembly identities and their display names.
+//!
+//! An [AssemblyIdentity] is the name, version, culture and publisher of an assembly, as recorded by
+//! the `Assembly` table of the assembly itself and by the `AssemblyRef` tables of the assemblies
+//! referring to it.  Identities are usually written as display names such as
+//! `System.Runtime, Version=8.0.0.0, Culture=neutral, PublicKeyToken=b03f5f7f11d50a3a`.
+
+use crate::metadata::errors::{ReadError, ReadErrorKind, ReadLocation};
+use crate::metadata::streams::blobs::BlobsHeap;
+use crate::metadata::streams::strings::StringsHeap;
+use crate::metadata::streams::tables::flags::AssemblyFlags;
+use crate::metadata::streams::tables::rows::{AssemblyRefRow, AssemblyRow};
+use crate::pe::strong_name::public_key_token;
+use core::fmt;
+use core::str::FromStr;
+
+/// The four-part version of an assembly.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
+pub struct AssemblyVersion {
+  pub major: u16,
+  pub minor: u16,
+  pub build: u16,
+  pub revision: u16,
+}
+
+impl AssemblyVersion {
+  /// Creates a new [AssemblyVersion].
+  pub const fn new(major: u16, minor: u16, build: u16, revision: u16) -> Self {
+    Self {
+      major,
+      minor,
+      build,
+      revision,
+    }
+  }
+}
+
+impl fmt::Display for AssemblyVersion {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    write!(
+      f,
+      "{}.{}.{}.{}",
+      self.major, self.minor, self.build, self.revision
+    )
+  }
+}
+
+impl FromStr for AssemblyVersion {
+  type Err = ParseAssemblyIdentityError;
+
+  /// Parses 2 to 4 dot-separated parts, missing parts are 0.
+  fn from_str(s: &str) -> Result<Self, Self::Err> {
+    let mut parts = [0; 4];
+    let mut len = 0;
+
+    for part in s.split('.') {
+      *parts
+        .get_mut(len)
+        .ok_or(ParseAssemblyIdentityError::InvalidVersion)? = part
+        .trim()
+        .parse()
+        .map_err(|_| ParseAssemblyIdentityError::InvalidVersion)?;
+      len += 1;
+    }
+
+    if len < 2 {
+      return Err(ParseAssemblyIdentityError::InvalidVersion);
+    }
+
+    let [major, minor, build, revision] = parts;
+
+    Ok(Self::new(major, minor, build, revision))
+  }
+}
+
+/// The publisher of an assembly, either its full public key or the token derived from it.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum PublicKeyOrToken {
+  /// The public key blob.
+  PublicKey(Vec<u8>),
+  /// The public key token, see [public_key_token].
+  Token([u8; 8]),
+}
+
+impl PublicKeyOrToken {
+  /// Returns the public key token, deriving it from the public key if needed.
+  pub fn token(&self) -> [u8; 8] {
+    match self {
+      Self::PublicKey(key) => public_key_token(key),
+      Self::Token(token) => *token,
+    }
+  }
+}
+
+/// The kind of types an assembly contains.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum AssemblyContentType {
+  /// CLI types.
+  #[default]
+  Default,
+  /// Windows Runtime types.
+  WindowsRuntime,
+}
+
+/// How versions are compared when matching an assembly reference to an assembly.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum VersionPolicy {
+  /// The versions must be equal, as for strong named assemblies on .NET Framework.
+  Exact,
+  /// The version of the assembly must be at least the referenced version, as on .NET Core.
+  Minimum,
+  /// Any version matches, as for framework assemblies unified to the version of the runtime.
+  Any,
+}
+
+/// The identity of an assembly.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct AssemblyIdentity {
+  /// The simple name of the assembly, without a file extension.
+  pub name: String,
+  /// The version of the assembly.
+  pub version: AssemblyVersion,
+  /// The culture of the assembly, or [None] for culture neutral assemblies.
+  pub culture: Option<String>,
+  /// The publisher of the assembly, or [None] if the assembly is not strong named.
+  pub public_key: Option<PublicKeyOrToken>,
+  /// The assembly may be retargeted to an assembly with a different publisher at runtime.
+  pub retargetable: bool,
+  /// The kind of types the assembly contains.
+  pub content_type: AssemblyContentType,
+}
+
+impl AssemblyIdentity {
+  /// Creates an [AssemblyIdentity] with the given name, version `0.0.0.0`, neutral culture and no
+  /// publisher.
+  pub fn new(name: impl Into<String>) -> Self {
+    Self {
+      name: name.into(),
+      version: AssemblyVersion::default(),
+      culture: None,
+      public_key: None,
+      retargetable: false,
+      content_type: AssemblyContentType::Default,
+    }
+  }
+
+  /// Creates the [AssemblyIdentity] of the assembly described by the given [AssemblyRow].
+  pub fn from_assembly(
+    row: &AssemblyRow,
+    strings: &StringsHeap<'_>,
+    blobs: &BlobsHeap<'_>,
+  ) -> Result<Self, ReadError> {
+    let public_key = blobs.get(row.public_key())?;
+
+    Ok(Self {
+      name: strings.get_str(row.name())?.into(),
+      version: AssemblyVersion::new(
+        row.major_version(),
+        row.minor_version(),
+        row.build_number(),
+        row.revision_number(),
+      ),
+      culture: culture(strings.get_str(row.culture())?),
+      public_key: (!public_key.is_empty()).then(|| PublicKeyOrToken::PublicKey(public_key.into())),
+      retargetable: row.flags().contains(AssemblyFlags::RETARGETABLE),
+      content_type: content_type(row.flags()),
+    })
+  }
+
+  /// Creates the [AssemblyIdentity] of the assembly referred to by the given [AssemblyRefRow].
+  ///
+  /// Returns [ReadErrorKind::Invalid] if the public key token is not 8 bytes long.
+  pub fn from_assembly_ref(
+    row: &AssemblyRefRow,
+    strings: &StringsHeap<'_>,
+    blobs: &BlobsHeap<'_>,
+  ) -> Result<Self, ReadError> {
+    let id = row.public_key_or_token();
+    let blob = blobs.get(id)?;
+    let public_key = if blob.is_empty() {
+      None
+    } else if row.flags().contains(AssemblyFlags::PUBLIC_KEY) {
+      Some(PublicKeyOrToken::PublicKey(blob.into()))
+    } else {
+      let token = blob
+        .try_into()
+        .map_err(|_| ReadError::new(ReadLocation::Stream("#Blob"), id.0, ReadErrorKind::Invalid))?;
+
+      Some(PublicKeyOrToken::Token(token))
+    };
+
+    Ok(Self {
+      name: strings.get_str(row.name())?.into(),
+      version: AssemblyVersion::new(
+        row.major_version(),
+        row.minor_version(),
+        row.build_number(),
+        row.revision_number(),
+      ),
+      culture: culture(strings.get_str(row.culture())?),
+      public_key,
+      retargetable: row.flags().contains(AssemblyFlags::RETARGETABLE),
+      content_type: content_type(row.flags()),
+    })
+  }
+
+  /// Returns the public key token, or [None] if the assembly is not strong named.
+  pub fn public_key_token(&self) -> Option<[u8; 8]> {
+    self.public_key.as_ref().map(PublicKeyOrToken::token)
+  }
+
+  /// Returns `true` if the given assembly satisfies this identity when used as a reference.
+  ///
+  /// Names and cultures are compared ignoring ASCII case and publishers by their public key token.
+  /// The publisher of a retargetable reference is not compared, and versions are compared using the
+  /// given [VersionPolicy].
+  pub fn matches(&self, assembly: &AssemblyIdentity, policy: VersionPolicy) -> bool {
+    let culture = match (&self.culture, &assembly.culture) {
+      (Some(a), Some(b)) => a.eq_ignore_ascii_case(b),
+      (a, b) => a.is_none() && b.is_none(),
+    };
+    let version = match policy {
+      VersionPolicy::Exact => assembly.version == self.version,
+      VersionPolicy::Minimum => assembly.version >= self.version,
+      VersionPolicy::Any => true,
+    };
+
+    self.name.eq_ignore_ascii_case(&assembly.name)
+      && culture
+      && version
+      && self.content_type == assembly.content_type
+      && (self.retargetable || self.public_key_token() == assembly.public_key_token())
+  }
+}
+
+impl fmt::Display for AssemblyIdentity {
+  /// Formats the display name of the assembly, such as
+  /// `mscorlib, Version=4.0.0.0, Culture=neutral, PublicKeyToken=b77a5c561934e089`.
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    escape(f, &self.name)?;
+    write!(f, ", Version={}, Culture=", self.version)?;
+
+    match &self.culture {
+      Some(culture) => escape(f, culture)?,
+      None => write!(f, "neutral")?,
+    }
+
+    write!(f, ", PublicKeyToken=")?;
+
+    match self.public_key_token() {
+      Some(token) => token.iter().try_for_each(|b| write!(f, "{b:02x}"))?,
+      None => write!(f, "null")?,
+    }
+
+    if self.retargetable {
+      write!(f, ", Retargetable=Yes")?;
+    }
+
+    if self.content_type == AssemblyContentType::WindowsRuntime {
+      write!(f, ", ContentType=WindowsRuntime")?;
+    }
+
+    Ok(())
+  }
+}
+
+impl FromStr for AssemblyIdentity {
+  type Err = ParseAssemblyIdentityError;
+
+  /// Parses a display name.
+  ///
+  /// Only the name is required, missing components take the values of [AssemblyIdentity::new].
+  /// Keys are case insensitive and unknown keys, such as `ProcessorArchitecture`, are ignored.
+  fn from_str(s: &str) -> Result<Self, Self::Err> {
+    let mut parts = split(s)?.into_iter();
+    let (_, name) = parts.next().unwrap_or_default();
+
+    if name.is_empty() {
+      return Err(ParseAssemblyIdentityError::MissingName);
+    }
+
+    let mut identity = Self::new(name);
+
+    for (key, value) in parts {
+      let key = key.ok_or(ParseAssemblyIdentityError::Malformed)?;
+      let null = value.eq_ignore_ascii_case("null");
+
+      match key.to_ascii_lowercase().as_str() {
+        "version" => identity.version = value.parse()?,
+        "culture" if value.eq_ignore_ascii_case("neutral") => identity.culture = None,
+        "culture" => identity.culture = Some(value),
+        "publickeytoken" | "publickey" if null => identity.public_key = None,
+        "publickeytoken" => {
+          let token = hex(&value)
+            .and_then(|token| token.try_into().ok())
+            .ok_or(ParseAssemblyIdentityError::InvalidPublicKey)?;
+
+          identity.public_key = Some(PublicKeyOrToken::Token(token));
+        }
+        "publickey" => {
+          let key = hex(&value).ok_or(ParseAssemblyIdentityError::InvalidPublicKey)?;
+
+          identity.public_key = Some(PublicKeyOrToken::PublicKey(key));
+        }
+        "retargetable" if value.eq_ignore_ascii_case("yes") => identity.retargetable = true,
+        "retargetable" if value.eq_ignore_ascii_case("no") => identity.retargetable = false,
+        "contenttype" if value.eq_ignore_ascii_case("windowsruntime") => {
+          identity.content_type = AssemblyContentType::WindowsRuntime
+        }
+        "contenttype" if value.eq_ignore_ascii_case("default") => {
+          identity.content_type = AssemblyContentType::Default
+        }
+        "retargetable" | "contenttype" => return Err(ParseAssemblyIdentityError::InvalidValue),
+        _ => {}
+      }
+    }
+
+    Ok(identity)
+  }
+}
+
+/// An error that occurred while parsing an assembly display name.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum ParseAssemblyIdentityError {
+  /// The display name does not start with an assembly name.
+  MissingName,
+  /// A component is not a `Key=Value` pair, or a quote or escape sequence is not closed.
+  Malformed,
+  /// The version is not 2 to 4 dot-separated 16-bit numbers.
+  InvalidVersion,
+  /// The public key is not hexadecimal, or the public key token is not 8 hexadecimal bytes.
+  InvalidPublicKey,
+  /// The value of `Retargetable` or `ContentType` is not one of the values it allows.
+  InvalidValue,
+}
+
+impl fmt::Display for ParseAssemblyIdentityError {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match self {
+      Self::MissingName => write!(f, "The display name has no assembly name"),
+      Self::Malformed => write!(f, "The display name is malformed"),
+      Self::InvalidVersion => write!(f, "Invalid assembly version"),
+      Self::InvalidPublicKey => write!(f, "Invalid public key or public key token"),
+      Self::InvalidValue => write!(f, "Invalid `Retargetable` or `ContentType` value"),
+    }
+  }
+}
+
+impl std::error::Error for ParseAssemblyIdentityError {}
+
+fn culture(culture: &str) -> Option<String> {
+  (!culture.is_empty()).then(|| culture.into())
+}
+
+fn content_type(flags: AssemblyFlags) -> AssemblyContentType {
+  if flags.contains(AssemblyFlags::WINDOWS_RUNTIME) {
+    AssemblyContentType::WindowsRuntime
+  } else {
+    AssemblyContentType::Default
+  }
+}
+
+/// Writes the given value escaping the characters that delimit display name components.
+fn escape(f: &mut fmt::Formatter<'_>, value: &str) -> fmt::Result {
+  for c in value.chars() {
+    if matches!(c, '\\' | ',' | '=' | '"' | '\'') {
+      write!(f, "\\")?;
+    }
+
+    write!(f, "{c}")?;
+  }
+
+  Ok(())
+}
+
+/// Splits a display name into its trimmed components and their keys, unescaping and unquoting them.
+fn split(s: &str) -> Result<Vec<(Option<String>, String)>, ParseAssemblyIdentityError> {
+  let mut parts = Vec::new();
+  let mut key = None;
+  let mut value = String::new();
+  let mut quote = None;
+  let mut chars = s.chars();
+
+  while let Some(c) = chars.next() {
+    match c {
+      '\\' => value.push(chars.next().ok_or(ParseAssemblyIdentityError::Malformed)?),
+      '"' | '\'' if quote == Some(c) => quote = None,
+      '"' | '\'' if quote.is_none() && value.trim().is_empty() => {
+        value.clear();
+        quote = Some(c);
+      }
+      ',' if quote.is_none() => {
+        parts.push((key.take(), value.trim().into()));
+        value.clear();
+      }
+      '=' if quote.is_none() && key.is_none() && !parts.is_empty() => {
+        key = Some(value.trim().into());
+        value.clear();
+      }
+      _ => value.push(c),
+    }
+  }
+
+  if quote.is_some() {
+    return Err(ParseAssemblyIdentityError::Malformed);
+  }
+
+  parts.push((key, value.trim().into()));
+
+  Ok(parts)
+}
+
+fn hex(s: &str) -> Option<Vec<u8>> {
+  if !s.len().is_multiple_of(2) {
+    return None;
+  }
+
+  (0..s.len())
+    .step_by(2)
+    .map(|i| u8::from_str_radix(s.get(i..i + 2)?, 16).ok())
+    .collect()
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_display_name_round_trip() {
+    let name = "mscorlib, Version=4.0.0.0, Culture=neutral, PublicKeyToken=b77a5c561934e089";
+    let identity = name.parse::<AssemblyIdentity>().unwrap();
+
+    assert_eq!("mscorlib", identity.name);
+    assert_eq!(AssemblyVersion::new(4, 0, 0, 0), identity.version);
+    assert_eq!(None, identity.culture);
+    assert_eq!(
+      Some([0xb7, 0x7a, 0x5c, 0x56, 0x19, 0x34, 0xe0, 0x89]),
+      identity.public_key_token()
+    );
+    assert_eq!(name, identity.to_string());
+
+    let identity =
+      "'a\\,b' , version = 1.2 ,CULTURE=en-US, publickeytoken=null, Retargetable=Yes, \
+                    ContentType=WindowsRuntime, ProcessorArchitecture=MSIL"
+        .parse::<AssemblyIdentity>()
+        .unwrap();
+
+    assert_eq!("a,b", identity.name);
+    assert_eq!(AssemblyVersion::new(1, 2, 0, 0), identity.version);
+    assert_eq!(Some("en-US"), identity.culture.as_deref());
+    assert_eq!(
+      "a\\,b, Version=1.2.0.0, Culture=en-US, PublicKeyToken=null, Retargetable=Yes, \
+       ContentType=WindowsRuntime",
+      identity.to_string()
+    );
+    assert_eq!(Ok(identity.clone()), identity.to_string().parse());
+
+    // The token of a full public key is derived from it.
+    let identity = "System.Private.CoreLib, PublicKey=00000000000000000400000000000000"
+      .parse::<AssemblyIdentity>()
+      .unwrap();
+
+    assert_eq!(
+      Some([0xb7, 0x7a, 0x5c, 0x56, 0x19, 0x34, 0xe0, 0x89]),
+      identity.public_key_token()
+    );
+
+    for (name, err) in [
+      ("", ParseAssemblyIdentityError::MissingName),
+      ("a, Version", ParseAssemblyIdentityError::Malformed),
+      ("'a", ParseAssemblyIdentityError::Malformed),
+      ("a, Version=1", ParseAssemblyIdentityError::InvalidVersion),
+      (
+        "a, Version=1.2.3.4.5",
+        ParseAssemblyIdentityError::InvalidVersion,
+      ),
+      (
+        "a, Version=1.65536",
+        ParseAssemblyIdentityError::InvalidVersion,
+      ),
+      (
+        "a, PublicKeyToken=b77a",
+        ParseAssemblyIdentityError::InvalidPublicKey,
+      ),
+      (
+        "a, PublicKey=xy",
+        ParseAssemblyIdentityError::InvalidPublicKey,
+      ),
+      (
+        "a, Retargetable=maybe",
+        ParseAssemblyIdentityError::InvalidValue,
+      ),
+    ] {
+      assert_eq!(Err(err), name.parse::<AssemblyIdentity>(), "{name}");
+    }
+  }
+
+  #[test]
+  fn test_matches() {
+    let reference = "System.Runtime, Version=6.0.0.0, PublicKeyToken=b03f5f7f11d50a3a"
+      .parse::<AssemblyIdentity>()
+      .unwrap();
+    let newer = "system.runtime, Version=8.0.0.0, PublicKeyToken=b03f5f7f11d50a3a"
+      .parse::<AssemblyIdentity>()
+      .unwrap();
+    let other = "System.Runtime, Version=8.0.0.0, PublicKeyToken=cc7b13ffcd2ddd51"
+      .parse::<AssemblyIdentity>()
+      .unwrap();
+
+    assert!(reference.matches(&reference, VersionPolicy::Exact));
+    assert!(!reference.matches(&newer, VersionPolicy::Exact));
+    assert!(reference.matches(&newer, VersionPolicy::Minimum));
+    assert!(!newer.matches(&reference, VersionPolicy::Minimum));
+    assert!(newer.matches(&reference, VersionPolicy::Any));
+    assert!(!reference.matches(&other, VersionPolicy::Any));
+
+    let retargetable = AssemblyIdentity {
+      retargetable: true,
+      ..reference.clone()
+    };
+
+    assert!(retargetable.matches(&other, VersionPolicy::Minimum));
+  }
+}
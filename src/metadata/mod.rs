@@ -2,7 +2,13 @@
 
 pub mod errors;
 pub mod headers;
+#[cfg(all(feature = "read", feature = "std"))]
+pub mod identity;
+#[cfg(all(feature = "read", feature = "std"))]
+pub mod model;
 pub mod streams;
+#[cfg(all(feature = "read", feature = "std"))]
+pub mod validate;
 
 #[cfg(feature = "read")]
 #[doc(inline)]
@@ -14,7 +20,7 @@ pub use write::*;
 #[cfg(feature = "read")]
 #[doc(hidden)]
 mod read {
-  use super::errors::MetadataReadError;
+  use super::errors::{MetadataReadError, ReadError, ReadErrorKind, ReadLocation};
   use super::headers::MetadataHeader;
   use super::streams::MetadataStreamIter;
   use crate::bytes::ByteSliceExt;
@@ -34,9 +40,10 @@ mod read {
     /// reader.
     pub fn from_bytes(bytes: &'a [u8]) -> Result<Self, MetadataReadError> {
       let offset = &mut 0;
-      let header = bytes
-        .read::<MetadataHeader>(offset)
-        .ok_or(MetadataReadError::NotEnough)?;
+      let header = bytes.read::<MetadataHeader>(offset).ok_or_else(|| {
+        // The offset is left where reading the header stopped.
+        ReadError::new(ReadLocation::Root, *offset, ReadErrorKind::Truncated)
+      })?;
 
       if header.signature != METADATA_MAGIC {
         return Err(MetadataReadError::BadSignature(header.signature));
@@ -63,4 +70,468 @@ mod read {
 
 #[cfg(feature = "write")]
 #[doc(hidden)]
-mod write {}
+mod write {
+  use super::errors::MetadataStreamReadError;
+  use super::headers::{MetadataHeader, MetadataStreamHeader, METADATA_MAGIC};
+  use super::streams::blobs::BlobsHeapBuilder;
+  use super::streams::guids::GuidsHeapBuilder;
+  use super::streams::strings::StringsHeapBuilder;
+  use super::streams::tables::TablesBuilder;
+  use super::streams::user_strings::UserStringsHeapBuilder;
+  use super::streams::MetadataStream;
+  use super::MetadataReader;
+  use crate::bytes::ByteVecExt;
+  use std::ffi::{CStr, CString};
+  use std::vec::Vec;
+
+  /// Builds the ECMA-335 metadata root and its streams.
+  ///
+  /// The inverse of [MetadataReader].  The heaps and tables are filled in through the public
+  /// builders, which are written as the `#~`, `#Strings`, `#US`, `#GUID` and `#Blob` streams in
+  /// that order by [MetadataBuilder::finish], followed by any streams added with
+  /// [MetadataBuilder::add_stream].
+  ///
+  /// Use [MetadataBuilder::from_reader] to modify existing metadata.
+  #[derive(Debug, Clone)]
+  pub struct MetadataBuilder {
+    /// The version string, `v4.0.30319` by default.
+    pub version: CString,
+    /// The metadata flags, `0` by default.
+    pub flags: u16,
+    /// The `#~` stream.
+    pub tables: TablesBuilder,
+    /// The `#Strings` heap.
+    pub strings: StringsHeapBuilder,
+    /// The `#US` heap.
+    pub user_strings: UserStringsHeapBuilder,
+    /// The `#GUID` heap.
+    pub guids: GuidsHeapBuilder,
+    /// The `#Blob` heap.
+    pub blobs: BlobsHeapBuilder,
+    streams: Vec<Stream>,
+  }
+
+  /// A stream of the metadata in the order it is written.
+  #[derive(Debug, Clone, PartialEq, Eq)]
+  enum Stream {
+    Tables,
+    Strings,
+    UserStrings,
+    Guids,
+    Blobs,
+    Other(CString, Vec<u8>),
+  }
+
+  impl MetadataBuilder {
+    /// Creates a new [MetadataBuilder] with empty heaps and tables.
+    pub fn new() -> Self {
+      Self {
+        version: c"v4.0.30319".into(),
+        flags: 0,
+        tables: TablesBuilder::new(),
+        strings: StringsHeapBuilder::new(),
+        user_strings: UserStringsHeapBuilder::new(),
+        guids: GuidsHeapBuilder::new(),
+        blobs: BlobsHeapBuilder::new(),
+        streams: Vec::from([
+          Stream::Tables,
+          Stream::Strings,
+          Stream::UserStrings,
+          Stream::Guids,
+          Stream::Blobs,
+        ]),
+      }
+    }
+
+    /// Creates a [MetadataBuilder] containing the streams of existing metadata.
+    ///
+    /// The version string, flags, order of the streams, the bytes of the heaps and any stream that
+    /// is not understood, such as the `#Pdb` stream, are kept, so that finishing the builder
+    /// without changes reproduces metadata whose streams are laid out back to back exactly.  Only
+    /// the first of several streams with the same name is modifiable.
+    ///
+    /// The tables of portable PDB metadata keep the row counts of the type-system tables found in
+    /// the `#Pdb` stream, see [TablesBuilder::set_external_rows].
+    pub fn from_reader<'a>(
+      metadata: &MetadataReader<'a>,
+    ) -> Result<Self, MetadataStreamReadError<'a>> {
+      let mut builder = Self::new();
+
+      builder.version = metadata.header().version.into();
+      builder.flags = metadata.header().flags;
+      builder.streams.clear();
+
+      for stream in metadata.streams() {
+        let streams = &mut builder.streams;
+
+        match stream? {
+          MetadataStream::Tables(tables) if !streams.contains(&Stream::Tables) => {
+            streams.push(Stream::Tables);
+            builder.tables = TablesBuilder::from_stream(&tables)?;
+          }
+          MetadataStream::Strings(heap) if !streams.contains(&Stream::Strings) => {
+            streams.push(Stream::Strings);
+            builder.strings = StringsHeapBuilder::from_heap(heap);
+          }
+          MetadataStream::UserStrings(heap) if !streams.contains(&Stream::UserStrings) => {
+            streams.push(Stream::UserStrings);
+            builder.user_strings = UserStringsHeapBuilder::from_heap(heap);
+          }
+          MetadataStream::Guids(heap) if !streams.contains(&Stream::Guids) => {
+            streams.push(Stream::Guids);
+            builder.guids = GuidsHeapBuilder::from_heap(heap);
+          }
+          MetadataStream::Blobs(heap) if !streams.contains(&Stream::Blobs) => {
+            streams.push(Stream::Blobs);
+            builder.blobs = BlobsHeapBuilder::from_heap(heap);
+          }
+          MetadataStream::Tables(tables) => builder.add_stream(
+            tables_name(tables.is_uncompressed()).into(),
+            tables.bytes().into(),
+          ),
+          MetadataStream::Strings(heap) => builder.add_stream(c"#Strings".into(), heap.0.into()),
+          MetadataStream::UserStrings(heap) => builder.add_stream(c"#US".into(), heap.0.into()),
+          MetadataStream::Guids(heap) => builder.add_stream(c"#GUID".into(), heap.0.into()),
+          MetadataStream::Blobs(heap) => builder.add_stream(c"#Blob".into(), heap.0.into()),
+          MetadataStream::Pdb(pdb) => builder.add_stream(c"#Pdb".into(), pdb.0.into()),
+          MetadataStream::Unrecognized { header, data } => {
+            builder.add_stream(header.name.into(), data.into())
+          }
+        }
+      }
+
+      Ok(builder)
+    }
+
+    /// Adds a stream the builder does not otherwise write, such as the `#Pdb` stream.
+    pub fn add_stream(&mut self, name: CString, data: Vec<u8>) {
+      self.streams.push(Stream::Other(name, data));
+    }
+
+    /// Returns the bytes of the metadata root followed by its streams.
+    ///
+    /// The index widths of the `#~` stream are derived from the sizes of the heaps.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the version string is longer than 255 bytes.
+    pub fn finish(mut self) -> Vec<u8> {
+      assert!(
+        self.version.to_bytes_with_nul().len() <= 256,
+        "the metadata version string cannot be longer than 255 bytes"
+      );
+
+      let heap_sizes = self.tables.heap_sizes()
+        | self.strings.heap_sizes()
+        | self.guids.heap_sizes()
+        | self.blobs.heap_sizes();
+
+      self.tables.set_heap_sizes(heap_sizes);
+
+      // Streams missing from the metadata the builder was created from are only written when they
+      // are needed.
+      let missing = [
+        (Stream::Tables, false),
+        (Stream::Strings, self.strings.is_empty()),
+        (Stream::UserStrings, self.user_strings.is_empty()),
+        (Stream::Guids, self.guids.is_empty()),
+        (Stream::Blobs, self.blobs.is_empty()),
+      ];
+
+      for (stream, empty) in missing {
+        if !empty && !self.streams.contains(&stream) {
+          self.streams.push(stream);
+        }
+      }
+
+      let tables_name = tables_name(self.tables.is_uncompressed());
+      let mut tables = Some(self.tables.finish());
+      let mut strings = Some(self.strings.finish());
+      let mut user_strings = Some(self.user_strings.finish());
+      let mut guids = Some(self.guids.finish());
+      let mut blobs = Some(self.blobs.finish());
+      let streams = self
+        .streams
+        .into_iter()
+        .map(|stream| match stream {
+          Stream::Tables => (tables_name.into(), tables.take().unwrap_or_default()),
+          Stream::Strings => (c"#Strings".into(), strings.take().unwrap_or_default()),
+          Stream::UserStrings => (c"#US".into(), user_strings.take().unwrap_or_default()),
+          Stream::Guids => (c"#GUID".into(), guids.take().unwrap_or_default()),
+          Stream::Blobs => (c"#Blob".into(), blobs.take().unwrap_or_default()),
+          Stream::Other(name, data) => (name, data),
+        })
+        .collect::<Vec<(CString, Vec<u8>)>>();
+
+      let header = MetadataHeader {
+        signature: METADATA_MAGIC,
+        major_version: 1,
+        minor_version: 1,
+        reserved: 0,
+        version: &self.version,
+        flags: self.flags,
+        streams: streams.len() as u16,
+      };
+
+      let mut buf = Vec::new();
+
+      buf.write(header);
+
+      // Stream data starts after the stream headers, each of which is 8 bytes followed by the
+      // name padded to a 4 byte boundary.
+      let mut offset = buf.len()
+        + streams
+          .iter()
+          .map(|(name, _)| 8 + ((name.to_bytes_with_nul().len() + 3) & !3))
+          .sum::<usize>();
+
+      for (name, data) in &streams {
+        buf.write(MetadataStreamHeader {
+          offset: offset as u32,
+          size: data.len() as u32,
+          name: name.as_c_str(),
+        });
+
+        offset += data.len();
+      }
+
+      for (_, data) in &streams {
+        buf.extend_from_slice(data);
+      }
+
+      buf
+    }
+  }
+
+  /// Returns the name of the tables stream, `#-` for uncompressed tables.
+  fn tables_name(uncompressed: bool) -> &'static CStr {
+    match uncompressed {
+      true => c"#-",
+      false => c"#~",
+    }
+  }
+
+  impl Default for MetadataBuilder {
+    fn default() -> Self {
+      Self::new()
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  #[test]
+  fn test_read_truncated_header() {
+    use super::errors::{MetadataReadError, ReadErrorKind, ReadLocation};
+    use super::MetadataReader;
+
+    // The signature, versions and reserved field, followed by a version length of 12 bytes but
+    // only part of the version string.
+    let mut bytes = vec![0x42, 0x53, 0x4a, 0x42, 1, 0, 1, 0, 0, 0, 0, 0, 12, 0, 0, 0];
+    bytes.extend_from_slice(b"v4.0.3");
+
+    let Err(MetadataReadError::Read(err)) = MetadataReader::from_bytes(&bytes[..10]) else {
+      panic!("expected a read error");
+    };
+
+    assert_eq!(ReadLocation::Root, err.location());
+    assert_eq!(8, err.offset());
+    assert_eq!(ReadErrorKind::Truncated, err.kind());
+
+    let Err(MetadataReadError::Read(err)) = MetadataReader::from_bytes(&bytes) else {
+      panic!("expected a read error");
+    };
+
+    assert_eq!(16, err.offset());
+  }
+
+  #[test]
+  #[cfg(feature = "write")]
+  fn test_build_round_trip() {
+    use super::streams::tables::flags::TypeAttributes;
+    use super::streams::tables::id::{RowId, TypeDefOrRef};
+    use super::streams::tables::rows::{ModuleRow, TypeDefRow};
+    use super::{MetadataBuilder, MetadataReader};
+
+    let mvid = [0xab; 16];
+    let mut builder = MetadataBuilder::new();
+    let name = builder.strings.add("<Module>");
+    let guid = builder.guids.add(mvid);
+    let hello = builder.user_strings.add("Hello").unwrap();
+
+    builder.tables.add_module(ModuleRow::new(
+      0,
+      builder.strings.add("empty.dll"),
+      guid,
+      builder.guids.add([0; 16]),
+      builder.guids.add([0; 16]),
+    ));
+    builder.tables.add_type_def(TypeDefRow::new(
+      TypeAttributes::empty(),
+      name,
+      builder.strings.add(""),
+      TypeDefOrRef::TypeDef(RowId::new(0)),
+      RowId::new(1),
+      RowId::new(1),
+    ));
+
+    let bytes = builder.finish();
+    let md = MetadataReader::from_bytes(&bytes).unwrap();
+    let streams = md.streams().collect::<Result<Vec<_>, _>>().unwrap();
+
+    assert_eq!(c"v4.0.30319", md.header().version);
+    assert_eq!(5, streams.len());
+
+    let tables = streams[0].as_tables().unwrap();
+    let strings = streams[1].as_strings().unwrap();
+    let user_strings = streams[2].as_user_strings().unwrap();
+    let guids = streams[3].as_guids().unwrap();
+    let module = tables.modules().get(RowId::new(1)).unwrap();
+    let type_def = tables.type_defs().get(RowId::new(1)).unwrap();
+
+    assert!(streams[4].as_blobs().is_some());
+    assert_eq!(Ok("empty.dll"), strings.get_str(module.name()));
+    assert_eq!(Ok(mvid), guids.get(module.mvid()));
+    assert_eq!(Ok("<Module>"), strings.get_str(type_def.name()));
+    assert_eq!(b"H\0e\0l\0l\0o\0\0", user_strings.get(hello).unwrap());
+  }
+
+  #[test]
+  #[cfg(feature = "write")]
+  fn test_pdb_round_trip() {
+    use super::headers::{MetadataHeader, MetadataStreamHeader, METADATA_MAGIC};
+    use super::streams::blobs::BlobsHeapBuilder;
+    use super::streams::guids::GuidsHeapBuilder;
+    use super::streams::tables::id::{HasCustomDebugInformation, RowId};
+    use super::streams::tables::rows::{
+      CustomDebugInformationRow, LocalScopeRow, MethodDefRow, TypeDefRow,
+    };
+    use super::streams::tables::TablesBuilder;
+    use super::{MetadataBuilder, MetadataReader};
+    use crate::bytes::ByteVecExt;
+
+    // Enough methods to widen method indexes and enough types to widen the coded indexes of the
+    // debug tables, which are 5 bits wide.
+    let mut rows = [0; 64];
+    rows[TypeDefRow::ID] = 3000;
+    rows[MethodDefRow::ID] = 70000;
+
+    let mut pdb = vec![0; 24];
+    pdb.extend_from_slice(&(1u64 << TypeDefRow::ID | 1u64 << MethodDefRow::ID).to_le_bytes());
+    pdb.extend_from_slice(&3000u32.to_le_bytes());
+    pdb.extend_from_slice(&70000u32.to_le_bytes());
+
+    let mut tables = TablesBuilder::new();
+    tables.set_external_rows(rows);
+    tables.add_local_scope(LocalScopeRow::new(
+      RowId::new(69999),
+      RowId::new(0),
+      RowId::new(1),
+      RowId::new(1),
+      0,
+      4,
+    ));
+    tables.add_custom_debug_information(CustomDebugInformationRow::new(
+      HasCustomDebugInformation::TypeDef(RowId::new(2999)),
+      GuidsHeapBuilder::new().add([0; 16]),
+      BlobsHeapBuilder::new().add(&[]),
+    ));
+
+    // The `#Pdb` stream has to precede the `#~` stream, which the builder does not write.
+    let streams = [(c"#Pdb", pdb), (c"#~", tables.finish())];
+    let mut bytes = Vec::new();
+
+    bytes.write(MetadataHeader {
+      signature: METADATA_MAGIC,
+      major_version: 1,
+      minor_version: 1,
+      reserved: 0,
+      version: c"PDB v1.0",
+      flags: 0,
+      streams: 2,
+    });
+
+    let mut offset = bytes.len() + 16 + 12;
+
+    for (name, data) in &streams {
+      bytes.write(MetadataStreamHeader {
+        offset: offset as u32,
+        size: data.len() as u32,
+        name,
+      });
+
+      offset += data.len();
+    }
+
+    for (_, data) in &streams {
+      bytes.extend_from_slice(data);
+    }
+
+    let md = MetadataReader::from_bytes(&bytes).unwrap();
+    let tables = md.streams().find_map(|s| s.ok()?.as_tables()).unwrap();
+
+    assert_eq!(
+      RowId::new(69999),
+      tables.local_scopes().get(RowId::new(1)).unwrap().method()
+    );
+    assert_eq!(
+      HasCustomDebugInformation::TypeDef(RowId::new(2999)),
+      tables
+        .custom_debug_informations()
+        .get(RowId::new(1))
+        .unwrap()
+        .parent()
+    );
+    assert_eq!(bytes, MetadataBuilder::from_reader(&md).unwrap().finish());
+  }
+
+  #[test]
+  #[cfg(feature = "write")]
+  fn test_uncompressed_round_trip() {
+    use super::headers::HeapSizes;
+    use super::streams::tables::id::RowId;
+    use super::streams::tables::rows::{MethodPtrRow, NestedClassRow};
+    use super::{MetadataBuilder, MetadataReader};
+
+    let mut builder = MetadataBuilder::new();
+
+    builder.tables.set_uncompressed(true);
+    builder.tables.set_heap_sizes(HeapSizes::EXTRA_DATA);
+    builder
+      .tables
+      .add_method_ptr(MethodPtrRow::new(RowId::new(2)));
+    builder
+      .tables
+      .add_method_ptr(MethodPtrRow::new(RowId::new(1)));
+    builder
+      .tables
+      .add_nested_class(NestedClassRow::new(RowId::new(3), RowId::new(1)));
+    builder
+      .tables
+      .add_nested_class(NestedClassRow::new(RowId::new(2), RowId::new(1)));
+
+    let mut bytes = builder.finish();
+    let md = MetadataReader::from_bytes(&bytes).unwrap();
+    let tables = md.streams().find_map(|s| s.ok()?.as_tables()).unwrap();
+
+    // The extra data follows the two row counts of the `MethodPtr` and `NestedClass` tables.
+    let extra_data = tables.offset_in(&bytes).unwrap() + 24 + 2 * 4;
+
+    bytes[extra_data..extra_data + 4].copy_from_slice(&0xdeadbeefu32.to_le_bytes());
+
+    let md = MetadataReader::from_bytes(&bytes).unwrap();
+    let tables = md.streams().find_map(|s| s.ok()?.as_tables()).unwrap();
+    let nested = tables.nested_classes().into_iter().collect::<Vec<_>>();
+
+    assert!(tables.is_uncompressed());
+    assert_eq!(0xdeadbeef, tables.header().extra_data);
+    assert_eq!(0, tables.header().sorted & (1 << NestedClassRow::ID));
+    assert_eq!(
+      RowId::new(2),
+      tables.method_ptrs().get(RowId::new(1)).unwrap().method()
+    );
+    assert_eq!(RowId::new(3), nested[0].nested_class());
+    assert_eq!(RowId::new(2), nested[1].nested_class());
+    assert_eq!(bytes, MetadataBuilder::from_reader(&md).unwrap().finish());
+  }
+}
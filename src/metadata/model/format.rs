@@ -0,0 +1,1299 @@
+//! Formatting of signatures as C#, ILAsm or documentation ids.
+//!
+//! A [SignatureFormatter] writes decoded [TypeSig]s and the signatures of members in the syntax of
+//! a [SignatureStyle], resolving the `TypeDef`, `TypeRef` and `TypeSpec` rows they refer to into
+//! names read from the `#Strings` heap.
+
+use super::members::{EventDefinition, FieldDefinition, MethodDefinition, PropertyDefinition};
+use super::types::TypeDefinition;
+use super::Metadata;
+use crate::metadata::errors::{ReadError, ReadErrorKind, ReadLocation};
+use crate::metadata::streams::tables::flags::{CallingConvention, ParamAttributes};
+use crate::metadata::streams::tables::id::{
+  CustomAttributeType, HasCustomAttribute, MemberRefParent, ResolutionScope, RowId, TypeDefOrRef,
+};
+use crate::metadata::streams::tables::rows::{
+  GenericParamRow, NestedClassRow, ParamRow, TypeDefRow, TypeRefRow, TypeSpecRow,
+};
+use crate::metadata::streams::tables::signatures::{ArrayShape, MethodSig, TypeSig};
+use crate::metadata::streams::tables::table::Row;
+use std::format;
+use std::string::String;
+use std::vec::Vec;
+
+/// The deepest nesting of types written, which bounds the recursion through `TypeSpec` and
+/// `TypeRef` rows referring to each other.
+const MAX_DEPTH: usize = 256;
+
+/// The syntax written by a [SignatureFormatter].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum SignatureStyle {
+  /// C# syntax, such as `List<int> Foo<T>(ref T x, params string[] rest)`.
+  CSharp,
+  /// ILAsm syntax, such as ```class [mscorlib]System.Collections.Generic.List`1<int32>```.
+  ILAsm,
+  /// The ids of members in XML documentation files, such as
+  /// ```M:Ns.Type.Foo``1(``0@,System.String[])```.
+  DocumentationId,
+}
+
+/// Formats signatures in a [SignatureStyle], resolving the types they refer to through the
+/// [Metadata] of their module.
+///
+/// Generic parameters are written by name when the formatter knows the generic parameters of the
+/// enclosing type and method, and by number otherwise, such as `!0` for the first parameter of
+/// the type and `!!0` for the first parameter of the method.  Formatting a member uses the
+/// generic parameters of the member and its declaring type.
+#[derive(Debug, Clone)]
+pub struct SignatureFormatter<'a> {
+  metadata: &'a Metadata<'a>,
+  style: SignatureStyle,
+  qualified: bool,
+  type_params: Vec<&'a str>,
+  method_params: Vec<&'a str>,
+}
+
+/// The names of the generic parameters of the enclosing type and method.
+#[derive(Clone, Copy)]
+struct Generics<'s, 'a> {
+  types: &'s [&'a str],
+  methods: &'s [&'a str],
+}
+
+/// The name of a type definition or reference.
+struct TypeName<'a> {
+  /// The assembly or module of a type reference, for ILAsm.
+  scope: Option<Scope<'a>>,
+  namespace: &'a str,
+  /// The names of the outermost type and of the types nested in it, in order.
+  names: Vec<&'a str>,
+}
+
+impl Generics<'static, 'static> {
+  /// No generic parameters, for names written outside of a signature.
+  const NONE: Self = Self {
+    types: &[],
+    methods: &[],
+  };
+}
+
+enum Scope<'a> {
+  Assembly(&'a str),
+  Module(&'a str),
+}
+
+impl<'a> SignatureFormatter<'a> {
+  /// Creates a [SignatureFormatter] for the signatures of the given metadata.
+  pub fn new(metadata: &'a Metadata<'a>, style: SignatureStyle) -> Self {
+    Self {
+      metadata,
+      style,
+      qualified: false,
+      type_params: Vec::new(),
+      method_params: Vec::new(),
+    }
+  }
+
+  /// Returns the [SignatureStyle] of the formatter.
+  pub fn style(&self) -> SignatureStyle {
+    self.style
+  }
+
+  /// Sets whether C# type names include their namespace, `false` by default.
+  ///
+  /// ILAsm and documentation ids always include namespaces.
+  pub fn set_qualified(&mut self, qualified: bool) {
+    self.qualified = qualified;
+  }
+
+  /// Sets the names of the generic parameters of the enclosing type and method, used by
+  /// [SignatureFormatter::format_type].
+  pub fn set_generic_params(&mut self, type_params: Vec<&'a str>, method_params: Vec<&'a str>) {
+    self.type_params = type_params;
+    self.method_params = method_params;
+  }
+
+  /// Formats a type.
+  pub fn format_type(&self, ty: &TypeSig) -> Result<String, ReadError> {
+    let mut out = String::new();
+    let generics = Generics {
+      types: &self.type_params,
+      methods: &self.method_params,
+    };
+
+    self.write_type(&mut out, ty, generics, 0)?;
+    Ok(out)
+  }
+
+  /// Formats the type of a `TypeDef`, `TypeRef` or `TypeSpec` row, such as the base type of a
+  /// type.
+  pub fn format_type_def_or_ref(&self, ty: TypeDefOrRef) -> Result<String, ReadError> {
+    let mut out = String::new();
+    let generics = Generics {
+      types: &self.type_params,
+      methods: &self.method_params,
+    };
+
+    self.write_type_def_or_ref(&mut out, ty, generics, 0)?;
+    Ok(out)
+  }
+
+  /// Formats a type definition, with its generic parameters in C#.
+  pub fn format_type_def(&self, ty: &TypeDefinition<'a>) -> Result<String, ReadError> {
+    let mut out = String::new();
+    let name = self.type_def_name(ty.id())?;
+
+    match self.style {
+      SignatureStyle::CSharp => {
+        let params = self.param_names(ty.generic_params())?;
+        let args = (0..params.len() as u32)
+          .map(TypeSig::Var)
+          .collect::<Vec<_>>();
+        let generics = Generics {
+          types: &params,
+          methods: &[],
+        };
+
+        self.write_type_name(&mut out, &name, &args, generics, 0)?;
+      }
+      SignatureStyle::ILAsm => self.write_type_name(&mut out, &name, &[], Generics::NONE, 0)?,
+      SignatureStyle::DocumentationId => {
+        out.push_str("T:");
+        self.write_type_name(&mut out, &name, &[], Generics::NONE, 0)?;
+      }
+    }
+
+    Ok(out)
+  }
+
+  /// Formats the signature of a method, with the names of its parameters in C# and ILAsm.
+  pub fn format_method(&self, method: &MethodDefinition<'a>) -> Result<String, ReadError> {
+    let mut out = String::new();
+    let name = method.name()?;
+    let sig = method.signature()?;
+    let declaring_type = method.declaring_type()?;
+    let type_params = self.declaring_type_params(declaring_type.as_ref())?;
+    let method_params = self.param_names(method.generic_params())?;
+    let generics = Generics {
+      types: &type_params,
+      methods: &method_params,
+    };
+
+    match self.style {
+      SignatureStyle::CSharp => {
+        match (name, &declaring_type) {
+          (".ctor" | ".cctor", Some(ty)) => {
+            let name = self.type_def_name(ty.id())?;
+
+            out.push_str(strip_arity(name.names.last().copied().unwrap_or_default()));
+          }
+          _ => {
+            self.write_type(&mut out, &sig.return_type, generics, 0)?;
+            out.push_str(&format!(" {name}"));
+          }
+        }
+
+        write_list(&mut out, "<", &method_params, ", ", ">");
+        out.push('(');
+
+        for (i, param) in method.parameters()?.iter().enumerate() {
+          if i > 0 {
+            out.push_str(", ");
+          }
+
+          self.write_csharp_param(&mut out, param.ty(), param.row(), generics)?;
+
+          if !param.name()?.is_empty() {
+            out.push_str(&format!(" {}", param.name()?));
+          }
+        }
+
+        if sig.kind() == CallingConvention::VARARG {
+          out.push_str(match sig.params.is_empty() {
+            true => "__arglist",
+            false => ", __arglist",
+          });
+        }
+
+        out.push(')');
+      }
+      SignatureStyle::ILAsm => {
+        self.write_ilasm_convention(&mut out, &sig);
+        self.write_type(&mut out, &sig.return_type, generics, 0)?;
+        out.push_str(&format!(" {name}"));
+        write_list(&mut out, "<", &method_params, ",", ">");
+        out.push('(');
+
+        for (i, param) in method.parameters()?.iter().enumerate() {
+          if i > 0 {
+            out.push_str(", ");
+          }
+
+          self.write_type(&mut out, param.ty(), generics, 0)?;
+
+          if !param.name()?.is_empty() {
+            out.push_str(&format!(" {}", param.name()?));
+          }
+        }
+
+        out.push(')');
+      }
+      SignatureStyle::DocumentationId => {
+        out.push_str("M:");
+        self.write_doc_member_name(&mut out, declaring_type.as_ref(), name)?;
+
+        if sig.generic_param_count > 0 {
+          out.push_str(&format!("``{}", sig.generic_param_count));
+        }
+
+        self.write_doc_params(&mut out, &sig.params, generics)?;
+
+        if name == "op_Implicit" || name == "op_Explicit" {
+          out.push('~');
+          self.write_type(&mut out, &sig.return_type, generics, 0)?;
+        }
+      }
+    }
+
+    Ok(out)
+  }
+
+  /// Formats the type and name of a field.
+  pub fn format_field(&self, field: &FieldDefinition<'a>) -> Result<String, ReadError> {
+    let mut out = String::new();
+    let name = field.name()?;
+    let declaring_type = field.declaring_type()?;
+    let type_params = self.declaring_type_params(declaring_type.as_ref())?;
+    let generics = Generics {
+      types: &type_params,
+      methods: &[],
+    };
+
+    match self.style {
+      SignatureStyle::CSharp | SignatureStyle::ILAsm => {
+        self.write_type(&mut out, &field.signature()?.ty, generics, 0)?;
+        out.push_str(&format!(" {name}"));
+      }
+      SignatureStyle::DocumentationId => {
+        out.push_str("F:");
+        self.write_doc_member_name(&mut out, declaring_type.as_ref(), name)?;
+      }
+    }
+
+    Ok(out)
+  }
+
+  /// Formats the type, name and parameters of a property, written as an indexer in C# if it has
+  /// parameters.
+  pub fn format_property(&self, property: &PropertyDefinition<'a>) -> Result<String, ReadError> {
+    let mut out = String::new();
+    let name = property.name()?;
+    let sig = property.signature()?;
+    let declaring_type = property.declaring_type()?;
+    let type_params = self.declaring_type_params(declaring_type.as_ref())?;
+    let generics = Generics {
+      types: &type_params,
+      methods: &[],
+    };
+
+    match self.style {
+      SignatureStyle::CSharp => {
+        self.write_type(&mut out, &sig.ty, generics, 0)?;
+
+        match sig.params.is_empty() {
+          true => out.push_str(&format!(" {name}")),
+          false => {
+            out.push_str(" this[");
+            self.write_types(&mut out, &sig.params, ", ", generics)?;
+            out.push(']');
+          }
+        }
+      }
+      SignatureStyle::ILAsm => {
+        if sig.has_this {
+          out.push_str("instance ");
+        }
+
+        self.write_type(&mut out, &sig.ty, generics, 0)?;
+        out.push_str(&format!(" {name}("));
+        self.write_types(&mut out, &sig.params, ", ", generics)?;
+        out.push(')');
+      }
+      SignatureStyle::DocumentationId => {
+        out.push_str("P:");
+        self.write_doc_member_name(&mut out, declaring_type.as_ref(), name)?;
+        self.write_doc_params(&mut out, &sig.params, generics)?;
+      }
+    }
+
+    Ok(out)
+  }
+
+  /// Formats the delegate type and name of an event.
+  pub fn format_event(&self, event: &EventDefinition<'a>) -> Result<String, ReadError> {
+    let mut out = String::new();
+    let name = event.name()?;
+    let declaring_type = event.declaring_type()?;
+    let type_params = self.declaring_type_params(declaring_type.as_ref())?;
+    let generics = Generics {
+      types: &type_params,
+      methods: &[],
+    };
+
+    match self.style {
+      SignatureStyle::CSharp | SignatureStyle::ILAsm => {
+        if self.style == SignatureStyle::CSharp {
+          out.push_str("event ");
+        }
+
+        self.write_type_def_or_ref(&mut out, event.event_type(), generics, 0)?;
+        out.push_str(&format!(" {name}"));
+      }
+      SignatureStyle::DocumentationId => {
+        out.push_str("E:");
+        self.write_doc_member_name(&mut out, declaring_type.as_ref(), name)?;
+      }
+    }
+
+    Ok(out)
+  }
+
+  fn write_type(
+    &self,
+    out: &mut String,
+    ty: &TypeSig,
+    generics: Generics<'_, 'a>,
+    depth: usize,
+  ) -> Result<(), ReadError> {
+    if depth > MAX_DEPTH {
+      return Err(self.too_deep());
+    }
+
+    if let Some(name) = self.primitive(ty) {
+      out.push_str(name);
+      return Ok(());
+    }
+
+    let depth = depth + 1;
+
+    match ty {
+      TypeSig::Class(TypeDefOrRef::TypeSpec(id))
+      | TypeSig::ValueType(TypeDefOrRef::TypeSpec(id)) => {
+        self.write_type_spec(out, *id, generics, depth)?;
+      }
+      TypeSig::Class(inner) | TypeSig::ValueType(inner) => {
+        if self.style == SignatureStyle::ILAsm {
+          out.push_str(match ty {
+            TypeSig::ValueType(_) => "valuetype ",
+            _ => "class ",
+          });
+        }
+
+        self.write_type_def_or_ref(out, *inner, generics, depth)?;
+      }
+      TypeSig::GenericInst {
+        value_type,
+        generic,
+        args,
+      } => {
+        if self.style == SignatureStyle::ILAsm {
+          out.push_str(match value_type {
+            true => "valuetype ",
+            false => "class ",
+          });
+        }
+
+        let name = self.type_name(*generic)?;
+
+        self.write_type_name(out, &name, args, generics, depth)?;
+      }
+      TypeSig::Var(number) | TypeSig::MVar(number) => {
+        let method = matches!(ty, TypeSig::MVar(_));
+        let names = match method {
+          true => generics.methods,
+          false => generics.types,
+        };
+        let prefix = match method {
+          true => "!!",
+          false => "!",
+        };
+
+        match (self.style, names.get(*number as usize)) {
+          (SignatureStyle::DocumentationId, _) => match method {
+            true => out.push_str(&format!("``{number}")),
+            false => out.push_str(&format!("`{number}")),
+          },
+          (SignatureStyle::CSharp, Some(name)) => out.push_str(name),
+          (SignatureStyle::ILAsm, Some(name)) => out.push_str(&format!("{prefix}{name}")),
+          (_, None) => out.push_str(&format!("{prefix}{number}")),
+        }
+      }
+      TypeSig::Ptr(ty) => {
+        self.write_type(out, ty, generics, depth)?;
+        out.push('*');
+      }
+      TypeSig::ByRef(ty) => {
+        if self.style == SignatureStyle::CSharp {
+          out.push_str("ref ");
+        }
+
+        self.write_type(out, ty, generics, depth)?;
+
+        match self.style {
+          SignatureStyle::CSharp => {}
+          SignatureStyle::ILAsm => out.push('&'),
+          SignatureStyle::DocumentationId => out.push('@'),
+        }
+      }
+      TypeSig::SzArray(ty) => {
+        self.write_type(out, ty, generics, depth)?;
+        out.push_str("[]");
+      }
+      TypeSig::Array(ty, shape) => {
+        self.write_type(out, ty, generics, depth)?;
+        self.write_array_shape(out, shape);
+      }
+      TypeSig::FnPtr(sig) => self.write_fn_ptr(out, sig, generics, depth)?,
+      TypeSig::Modified(modifier, ty) => {
+        self.write_type(out, ty, generics, depth)?;
+
+        match (self.style, modifier.required) {
+          (SignatureStyle::CSharp, _) => return Ok(()),
+          (SignatureStyle::ILAsm, true) => out.push_str(" modreq("),
+          (SignatureStyle::ILAsm, false) => out.push_str(" modopt("),
+          (SignatureStyle::DocumentationId, true) => out.push('|'),
+          (SignatureStyle::DocumentationId, false) => out.push('!'),
+        }
+
+        self.write_type_def_or_ref(out, modifier.modifier, generics, depth)?;
+
+        if self.style == SignatureStyle::ILAsm {
+          out.push(')');
+        }
+      }
+      // Primitive types are written above.
+      _ => {}
+    }
+
+    Ok(())
+  }
+
+  /// Returns the name of a primitive type in the style of the formatter.
+  fn primitive(&self, ty: &TypeSig) -> Option<&'static str> {
+    let names = match ty {
+      TypeSig::Void => ("void", "void", "System.Void"),
+      TypeSig::Boolean => ("bool", "bool", "System.Boolean"),
+      TypeSig::Char => ("char", "char", "System.Char"),
+      TypeSig::I1 => ("sbyte", "int8", "System.SByte"),
+      TypeSig::U1 => ("byte", "uint8", "System.Byte"),
+      TypeSig::I2 => ("short", "int16", "System.Int16"),
+      TypeSig::U2 => ("ushort", "uint16", "System.UInt16"),
+      TypeSig::I4 => ("int", "int32", "System.Int32"),
+      TypeSig::U4 => ("uint", "uint32", "System.UInt32"),
+      TypeSig::I8 => ("long", "int64", "System.Int64"),
+      TypeSig::U8 => ("ulong", "uint64", "System.UInt64"),
+      TypeSig::R4 => ("float", "float32", "System.Single"),
+      TypeSig::R8 => ("double", "float64", "System.Double"),
+      TypeSig::String => ("string", "string", "System.String"),
+      TypeSig::I => ("nint", "native int", "System.IntPtr"),
+      TypeSig::U => ("nuint", "native uint", "System.UIntPtr"),
+      TypeSig::Object => ("object", "object", "System.Object"),
+      TypeSig::TypedByRef => match self.qualified {
+        true => ("System.TypedReference", "typedref", "System.TypedReference"),
+        false => ("TypedReference", "typedref", "System.TypedReference"),
+      },
+      _ => return None,
+    };
+
+    Some(match self.style {
+      SignatureStyle::CSharp => names.0,
+      SignatureStyle::ILAsm => names.1,
+      SignatureStyle::DocumentationId => names.2,
+    })
+  }
+
+  fn write_type_def_or_ref(
+    &self,
+    out: &mut String,
+    ty: TypeDefOrRef,
+    generics: Generics<'_, 'a>,
+    depth: usize,
+  ) -> Result<(), ReadError> {
+    match ty {
+      TypeDefOrRef::TypeSpec(id) => self.write_type_spec(out, id, generics, depth),
+      ty => {
+        let name = self.type_name(ty)?;
+
+        self.write_type_name(out, &name, &[], generics, depth)
+      }
+    }
+  }
+
+  fn write_type_spec(
+    &self,
+    out: &mut String,
+    id: RowId<TypeSpecRow>,
+    generics: Generics<'_, 'a>,
+    depth: usize,
+  ) -> Result<(), ReadError> {
+    let row = self.metadata.tables().type_specs().get(id)?;
+    let ty = self.metadata.read_blob::<TypeSig>(row.signature())?;
+
+    self.write_type(out, &ty, generics, depth + 1)
+  }
+
+  /// Writes the name of a type with the given type arguments, which are distributed to the
+  /// enclosing types according to the number of generic parameters in their names.
+  fn write_type_name(
+    &self,
+    out: &mut String,
+    name: &TypeName<'a>,
+    args: &[TypeSig],
+    generics: Generics<'_, 'a>,
+    depth: usize,
+  ) -> Result<(), ReadError> {
+    let (separator, open, close) = match self.style {
+      SignatureStyle::CSharp => (".", "<", ">"),
+      SignatureStyle::ILAsm => ("/", "<", ">"),
+      SignatureStyle::DocumentationId => (".", "{", "}"),
+    };
+    let args_separator = match self.style {
+      SignatureStyle::CSharp => ", ",
+      _ => ",",
+    };
+
+    if self.style == SignatureStyle::CSharp && name.namespace == "System" && name.names.len() == 1 {
+      match (name.names[0], args) {
+        ("Nullable`1", [arg]) => {
+          self.write_type(out, arg, generics, depth)?;
+          out.push('?');
+          return Ok(());
+        }
+        (type_name, []) => {
+          if let Some(keyword) = csharp_keyword(type_name) {
+            out.push_str(keyword);
+            return Ok(());
+          }
+        }
+        _ => {}
+      }
+    }
+
+    if self.style == SignatureStyle::ILAsm {
+      match name.scope {
+        Some(Scope::Assembly(assembly)) => out.push_str(&format!("[{assembly}]")),
+        Some(Scope::Module(module)) => out.push_str(&format!("[.module {module}]")),
+        None => {}
+      }
+    }
+
+    if !name.namespace.is_empty() && (self.qualified || self.style != SignatureStyle::CSharp) {
+      out.push_str(&format!("{}.", name.namespace));
+    }
+
+    // Documentation ids keep the number of generic parameters in the names of types that are not
+    // instantiated.
+    let keep_arity = self.style == SignatureStyle::DocumentationId && args.is_empty();
+    let mut args = args;
+
+    for (i, type_name) in name.names.iter().enumerate() {
+      if i > 0 {
+        out.push_str(separator);
+      }
+
+      // ILAsm keeps the arity in names and lists all the arguments after the innermost type.
+      if self.style == SignatureStyle::ILAsm {
+        out.push_str(type_name);
+        continue;
+      }
+
+      let count = match i == name.names.len() - 1 {
+        true => args.len(),
+        false => arity(type_name).min(args.len()),
+      };
+      let written = match keep_arity {
+        true => type_name,
+        false => strip_arity(type_name),
+      };
+
+      out.push_str(written);
+
+      if count > 0 {
+        out.push_str(open);
+        self.write_types_at(out, &args[..count], args_separator, generics, depth)?;
+        out.push_str(close);
+      }
+
+      args = &args[count..];
+    }
+
+    if self.style == SignatureStyle::ILAsm && !args.is_empty() {
+      out.push_str(open);
+      self.write_types_at(out, args, args_separator, generics, depth)?;
+      out.push_str(close);
+    }
+
+    Ok(())
+  }
+
+  fn write_array_shape(&self, out: &mut String, shape: &ArrayShape) {
+    out.push('[');
+
+    for i in 0..shape.rank as usize {
+      if i > 0 {
+        out.push(',');
+      }
+
+      let lower = shape.lower_bounds.get(i).copied();
+      let size = shape.sizes.get(i).copied();
+
+      match self.style {
+        SignatureStyle::CSharp => {}
+        SignatureStyle::ILAsm => match (lower, size) {
+          (Some(lower), Some(size)) => {
+            out.push_str(&format!("{lower}...{}", lower as i64 + size as i64 - 1));
+          }
+          (Some(lower), None) => out.push_str(&format!("{lower}...")),
+          (None, Some(size)) => out.push_str(&format!("{size}")),
+          (None, None) => {}
+        },
+        SignatureStyle::DocumentationId => {
+          out.push_str(&format!("{}:", lower.unwrap_or(0)));
+
+          if let Some(size) = size {
+            out.push_str(&format!("{size}"));
+          }
+        }
+      }
+    }
+
+    out.push(']');
+  }
+
+  fn write_fn_ptr(
+    &self,
+    out: &mut String,
+    sig: &MethodSig,
+    generics: Generics<'_, 'a>,
+    depth: usize,
+  ) -> Result<(), ReadError> {
+    match self.style {
+      SignatureStyle::CSharp => {
+        out.push_str(match sig.kind() {
+          CallingConvention::DEFAULT | CallingConvention::VARARG => "delegate*<",
+          _ => "delegate* unmanaged<",
+        });
+
+        for param in &sig.params {
+          self.write_type(out, param, generics, depth)?;
+          out.push_str(", ");
+        }
+
+        self.write_type(out, &sig.return_type, generics, depth)?;
+        out.push('>');
+      }
+      SignatureStyle::ILAsm => {
+        out.push_str("method ");
+        self.write_ilasm_convention(out, sig);
+        self.write_type(out, &sig.return_type, generics, depth)?;
+        out.push_str(" *(");
+        self.write_types_at(out, &sig.params, ",", generics, depth)?;
+        out.push(')');
+      }
+      SignatureStyle::DocumentationId => {
+        out.push_str("=FUNC:");
+        self.write_type(out, &sig.return_type, generics, depth)?;
+        out.push('(');
+        self.write_types_at(out, &sig.params, ",", generics, depth)?;
+        out.push(')');
+      }
+    }
+
+    Ok(())
+  }
+
+  fn write_ilasm_convention(&self, out: &mut String, sig: &MethodSig) {
+    if sig.has_this() {
+      out.push_str("instance ");
+    }
+
+    if sig.explicit_this() {
+      out.push_str("explicit ");
+    }
+
+    out.push_str(match sig.kind() {
+      CallingConvention::VARARG => "vararg ",
+      CallingConvention::C => "unmanaged cdecl ",
+      CallingConvention::STDCALL => "unmanaged stdcall ",
+      CallingConvention::THISCALL => "unmanaged thiscall ",
+      CallingConvention::FASTCALL => "unmanaged fastcall ",
+      _ => "",
+    });
+  }
+
+  /// Writes a parameter of a C# method, with its `ref`, `out`, `in` or `params` modifier.
+  fn write_csharp_param(
+    &self,
+    out: &mut String,
+    mut ty: &TypeSig,
+    row: Option<ParamRow>,
+    generics: Generics<'_, 'a>,
+  ) -> Result<(), ReadError> {
+    let mut read_only = false;
+
+    while let TypeSig::Modified(modifier, modified) = ty {
+      let name = self.type_name_of(modifier.modifier)?;
+
+      read_only |= name.is_some_and(|name| {
+        name.namespace == "System.Runtime.InteropServices" && name.names == ["InAttribute"]
+      });
+      ty = modified;
+    }
+
+    let flags = row.map_or(ParamAttributes::empty(), |row| row.flags());
+
+    match ty {
+      TypeSig::ByRef(ty) => {
+        out.push_str(match flags & (ParamAttributes::IN | ParamAttributes::OUT) {
+          _ if read_only => "in ",
+          ParamAttributes::OUT => "out ",
+          _ => "ref ",
+        });
+
+        self.write_type(out, ty, generics, 0)
+      }
+      ty => {
+        if let Some(row) = row {
+          if self.is_param_array(row)? {
+            out.push_str("params ");
+          }
+        }
+
+        self.write_type(out, ty, generics, 0)
+      }
+    }
+  }
+
+  /// Writes the full name of the declaring type, if any, followed by the name of a member, for
+  /// documentation ids.
+  fn write_doc_member_name(
+    &self,
+    out: &mut String,
+    declaring_type: Option<&TypeDefinition<'a>>,
+    name: &str,
+  ) -> Result<(), ReadError> {
+    if let Some(ty) = declaring_type {
+      let type_name = self.type_def_name(ty.id())?;
+
+      self.write_type_name(out, &type_name, &[], Generics::NONE, 0)?;
+      out.push('.');
+    }
+
+    out.push_str(&name.replace('.', "#"));
+    Ok(())
+  }
+
+  /// Writes the parenthesized parameters of a method or property, for documentation ids.
+  fn write_doc_params(
+    &self,
+    out: &mut String,
+    params: &[TypeSig],
+    generics: Generics<'_, 'a>,
+  ) -> Result<(), ReadError> {
+    if !params.is_empty() {
+      out.push('(');
+      self.write_types(out, params, ",", generics)?;
+      out.push(')');
+    }
+
+    Ok(())
+  }
+
+  fn write_types(
+    &self,
+    out: &mut String,
+    types: &[TypeSig],
+    separator: &str,
+    generics: Generics<'_, 'a>,
+  ) -> Result<(), ReadError> {
+    self.write_types_at(out, types, separator, generics, 0)
+  }
+
+  fn write_types_at(
+    &self,
+    out: &mut String,
+    types: &[TypeSig],
+    separator: &str,
+    generics: Generics<'_, 'a>,
+    depth: usize,
+  ) -> Result<(), ReadError> {
+    for (i, ty) in types.iter().enumerate() {
+      if i > 0 {
+        out.push_str(separator);
+      }
+
+      self.write_type(out, ty, generics, depth)?;
+    }
+
+    Ok(())
+  }
+
+  /// Returns the name of a `TypeDef` or `TypeRef` row, failing on `TypeSpec` rows.
+  fn type_name(&self, ty: TypeDefOrRef) -> Result<TypeName<'a>, ReadError> {
+    match self.type_name_of(ty)? {
+      Some(name) => Ok(name),
+      None => Err(ReadError::new(
+        ReadLocation::Table {
+          table: TypeSpecRow::NAME,
+        },
+        self.metadata.tables().type_specs().offset(),
+        ReadErrorKind::Invalid,
+      )),
+    }
+  }
+
+  /// Returns the name of a `TypeDef` or `TypeRef` row, or [None] for `TypeSpec` rows.
+  fn type_name_of(&self, ty: TypeDefOrRef) -> Result<Option<TypeName<'a>>, ReadError> {
+    match ty {
+      TypeDefOrRef::TypeDef(id) => self.type_def_name(id).map(Some),
+      TypeDefOrRef::TypeRef(id) => self.type_ref_name(id).map(Some),
+      TypeDefOrRef::TypeSpec(_) => Ok(None),
+    }
+  }
+
+  fn type_def_name(&self, id: RowId<TypeDefRow>) -> Result<TypeName<'a>, ReadError> {
+    let mut ty = self.metadata.type_def(id)?;
+    let mut names = vec![ty.name()?];
+
+    while let Some(enclosing) = ty.declaring_type()? {
+      if names.len() > MAX_DEPTH {
+        return Err(ReadError::new(
+          ReadLocation::Table {
+            table: NestedClassRow::NAME,
+          },
+          self.metadata.tables().nested_classes().offset(),
+          ReadErrorKind::Invalid,
+        ));
+      }
+
+      names.push(enclosing.name()?);
+      ty = enclosing;
+    }
+
+    names.reverse();
+
+    Ok(TypeName {
+      scope: None,
+      namespace: ty.namespace()?,
+      names,
+    })
+  }
+
+  fn type_ref_name(&self, id: RowId<TypeRefRow>) -> Result<TypeName<'a>, ReadError> {
+    let tables = self.metadata.tables();
+    let strings = self.metadata.strings();
+    let mut row = tables.type_refs().get(id)?;
+    let mut names = vec![strings.get_str(row.name())?];
+
+    let scope = loop {
+      match row.resolution_scope() {
+        ResolutionScope::TypeRef(enclosing) => {
+          if names.len() > MAX_DEPTH {
+            return Err(ReadError::new(
+              ReadLocation::Table {
+                table: TypeRefRow::NAME,
+              },
+              tables.type_refs().offset(),
+              ReadErrorKind::Invalid,
+            ));
+          }
+
+          row = tables.type_refs().get(enclosing)?;
+          names.push(strings.get_str(row.name())?);
+        }
+        ResolutionScope::AssemblyRef(id) if id.index() != 0 => {
+          let assembly = tables.assembly_refs().get(id)?;
+
+          break Some(Scope::Assembly(strings.get_str(assembly.name())?));
+        }
+        ResolutionScope::ModuleRef(id) if id.index() != 0 => {
+          let module = tables.module_refs().get(id)?;
+
+          break Some(Scope::Module(strings.get_str(module.name())?));
+        }
+        _ => break None,
+      }
+    };
+
+    names.reverse();
+
+    Ok(TypeName {
+      scope,
+      namespace: strings.get_str(row.namespace())?,
+      names,
+    })
+  }
+
+  /// Returns whether the parameter has the `System.ParamArrayAttribute` of C# `params`
+  /// parameters.
+  fn is_param_array(&self, row: ParamRow) -> Result<bool, ReadError> {
+    for attribute in self
+      .metadata
+      .custom_attributes(HasCustomAttribute::Param(row.id()))
+    {
+      let ty = match attribute.attribute_type() {
+        CustomAttributeType::MethodDef(id) => self
+          .metadata
+          .list_owner(id)?
+          .map(|ty| TypeDefOrRef::TypeDef(ty.id())),
+        CustomAttributeType::MemberRef(id) => {
+          match self.metadata.tables().member_refs().get(id)?.class() {
+            MemberRefParent::TypeDef(id) => Some(TypeDefOrRef::TypeDef(id)),
+            MemberRefParent::TypeRef(id) => Some(TypeDefOrRef::TypeRef(id)),
+            _ => None,
+          }
+        }
+      };
+      let name = match ty {
+        Some(ty) => self.type_name_of(ty)?,
+        None => None,
+      };
+
+      if name
+        .is_some_and(|name| name.namespace == "System" && name.names == ["ParamArrayAttribute"])
+      {
+        return Ok(true);
+      }
+    }
+
+    Ok(false)
+  }
+
+  fn declaring_type_params(
+    &self,
+    ty: Option<&TypeDefinition<'a>>,
+  ) -> Result<Vec<&'a str>, ReadError> {
+    match ty {
+      Some(ty) => self.param_names(ty.generic_params()),
+      None => Ok(Vec::new()),
+    }
+  }
+
+  fn param_names(&self, params: Vec<GenericParamRow>) -> Result<Vec<&'a str>, ReadError> {
+    let strings = self.metadata.strings();
+
+    params
+      .iter()
+      .map(|param| strings.get_str(param.name()))
+      .collect()
+  }
+
+  fn too_deep(&self) -> ReadError {
+    ReadError::new(
+      ReadLocation::Table {
+        table: TypeSpecRow::NAME,
+      },
+      self.metadata.tables().type_specs().offset(),
+      ReadErrorKind::Invalid,
+    )
+  }
+}
+
+/// Returns the C# keyword for a type of the `System` namespace.
+fn csharp_keyword(name: &str) -> Option<&'static str> {
+  Some(match name {
+    "Void" => "void",
+    "Boolean" => "bool",
+    "Char" => "char",
+    "SByte" => "sbyte",
+    "Byte" => "byte",
+    "Int16" => "short",
+    "UInt16" => "ushort",
+    "Int32" => "int",
+    "UInt32" => "uint",
+    "Int64" => "long",
+    "UInt64" => "ulong",
+    "Single" => "float",
+    "Double" => "double",
+    "Decimal" => "decimal",
+    "String" => "string",
+    "IntPtr" => "nint",
+    "UIntPtr" => "nuint",
+    "Object" => "object",
+    _ => return None,
+  })
+}
+
+/// Returns the number of generic parameters in the name of a type, such as 2 for
+/// ```Dictionary`2```.
+fn arity(name: &str) -> usize {
+  match name.rsplit_once('`') {
+    Some((_, arity)) => arity.parse().unwrap_or(0),
+    None => 0,
+  }
+}
+
+/// Removes the number of generic parameters from the name of a type.
+fn strip_arity(name: &str) -> &str {
+  match name.rsplit_once('`') {
+    Some((stripped, arity)) if arity.parse::<usize>().is_ok() => stripped,
+    _ => name,
+  }
+}
+
+fn write_list(out: &mut String, open: &str, items: &[&str], separator: &str, close: &str) {
+  if !items.is_empty() {
+    out.push_str(open);
+    out.push_str(&items.join(separator));
+    out.push_str(close);
+  }
+}
+
+#[cfg(all(test, feature = "write"))]
+mod tests {
+  use super::super::tests::{build, read};
+  use super::super::Metadata;
+  use super::{SignatureFormatter, SignatureStyle};
+  use crate::metadata::errors::ReadErrorKind;
+  use crate::metadata::streams::tables::flags::*;
+  use crate::metadata::streams::tables::id::*;
+  use crate::metadata::streams::tables::rows::*;
+  use crate::metadata::streams::tables::signatures::{ArrayShape, TypeSig};
+
+  /// The type `Ns.Type` with the field `int32[,] Grid` and the method
+  /// `List<int32> Foo<T>(ref T x, params string[] rest)`, referring to `System.Object`,
+  /// ``System.Collections.Generic.List`1`` and `System.ParamArrayAttribute` in `mscorlib`.
+  fn fixture() -> Vec<u8> {
+    build(|md| {
+      let empty = md.strings.add("");
+      let mscorlib = md.tables.add_assembly_ref(AssemblyRefRow::new(
+        4,
+        0,
+        0,
+        0,
+        AssemblyFlags::empty(),
+        md.blobs.add(&[]),
+        md.strings.add("mscorlib"),
+        empty,
+        md.blobs.add(&[]),
+      ));
+      let mut type_ref = |namespace, name| {
+        md.tables.add_type_ref(TypeRefRow::new(
+          ResolutionScope::AssemblyRef(mscorlib),
+          md.strings.add(name),
+          md.strings.add(namespace),
+        ))
+      };
+      let object = type_ref("System", "Object");
+      let list = type_ref("System.Collections.Generic", "List`1");
+      let param_array = type_ref("System", "ParamArrayAttribute");
+      let ctor = md.tables.add_member_ref(MemberRefRow::new(
+        MemberRefParent::TypeRef(param_array),
+        md.strings.add(".ctor"),
+        md.blobs.add(&[0x20, 0x00, 0x01]),
+      ));
+
+      for (name, namespace) in [("<Module>", ""), ("Type", "Ns")] {
+        md.tables.add_type_def(TypeDefRow::new(
+          TypeAttributes::PUBLIC,
+          md.strings.add(name),
+          md.strings.add(namespace),
+          TypeDefOrRef::TypeRef(object),
+          RowId::new(1),
+          RowId::new(1),
+        ));
+      }
+
+      // int32[,] Grid
+      md.tables.add_field(FieldRow::new(
+        FieldAttributes::PUBLIC,
+        md.strings.add("Grid"),
+        md.blobs
+          .add(&[0x06, 0x14, 0x08, 0x02, 0x00, 0x02, 0x00, 0x00]),
+      ));
+      // List<int32> Foo<T>(ref T x, params string[] rest)
+      let method = md.tables.add_method_def(MethodDefRow::new(
+        0,
+        MethodImplAttributes::empty(),
+        MethodAttributes::PUBLIC | MethodAttributes::STATIC,
+        md.strings.add("Foo"),
+        md.blobs.add(&[
+          0x10,
+          0x01,
+          0x02,
+          0x15,
+          0x12,
+          (list.index() << 2) as u8 | 1,
+          0x01,
+          0x08,
+          0x10,
+          0x1e,
+          0x00,
+          0x1d,
+          0x0e,
+        ]),
+        RowId::new(1),
+      ));
+      md.tables.add_generic_param(GenericParamRow::new(
+        0,
+        GenericParamAttributes::empty(),
+        TypeOrMethodDef::MethodDef(method),
+        md.strings.add("T"),
+      ));
+      md.tables.add_param(ParamRow::new(
+        ParamAttributes::empty(),
+        1,
+        md.strings.add("x"),
+      ));
+      let rest = md.tables.add_param(ParamRow::new(
+        ParamAttributes::empty(),
+        2,
+        md.strings.add("rest"),
+      ));
+      md.tables.add_custom_attribute(CustomAttributeRow::new(
+        HasCustomAttribute::Param(rest),
+        CustomAttributeType::MemberRef(ctor),
+        md.blobs.add(&[0x01, 0x00, 0x00, 0x00]),
+      ));
+    })
+  }
+
+  /// Formats the method, field and type of the fixture.
+  fn format(
+    metadata: &Metadata<'_>,
+    style: SignatureStyle,
+    qualified: bool,
+  ) -> (String, String, String) {
+    let ty = metadata.type_def(RowId::new(2)).unwrap();
+    let method = metadata.method_def(RowId::new(1)).unwrap();
+    let field = metadata.field(RowId::new(1)).unwrap();
+    let mut formatter = SignatureFormatter::new(metadata, style);
+
+    formatter.set_qualified(qualified);
+    (
+      formatter.format_method(&method).unwrap(),
+      formatter.format_field(&field).unwrap(),
+      formatter.format_type_def(&ty).unwrap(),
+    )
+  }
+
+  /// `valuetype List<!1>`.
+  fn instance() -> TypeSig {
+    TypeSig::GenericInst {
+      value_type: true,
+      generic: TypeDefOrRef::TypeRef(RowId::new(2)),
+      args: vec![TypeSig::Var(1)],
+    }
+  }
+
+  /// `uint8*[1...4,-1...]`.
+  fn array() -> TypeSig {
+    TypeSig::Array(
+      Box::new(TypeSig::Ptr(Box::new(TypeSig::U1))),
+      ArrayShape {
+        rank: 2,
+        sizes: vec![4],
+        lower_bounds: vec![1, -1],
+      },
+    )
+  }
+
+  #[test]
+  fn test_format_csharp() {
+    let bytes = fixture();
+    let metadata = read(&bytes);
+    let mut formatter = SignatureFormatter::new(&metadata, SignatureStyle::CSharp);
+
+    assert_eq!(
+      (
+        "List<int> Foo<T>(ref T x, params string[] rest)".into(),
+        "int[,] Grid".into(),
+        "Type".into(),
+      ),
+      format(&metadata, SignatureStyle::CSharp, false)
+    );
+    assert_eq!(
+      (
+        "System.Collections.Generic.List<int> Foo<T>(ref T x, params string[] rest)".into(),
+        "int[,] Grid".into(),
+        "Ns.Type".into(),
+      ),
+      format(&metadata, SignatureStyle::CSharp, true)
+    );
+
+    formatter.set_generic_params(vec!["T"], vec![]);
+    assert_eq!(Ok("List<!1>".into()), formatter.format_type(&instance()));
+    assert_eq!(Ok("byte*[,]".into()), formatter.format_type(&array()));
+  }
+
+  #[test]
+  fn test_format_ilasm() {
+    let bytes = fixture();
+    let metadata = read(&bytes);
+    let formatter = SignatureFormatter::new(&metadata, SignatureStyle::ILAsm);
+
+    assert_eq!(
+      (
+        "class [mscorlib]System.Collections.Generic.List`1<int32> Foo<T>(!!T& x, string[] rest)"
+          .into(),
+        "int32[0...,0...] Grid".into(),
+        "Ns.Type".into(),
+      ),
+      format(&metadata, SignatureStyle::ILAsm, false)
+    );
+    assert_eq!(
+      Ok("valuetype [mscorlib]System.Collections.Generic.List`1<!1>".into()),
+      formatter.format_type(&instance())
+    );
+    assert_eq!(
+      Ok("uint8*[1...4,-1...]".into()),
+      formatter.format_type(&array())
+    );
+    assert_eq!(
+      Ok("[mscorlib]System.ParamArrayAttribute".into()),
+      formatter.format_type_def_or_ref(TypeDefOrRef::TypeRef(RowId::new(3)))
+    );
+  }
+
+  #[test]
+  fn test_format_documentation_id() {
+    let bytes = fixture();
+    let metadata = read(&bytes);
+    let formatter = SignatureFormatter::new(&metadata, SignatureStyle::DocumentationId);
+
+    assert_eq!(
+      (
+        "M:Ns.Type.Foo``1(``0@,System.String[])".into(),
+        "F:Ns.Type.Grid".into(),
+        "T:Ns.Type".into(),
+      ),
+      format(&metadata, SignatureStyle::DocumentationId, false)
+    );
+    assert_eq!(
+      Ok("System.Collections.Generic.List{`1}".into()),
+      formatter.format_type(&instance())
+    );
+    assert_eq!(
+      Ok("System.Byte*[1:4,-1:]".into()),
+      formatter.format_type(&array())
+    );
+  }
+
+  #[test]
+  fn test_format_missing_type_ref() {
+    let bytes = fixture();
+    let metadata = read(&bytes);
+    let missing = TypeDefOrRef::TypeRef(RowId::new(9));
+
+    for style in [
+      SignatureStyle::CSharp,
+      SignatureStyle::ILAsm,
+      SignatureStyle::DocumentationId,
+    ] {
+      let formatter = SignatureFormatter::new(&metadata, style);
+      let err = formatter.format_type(&TypeSig::Class(missing)).unwrap_err();
+
+      assert_eq!(ReadErrorKind::OutOfRange { index: 9, len: 3 }, err.kind());
+    }
+  }
+}
@@ -0,0 +1,532 @@
+//! Instantiation of generic types and methods.
+//!
+//! Signatures refer to the generic parameters of the enclosing type and method by number, with
+//! `VAR` and `MVAR` placeholders.  A [GenericContext] holds the type arguments of an instantiation,
+//! read from a `GENERICINST` type or the signature of a `MethodSpec` row, and substitutes them for
+//! the placeholders, so that the base type of `Derived<int>` declared as `Base<List<!0>>` becomes
+//! `Base<List<int>>`.
+
+use super::Metadata;
+use crate::metadata::errors::{ReadError, ReadErrorKind, ReadLocation};
+use crate::metadata::streams::tables::flags::GenericParamAttributes;
+use crate::metadata::streams::tables::id::{RowId, TypeDefOrRef, TypeOrMethodDef};
+use crate::metadata::streams::tables::rows::{
+  GenericParamConstraintRow, GenericParamRow, InterfaceImplRow, MethodSpecRow, TypeDefRow,
+};
+use crate::metadata::streams::tables::signatures::{MethodSig, MethodSpecSig, TypeSig};
+use crate::metadata::streams::tables::table::Row;
+use core::fmt;
+use std::boxed::Box;
+use std::vec::Vec;
+
+/// The type arguments substituted for the generic parameters of a type and a method.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct GenericContext {
+  /// The arguments for the generic parameters of the type, `!0`, `!1` and so on.
+  pub type_args: Vec<TypeSig>,
+  /// The arguments for the generic parameters of the method, `!!0`, `!!1` and so on.
+  pub method_args: Vec<TypeSig>,
+}
+
+impl GenericContext {
+  /// Creates a [GenericContext] with the given type arguments.
+  pub fn new(type_args: Vec<TypeSig>, method_args: Vec<TypeSig>) -> Self {
+    Self {
+      type_args,
+      method_args,
+    }
+  }
+
+  /// Creates a [GenericContext] with the type arguments of the given instantiated type.
+  ///
+  /// The context is empty if the type is not a `GENERICINST` type.
+  pub fn for_type(ty: &TypeSig) -> Self {
+    match ty {
+      TypeSig::GenericInst { args, .. } => Self::new(args.clone(), Vec::new()),
+      _ => Self::default(),
+    }
+  }
+
+  /// Returns whether the context has no type arguments.
+  pub fn is_empty(&self) -> bool {
+    self.type_args.is_empty() && self.method_args.is_empty()
+  }
+
+  /// Returns the given type with its generic parameters replaced by the type arguments.
+  ///
+  /// Parameters without an argument in the context are left in place.
+  pub fn substitute(&self, ty: &TypeSig) -> TypeSig {
+    let inner = |ty: &TypeSig| Box::new(self.substitute(ty));
+
+    match ty {
+      TypeSig::Var(number) => self.arg(&self.type_args, ty, *number),
+      TypeSig::MVar(number) => self.arg(&self.method_args, ty, *number),
+      TypeSig::Ptr(ty) => TypeSig::Ptr(inner(ty)),
+      TypeSig::ByRef(ty) => TypeSig::ByRef(inner(ty)),
+      TypeSig::SzArray(ty) => TypeSig::SzArray(inner(ty)),
+      TypeSig::Array(ty, shape) => TypeSig::Array(inner(ty), shape.clone()),
+      TypeSig::GenericInst {
+        value_type,
+        generic,
+        args,
+      } => TypeSig::GenericInst {
+        value_type: *value_type,
+        generic: *generic,
+        args: args.iter().map(|arg| self.substitute(arg)).collect(),
+      },
+      TypeSig::FnPtr(method) => TypeSig::FnPtr(Box::new(self.substitute_method(method))),
+      TypeSig::Modified(modifier, ty) => TypeSig::Modified(*modifier, inner(ty)),
+      ty => ty.clone(),
+    }
+  }
+
+  /// Returns the given method signature with its generic parameters replaced by the type
+  /// arguments.
+  pub fn substitute_method(&self, method: &MethodSig) -> MethodSig {
+    MethodSig {
+      return_type: self.substitute(&method.return_type),
+      params: method
+        .params
+        .iter()
+        .map(|param| self.substitute(param))
+        .collect(),
+      ..method.clone()
+    }
+  }
+
+  fn arg(&self, args: &[TypeSig], param: &TypeSig, number: u32) -> TypeSig {
+    match args.get(number as usize) {
+      Some(arg) => arg.clone(),
+      None => param.clone(),
+    }
+  }
+}
+
+/// A generic parameter of a type or method, a [GenericParamRow] together with the [Metadata] it
+/// belongs to.
+#[derive(Clone, Copy)]
+pub struct GenericParameter<'a> {
+  metadata: &'a Metadata<'a>,
+  row: GenericParamRow,
+}
+
+impl<'a> GenericParameter<'a> {
+  /// Creates a [GenericParameter] for a row read from the given metadata.
+  pub fn new(metadata: &'a Metadata<'a>, row: GenericParamRow) -> Self {
+    Self { metadata, row }
+  }
+
+  /// Returns the id of the [GenericParamRow].
+  pub fn id(&self) -> RowId<GenericParamRow> {
+    self.row.id()
+  }
+
+  /// Returns the [GenericParamRow].
+  pub fn row(&self) -> GenericParamRow {
+    self.row
+  }
+
+  /// Returns the number of the parameter, its position in the parameters of its owner.
+  pub fn number(&self) -> u16 {
+    self.row.number()
+  }
+
+  /// Returns the [GenericParamAttributes] of the parameter.
+  pub fn flags(&self) -> GenericParamAttributes {
+    self.row.flags()
+  }
+
+  /// Returns the name of the parameter.
+  pub fn name(&self) -> Result<&'a str, ReadError> {
+    self.metadata.strings().get_str(self.row.name())
+  }
+
+  /// Returns the type or method declaring the parameter.
+  pub fn owner(&self) -> TypeOrMethodDef {
+    self.row.owner()
+  }
+
+  /// Returns the variance of the parameter, [GenericParamAttributes::NONE],
+  /// [GenericParamAttributes::COVARIANT] or [GenericParamAttributes::CONTRAVARIANT].
+  pub fn variance(&self) -> GenericParamAttributes {
+    self.flags() & GenericParamAttributes::VARIANCE_MASK
+  }
+
+  /// Returns whether the parameter is covariant, `out`.
+  pub fn is_covariant(&self) -> bool {
+    self.variance() == GenericParamAttributes::COVARIANT
+  }
+
+  /// Returns whether the parameter is contravariant, `in`.
+  pub fn is_contravariant(&self) -> bool {
+    self.variance() == GenericParamAttributes::CONTRAVARIANT
+  }
+
+  /// Returns the special constraints of the parameter.
+  pub fn special_constraints(&self) -> GenericParamAttributes {
+    self.flags() & GenericParamAttributes::SPECIAL_CONSTRAINT_MASK
+  }
+
+  /// Returns whether the parameter is constrained to reference types, `class`.
+  pub fn has_reference_type_constraint(&self) -> bool {
+    self
+      .flags()
+      .contains(GenericParamAttributes::REFERENCE_TYPE_CONSTRAINT)
+  }
+
+  /// Returns whether the parameter is constrained to non-nullable value types, `struct`.
+  pub fn has_value_type_constraint(&self) -> bool {
+    self
+      .flags()
+      .contains(GenericParamAttributes::NOT_NULLABLE_VALUE_TYPE_CONSTRAINT)
+  }
+
+  /// Returns whether the parameter is constrained to types with a public default constructor,
+  /// `new()`.
+  pub fn has_default_constructor_constraint(&self) -> bool {
+    self
+      .flags()
+      .contains(GenericParamAttributes::DEFAULT_CONSTRUCTOR_CONSTRAINT)
+  }
+
+  /// Returns an iterator over the types the parameter is constrained to derive from or implement.
+  pub fn constraints(&self) -> impl Iterator<Item = TypeDefOrRef> + 'a {
+    self
+      .metadata
+      .owned_rows::<GenericParamConstraintRow>(self.id())
+      .map(|row| row.constraint())
+  }
+
+  /// Returns the types the parameter is constrained to, instantiated with the given context.
+  ///
+  /// Constraints may refer to the generic parameters of the owner, such as `T : IComparable<T>`.
+  pub fn constraint_types(&self, context: &GenericContext) -> Result<Vec<TypeSig>, ReadError> {
+    self
+      .constraints()
+      .map(|ty| Ok(context.substitute(&self.metadata.type_def_or_ref_sig(ty)?)))
+      .collect()
+  }
+}
+
+impl PartialEq for GenericParameter<'_> {
+  fn eq(&self, other: &Self) -> bool {
+    core::ptr::eq(self.metadata, other.metadata) && self.row == other.row
+  }
+}
+
+impl Eq for GenericParameter<'_> {}
+
+impl fmt::Debug for GenericParameter<'_> {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    f.debug_struct("GenericParameter")
+      .field("row", &self.row)
+      .finish_non_exhaustive()
+  }
+}
+
+impl<'a> Metadata<'a> {
+  /// Returns the generic parameter with the given [GenericParamRow].
+  pub fn generic_param(
+    &self,
+    id: RowId<GenericParamRow>,
+  ) -> Result<GenericParameter<'_>, ReadError> {
+    Ok(GenericParameter::new(
+      self,
+      self.tables().generic_params().get(id)?,
+    ))
+  }
+
+  /// Returns the type referred to by a `TypeDef`, `TypeRef` or `TypeSpec` row as a [TypeSig].
+  ///
+  /// The signature of a `TypeSpec` row is decoded, while `TypeDef` and `TypeRef` rows are returned
+  /// as a [TypeSig::Class], as whether they are value types depends on their base type.
+  pub fn type_def_or_ref_sig(&self, ty: TypeDefOrRef) -> Result<TypeSig, ReadError> {
+    match ty {
+      TypeDefOrRef::TypeSpec(id) => {
+        let row = self.tables().type_specs().get(id)?;
+
+        self.read_blob(row.signature())
+      }
+      ty => Ok(TypeSig::Class(ty)),
+    }
+  }
+
+  /// Returns the type arguments of the generic method instantiation of a `MethodSpec` row.
+  pub fn method_spec_sig(&self, id: RowId<MethodSpecRow>) -> Result<MethodSpecSig, ReadError> {
+    let row = self.tables().method_specs().get(id)?;
+
+    self.read_blob(row.instantiation())
+  }
+
+  /// Returns the base type of a type defined by the module, with the type arguments of the given
+  /// type substituted for the generic parameters of its declaration.
+  ///
+  /// Returns [None] if the type has no base type, or is not a `TypeDef` row or an instantiation of
+  /// one.
+  pub fn instantiated_base_type(&self, ty: &TypeSig) -> Result<Option<TypeSig>, ReadError> {
+    let Some(id) = defined_type(ty) else {
+      return Ok(None);
+    };
+    let Some(base) = self.type_def(id)?.base_type() else {
+      return Ok(None);
+    };
+    let base = self.type_def_or_ref_sig(base)?;
+
+    Ok(Some(GenericContext::for_type(ty).substitute(&base)))
+  }
+
+  /// Returns the interfaces directly implemented by a type defined by the module, with the type
+  /// arguments of the given type substituted for the generic parameters of its declaration.
+  ///
+  /// Returns no interfaces if the type is not a `TypeDef` row or an instantiation of one.
+  pub fn instantiated_interfaces(&self, ty: &TypeSig) -> Result<Vec<TypeSig>, ReadError> {
+    let Some(id) = defined_type(ty) else {
+      return Ok(Vec::new());
+    };
+    let context = GenericContext::for_type(ty);
+
+    self
+      .owned_rows::<InterfaceImplRow>(id)
+      .map(|row| Ok(context.substitute(&self.type_def_or_ref_sig(row.interface())?)))
+      .collect()
+  }
+
+  /// Returns the chain of base types of the given type, starting with its direct base type, each
+  /// instantiated with the type arguments of the type deriving from it.
+  ///
+  /// The chain ends with a type without a base type, or with the first type not defined by the
+  /// module, such as a `TypeRef` to `System.Object`.  Fails if the base types form a cycle.
+  pub fn base_types(&self, ty: &TypeSig) -> Result<Vec<TypeSig>, ReadError> {
+    let mut bases = Vec::<TypeSig>::new();
+
+    while let Some(base) = self.instantiated_base_type(bases.last().unwrap_or(ty))? {
+      if bases.len() >= self.tables().type_defs().len() {
+        return Err(ReadError::new(
+          ReadLocation::Table {
+            table: TypeDefRow::NAME,
+          },
+          self.tables().type_defs().offset(),
+          ReadErrorKind::Invalid,
+        ));
+      }
+
+      bases.push(base);
+    }
+
+    Ok(bases)
+  }
+}
+
+/// Returns the `TypeDef` row of a type or generic type instantiation.
+pub(crate) fn defined_type(ty: &TypeSig) -> Option<RowId<TypeDefRow>> {
+  match ty {
+    TypeSig::Class(TypeDefOrRef::TypeDef(id))
+    | TypeSig::ValueType(TypeDefOrRef::TypeDef(id))
+    | TypeSig::GenericInst {
+      generic: TypeDefOrRef::TypeDef(id),
+      ..
+    } => Some(*id),
+    _ => None,
+  }
+}
+
+#[cfg(all(test, feature = "write"))]
+mod tests {
+  use super::super::tests::{build, read};
+  use super::GenericContext;
+  use crate::metadata::errors::ReadErrorKind;
+  use crate::metadata::streams::tables::flags::*;
+  use crate::metadata::streams::tables::id::*;
+  use crate::metadata::streams::tables::rows::*;
+  use crate::metadata::streams::tables::signatures::{MethodSig, TypeSig};
+
+  /// ``class Base`1<+T> : object``, ``class Derived`1<U> : Base<List<!0>>, List<!0>`` where
+  /// `U : class, new(), Base<List<!0>>`, two types `A` and `B` deriving from each other, and the
+  /// method instantiation `M<!0, string>`.
+  fn fixture() -> Vec<u8> {
+    build(|md| {
+      let empty = md.strings.add("");
+      let object = md.tables.add_type_ref(TypeRefRow::new(
+        ResolutionScope::AssemblyRef(RowId::new(1)),
+        md.strings.add("Object"),
+        md.strings.add("System"),
+      ));
+      md.tables.add_type_ref(TypeRefRow::new(
+        ResolutionScope::AssemblyRef(RowId::new(1)),
+        md.strings.add("List`1"),
+        md.strings.add("System.Collections.Generic"),
+      ));
+
+      let base_of_list = md.tables.add_type_spec(TypeSpecRow::new(
+        md.blobs
+          .add(&[0x15, 0x12, 0x08, 0x01, 0x15, 0x12, 0x09, 0x01, 0x13, 0x00]),
+      ));
+      let list_of_var = md.tables.add_type_spec(TypeSpecRow::new(
+        md.blobs.add(&[0x15, 0x12, 0x09, 0x01, 0x13, 0x00]),
+      ));
+
+      for (name, extends) in [
+        ("<Module>", TypeDefOrRef::TypeDef(RowId::new(0))),
+        ("Base`1", TypeDefOrRef::TypeRef(object)),
+        ("Derived`1", TypeDefOrRef::TypeSpec(base_of_list)),
+        ("A", TypeDefOrRef::TypeDef(RowId::new(5))),
+        ("B", TypeDefOrRef::TypeDef(RowId::new(4))),
+      ] {
+        md.tables.add_type_def(TypeDefRow::new(
+          TypeAttributes::PUBLIC,
+          md.strings.add(name),
+          empty,
+          extends,
+          RowId::new(1),
+          RowId::new(1),
+        ));
+      }
+
+      md.tables.add_interface_impl(InterfaceImplRow::new(
+        RowId::new(3),
+        TypeDefOrRef::TypeSpec(list_of_var),
+      ));
+      md.tables.add_generic_param(GenericParamRow::new(
+        0,
+        GenericParamAttributes::COVARIANT,
+        TypeOrMethodDef::TypeDef(RowId::new(2)),
+        md.strings.add("T"),
+      ));
+      md.tables.add_generic_param(GenericParamRow::new(
+        0,
+        GenericParamAttributes::REFERENCE_TYPE_CONSTRAINT
+          | GenericParamAttributes::DEFAULT_CONSTRUCTOR_CONSTRAINT,
+        TypeOrMethodDef::TypeDef(RowId::new(3)),
+        md.strings.add("U"),
+      ));
+      md.tables
+        .add_generic_param_constraint(GenericParamConstraintRow::new(
+          RowId::new(2),
+          TypeDefOrRef::TypeSpec(base_of_list),
+        ));
+      md.tables.add_method_spec(MethodSpecRow::new(
+        MethodDefOrRef::MemberRef(RowId::new(1)),
+        md.blobs.add(&[0x0a, 0x02, 0x13, 0x00, 0x0e]),
+      ));
+    })
+  }
+
+  fn object() -> TypeSig {
+    TypeSig::Class(TypeDefOrRef::TypeRef(RowId::new(1)))
+  }
+
+  fn instance(generic: TypeDefOrRef, args: Vec<TypeSig>) -> TypeSig {
+    TypeSig::GenericInst {
+      value_type: false,
+      generic,
+      args,
+    }
+  }
+
+  fn list_of_int() -> TypeSig {
+    instance(TypeDefOrRef::TypeRef(RowId::new(2)), vec![TypeSig::I4])
+  }
+
+  fn derived_of_int() -> TypeSig {
+    instance(TypeDefOrRef::TypeDef(RowId::new(3)), vec![TypeSig::I4])
+  }
+
+  #[test]
+  fn test_base_types() {
+    let bytes = fixture();
+    let metadata = read(&bytes);
+    let base_of_list_of_int = instance(TypeDefOrRef::TypeDef(RowId::new(2)), vec![list_of_int()]);
+
+    // Derived<int> : Base<List<int>> : object
+    assert_eq!(
+      Ok(vec![base_of_list_of_int, object()]),
+      metadata.base_types(&derived_of_int())
+    );
+    assert_eq!(Ok(None), metadata.instantiated_base_type(&object()));
+  }
+
+  #[test]
+  fn test_base_types_cycle() {
+    let bytes = fixture();
+    let metadata = read(&bytes);
+    let a = TypeSig::Class(TypeDefOrRef::TypeDef(RowId::new(4)));
+
+    assert_eq!(
+      Some(ReadErrorKind::Invalid),
+      metadata.base_types(&a).err().map(|err| err.kind())
+    );
+  }
+
+  #[test]
+  fn test_instantiated_interfaces() {
+    let bytes = fixture();
+    let metadata = read(&bytes);
+
+    assert_eq!(
+      Ok(vec![list_of_int()]),
+      metadata.instantiated_interfaces(&derived_of_int())
+    );
+  }
+
+  #[test]
+  fn test_generic_params() {
+    let bytes = fixture();
+    let metadata = read(&bytes);
+    let t = metadata.generic_param(RowId::new(1)).unwrap();
+    let u = metadata.generic_param(RowId::new(2)).unwrap();
+    let base_of_list_of_int = instance(TypeDefOrRef::TypeDef(RowId::new(2)), vec![list_of_int()]);
+
+    assert_eq!(Ok("T"), t.name());
+    assert!(t.is_covariant());
+    assert!(!t.is_contravariant());
+    assert!(!t.has_reference_type_constraint());
+    assert_eq!(0, t.constraints().count());
+    assert_eq!(GenericParamAttributes::NONE, u.variance());
+    assert!(u.has_reference_type_constraint());
+    assert!(u.has_default_constructor_constraint());
+    assert!(!u.has_value_type_constraint());
+    assert_eq!(
+      Ok(vec![base_of_list_of_int]),
+      u.constraint_types(&GenericContext::for_type(&derived_of_int()))
+    );
+    assert_eq!(
+      ReadErrorKind::OutOfRange { index: 3, len: 2 },
+      metadata.generic_param(RowId::new(3)).unwrap_err().kind()
+    );
+  }
+
+  #[test]
+  fn test_substitute_method_spec() {
+    let bytes = fixture();
+    let metadata = read(&bytes);
+
+    // M<!0, string> with a type argument of int: !!0 (!0[], !!1&) becomes !0 (int[], string&), as
+    // the method arguments are already in the context of the caller.
+    let spec = metadata.method_spec_sig(RowId::new(1)).unwrap();
+    let context = GenericContext::new(vec![TypeSig::I4], spec.args);
+    let method = MethodSig {
+      calling_convention: CallingConvention::GENERIC,
+      generic_param_count: 2,
+      return_type: TypeSig::MVar(0),
+      params: vec![
+        TypeSig::SzArray(Box::new(TypeSig::Var(0))),
+        TypeSig::ByRef(Box::new(TypeSig::MVar(1))),
+      ],
+      sentinel: None,
+    };
+
+    assert_eq!([TypeSig::Var(0), TypeSig::String], context.method_args[..]);
+    assert_eq!(
+      MethodSig {
+        return_type: TypeSig::Var(0),
+        params: vec![
+          TypeSig::SzArray(Box::new(TypeSig::I4)),
+          TypeSig::ByRef(Box::new(TypeSig::String)),
+        ],
+        ..method.clone()
+      },
+      context.substitute_method(&method)
+    );
+    assert_eq!(TypeSig::MVar(2), context.substitute(&TypeSig::MVar(2)));
+  }
+}
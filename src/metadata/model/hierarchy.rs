@@ -0,0 +1,554 @@
+//! The graph of the base types and interfaces of the types defined by a module.
+//!
+//! A type records its base type in the `extends` column of its `TypeDef` row and the interfaces it
+//! implements in `InterfaceImpl` rows.  The reverse edges, from a type to the types deriving from
+//! it or implementing it, are indexed the first time one is looked up.  Only types defined by the
+//! module are followed, hierarchies spanning assemblies are walked with an `AssemblyResolver`.
+
+use super::generics::{defined_type, GenericContext};
+use super::members::MethodDefinition;
+use super::types::TypeDefinition;
+use super::Metadata;
+use crate::metadata::errors::{ReadError, ReadErrorKind, ReadLocation};
+use crate::metadata::streams::tables::flags::MethodAttributes;
+use crate::metadata::streams::tables::id::{MethodDefOrRef, RowId, TypeDefOrRef};
+use crate::metadata::streams::tables::rows::{
+  InterfaceImplRow, MethodDefRow, MethodImplRow, TypeDefRow,
+};
+use crate::metadata::streams::tables::signatures::{MethodSig, TypeSig};
+use crate::metadata::streams::tables::table::Row;
+use std::collections::{HashMap, HashSet};
+use std::vec::Vec;
+
+/// The index of the types deriving from and implementing the types of a module.
+#[derive(Debug, Default)]
+pub(crate) struct HierarchyIndex {
+  /// The indices of the types directly deriving from a type, by the index of the base type.
+  derived: HashMap<usize, Vec<usize>>,
+  /// The indices of the types and interfaces directly implementing an interface, by the index of
+  /// the interface.
+  implementers: HashMap<usize, Vec<usize>>,
+}
+
+impl HierarchyIndex {
+  fn new(metadata: &Metadata<'_>) -> Result<Self, ReadError> {
+    let mut index = Self::default();
+
+    for ty in metadata.type_defs() {
+      if let Some(base) = ty.base_type() {
+        if let Some(base) = metadata.defined_type_of(base)? {
+          index
+            .derived
+            .entry(base.index())
+            .or_default()
+            .push(ty.id().index());
+        }
+      }
+    }
+
+    for row in metadata.tables().interface_impls() {
+      if let Some(interface) = metadata.defined_type_of(row.interface())? {
+        index
+          .implementers
+          .entry(interface.index())
+          .or_default()
+          .push(row.class().index());
+      }
+    }
+
+    Ok(index)
+  }
+}
+
+impl<'a> Metadata<'a> {
+  /// Returns the types defined by the module directly deriving from the given type.
+  pub fn derived_types(&self, id: RowId<TypeDefRow>) -> Result<Vec<TypeDefinition<'_>>, ReadError> {
+    let derived = self.hierarchy()?.derived.get(&id.index());
+
+    self.type_defs_at(derived.into_iter().flatten().copied())
+  }
+
+  /// Returns the types defined by the module deriving from the given type, directly or through
+  /// other types, ordered by their [TypeDefRow].
+  pub fn all_derived_types(
+    &self,
+    id: RowId<TypeDefRow>,
+  ) -> Result<Vec<TypeDefinition<'_>>, ReadError> {
+    let index = self.hierarchy()?;
+    let derived = reachable(id.index(), |ty| index.derived.get(&ty));
+
+    self.type_defs_at(derived)
+  }
+
+  /// Returns the types defined by the module implementing the given interface, ordered by their
+  /// [TypeDefRow].
+  ///
+  /// Includes the types implementing an interface deriving from it and the types deriving from a
+  /// type implementing it, but not the interfaces themselves.
+  pub fn implementations(
+    &self,
+    interface: RowId<TypeDefRow>,
+  ) -> Result<Vec<TypeDefinition<'_>>, ReadError> {
+    let index = self.hierarchy()?;
+    let types = reachable(interface.index(), |ty| {
+      let derived = index.derived.get(&ty).into_iter().flatten();
+
+      Some(derived.chain(index.implementers.get(&ty).into_iter().flatten()))
+    });
+    let mut types = self.type_defs_at(types)?;
+
+    types.retain(|ty| !ty.is_interface());
+    Ok(types)
+  }
+
+  /// Returns the interfaces implemented by a type defined by the module, including the interfaces
+  /// implemented by its base types and inherited from other interfaces.
+  ///
+  /// The interfaces are instantiated with the type arguments of the given type as described in
+  /// [Metadata::instantiated_interfaces], and each is returned once.  Fails if interfaces inherit
+  /// from each other in a cycle that instantiates them with ever larger type arguments.
+  pub fn all_interfaces(&self, ty: &TypeSig) -> Result<Vec<TypeSig>, ReadError> {
+    let mut interfaces = Vec::<TypeSig>::new();
+    let mut pending = self.instantiated_interfaces(ty)?;
+
+    for base in self.base_types(ty)? {
+      pending.extend(self.instantiated_interfaces(&base)?);
+    }
+
+    // Every step down the inheritance of interfaces follows an `InterfaceImpl` row, a longer
+    // chain repeats one of them.
+    for _ in 0..=self.tables().interface_impls().len() {
+      let mut next = Vec::new();
+
+      for interface in pending {
+        if !interfaces.contains(&interface) {
+          next.extend(self.instantiated_interfaces(&interface)?);
+          interfaces.push(interface);
+        }
+      }
+
+      if next.is_empty() {
+        return Ok(interfaces);
+      }
+
+      pending = next;
+    }
+
+    Err(ReadError::new(
+      ReadLocation::Table {
+        table: InterfaceImplRow::NAME,
+      },
+      self.tables().interface_impls().offset(),
+      ReadErrorKind::Invalid,
+    ))
+  }
+
+  /// Returns the methods of the types defined by the module implementing the given method of an
+  /// interface.
+  ///
+  /// The implementation of the method in each of the [Metadata::implementations] of the interface
+  /// is looked up in the type and then in its base types: first the methods explicitly overriding
+  /// the method through `MethodImpl` rows, then the virtual methods with the same name and
+  /// signature, once the type arguments of the interface are substituted.  Each method is returned
+  /// once, a method of a base type is shared by the types deriving from it.
+  pub fn interface_method_implementations(
+    &self,
+    method: RowId<MethodDefRow>,
+  ) -> Result<Vec<MethodDefinition<'_>>, ReadError> {
+    let method = self.method_def(method)?;
+    let Some(interface) = method.declaring_type()? else {
+      return Ok(Vec::new());
+    };
+    let mut methods = Vec::new();
+
+    for ty in self.implementations(interface.id())? {
+      if let Some(implementation) = self.find_implementation(ty, interface, method)? {
+        if !methods.contains(&implementation) {
+          methods.push(implementation);
+        }
+      }
+    }
+
+    Ok(methods)
+  }
+
+  /// Returns the `TypeDef` row a `TypeDef` row or a `TypeSpec` row instantiating a generic type
+  /// refers to, or [None] if the type is not defined by the module.
+  pub(crate) fn defined_type_of(
+    &self,
+    ty: TypeDefOrRef,
+  ) -> Result<Option<RowId<TypeDefRow>>, ReadError> {
+    match ty {
+      TypeDefOrRef::TypeDef(id) if id.index() != 0 => Ok(Some(id)),
+      TypeDefOrRef::TypeSpec(_) => Ok(defined_type(&self.type_def_or_ref_sig(ty)?)),
+      _ => Ok(None),
+    }
+  }
+
+  /// Returns the implementation of a method of an interface in the given type or its base types.
+  fn find_implementation(
+    &self,
+    ty: TypeDefinition<'_>,
+    interface: TypeDefinition<'_>,
+    method: MethodDefinition<'_>,
+  ) -> Result<Option<MethodDefinition<'_>>, ReadError> {
+    let open = TypeSig::Class(TypeDefOrRef::TypeDef(ty.id()));
+    let signature = method.signature()?;
+    let name = method.name()?;
+    let mut signatures = Vec::new();
+
+    // The signature of the method in each instantiation of the interface the type implements.
+    for implemented in self.all_interfaces(&open)? {
+      if defined_type(&implemented) == Some(interface.id()) {
+        let signature = GenericContext::for_type(&implemented).substitute_method(&signature);
+
+        signatures.push(signature);
+      }
+    }
+
+    let mut types = self.base_types(&open)?;
+
+    types.insert(0, open);
+
+    for ty in types {
+      let Some(id) = defined_type(&ty) else {
+        break;
+      };
+
+      for row in self.owned_rows::<MethodImplRow>(id) {
+        if let MethodDefOrRef::MethodDef(body) = row.method_body() {
+          if self.method_def_of(row.method_declaration())? == Some(method) {
+            return self.method_def(body).map(Some);
+          }
+        }
+      }
+
+      let context = GenericContext::for_type(&ty);
+
+      for candidate in self.type_def(id)?.methods() {
+        if !candidate.flags().contains(MethodAttributes::VIRTUAL) || candidate.name()? != name {
+          continue;
+        }
+
+        let candidate_signature = context.substitute_method(&candidate.signature()?);
+
+        if signatures
+          .iter()
+          .any(|signature| same_signature(signature, &candidate_signature))
+        {
+          return Ok(Some(candidate));
+        }
+      }
+    }
+
+    Ok(None)
+  }
+
+  fn type_defs_at(
+    &self,
+    indices: impl IntoIterator<Item = usize>,
+  ) -> Result<Vec<TypeDefinition<'_>>, ReadError> {
+    indices
+      .into_iter()
+      .map(|index| self.type_def(RowId::new(index)))
+      .collect()
+  }
+
+  fn hierarchy(&self) -> Result<&HierarchyIndex, ReadError> {
+    if let Some(hierarchy) = self.indexes.hierarchy.get() {
+      return Ok(hierarchy);
+    }
+
+    let hierarchy = HierarchyIndex::new(self)?;
+
+    Ok(self.indexes.hierarchy.get_or_init(|| hierarchy))
+  }
+}
+
+/// Returns the sorted indices of the types reachable from the given type through the given edges,
+/// not including the type itself unless it is part of a cycle.
+fn reachable<'i, E: IntoIterator<Item = &'i usize>>(
+  start: usize,
+  edges: impl Fn(usize) -> Option<E>,
+) -> Vec<usize> {
+  let mut found = HashSet::new();
+  let mut pending = Vec::from([start]);
+
+  while let Some(ty) = pending.pop() {
+    for &next in edges(ty).into_iter().flatten() {
+      if found.insert(next) {
+        pending.push(next);
+      }
+    }
+  }
+
+  let mut found = found.into_iter().collect::<Vec<_>>();
+
+  found.sort_unstable();
+  found
+}
+
+/// Returns whether two method signatures match for the purpose of implementing or overriding a
+/// method.
+pub(crate) fn same_signature(a: &MethodSig, b: &MethodSig) -> bool {
+  a.generic_param_count == b.generic_param_count
+    && a.return_type == b.return_type
+    && a.params == b.params
+}
+
+#[cfg(all(test, feature = "write"))]
+mod tests {
+  use super::super::tests::{build, read};
+  use super::TypeDefinition;
+  use crate::metadata::errors::ReadErrorKind;
+  use crate::metadata::streams::tables::flags::*;
+  use crate::metadata::streams::tables::id::*;
+  use crate::metadata::streams::tables::rows::*;
+  use crate::metadata::streams::tables::signatures::TypeSig;
+  use crate::metadata::MetadataBuilder;
+
+  /// ``interface IBase`1<T> { void M(T); }``, `interface IDerived : IBase<int>`,
+  /// `class A : IDerived { void M(int); }`, `class B : A`,
+  /// `class C : IBase<string> { void IBase<string>.M(string); }` and `class D { void M(int); }`.
+  fn fixture() -> Vec<u8> {
+    build(|md| {
+      let empty = md.strings.add("");
+      let m = md.strings.add("M");
+      let object = md.tables.add_type_ref(TypeRefRow::new(
+        ResolutionScope::AssemblyRef(RowId::new(1)),
+        md.strings.add("Object"),
+        md.strings.add("System"),
+      ));
+      let base_of_int = md.tables.add_type_spec(TypeSpecRow::new(
+        md.blobs.add(&[0x15, 0x12, 0x08, 0x01, 0x08]),
+      ));
+      let base_of_string = md.tables.add_type_spec(TypeSpecRow::new(
+        md.blobs.add(&[0x15, 0x12, 0x08, 0x01, 0x0e]),
+      ));
+      let interface = TypeAttributes::PUBLIC | TypeAttributes::INTERFACE | TypeAttributes::ABSTRACT;
+      let class = TypeDefOrRef::TypeRef(object);
+
+      for (name, flags, extends, methods) in [
+        (
+          "<Module>",
+          TypeAttributes::empty(),
+          TypeDefOrRef::TypeDef(RowId::new(0)),
+          1,
+        ),
+        (
+          "IBase`1",
+          interface,
+          TypeDefOrRef::TypeDef(RowId::new(0)),
+          1,
+        ),
+        (
+          "IDerived",
+          interface,
+          TypeDefOrRef::TypeDef(RowId::new(0)),
+          2,
+        ),
+        ("A", TypeAttributes::PUBLIC, class, 2),
+        (
+          "B",
+          TypeAttributes::PUBLIC,
+          TypeDefOrRef::TypeDef(RowId::new(4)),
+          3,
+        ),
+        ("C", TypeAttributes::PUBLIC, class, 3),
+        ("D", TypeAttributes::PUBLIC, class, 4),
+      ] {
+        md.tables.add_type_def(TypeDefRow::new(
+          flags,
+          md.strings.add(name),
+          empty,
+          extends,
+          RowId::new(1),
+          RowId::new(methods),
+        ));
+      }
+
+      let virtual_method = MethodAttributes::VIRTUAL | MethodAttributes::NEW_SLOT;
+      let interface_signature = md.blobs.add(&[0x20, 0x01, 0x01, 0x13, 0x00]);
+
+      for (name, flags, signature) in [
+        (m, MethodAttributes::ABSTRACT, interface_signature),
+        (
+          m,
+          MethodAttributes::PUBLIC,
+          md.blobs.add(&[0x20, 0x01, 0x01, 0x08]),
+        ),
+        (
+          md.strings.add("IBase<System.String>.M"),
+          MethodAttributes::PRIVATE,
+          md.blobs.add(&[0x20, 0x01, 0x01, 0x0e]),
+        ),
+        (
+          m,
+          MethodAttributes::PUBLIC,
+          md.blobs.add(&[0x20, 0x01, 0x01, 0x08]),
+        ),
+      ] {
+        md.tables.add_method_def(MethodDefRow::new(
+          0,
+          MethodImplAttributes::empty(),
+          flags | virtual_method,
+          name,
+          signature,
+          RowId::new(1),
+        ));
+      }
+
+      for (class, interface) in [
+        (3, TypeDefOrRef::TypeSpec(base_of_int)),
+        (4, TypeDefOrRef::TypeDef(RowId::new(3))),
+        (6, TypeDefOrRef::TypeSpec(base_of_string)),
+      ] {
+        md.tables
+          .add_interface_impl(InterfaceImplRow::new(RowId::new(class), interface));
+      }
+
+      let declaration = md.tables.add_member_ref(MemberRefRow::new(
+        MemberRefParent::TypeSpec(base_of_string),
+        m,
+        interface_signature,
+      ));
+
+      md.tables.add_method_impl(MethodImplRow::new(
+        RowId::new(6),
+        MethodDefOrRef::MethodDef(RowId::new(3)),
+        MethodDefOrRef::MemberRef(declaration),
+      ));
+    })
+  }
+
+  fn ids(types: Vec<TypeDefinition<'_>>) -> Vec<usize> {
+    types.iter().map(|ty| ty.id().index()).collect()
+  }
+
+  #[test]
+  fn test_derived_types() {
+    let bytes = fixture();
+    let metadata = read(&bytes);
+
+    assert_eq!(Ok(vec![5]), metadata.derived_types(RowId::new(4)).map(ids));
+    assert_eq!(
+      Ok(vec![5]),
+      metadata.all_derived_types(RowId::new(4)).map(ids)
+    );
+    assert_eq!(Ok(vec![]), metadata.derived_types(RowId::new(5)).map(ids));
+  }
+
+  #[test]
+  fn test_implementations() {
+    let bytes = fixture();
+    let metadata = read(&bytes);
+
+    assert_eq!(
+      Ok(vec![4, 5, 6]),
+      metadata.implementations(RowId::new(2)).map(ids)
+    );
+    assert_eq!(
+      Ok(vec![4, 5]),
+      metadata.implementations(RowId::new(3)).map(ids)
+    );
+    assert!(metadata.type_def(RowId::new(2)).unwrap().is_interface());
+    assert!(!metadata.type_def(RowId::new(4)).unwrap().is_interface());
+  }
+
+  #[test]
+  fn test_all_interfaces() {
+    let bytes = fixture();
+    let metadata = read(&bytes);
+
+    assert_eq!(
+      Ok(vec![
+        TypeSig::Class(TypeDefOrRef::TypeDef(RowId::new(3))),
+        TypeSig::GenericInst {
+          value_type: false,
+          generic: TypeDefOrRef::TypeDef(RowId::new(2)),
+          args: vec![TypeSig::I4],
+        },
+      ]),
+      metadata.all_interfaces(&TypeSig::Class(TypeDefOrRef::TypeDef(RowId::new(5))))
+    );
+  }
+
+  #[test]
+  fn test_interface_method_implementations() {
+    let bytes = fixture();
+    let metadata = read(&bytes);
+    let methods = metadata
+      .interface_method_implementations(RowId::new(1))
+      .unwrap();
+
+    assert_eq!(
+      vec![2, 3],
+      methods
+        .iter()
+        .map(|method| method.id().index())
+        .collect::<Vec<_>>()
+    );
+  }
+
+  #[test]
+  fn test_cycles() {
+    let interface = TypeAttributes::PUBLIC | TypeAttributes::INTERFACE | TypeAttributes::ABSTRACT;
+    let add_type = |md: &mut MetadataBuilder, name, flags, extends| {
+      md.tables.add_type_def(TypeDefRow::new(
+        flags,
+        md.strings.add(name),
+        md.strings.add(""),
+        TypeDefOrRef::TypeDef(RowId::new(extends)),
+        RowId::new(1),
+        RowId::new(1),
+      ));
+    };
+
+    // `class X : Y`, `class Y : X`, `interface I : J`, `interface J : I` and
+    // `interface G`1<T> : G<G<T>>`.
+    let bytes = build(|md| {
+      add_type(md, "X", TypeAttributes::PUBLIC, 2);
+      add_type(md, "Y", TypeAttributes::PUBLIC, 1);
+      add_type(md, "I", interface, 0);
+      add_type(md, "J", interface, 0);
+      add_type(md, "G`1", interface, 0);
+
+      let g_of_g = md.tables.add_type_spec(TypeSpecRow::new(
+        md.blobs
+          .add(&[0x15, 0x12, 0x14, 0x01, 0x15, 0x12, 0x14, 0x01, 0x13, 0x00]),
+      ));
+
+      for (class, interface) in [
+        (3, TypeDefOrRef::TypeDef(RowId::new(4))),
+        (4, TypeDefOrRef::TypeDef(RowId::new(3))),
+        (5, TypeDefOrRef::TypeSpec(g_of_g)),
+      ] {
+        md.tables
+          .add_interface_impl(InterfaceImplRow::new(RowId::new(class), interface));
+      }
+    });
+    let metadata = read(&bytes);
+    let class = |index| TypeSig::Class(TypeDefOrRef::TypeDef(RowId::new(index)));
+    let g_of_int = TypeSig::GenericInst {
+      value_type: false,
+      generic: TypeDefOrRef::TypeDef(RowId::new(5)),
+      args: vec![TypeSig::I4],
+    };
+
+    assert_eq!(
+      Ok(vec![1, 2]),
+      metadata.all_derived_types(RowId::new(1)).map(ids)
+    );
+    assert_eq!(
+      Ok(vec![class(4), class(3)]),
+      metadata.all_interfaces(&class(3))
+    );
+    assert_eq!(
+      Some(ReadErrorKind::Invalid),
+      metadata
+        .all_interfaces(&g_of_int)
+        .err()
+        .map(|err| err.kind())
+    );
+  }
+}
@@ -0,0 +1,314 @@
+//! Lookups of the rows of tables keyed by the row they belong to.
+//!
+//! Tables such as `CustomAttribute` or `Constant` add information to rows of other tables, and
+//! are sorted by the column referring to that row when their bit in
+//! [MetadataTablesHeader::sorted] is set.  The rows belonging to a given row are then found with a
+//! binary search, and otherwise through a hash index built the first time the table is searched.
+//!
+//! [MetadataTablesHeader::sorted]: crate::metadata::headers::MetadataTablesHeader::sorted
+
+use super::hierarchy::HierarchyIndex;
+use super::names::NameIndex;
+use super::Metadata;
+use crate::metadata::streams::tables::id::{
+  HasConstant, HasCustomAttribute, HasDeclSecurity, HasFieldMarshal, HasSemantics, MemberForwarded,
+  RowId, TypeOrMethodDef,
+};
+use crate::metadata::streams::tables::rows::*;
+use crate::metadata::streams::tables::table::{RowRead, TableReader};
+use crate::metadata::streams::tables::TablesStream;
+use core::fmt;
+use core::ops::Range;
+use std::collections::HashMap;
+use std::sync::OnceLock;
+use std::vec::Vec;
+
+/// A row of a table keyed by the row it belongs to, its owner.
+pub trait OwnedRow: RowRead + 'static {
+  /// The type of the column referring to the owner.
+  type Owner: Copy + Eq;
+
+  /// Returns the table of this row type in the given stream.
+  fn table<'a, 'h>(tables: &'h TablesStream<'a>) -> TableReader<'a, 'h, Self>;
+
+  /// Returns the owner of the row.
+  fn owner(&self) -> Self::Owner;
+
+  /// Returns the value the table is sorted by for the given owner, the index of a row or the
+  /// encoded value of a coded index.
+  fn key(owner: Self::Owner) -> usize;
+}
+
+macro_rules! owned_row {
+  ($($row:ident . $column:ident : $owner:ty => $table:ident, $key:ident;)+) => {
+    $(
+      impl OwnedRow for $row {
+        type Owner = $owner;
+
+        fn table<'a, 'h>(tables: &'h TablesStream<'a>) -> TableReader<'a, 'h, Self> {
+          tables.$table()
+        }
+
+        fn owner(&self) -> Self::Owner {
+          $row::$column(*self)
+        }
+
+        fn key(owner: Self::Owner) -> usize {
+          owner.$key()
+        }
+      }
+    )+
+  };
+}
+
+owned_row! {
+  ClassLayoutRow.parent: RowId<TypeDefRow> => class_layouts, index;
+  ConstantRow.parent: HasConstant => constants, to_tag;
+  CustomAttributeRow.parent: HasCustomAttribute => custom_attributes, to_tag;
+  DeclSecurityRow.parent: HasDeclSecurity => decl_securities, to_tag;
+  EventPtrRow.event: RowId<EventRow> => event_ptrs, index;
+  FieldLayoutRow.field: RowId<FieldRow> => field_layouts, index;
+  FieldMarshalRow.parent: HasFieldMarshal => field_marshals, to_tag;
+  FieldPtrRow.field: RowId<FieldRow> => field_ptrs, index;
+  FieldRvaRow.field: RowId<FieldRow> => field_rvas, index;
+  GenericParamRow.owner: TypeOrMethodDef => generic_params, to_tag;
+  GenericParamConstraintRow.owner: RowId<GenericParamRow> => generic_param_constraints, index;
+  ImplMapRow.member_forwarded: MemberForwarded => impl_maps, to_tag;
+  InterfaceImplRow.class: RowId<TypeDefRow> => interface_impls, index;
+  MethodImplRow.class: RowId<TypeDefRow> => method_impls, index;
+  MethodPtrRow.method: RowId<MethodDefRow> => method_ptrs, index;
+  MethodSemanticsRow.association: HasSemantics => method_semantics, to_tag;
+  NestedClassRow.nested_class: RowId<TypeDefRow> => nested_classes, index;
+  ParamPtrRow.param: RowId<ParamRow> => param_ptrs, index;
+  PropertyPtrRow.property: RowId<PropertyRow> => property_ptrs, index;
+}
+
+/// The hash indexes of the tables that are not flagged as sorted, by table id, and the indexes of
+/// the names of types and of the types deriving from and implementing each type.
+pub(crate) struct Indexes {
+  tables: [OnceLock<HashMap<usize, Vec<usize>>>; 64],
+  pub(crate) names: OnceLock<NameIndex>,
+  pub(crate) hierarchy: OnceLock<HierarchyIndex>,
+}
+
+impl Indexes {
+  pub(crate) fn new() -> Self {
+    Self {
+      tables: core::array::from_fn(|_| OnceLock::new()),
+      names: OnceLock::new(),
+      hierarchy: OnceLock::new(),
+    }
+  }
+}
+
+impl fmt::Debug for Indexes {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    f.debug_struct("Indexes").finish_non_exhaustive()
+  }
+}
+
+impl<'a> Metadata<'a> {
+  /// Returns an iterator over the rows of a table belonging to the given owner.
+  ///
+  /// Uses a binary search if the table is flagged as sorted, and otherwise a hash index of the
+  /// table built by the first call.
+  pub fn owned_rows<R: OwnedRow>(&self, owner: R::Owner) -> impl Iterator<Item = R> + '_ {
+    let table = R::table(self.tables());
+
+    self
+      .owned_indices::<R>(owner)
+      .filter_map(move |index| table.get(RowId::new(index)).ok())
+  }
+
+  /// Returns the first row of a table belonging to the given owner, for tables with at most one
+  /// row per owner.
+  pub fn owned_row<R: OwnedRow>(&self, owner: R::Owner) -> Option<R> {
+    self.owned_rows(owner).next()
+  }
+
+  /// Returns the 1-based indices of the rows of a table belonging to the given owner.
+  pub(crate) fn owned_indices<R: OwnedRow>(
+    &self,
+    owner: R::Owner,
+  ) -> impl Iterator<Item = usize> + '_ {
+    let table = R::table(self.tables());
+    let key = R::key(owner);
+    let sorted = match table.is_sorted() {
+      true => table.equal_range(&key, |row| R::key(row.owner())).ok(),
+      false => None,
+    };
+    match sorted {
+      Some(range) => Indices::Range(range),
+      None => Indices::List(
+        self
+          .index(table)
+          .get(&key)
+          .map_or(&[][..], Vec::as_slice)
+          .iter(),
+      ),
+    }
+  }
+
+  fn index<R: OwnedRow>(&self, table: TableReader<'a, '_, R>) -> &HashMap<usize, Vec<usize>> {
+    self.indexes.tables[R::ID % 64].get_or_init(|| {
+      let mut index = HashMap::<_, Vec<_>>::new();
+
+      for (i, row) in table.into_iter().enumerate() {
+        index.entry(R::key(row.owner())).or_default().push(i + 1);
+      }
+
+      index
+    })
+  }
+}
+
+/// The 1-based indices of the rows found by a lookup.
+enum Indices<'a> {
+  Range(Range<usize>),
+  List(core::slice::Iter<'a, usize>),
+}
+
+impl Iterator for Indices<'_> {
+  type Item = usize;
+
+  fn next(&mut self) -> Option<Self::Item> {
+    match self {
+      Self::Range(range) => range.next(),
+      Self::List(list) => list.next().copied(),
+    }
+  }
+}
+
+#[cfg(all(test, feature = "write"))]
+mod tests {
+  use super::super::tests::{build, read};
+  use super::super::Metadata;
+  use crate::metadata::streams::tables::id::*;
+  use crate::metadata::streams::tables::rows::*;
+  use crate::metadata::MetadataBuilder;
+
+  fn assembly() -> HasCustomAttribute {
+    HasCustomAttribute::Assembly(RowId::new(1))
+  }
+
+  /// Three attributes on the assembly and one on each of the first 100 type definitions, with the
+  /// attributes of the assembly between those of the type definitions, and a type nested in
+  /// another.  The rows are sorted when written unless the stream is uncompressed.
+  fn fixture(sorted: bool) -> Vec<u8> {
+    fn add(md: &mut MetadataBuilder, parent: HasCustomAttribute, ty: CustomAttributeType) {
+      let value = md.blobs.add(&[]);
+
+      md.tables
+        .add_custom_attribute(CustomAttributeRow::new(parent, ty, value));
+    }
+
+    build(|md| {
+      for index in (1..=100).rev() {
+        if index % 50 == 0 {
+          add(
+            md,
+            assembly(),
+            CustomAttributeType::MemberRef(RowId::new(3 - index / 50)),
+          );
+        }
+
+        add(
+          md,
+          HasCustomAttribute::TypeDef(RowId::new(index)),
+          CustomAttributeType::MethodDef(RowId::new(index)),
+        );
+      }
+
+      add(
+        md,
+        assembly(),
+        CustomAttributeType::MemberRef(RowId::new(3)),
+      );
+
+      md.tables
+        .add_nested_class(NestedClassRow::new(RowId::new(3), RowId::new(2)));
+      md.tables.set_uncompressed(!sorted);
+    })
+  }
+
+  fn attributes(metadata: &Metadata<'_>, parent: HasCustomAttribute) -> Vec<CustomAttributeType> {
+    metadata
+      .owned_rows::<CustomAttributeRow>(parent)
+      .map(|row| row.attribute_type())
+      .collect()
+  }
+
+  fn check_owned_rows(metadata: &Metadata<'_>) {
+    assert_eq!(
+      [CustomAttributeType::MethodDef(RowId::new(42))],
+      attributes(metadata, HasCustomAttribute::TypeDef(RowId::new(42)))[..]
+    );
+    assert_eq!(
+      (1..=3)
+        .map(|index| CustomAttributeType::MemberRef(RowId::new(index)))
+        .collect::<Vec<_>>(),
+      attributes(metadata, assembly())
+    );
+  }
+
+  #[test]
+  fn test_owned_rows_sorted() {
+    let bytes = fixture(true);
+    let metadata = read(&bytes);
+
+    assert!(metadata.tables().custom_attributes().is_sorted());
+    check_owned_rows(&metadata);
+    assert_eq!(
+      vec![2, 3, 4],
+      metadata
+        .owned_indices::<CustomAttributeRow>(assembly())
+        .collect::<Vec<_>>()
+    );
+  }
+
+  #[test]
+  fn test_owned_rows_unsorted() {
+    let bytes = fixture(false);
+    let metadata = read(&bytes);
+
+    assert!(!metadata.tables().custom_attributes().is_sorted());
+    check_owned_rows(&metadata);
+
+    // The rows are kept in the order they were added, so the attributes of the assembly are found
+    // through the hash index rather than as a range of rows.
+    assert_eq!(
+      vec![1, 52, 103],
+      metadata
+        .owned_indices::<CustomAttributeRow>(assembly())
+        .collect::<Vec<_>>()
+    );
+  }
+
+  #[test]
+  fn test_owned_rows_of_missing_owner() {
+    for sorted in [true, false] {
+      let bytes = fixture(sorted);
+      let metadata = read(&bytes);
+
+      assert!(attributes(&metadata, HasCustomAttribute::TypeDef(RowId::new(101))).is_empty());
+      assert!(attributes(&metadata, HasCustomAttribute::Field(RowId::new(1))).is_empty());
+      assert_eq!(None, metadata.owned_row::<NestedClassRow>(RowId::new(0)));
+    }
+  }
+
+  #[test]
+  fn test_owned_row() {
+    for sorted in [true, false] {
+      let bytes = fixture(sorted);
+      let metadata = read(&bytes);
+
+      assert_eq!(
+        Some(RowId::new(2)),
+        metadata
+          .owned_row::<NestedClassRow>(RowId::new(3))
+          .map(|row| row.enclosing_class())
+      );
+      assert_eq!(None, metadata.owned_row::<NestedClassRow>(RowId::new(2)));
+    }
+  }
+}
@@ -0,0 +1,430 @@
+//! The layout of the instance fields of value types and formatted classes.
+//!
+//! Types with sequential layout place their fields in declaration order, each at the next offset
+//! aligned to the smaller of its alignment and the packing of the type, and types with explicit
+//! layout place them at the offsets of their `FieldLayout` rows.  The size of the type is then
+//! rounded up to its alignment and to at least the size of its `ClassLayout` row, as
+//! `Marshal.SizeOf` does for blittable types.
+//!
+//! Only blittable fields are supported: primitive numbers, pointers and value types defined by the
+//! module made of them.  The fields of base types other than `System.Object`, `System.ValueType`
+//! and `System.Enum` are not included.
+
+use super::members::FieldDefinition;
+use super::Metadata;
+use crate::metadata::errors::ReadError;
+use crate::metadata::streams::tables::flags::{FieldAttributes, TypeAttributes};
+use crate::metadata::streams::tables::id::{RowId, TypeDefOrRef};
+use crate::metadata::streams::tables::rows::{FieldRow, TypeDefRow};
+use crate::metadata::streams::tables::signatures::TypeSig;
+use core::fmt;
+use std::vec::Vec;
+
+/// The packing of types without a `ClassLayout` row or with a packing of zero.
+const DEFAULT_PACKING: u32 = 8;
+
+/// The size of native integers and pointers on the platform a layout is computed for.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PointerSize {
+  /// 4-byte pointers, as used by 32-bit processes.
+  Bits32,
+  /// 8-byte pointers, as used by 64-bit processes.
+  Bits64,
+}
+
+impl PointerSize {
+  /// Returns the size of a pointer in bytes.
+  pub fn bytes(self) -> u32 {
+    match self {
+      Self::Bits32 => 4,
+      Self::Bits64 => 8,
+    }
+  }
+}
+
+/// The layout of the instance fields of a type.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TypeLayout<'a> {
+  /// The size of the type in bytes.
+  pub size: u32,
+  /// The alignment of the type in bytes, when it is the field of another type.
+  pub alignment: u32,
+  /// The instance fields of the type, in declaration order.
+  pub fields: Vec<FieldOffset<'a>>,
+}
+
+/// The position of an instance field within its type.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FieldOffset<'a> {
+  /// The field.
+  pub field: FieldDefinition<'a>,
+  /// The offset of the field from the start of the type in bytes.
+  pub offset: u32,
+  /// The size of the field in bytes.
+  pub size: u32,
+  /// The alignment of the field in bytes, before packing.
+  pub alignment: u32,
+}
+
+impl<'a> Metadata<'a> {
+  /// Computes the layout of the instance fields of the given type, with pointers of the given
+  /// size.
+  ///
+  /// Fails like `Marshal.SizeOf` for types with automatic layout other than enums, and for fields
+  /// whose layout is not known, such as reference types or value types defined by other modules.
+  pub fn type_layout(
+    &self,
+    id: RowId<TypeDefRow>,
+    pointer_size: PointerSize,
+  ) -> Result<TypeLayout<'_>, LayoutError> {
+    self.layout(id, pointer_size.bytes(), &mut Vec::new())
+  }
+
+  fn layout(
+    &self,
+    id: RowId<TypeDefRow>,
+    pointer_size: u32,
+    enclosing: &mut Vec<RowId<TypeDefRow>>,
+  ) -> Result<TypeLayout<'_>, LayoutError> {
+    if enclosing.contains(&id) {
+      return Err(LayoutError::Recursive(id));
+    }
+
+    let ty = self.type_def(id)?;
+    let explicit = match ty.flags() & TypeAttributes::LAYOUT_MASK {
+      TypeAttributes::EXPLICIT_LAYOUT => true,
+      TypeAttributes::SEQUENTIAL_LAYOUT => false,
+      _ if self.is_enum(id)? => false,
+      _ => return Err(LayoutError::AutoLayout(id)),
+    };
+    let (packing, class_size) = match ty.layout() {
+      Some(layout) => (u32::from(layout.packing_size()), layout.class_size()),
+      None => (0, 0),
+    };
+    let packing = match packing {
+      0 => DEFAULT_PACKING,
+      packing => packing,
+    };
+    let mut layout = TypeLayout {
+      size: 0,
+      alignment: 1,
+      fields: Vec::new(),
+    };
+
+    enclosing.push(id);
+
+    for field in ty.fields() {
+      if field.flags().contains(FieldAttributes::STATIC) {
+        continue;
+      }
+
+      let ty = field.signature()?.ty;
+      let (size, alignment) = self.field_layout(field.id(), &ty, pointer_size, enclosing)?;
+      let packed = alignment.min(packing);
+      let offset = match explicit {
+        true => field
+          .offset()
+          .ok_or(LayoutError::MissingOffset(field.id()))?,
+        false => align_up(layout.size, packed),
+      };
+
+      layout.size = layout.size.max(offset.saturating_add(size));
+      layout.alignment = layout.alignment.max(packed);
+      layout.fields.push(FieldOffset {
+        field,
+        offset,
+        size,
+        alignment,
+      });
+    }
+
+    enclosing.pop();
+
+    // Empty types still take a byte.
+    layout.size = align_up(layout.size, layout.alignment)
+      .max(class_size)
+      .max(1);
+
+    Ok(layout)
+  }
+
+  /// Returns the size and alignment of a field of the given type.
+  fn field_layout(
+    &self,
+    field: RowId<FieldRow>,
+    ty: &TypeSig,
+    pointer_size: u32,
+    enclosing: &mut Vec<RowId<TypeDefRow>>,
+  ) -> Result<(u32, u32), LayoutError> {
+    Ok(match ty {
+      TypeSig::I1 | TypeSig::U1 => (1, 1),
+      TypeSig::I2 | TypeSig::U2 => (2, 2),
+      TypeSig::I4 | TypeSig::U4 | TypeSig::R4 => (4, 4),
+      TypeSig::I8 | TypeSig::U8 | TypeSig::R8 => (8, 8),
+      TypeSig::I | TypeSig::U | TypeSig::Ptr(_) | TypeSig::FnPtr(_) => (pointer_size, pointer_size),
+      TypeSig::ValueType(TypeDefOrRef::TypeDef(id)) => {
+        let layout = self.layout(*id, pointer_size, enclosing)?;
+
+        (layout.size, layout.alignment)
+      }
+      TypeSig::Modified(_, ty) => self.field_layout(field, ty, pointer_size, enclosing)?,
+      TypeSig::ValueType(_)
+      | TypeSig::GenericInst {
+        value_type: true, ..
+      }
+      | TypeSig::Var(_)
+      | TypeSig::MVar(_) => return Err(LayoutError::UnknownFieldType(field)),
+      _ => return Err(LayoutError::NotBlittable(field)),
+    })
+  }
+
+  /// Returns whether the given type derives from `System.Enum`.
+  fn is_enum(&self, id: RowId<TypeDefRow>) -> Result<bool, ReadError> {
+    let strings = self.strings();
+    let (namespace, name) = match self.type_def(id)?.base_type() {
+      Some(TypeDefOrRef::TypeDef(id)) => {
+        let row = self.tables().type_defs().get(id)?;
+
+        (row.namespace(), row.name())
+      }
+      Some(TypeDefOrRef::TypeRef(id)) => {
+        let row = self.tables().type_refs().get(id)?;
+
+        (row.namespace(), row.name())
+      }
+      _ => return Ok(false),
+    };
+
+    Ok(strings.get_str(namespace)? == "System" && strings.get_str(name)? == "Enum")
+  }
+}
+
+/// Returns the given offset rounded up to a multiple of the given alignment.
+fn align_up(offset: u32, alignment: u32) -> u32 {
+  offset.div_ceil(alignment).saturating_mul(alignment)
+}
+
+/// An error that occurred while computing the layout of a type.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum LayoutError {
+  /// A row or heap entry could not be read.
+  Read(ReadError),
+  /// The type has automatic layout, which is chosen by the runtime.
+  AutoLayout(RowId<TypeDefRow>),
+  /// A field of a type with explicit layout has no offset.
+  MissingOffset(RowId<FieldRow>),
+  /// A field has a type that is not blittable, such as a reference type or `bool`.
+  NotBlittable(RowId<FieldRow>),
+  /// The layout of the type of a field is not known, such as a value type defined by another
+  /// module, a generic value type or a generic parameter.
+  UnknownFieldType(RowId<FieldRow>),
+  /// A value type contains itself.
+  Recursive(RowId<TypeDefRow>),
+}
+
+impl fmt::Display for LayoutError {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match self {
+      Self::Read(err) => err.fmt(f),
+      Self::AutoLayout(id) => write!(f, "The type `{}` has automatic layout", id.index()),
+      Self::MissingOffset(id) => write!(f, "The field `{}` has no offset", id.index()),
+      Self::NotBlittable(id) => write!(f, "The field `{}` is not blittable", id.index()),
+      Self::UnknownFieldType(id) => {
+        write!(
+          f,
+          "The layout of the type of field `{}` is unknown",
+          id.index()
+        )
+      }
+      Self::Recursive(id) => write!(f, "The type `{}` contains itself", id.index()),
+    }
+  }
+}
+
+impl std::error::Error for LayoutError {
+  fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+    match self {
+      Self::Read(err) => Some(err),
+      _ => None,
+    }
+  }
+}
+
+impl From<ReadError> for LayoutError {
+  fn from(err: ReadError) -> Self {
+    Self::Read(err)
+  }
+}
+
+#[cfg(all(test, feature = "write"))]
+mod tests {
+  use super::super::tests::{build, read};
+  use super::super::Metadata;
+  use super::{LayoutError, PointerSize};
+  use crate::metadata::errors::ReadErrorKind;
+  use crate::metadata::streams::tables::flags::*;
+  use crate::metadata::streams::tables::id::*;
+  use crate::metadata::streams::tables::rows::*;
+
+  /// `struct Point { int X, Y; }`, `struct Mixed { byte A; double B; Point P; IntPtr Q; }`,
+  /// `[Pack = 1] struct Packed { byte A; int B; }`,
+  /// `[Explicit, Size = 16] struct Union { [0] int A; [2] byte B; }`,
+  /// `enum Color : short { Red }`, `struct Bad { string S; }`, `struct Auto { int X; }` with
+  /// automatic layout and `struct Loop { Loop L; }`.
+  fn fixture() -> Vec<u8> {
+    build(|md| {
+      let empty = md.strings.add("");
+      let mut type_ref = |name| {
+        md.tables.add_type_ref(TypeRefRow::new(
+          ResolutionScope::AssemblyRef(RowId::new(1)),
+          md.strings.add(name),
+          md.strings.add("System"),
+        ))
+      };
+      let value_type = TypeDefOrRef::TypeRef(type_ref("ValueType"));
+      let enum_type = TypeDefOrRef::TypeRef(type_ref("Enum"));
+      let sequential = TypeAttributes::SEQUENTIAL_LAYOUT;
+
+      for (name, flags, extends, fields) in [
+        (
+          "<Module>",
+          TypeAttributes::empty(),
+          TypeDefOrRef::TypeDef(RowId::new(0)),
+          1,
+        ),
+        ("Point", sequential, value_type, 1),
+        ("Mixed", sequential, value_type, 3),
+        ("Packed", sequential, value_type, 7),
+        ("Union", TypeAttributes::EXPLICIT_LAYOUT, value_type, 9),
+        ("Color", TypeAttributes::AUTO_LAYOUT, enum_type, 11),
+        ("Bad", sequential, value_type, 13),
+        ("Auto", TypeAttributes::AUTO_LAYOUT, value_type, 14),
+        ("Loop", sequential, value_type, 15),
+      ] {
+        md.tables.add_type_def(TypeDefRow::new(
+          flags | TypeAttributes::PUBLIC | TypeAttributes::SEALED,
+          md.strings.add(name),
+          empty,
+          extends,
+          RowId::new(fields),
+          RowId::new(1),
+        ));
+      }
+
+      let instance = FieldAttributes::PUBLIC;
+
+      for (flags, ty) in [
+        (instance, &[0x08][..]),
+        (instance, &[0x08]),
+        (instance, &[0x05]),
+        (instance, &[0x0d]),
+        (instance, &[0x11, 0x08]),
+        (instance, &[0x18]),
+        (instance, &[0x05]),
+        (instance, &[0x08]),
+        (instance, &[0x08]),
+        (instance, &[0x05]),
+        (FieldAttributes::SPECIAL_NAME, &[0x06]),
+        (
+          instance | FieldAttributes::STATIC | FieldAttributes::LITERAL,
+          &[0x11, 0x18],
+        ),
+        (instance, &[0x0e]),
+        (instance, &[0x08]),
+        (instance, &[0x11, 0x24]),
+      ] {
+        let signature = md.blobs.add(&[&[0x06][..], ty].concat());
+
+        md.tables
+          .add_field(FieldRow::new(flags, md.strings.add("F"), signature));
+      }
+
+      md.tables
+        .add_class_layout(ClassLayoutRow::new(1, 0, RowId::new(4)));
+      md.tables
+        .add_class_layout(ClassLayoutRow::new(0, 16, RowId::new(5)));
+      md.tables
+        .add_field_layout(FieldLayoutRow::new(0, RowId::new(9)));
+      md.tables
+        .add_field_layout(FieldLayoutRow::new(2, RowId::new(10)));
+    })
+  }
+
+  /// Returns the size, alignment and field offsets of the given type.
+  fn layout(
+    metadata: &Metadata<'_>,
+    ty: usize,
+    pointer_size: PointerSize,
+  ) -> Result<(u32, u32, Vec<u32>), LayoutError> {
+    let layout = metadata.type_layout(RowId::new(ty), pointer_size)?;
+    let offsets = layout.fields.iter().map(|field| field.offset).collect();
+
+    Ok((layout.size, layout.alignment, offsets))
+  }
+
+  #[test]
+  fn test_sequential_layout() {
+    let bytes = fixture();
+    let metadata = read(&bytes);
+    let mixed = metadata
+      .type_layout(RowId::new(3), PointerSize::Bits32)
+      .unwrap();
+
+    assert_eq!(
+      Ok((8, 4, vec![0, 4])),
+      layout(&metadata, 2, PointerSize::Bits64)
+    );
+    assert_eq!(
+      Ok((32, 8, vec![0, 8, 16, 24])),
+      layout(&metadata, 3, PointerSize::Bits64)
+    );
+    assert_eq!(
+      Ok((32, 8, vec![0, 8, 16, 24])),
+      layout(&metadata, 3, PointerSize::Bits32)
+    );
+    assert_eq!((4, 4), (mixed.fields[3].size, mixed.fields[3].alignment));
+    assert_eq!((8, 4), (mixed.fields[2].size, mixed.fields[2].alignment));
+  }
+
+  #[test]
+  fn test_packed_and_explicit_layout() {
+    let bytes = fixture();
+    let metadata = read(&bytes);
+
+    assert_eq!(
+      Ok((5, 1, vec![0, 1])),
+      layout(&metadata, 4, PointerSize::Bits64)
+    );
+    assert_eq!(
+      Ok((16, 4, vec![0, 2])),
+      layout(&metadata, 5, PointerSize::Bits64)
+    );
+  }
+
+  #[test]
+  fn test_enum_layout() {
+    let bytes = fixture();
+    let metadata = read(&bytes);
+
+    assert_eq!(
+      Ok((2, 2, vec![0])),
+      layout(&metadata, 6, PointerSize::Bits64)
+    );
+  }
+
+  #[test]
+  fn test_layout_errors() {
+    let bytes = fixture();
+    let metadata = read(&bytes);
+    let layout = |ty| layout(&metadata, ty, PointerSize::Bits64);
+
+    assert_eq!(Err(LayoutError::NotBlittable(RowId::new(13))), layout(7));
+    assert_eq!(Err(LayoutError::AutoLayout(RowId::new(8))), layout(8));
+    assert_eq!(Err(LayoutError::Recursive(RowId::new(9))), layout(9));
+    assert!(matches!(
+      layout(10),
+      Err(LayoutError::Read(err)) if err.kind() == ReadErrorKind::OutOfRange { index: 10, len: 9 }
+    ));
+  }
+}
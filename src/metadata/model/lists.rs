@@ -0,0 +1,306 @@
+//! Lookups of the rows owned through list columns and of their owners.
+//!
+//! Fields, methods, parameters, properties and events belong to the row of the `TypeDef`,
+//! `MethodDef`, `PropertyMap` or `EventMap` table whose list column starts the run of rows
+//! containing them, and the run ends where the list of the next row starts.  When the optional
+//! `FieldPtr`, `MethodPtr`, `ParamPtr`, `PropertyPtr` or `EventPtr` table of an uncompressed `#-`
+//! stream is present, lists index that table instead, whose rows refer to the owned rows in turn.
+//!
+//! The owner of a row is found with a binary search over the list column, as the lists of
+//! consecutive owners are in ascending order.
+
+use super::index::OwnedRow;
+use super::members::MethodDefinition;
+use super::Metadata;
+use crate::metadata::errors::ReadError;
+use crate::metadata::streams::tables::id::RowId;
+use crate::metadata::streams::tables::rows::*;
+use crate::metadata::streams::tables::table::{RowRead, TableReader};
+use crate::metadata::streams::tables::TablesStream;
+use core::ops::Range;
+
+/// A row of a table owned by a row of another table through a list column.
+pub trait ListRow: RowRead + 'static {
+  /// The row whose list column starts the run of rows it owns.
+  type Owner: RowRead + 'static;
+  /// The row of the table of pointers to the rows of this table.
+  type Ptr: OwnedRow<Owner = RowId<Self>>;
+
+  /// Returns the table of this row type in the given stream.
+  fn table<'a, 'h>(tables: &'h TablesStream<'a>) -> TableReader<'a, 'h, Self>;
+
+  /// Returns the table of the owners in the given stream.
+  fn owner_table<'a, 'h>(tables: &'h TablesStream<'a>) -> TableReader<'a, 'h, Self::Owner>;
+
+  /// Returns the start of the list of rows of this table owned by the given owner.
+  fn list(owner: &Self::Owner) -> RowId<Self>;
+}
+
+macro_rules! list_row {
+  ($($row:ident => $table:ident, $owner:ident . $list:ident => $owners:ident, $ptr:ident;)+) => {
+    $(
+      impl ListRow for $row {
+        type Owner = $owner;
+        type Ptr = $ptr;
+
+        fn table<'a, 'h>(tables: &'h TablesStream<'a>) -> TableReader<'a, 'h, Self> {
+          tables.$table()
+        }
+
+        fn owner_table<'a, 'h>(tables: &'h TablesStream<'a>) -> TableReader<'a, 'h, Self::Owner> {
+          tables.$owners()
+        }
+
+        fn list(owner: &Self::Owner) -> RowId<Self> {
+          owner.$list()
+        }
+      }
+    )+
+  };
+}
+
+list_row! {
+  EventRow => events, EventMapRow.event_list => event_maps, EventPtrRow;
+  FieldRow => fields, TypeDefRow.field_list => type_defs, FieldPtrRow;
+  MethodDefRow => method_defs, TypeDefRow.method_list => type_defs, MethodPtrRow;
+  ParamRow => params, MethodDefRow.param_list => method_defs, ParamPtrRow;
+  PropertyRow => properties, PropertyMapRow.property_list => property_maps, PropertyPtrRow;
+}
+
+impl<'a> Metadata<'a> {
+  /// Returns an iterator over the rows owned by the given row through its list column, such as the
+  /// fields of a type.
+  pub fn list<R: ListRow>(&self, owner: RowId<R::Owner>) -> impl Iterator<Item = R> + '_ {
+    let tables = self.tables();
+    let table = R::table(tables);
+    let ptrs = R::Ptr::table(tables);
+    let owners = R::owner_table(tables);
+    let len = match ptrs.is_empty() {
+      true => table.len(),
+      false => ptrs.len(),
+    };
+    let range = match owners.get(owner) {
+      Ok(row) => list_range(
+        len,
+        R::list(&row),
+        owners.get(owner.next()).ok().map(|next| R::list(&next)),
+      ),
+      Err(_) => 0..0,
+    };
+
+    range.map_while(move |index| {
+      let id = match ptrs.is_empty() {
+        true => RowId::new(index),
+        false => ptrs.get(RowId::new(index)).ok()?.owner(),
+      };
+
+      table.get(id).ok()
+    })
+  }
+
+  /// Returns the row owning the given row through its list column, such as the type declaring a
+  /// field.
+  ///
+  /// Returns [None] if the row is not in the list of any owner.
+  pub fn list_owner<R: ListRow>(&self, id: RowId<R>) -> Result<Option<R::Owner>, ReadError> {
+    let tables = self.tables();
+    let ptrs = R::Ptr::table(tables);
+    let (index, len) = match ptrs.is_empty() {
+      true => (Some(id.index()), R::table(tables).len()),
+      false => (self.owned_indices::<R::Ptr>(id).next(), ptrs.len()),
+    };
+    let Some(index) = index.filter(|index| (1..=len).contains(index)) else {
+      return Ok(None);
+    };
+
+    // The owner is the last row whose list starts at or before the row.
+    let owners = R::owner_table(tables);
+    let owner = owners.partition_point(|owner| R::list(owner).index() <= index)? - 1;
+
+    match owner {
+      0 => Ok(None),
+      owner => owners.get(RowId::new(owner)).map(Some),
+    }
+  }
+
+  /// Returns the method declaring the given parameter.
+  pub fn declaring_method(
+    &self,
+    param: RowId<ParamRow>,
+  ) -> Result<Option<MethodDefinition<'_>>, ReadError> {
+    let method = self.list_owner(param)?;
+
+    Ok(method.map(|row| MethodDefinition::new(self, row)))
+  }
+}
+
+/// Returns the 1-based indices of the list starting at `start`, in a table or table of pointers of
+/// `len` rows.
+///
+/// The list runs up to the start of the list of the next owner, `next`, or to the end of the table
+/// if the owner is the last row.
+fn list_range<R>(len: usize, start: RowId<R>, next: Option<RowId<R>>) -> Range<usize> {
+  let end = len + 1;
+  let start = start.index().clamp(1, end);
+  let next = next.map_or(end, |next| next.index().clamp(start, end));
+
+  start..next
+}
+
+#[cfg(all(test, feature = "write"))]
+mod tests {
+  use super::super::tests::{build, read};
+  use super::super::Metadata;
+  use crate::metadata::streams::tables::flags::*;
+  use crate::metadata::streams::tables::id::*;
+  use crate::metadata::streams::tables::rows::*;
+
+  /// `<Module>` owns nothing, A owns the first two fields, the first method and an event, B owns
+  /// the last field, the two other methods and a property.  The first method has two parameters.
+  ///
+  /// With the given `FieldPtr` rows the fields are listed through the pointers instead.
+  fn fixture(field_ptrs: &[usize]) -> Vec<u8> {
+    build(|md| {
+      let name = md.strings.add("M");
+      let signature = md.blobs.add(&[0x00, 0x00, 0x01]);
+
+      for (type_name, fields, methods) in [("<Module>", 1, 1), ("A", 1, 1), ("B", 3, 2)] {
+        md.tables.add_type_def(TypeDefRow::new(
+          TypeAttributes::empty(),
+          md.strings.add(type_name),
+          md.strings.add(""),
+          TypeDefOrRef::TypeDef(RowId::new(0)),
+          RowId::new(fields),
+          RowId::new(methods),
+        ));
+      }
+
+      for _ in 0..3 {
+        md.tables
+          .add_field(FieldRow::new(FieldAttributes::empty(), name, signature));
+      }
+
+      for params in [1, 3, 3] {
+        md.tables.add_method_def(MethodDefRow::new(
+          0,
+          MethodImplAttributes::empty(),
+          MethodAttributes::empty(),
+          name,
+          signature,
+          RowId::new(params),
+        ));
+      }
+
+      for sequence in 1..=2 {
+        md.tables
+          .add_param(ParamRow::new(ParamAttributes::empty(), sequence, name));
+      }
+
+      md.tables
+        .add_property_map(PropertyMapRow::new(RowId::new(3), RowId::new(1)));
+      md.tables.add_property(PropertyRow::new(
+        PropertyAttributes::empty(),
+        name,
+        signature,
+      ));
+      md.tables
+        .add_event_map(EventMapRow::new(RowId::new(2), RowId::new(1)));
+      md.tables.add_event(EventRow::new(
+        EventAttributes::empty(),
+        name,
+        TypeDefOrRef::TypeDef(RowId::new(0)),
+      ));
+
+      for &field in field_ptrs {
+        md.tables.add_field_ptr(FieldPtrRow::new(RowId::new(field)));
+      }
+    })
+  }
+
+  fn field_ids(ty: RowId<TypeDefRow>, metadata: &Metadata<'_>) -> Vec<usize> {
+    let ty = metadata.type_def(ty).unwrap();
+
+    ty.fields().map(|field| field.id().index()).collect()
+  }
+
+  fn declaring_type(field: usize, metadata: &Metadata<'_>) -> Option<usize> {
+    let field = metadata.field(RowId::new(field)).unwrap();
+
+    field.declaring_type().unwrap().map(|ty| ty.id().index())
+  }
+
+  #[test]
+  fn test_field_owners() {
+    let bytes = fixture(&[]);
+    let metadata = read(&bytes);
+
+    assert_eq!(vec![1, 2], field_ids(RowId::new(2), &metadata));
+    assert_eq!(Some(2), declaring_type(1, &metadata));
+    assert_eq!(Some(2), declaring_type(2, &metadata));
+    assert_eq!(Some(3), declaring_type(3, &metadata));
+  }
+
+  #[test]
+  fn test_field_owners_through_pointers() {
+    // The pointers are in reverse order, so A owns the last two fields and B the first.
+    let bytes = fixture(&[3, 2, 1]);
+    let metadata = read(&bytes);
+
+    assert_eq!(vec![3, 2], field_ids(RowId::new(2), &metadata));
+    assert_eq!(Some(2), declaring_type(3, &metadata));
+    assert_eq!(Some(2), declaring_type(2, &metadata));
+    assert_eq!(Some(3), declaring_type(1, &metadata));
+  }
+
+  #[test]
+  fn test_method_and_param_owners() {
+    let bytes = fixture(&[]);
+    let metadata = read(&bytes);
+    let a = metadata.type_def(RowId::new(2)).unwrap();
+    let b = metadata.type_def(RowId::new(3)).unwrap();
+
+    assert_eq!(
+      Ok(Some(a)),
+      metadata.method_def(RowId::new(1)).unwrap().declaring_type()
+    );
+    assert_eq!(
+      Ok(Some(b)),
+      metadata.method_def(RowId::new(3)).unwrap().declaring_type()
+    );
+    assert_eq!(
+      Ok(Some(RowId::new(1))),
+      metadata
+        .declaring_method(RowId::new(2))
+        .map(|method| method.map(|method| method.id()))
+    );
+  }
+
+  #[test]
+  fn test_property_and_event_owners() {
+    let bytes = fixture(&[]);
+    let metadata = read(&bytes);
+    let a = metadata.type_def(RowId::new(2)).unwrap();
+    let b = metadata.type_def(RowId::new(3)).unwrap();
+
+    assert_eq!(
+      Ok(Some(b)),
+      metadata.property(RowId::new(1)).unwrap().declaring_type()
+    );
+    assert_eq!(
+      Ok(Some(a)),
+      metadata.event(RowId::new(1)).unwrap().declaring_type()
+    );
+  }
+
+  #[test]
+  fn test_missing_rows() {
+    // The first pointer refers to a field that does not exist.
+    let bytes = fixture(&[9, 2, 1]);
+    let metadata = read(&bytes);
+
+    assert_eq!(Ok(None), metadata.list_owner::<ParamRow>(RowId::new(3)));
+    assert_eq!(Ok(None), metadata.list_owner::<FieldRow>(RowId::new(4)));
+    // The list of A stops at the missing field.
+    assert!(field_ids(RowId::new(2), &metadata).is_empty());
+  }
+}
@@ -0,0 +1,1025 @@
+//! Views of the methods, fields, properties and events defined by a module.
+
+use super::types::TypeDefinition;
+use super::Metadata;
+use crate::bytes::{ByteSliceExt, FromBytes};
+use crate::metadata::errors::{ReadError, ReadErrorKind, ReadLocation};
+use crate::metadata::streams::blobs::BlobId;
+use crate::metadata::streams::tables::flags::{
+  CallingConvention, ElementType, EventAttributes, FieldAttributes, MethodAttributes,
+  MethodImplAttributes, MethodSemanticsAttributes, PInvokeAttributes, ParamAttributes,
+  PropertyAttributes,
+};
+use crate::metadata::streams::tables::id::{
+  HasConstant, HasCustomAttribute, HasFieldMarshal, HasSemantics, MemberForwarded, MemberRefParent,
+  MethodDefOrRef, RowId, TypeDefOrRef, TypeOrMethodDef,
+};
+use crate::metadata::streams::tables::rows::{
+  ConstantRow, CustomAttributeRow, EventRow, FieldLayoutRow, FieldMarshalRow, FieldRow,
+  FieldRvaRow, GenericParamRow, ImplMapRow, MethodDefRow, MethodImplRow, MethodSemanticsRow,
+  ParamRow, PropertyRow,
+};
+use crate::metadata::streams::tables::signatures::{FieldSig, MethodSig, PropertySig, TypeSig};
+#[cfg(feature = "object")]
+use crate::pe::{body::MethodBody, ManagedPe};
+use core::fmt;
+use std::string::String;
+use std::vec::Vec;
+
+/// A method defined by a module, a [MethodDefRow] together with the [Metadata] it belongs to.
+#[derive(Clone, Copy)]
+pub struct MethodDefinition<'a> {
+  metadata: &'a Metadata<'a>,
+  row: MethodDefRow,
+}
+
+impl<'a> MethodDefinition<'a> {
+  /// Creates a [MethodDefinition] for a row read from the given metadata.
+  pub fn new(metadata: &'a Metadata<'a>, row: MethodDefRow) -> Self {
+    Self { metadata, row }
+  }
+
+  /// Returns the id of the [MethodDefRow].
+  pub fn id(&self) -> RowId<MethodDefRow> {
+    self.row.id()
+  }
+
+  /// Returns the [MethodDefRow].
+  pub fn row(&self) -> MethodDefRow {
+    self.row
+  }
+
+  /// Returns the [MethodAttributes] of the method.
+  pub fn flags(&self) -> MethodAttributes {
+    self.row.flags()
+  }
+
+  /// Returns the [MethodImplAttributes] of the method.
+  pub fn impl_flags(&self) -> MethodImplAttributes {
+    self.row.impl_flags()
+  }
+
+  /// Returns the name of the method.
+  pub fn name(&self) -> Result<&'a str, ReadError> {
+    self.metadata.strings().get_str(self.row.name())
+  }
+
+  /// Returns the type declaring the method, or [None] if it is not in the method list of any type.
+  pub fn declaring_type(&self) -> Result<Option<TypeDefinition<'a>>, ReadError> {
+    let ty = self.metadata.list_owner(self.id())?;
+
+    Ok(ty.map(|row| TypeDefinition::new(self.metadata, row)))
+  }
+
+  /// Returns the decoded signature of the method.
+  pub fn signature(&self) -> Result<MethodSig, ReadError> {
+    self.metadata.read_blob(self.row.signature())
+  }
+
+  /// Returns the return value of the method.
+  ///
+  /// Its [ParamRow] is the row with sequence number `0`, if the method has one.
+  pub fn return_parameter(&self) -> Result<Parameter<'a>, ReadError> {
+    let signature = self.signature()?;
+
+    Ok(Parameter {
+      metadata: self.metadata,
+      sequence: 0,
+      ty: signature.return_type,
+      row: self.param_rows().find(|row| row.sequence() == 0),
+    })
+  }
+
+  /// Returns the parameters of the method in order, each with its type from the signature and its
+  /// [ParamRow], if it has one.
+  pub fn parameters(&self) -> Result<Vec<Parameter<'a>>, ReadError> {
+    let signature = self.signature()?;
+    let mut params = signature
+      .params
+      .into_iter()
+      .zip(1..)
+      .map(|(ty, sequence)| Parameter {
+        metadata: self.metadata,
+        sequence,
+        ty,
+        row: None,
+      })
+      .collect::<Vec<_>>();
+
+    for row in self.param_rows() {
+      if let Some(param) = params.get_mut((row.sequence() as usize).wrapping_sub(1)) {
+        param.row = Some(row);
+      }
+    }
+
+    Ok(params)
+  }
+
+  /// Returns an iterator over the [ParamRow]s of the method.
+  pub fn param_rows(&self) -> impl Iterator<Item = ParamRow> + 'a {
+    self.metadata.list(self.id())
+  }
+
+  /// Returns the generic parameters of the method, ordered by their number.
+  pub fn generic_params(&self) -> Vec<GenericParamRow> {
+    self
+      .metadata
+      .generic_params(TypeOrMethodDef::MethodDef(self.id()))
+  }
+
+  /// Returns the platform invoke information of the method, or [None] if it is not implemented by
+  /// an unmanaged function.
+  pub fn pinvoke(&self) -> Result<Option<PInvoke<'a>>, ReadError> {
+    let member = MemberForwarded::MethodDef(self.id());
+    let tables = self.metadata.tables();
+    let strings = self.metadata.strings();
+    let Some(row) = self.metadata.owned_row::<ImplMapRow>(member) else {
+      return Ok(None);
+    };
+
+    let module = tables.module_refs().get(row.import_scope())?;
+
+    Ok(Some(PInvoke {
+      flags: row.mapping_flags(),
+      import_name: strings.get_str(row.import_name())?,
+      module: strings.get_str(module.name())?,
+    }))
+  }
+
+  /// Returns an iterator over the methods of interfaces or base types this method explicitly
+  /// overrides, as recorded by the `MethodImpl` rows of its declaring type.
+  pub fn overrides(&self) -> Result<impl Iterator<Item = MethodDefOrRef> + 'a, ReadError> {
+    let metadata = self.metadata;
+    let body = MethodDefOrRef::MethodDef(self.id());
+    let class = metadata.list_owner(self.id())?;
+    let rows = class
+      .into_iter()
+      .flat_map(move |class| metadata.owned_rows::<MethodImplRow>(class.id()));
+
+    Ok(
+      rows
+        .filter(move |row| row.method_body() == body)
+        .map(|row| row.method_declaration()),
+    )
+  }
+
+  /// Returns the relative virtual address of the method body, or [None] if the method has no body.
+  pub fn rva(&self) -> Option<u32> {
+    Some(self.row.rva()).filter(|&rva| rva != 0)
+  }
+
+  /// Returns the body of the method read from the given PE file, or [None] if the method has no
+  /// body or it could not be read.
+  #[cfg(feature = "object")]
+  pub fn body<'b>(&self, pe: &ManagedPe<'b>) -> Option<MethodBody<'b>> {
+    pe.method_body(self.rva()?)
+  }
+
+  /// Returns an iterator over the custom attributes applied to the method.
+  pub fn custom_attributes(&self) -> impl Iterator<Item = CustomAttributeRow> + 'a {
+    self
+      .metadata
+      .custom_attributes(HasCustomAttribute::MethodDef(self.id()))
+  }
+}
+
+/// A parameter or return value of a method.
+#[derive(Clone)]
+pub struct Parameter<'a> {
+  metadata: &'a Metadata<'a>,
+  sequence: u16,
+  ty: TypeSig,
+  row: Option<ParamRow>,
+}
+
+impl<'a> Parameter<'a> {
+  /// Returns the 1-based position of the parameter, `0` for the return value.
+  pub fn sequence(&self) -> u16 {
+    self.sequence
+  }
+
+  /// Returns the type of the parameter from the method signature.
+  pub fn ty(&self) -> &TypeSig {
+    &self.ty
+  }
+
+  /// Returns the [ParamRow] of the parameter, which is optional.
+  pub fn row(&self) -> Option<ParamRow> {
+    self.row
+  }
+
+  /// Returns the [ParamAttributes] of the parameter, empty if it has no [ParamRow].
+  pub fn flags(&self) -> ParamAttributes {
+    self.row.map_or(ParamAttributes::empty(), |row| row.flags())
+  }
+
+  /// Returns the name of the parameter, empty if it has no [ParamRow].
+  pub fn name(&self) -> Result<&'a str, ReadError> {
+    match self.row {
+      Some(row) => self.metadata.strings().get_str(row.name()),
+      None => Ok(""),
+    }
+  }
+
+  /// Returns the default value of the parameter.
+  pub fn constant(&self) -> Result<Option<Constant>, ReadError> {
+    match self.row {
+      Some(row) => self.metadata.constant(HasConstant::Param(row.id())),
+      None => Ok(None),
+    }
+  }
+
+  /// Returns the native type the parameter is marshalled as, a `MarshalSpec` blob.
+  pub fn marshal(&self) -> Result<Option<&'a [u8]>, ReadError> {
+    match self.row {
+      Some(row) => self.metadata.marshal(HasFieldMarshal::Param(row.id())),
+      None => Ok(None),
+    }
+  }
+}
+
+impl fmt::Debug for Parameter<'_> {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    f.debug_struct("Parameter")
+      .field("sequence", &self.sequence)
+      .field("ty", &self.ty)
+      .field("row", &self.row)
+      .finish_non_exhaustive()
+  }
+}
+
+/// The unmanaged function implementing a method, from its `ImplMap` row.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PInvoke<'a> {
+  /// The [PInvokeAttributes] of the import.
+  pub flags: PInvokeAttributes,
+  /// The name of the function.
+  pub import_name: &'a str,
+  /// The name of the library exporting the function.
+  pub module: &'a str,
+}
+
+/// A field defined by a module, a [FieldRow] together with the [Metadata] it belongs to.
+#[derive(Clone, Copy)]
+pub struct FieldDefinition<'a> {
+  metadata: &'a Metadata<'a>,
+  row: FieldRow,
+}
+
+impl<'a> FieldDefinition<'a> {
+  /// Creates a [FieldDefinition] for a row read from the given metadata.
+  pub fn new(metadata: &'a Metadata<'a>, row: FieldRow) -> Self {
+    Self { metadata, row }
+  }
+
+  /// Returns the id of the [FieldRow].
+  pub fn id(&self) -> RowId<FieldRow> {
+    self.row.id()
+  }
+
+  /// Returns the [FieldRow].
+  pub fn row(&self) -> FieldRow {
+    self.row
+  }
+
+  /// Returns the [FieldAttributes] of the field.
+  pub fn flags(&self) -> FieldAttributes {
+    self.row.flags()
+  }
+
+  /// Returns the name of the field.
+  pub fn name(&self) -> Result<&'a str, ReadError> {
+    self.metadata.strings().get_str(self.row.name())
+  }
+
+  /// Returns the type declaring the field, or [None] if it is not in the field list of any type.
+  pub fn declaring_type(&self) -> Result<Option<TypeDefinition<'a>>, ReadError> {
+    let ty = self.metadata.list_owner(self.id())?;
+
+    Ok(ty.map(|row| TypeDefinition::new(self.metadata, row)))
+  }
+
+  /// Returns the decoded signature of the field.
+  pub fn signature(&self) -> Result<FieldSig, ReadError> {
+    self.metadata.read_blob(self.row.signature())
+  }
+
+  /// Returns the value of a literal field.
+  pub fn constant(&self) -> Result<Option<Constant>, ReadError> {
+    self.metadata.constant(HasConstant::Field(self.id()))
+  }
+
+  /// Returns the native type the field is marshalled as, a `MarshalSpec` blob.
+  pub fn marshal(&self) -> Result<Option<&'a [u8]>, ReadError> {
+    self.metadata.marshal(HasFieldMarshal::Field(self.id()))
+  }
+
+  /// Returns the relative virtual address of the initial value of the field, or [None] if it has
+  /// none.
+  pub fn rva(&self) -> Option<u32> {
+    self
+      .metadata
+      .owned_row::<FieldRvaRow>(self.id())
+      .map(|row| row.rva())
+  }
+
+  /// Returns the data starting at the initial value of the field in the given PE file, whose
+  /// length depends on the type of the field.
+  #[cfg(feature = "object")]
+  pub fn data<'b>(&self, pe: &ManagedPe<'b>) -> Option<&'b [u8]> {
+    pe.data_at(self.rva()?)
+  }
+
+  /// Returns the explicit offset of the field within its type, or [None] if it has none.
+  pub fn offset(&self) -> Option<u32> {
+    self
+      .metadata
+      .owned_row::<FieldLayoutRow>(self.id())
+      .map(|row| row.offset())
+  }
+
+  /// Returns an iterator over the custom attributes applied to the field.
+  pub fn custom_attributes(&self) -> impl Iterator<Item = CustomAttributeRow> + 'a {
+    self
+      .metadata
+      .custom_attributes(HasCustomAttribute::Field(self.id()))
+  }
+}
+
+/// A property defined by a module, a [PropertyRow] together with the [Metadata] it belongs to.
+#[derive(Clone, Copy)]
+pub struct PropertyDefinition<'a> {
+  metadata: &'a Metadata<'a>,
+  row: PropertyRow,
+}
+
+impl<'a> PropertyDefinition<'a> {
+  /// Creates a [PropertyDefinition] for a row read from the given metadata.
+  pub fn new(metadata: &'a Metadata<'a>, row: PropertyRow) -> Self {
+    Self { metadata, row }
+  }
+
+  /// Returns the id of the [PropertyRow].
+  pub fn id(&self) -> RowId<PropertyRow> {
+    self.row.id()
+  }
+
+  /// Returns the [PropertyRow].
+  pub fn row(&self) -> PropertyRow {
+    self.row
+  }
+
+  /// Returns the [PropertyAttributes] of the property.
+  pub fn flags(&self) -> PropertyAttributes {
+    self.row.flags()
+  }
+
+  /// Returns the name of the property.
+  pub fn name(&self) -> Result<&'a str, ReadError> {
+    self.metadata.strings().get_str(self.row.name())
+  }
+
+  /// Returns the type declaring the property, or [None] if it is not in the property list of any
+  /// `PropertyMap` row.
+  pub fn declaring_type(&self) -> Result<Option<TypeDefinition<'a>>, ReadError> {
+    let map = self.metadata.list_owner(self.id())?;
+
+    map
+      .map(|row| self.metadata.type_def(row.parent()))
+      .transpose()
+  }
+
+  /// Returns the decoded signature of the property.
+  pub fn signature(&self) -> Result<PropertySig, ReadError> {
+    self.metadata.read_blob(self.row.signature())
+  }
+
+  /// Returns the default value of the property.
+  pub fn constant(&self) -> Result<Option<Constant>, ReadError> {
+    self.metadata.constant(HasConstant::Property(self.id()))
+  }
+
+  /// Returns the `get` accessor of the property.
+  pub fn getter(&self) -> Option<MethodDefinition<'a>> {
+    self.accessors(MethodSemanticsAttributes::GETTER).next()
+  }
+
+  /// Returns the `set` accessor of the property.
+  pub fn setter(&self) -> Option<MethodDefinition<'a>> {
+    self.accessors(MethodSemanticsAttributes::SETTER).next()
+  }
+
+  /// Returns an iterator over the other methods associated with the property.
+  pub fn other_methods(&self) -> impl Iterator<Item = MethodDefinition<'a>> + 'a {
+    self.accessors(MethodSemanticsAttributes::OTHER)
+  }
+
+  /// Returns an iterator over the custom attributes applied to the property.
+  pub fn custom_attributes(&self) -> impl Iterator<Item = CustomAttributeRow> + 'a {
+    self
+      .metadata
+      .custom_attributes(HasCustomAttribute::Property(self.id()))
+  }
+
+  fn accessors(
+    &self,
+    semantics: MethodSemanticsAttributes,
+  ) -> impl Iterator<Item = MethodDefinition<'a>> + 'a {
+    self
+      .metadata
+      .accessors(HasSemantics::Property(self.id()), semantics)
+  }
+}
+
+/// An event defined by a module, an [EventRow] together with the [Metadata] it belongs to.
+#[derive(Clone, Copy)]
+pub struct EventDefinition<'a> {
+  metadata: &'a Metadata<'a>,
+  row: EventRow,
+}
+
+impl<'a> EventDefinition<'a> {
+  /// Creates an [EventDefinition] for a row read from the given metadata.
+  pub fn new(metadata: &'a Metadata<'a>, row: EventRow) -> Self {
+    Self { metadata, row }
+  }
+
+  /// Returns the id of the [EventRow].
+  pub fn id(&self) -> RowId<EventRow> {
+    self.row.id()
+  }
+
+  /// Returns the [EventRow].
+  pub fn row(&self) -> EventRow {
+    self.row
+  }
+
+  /// Returns the [EventAttributes] of the event.
+  pub fn flags(&self) -> EventAttributes {
+    self.row.flags()
+  }
+
+  /// Returns the name of the event.
+  pub fn name(&self) -> Result<&'a str, ReadError> {
+    self.metadata.strings().get_str(self.row.name())
+  }
+
+  /// Returns the type declaring the event, or [None] if it is not in the event list of any
+  /// `EventMap` row.
+  pub fn declaring_type(&self) -> Result<Option<TypeDefinition<'a>>, ReadError> {
+    let map = self.metadata.list_owner(self.id())?;
+
+    map
+      .map(|row| self.metadata.type_def(row.parent()))
+      .transpose()
+  }
+
+  /// Returns the delegate type of the event.
+  pub fn event_type(&self) -> TypeDefOrRef {
+    self.row.event_type()
+  }
+
+  /// Returns the `add` accessor of the event.
+  pub fn add_method(&self) -> Option<MethodDefinition<'a>> {
+    self.accessors(MethodSemanticsAttributes::ADD_ON).next()
+  }
+
+  /// Returns the `remove` accessor of the event.
+  pub fn remove_method(&self) -> Option<MethodDefinition<'a>> {
+    self.accessors(MethodSemanticsAttributes::REMOVE_ON).next()
+  }
+
+  /// Returns the `raise` accessor of the event.
+  pub fn raise_method(&self) -> Option<MethodDefinition<'a>> {
+    self.accessors(MethodSemanticsAttributes::FIRE).next()
+  }
+
+  /// Returns an iterator over the other methods associated with the event.
+  pub fn other_methods(&self) -> impl Iterator<Item = MethodDefinition<'a>> + 'a {
+    self.accessors(MethodSemanticsAttributes::OTHER)
+  }
+
+  /// Returns an iterator over the custom attributes applied to the event.
+  pub fn custom_attributes(&self) -> impl Iterator<Item = CustomAttributeRow> + 'a {
+    self
+      .metadata
+      .custom_attributes(HasCustomAttribute::Event(self.id()))
+  }
+
+  fn accessors(
+    &self,
+    semantics: MethodSemanticsAttributes,
+  ) -> impl Iterator<Item = MethodDefinition<'a>> + 'a {
+    self
+      .metadata
+      .accessors(HasSemantics::Event(self.id()), semantics)
+  }
+}
+
+macro_rules! member_traits {
+  ($($name:ident),+ $(,)?) => {
+    $(
+      impl fmt::Debug for $name<'_> {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+          f.debug_struct(stringify!($name))
+            .field("row", &self.row)
+            .finish_non_exhaustive()
+        }
+      }
+
+      impl PartialEq for $name<'_> {
+        fn eq(&self, other: &Self) -> bool {
+          core::ptr::eq(self.metadata, other.metadata) && self.row == other.row
+        }
+      }
+
+      impl Eq for $name<'_> {}
+    )+
+  };
+}
+
+member_traits!(
+  MethodDefinition,
+  FieldDefinition,
+  PropertyDefinition,
+  EventDefinition,
+);
+
+/// The value of a literal field, or the default value of a parameter or property.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Constant {
+  /// A `bool`.
+  Boolean(bool),
+  /// A `char`.
+  Char(u16),
+  /// An `sbyte`.
+  I1(i8),
+  /// A `byte`.
+  U1(u8),
+  /// A `short`.
+  I2(i16),
+  /// A `ushort`.
+  U2(u16),
+  /// An `int`.
+  I4(i32),
+  /// A `uint`.
+  U4(u32),
+  /// A `long`.
+  I8(i64),
+  /// A `ulong`.
+  U8(u64),
+  /// A `float`.
+  R4(f32),
+  /// A `double`.
+  R8(f64),
+  /// A string, with unpaired surrogates replaced.
+  String(String),
+  /// A `null` reference.
+  Null,
+}
+
+impl Constant {
+  /// Decodes the value of a [ConstantRow] of the given kind.
+  ///
+  /// Returns [None] if the kind is not valid for a constant or the value is too short.
+  pub fn from_bytes(kind: ElementType, value: &[u8]) -> Option<Self> {
+    let offset = &mut 0;
+
+    Some(match kind {
+      ElementType::ELEMENT_TYPE_BOOLEAN => Self::Boolean(value.read::<u8>(offset)? != 0),
+      ElementType::ELEMENT_TYPE_CHAR => Self::Char(value.read(offset)?),
+      ElementType::ELEMENT_TYPE_I1 => Self::I1(value.read(offset)?),
+      ElementType::ELEMENT_TYPE_U1 => Self::U1(value.read(offset)?),
+      ElementType::ELEMENT_TYPE_I2 => Self::I2(value.read(offset)?),
+      ElementType::ELEMENT_TYPE_U2 => Self::U2(value.read(offset)?),
+      ElementType::ELEMENT_TYPE_I4 => Self::I4(value.read(offset)?),
+      ElementType::ELEMENT_TYPE_U4 => Self::U4(value.read(offset)?),
+      ElementType::ELEMENT_TYPE_I8 => Self::I8(value.read(offset)?),
+      ElementType::ELEMENT_TYPE_U8 => Self::U8(value.read(offset)?),
+      ElementType::ELEMENT_TYPE_R4 => Self::R4(f32::from_bits(value.read(offset)?)),
+      ElementType::ELEMENT_TYPE_R8 => Self::R8(f64::from_bits(value.read(offset)?)),
+      ElementType::ELEMENT_TYPE_STRING if value.len().is_multiple_of(2) => {
+        let units = value
+          .chunks_exact(2)
+          .map(|unit| u16::from_le_bytes([unit[0], unit[1]]))
+          .collect::<Vec<_>>();
+
+        Self::String(String::from_utf16_lossy(&units))
+      }
+      ElementType::ELEMENT_TYPE_CLASS => Self::Null,
+      _ => return None,
+    })
+  }
+}
+
+impl<'a> Metadata<'a> {
+  /// Returns the constant owned by the given row.
+  fn constant(&self, parent: HasConstant) -> Result<Option<Constant>, ReadError> {
+    let Some(row) = self.owned_row::<ConstantRow>(parent) else {
+      return Ok(None);
+    };
+
+    let value = self.blobs().get(row.value())?;
+
+    Constant::from_bytes(row.kind(), value)
+      .map(Some)
+      .ok_or_else(|| invalid_blob(row.value()))
+  }
+
+  /// Returns the `MarshalSpec` blob owned by the given row.
+  fn marshal(&self, parent: HasFieldMarshal) -> Result<Option<&'a [u8]>, ReadError> {
+    self
+      .owned_row::<FieldMarshalRow>(parent)
+      .map(|row| self.blobs().get(row.native_type()))
+      .transpose()
+  }
+
+  /// Returns the methods associated with the given property or event with the given semantics.
+  fn accessors(
+    &'a self,
+    association: HasSemantics,
+    semantics: MethodSemanticsAttributes,
+  ) -> impl Iterator<Item = MethodDefinition<'a>> + 'a {
+    self
+      .owned_rows::<MethodSemanticsRow>(association)
+      .filter(move |row| row.semantics().contains(semantics))
+      .filter_map(|row| self.method_def(row.method()).ok())
+  }
+
+  /// Returns the method defined by the module a `MethodDef` or `MemberRef` row refers to, or [None]
+  /// if the reference is to a field or to a method of another module.
+  ///
+  /// A member reference is matched by name and signature to the methods of the type it refers to,
+  /// or of the generic type a `TypeSpec` parent instantiates.
+  pub fn method_def_of(
+    &self,
+    method: MethodDefOrRef,
+  ) -> Result<Option<MethodDefinition<'_>>, ReadError> {
+    let row = match method {
+      MethodDefOrRef::MethodDef(id) => return self.method_def(id).map(Some),
+      MethodDefOrRef::MemberRef(id) => self.tables().member_refs().get(id)?,
+    };
+    let parent = match row.class() {
+      MemberRefParent::TypeDef(id) => self.defined_type_of(TypeDefOrRef::TypeDef(id))?,
+      MemberRefParent::TypeSpec(id) => self.defined_type_of(TypeDefOrRef::TypeSpec(id))?,
+      MemberRefParent::MethodDef(id) => return self.method_def(id).map(Some),
+      _ => None,
+    };
+    let Some(parent) = parent else {
+      return Ok(None);
+    };
+
+    if self.blobs().get(row.signature())?.first() == Some(&CallingConvention::FIELD.bits()) {
+      return Ok(None);
+    }
+
+    let name = self.strings().get_str(row.name())?;
+    let signature = self.read_blob::<MethodSig>(row.signature())?;
+
+    for method in self.type_def(parent)?.methods() {
+      if method.name()? == name && method.signature()? == signature {
+        return Ok(Some(method));
+      }
+    }
+
+    Ok(None)
+  }
+
+  /// Decodes the signature stored in the blob with the given id.
+  pub(crate) fn read_blob<T: for<'b> FromBytes<'b>>(&self, id: BlobId) -> Result<T, ReadError> {
+    let blob = self.blobs().get(id)?;
+
+    blob.read(&mut 0).ok_or_else(|| invalid_blob(id))
+  }
+}
+
+/// Returns the error for a blob whose contents could not be decoded.
+fn invalid_blob(id: BlobId) -> ReadError {
+  ReadError::new(ReadLocation::Stream("#Blob"), id.0, ReadErrorKind::Invalid)
+}
+
+#[cfg(all(test, feature = "write"))]
+mod tests {
+  use super::super::tests::{build, read};
+  use super::Constant;
+  use crate::metadata::errors::ReadErrorKind;
+  use crate::metadata::streams::tables::flags::*;
+  use crate::metadata::streams::tables::id::*;
+  use crate::metadata::streams::tables::rows::*;
+  use crate::metadata::streams::tables::signatures::TypeSig;
+
+  /// `<Module>` and a class `C` with the methods `M`, the accessors of a property `P` and an event
+  /// `E`, and the P/Invoke method `MessageBox`, and the literal fields `F` and `S`.
+  fn fixture() -> Vec<u8> {
+    build(|md| {
+      let handler = md.tables.add_type_ref(TypeRefRow::new(
+        ResolutionScope::AssemblyRef(RowId::new(1)),
+        md.strings.add("EventHandler"),
+        md.strings.add("System"),
+      ));
+
+      for name in ["<Module>", "C"] {
+        md.tables.add_type_def(TypeDefRow::new(
+          TypeAttributes::empty(),
+          md.strings.add(name),
+          md.strings.add(""),
+          TypeDefOrRef::TypeDef(RowId::new(0)),
+          RowId::new(1),
+          RowId::new(1),
+        ));
+      }
+
+      // static int32 M(string, int32), instance int32 get_P(), instance void set_P(int32),
+      // instance void add_E(class EventHandler), instance void remove_E(class EventHandler) and
+      // static void MessageBox().
+      let methods: [(&str, &[u8], usize); 6] = [
+        ("M", &[0x00, 0x02, 0x08, 0x0e, 0x08], 1),
+        ("get_P", &[0x20, 0x00, 0x08], 3),
+        ("set_P", &[0x20, 0x01, 0x01, 0x08], 3),
+        ("add_E", &[0x20, 0x01, 0x01, 0x12, 0x05], 3),
+        ("remove_E", &[0x20, 0x01, 0x01, 0x12, 0x05], 3),
+        ("MessageBox", &[0x00, 0x00, 0x01], 3),
+      ];
+
+      for (name, signature, params) in methods {
+        md.tables.add_method_def(MethodDefRow::new(
+          0,
+          MethodImplAttributes::empty(),
+          MethodAttributes::empty(),
+          md.strings.add(name),
+          md.blobs.add(signature),
+          RowId::new(params),
+        ));
+      }
+
+      md.tables.add_param(ParamRow::new(
+        ParamAttributes::empty(),
+        0,
+        md.strings.add(""),
+      ));
+      md.tables.add_param(ParamRow::new(
+        ParamAttributes::HAS_DEFAULT,
+        1,
+        md.strings.add("s"),
+      ));
+      md.tables.add_constant(ConstantRow::new(
+        ElementType::ELEMENT_TYPE_CLASS,
+        0,
+        HasConstant::Param(RowId::new(2)),
+        md.blobs.add(&[0; 4]),
+      ));
+      md.tables.add_method_impl(MethodImplRow::new(
+        RowId::new(2),
+        MethodDefOrRef::MethodDef(RowId::new(2)),
+        MethodDefOrRef::MemberRef(RowId::new(1)),
+      ));
+
+      let user32 = md
+        .tables
+        .add_module_ref(ModuleRefRow::new(md.strings.add("user32.dll")));
+
+      md.tables.add_impl_map(ImplMapRow::new(
+        PInvokeAttributes::CHAR_SET_UNICODE,
+        MemberForwarded::MethodDef(RowId::new(6)),
+        md.strings.add("MessageBoxW"),
+        user32,
+      ));
+
+      for (name, signature) in [("F", [0x06, 0x08]), ("S", [0x06, 0x0e])] {
+        md.tables.add_field(FieldRow::new(
+          FieldAttributes::LITERAL,
+          md.strings.add(name),
+          md.blobs.add(&signature),
+        ));
+      }
+
+      md.tables.add_constant(ConstantRow::new(
+        ElementType::ELEMENT_TYPE_I4,
+        0,
+        HasConstant::Field(RowId::new(1)),
+        md.blobs.add(&42i32.to_le_bytes()),
+      ));
+      md.tables.add_constant(ConstantRow::new(
+        ElementType::ELEMENT_TYPE_STRING,
+        0,
+        HasConstant::Field(RowId::new(2)),
+        md.blobs.add(b"h\0i\0"),
+      ));
+      md.tables.add_field_marshal(FieldMarshalRow::new(
+        HasFieldMarshal::Field(RowId::new(1)),
+        md.blobs.add(&[0x14]),
+      ));
+      md.tables
+        .add_field_rva(FieldRvaRow::new(0x2000, RowId::new(1)));
+      md.tables
+        .add_field_layout(FieldLayoutRow::new(4, RowId::new(1)));
+
+      md.tables
+        .add_property_map(PropertyMapRow::new(RowId::new(2), RowId::new(1)));
+      md.tables.add_property(PropertyRow::new(
+        PropertyAttributes::empty(),
+        md.strings.add("P"),
+        md.blobs.add(&[0x28, 0x00, 0x08]),
+      ));
+      md.tables
+        .add_event_map(EventMapRow::new(RowId::new(2), RowId::new(1)));
+      md.tables.add_event(EventRow::new(
+        EventAttributes::empty(),
+        md.strings.add("E"),
+        TypeDefOrRef::TypeRef(handler),
+      ));
+
+      let semantics = [
+        (
+          MethodSemanticsAttributes::GETTER,
+          2,
+          HasSemantics::Property(RowId::new(1)),
+        ),
+        (
+          MethodSemanticsAttributes::SETTER,
+          3,
+          HasSemantics::Property(RowId::new(1)),
+        ),
+        (
+          MethodSemanticsAttributes::ADD_ON,
+          4,
+          HasSemantics::Event(RowId::new(1)),
+        ),
+        (
+          MethodSemanticsAttributes::REMOVE_ON,
+          5,
+          HasSemantics::Event(RowId::new(1)),
+        ),
+      ];
+
+      for (semantics, method, association) in semantics {
+        md.tables.add_method_semantics(MethodSemanticsRow::new(
+          semantics,
+          RowId::new(method),
+          association,
+        ));
+      }
+    })
+  }
+
+  #[test]
+  fn test_method_parameters() {
+    let bytes = fixture();
+    let metadata = read(&bytes);
+    let methods = metadata
+      .type_def(RowId::new(2))
+      .unwrap()
+      .methods()
+      .collect::<Vec<_>>();
+
+    assert_eq!(6, methods.len());
+    assert_eq!(Ok("M"), methods[0].name());
+    assert!(!methods[0].signature().unwrap().has_this());
+
+    let ret = methods[0].return_parameter().unwrap();
+    let params = methods[0].parameters().unwrap();
+
+    assert_eq!((0, &TypeSig::I4), (ret.sequence(), ret.ty()));
+    assert!(ret.row().is_some());
+    assert_eq!(2, params.len());
+    assert_eq!(
+      (Ok("s"), &TypeSig::String),
+      (params[0].name(), params[0].ty())
+    );
+    assert_eq!(Ok(Some(Constant::Null)), params[0].constant());
+    assert_eq!(ParamAttributes::HAS_DEFAULT, params[0].flags());
+    assert_eq!((Ok(""), None), (params[1].name(), params[1].row()));
+    assert_eq!(0, methods[1].parameters().unwrap().len());
+    assert_eq!(0, methods[1].param_rows().count());
+  }
+
+  #[test]
+  fn test_method_pinvoke_and_overrides() {
+    let bytes = fixture();
+    let metadata = read(&bytes);
+    let methods = metadata
+      .type_def(RowId::new(2))
+      .unwrap()
+      .methods()
+      .collect::<Vec<_>>();
+
+    assert_eq!(Ok(None), methods[0].pinvoke());
+    assert_eq!(
+      Ok("user32.dll"),
+      methods[5].pinvoke().map(|p| p.unwrap().module)
+    );
+    assert_eq!(
+      Ok("MessageBoxW"),
+      methods[5].pinvoke().map(|p| p.unwrap().import_name)
+    );
+    assert_eq!(
+      [MethodDefOrRef::MemberRef(RowId::new(1))],
+      methods[1].overrides().unwrap().collect::<Vec<_>>()[..]
+    );
+    assert_eq!(0, methods[0].overrides().unwrap().count());
+    assert_eq!(None, methods[0].rva());
+  }
+
+  #[test]
+  fn test_fields() {
+    let bytes = fixture();
+    let metadata = read(&bytes);
+    let fields = metadata
+      .type_def(RowId::new(2))
+      .unwrap()
+      .fields()
+      .collect::<Vec<_>>();
+
+    assert_eq!(2, fields.len());
+    assert_eq!(TypeSig::I4, fields[0].signature().unwrap().ty);
+    assert_eq!(Ok(Some(Constant::I4(42))), fields[0].constant());
+    assert_eq!(
+      Ok(Some(Constant::String("hi".into()))),
+      fields[1].constant()
+    );
+    assert_eq!(Ok(Some(&[0x14][..])), fields[0].marshal());
+    assert_eq!(Ok(None), fields[1].marshal());
+    assert_eq!(
+      (Some(0x2000), Some(4)),
+      (fields[0].rva(), fields[0].offset())
+    );
+    assert_eq!((None, None), (fields[1].rva(), fields[1].offset()));
+  }
+
+  #[test]
+  fn test_property_and_event() {
+    let bytes = fixture();
+    let metadata = read(&bytes);
+    let class = metadata.type_def(RowId::new(2)).unwrap();
+    let methods = class.methods().collect::<Vec<_>>();
+    let property = class.properties().next().unwrap();
+    let event = class.events().next().unwrap();
+
+    assert_eq!(Ok("P"), property.name());
+    assert_eq!(TypeSig::I4, property.signature().unwrap().ty);
+    assert_eq!(Some(methods[1]), property.getter());
+    assert_eq!(Some(methods[2]), property.setter());
+    assert_eq!(0, property.other_methods().count());
+    assert_eq!(Ok("E"), event.name());
+    assert_eq!(TypeDefOrRef::TypeRef(RowId::new(1)), event.event_type());
+    assert_eq!(Some(methods[3]), event.add_method());
+    assert_eq!(Some(methods[4]), event.remove_method());
+    assert_eq!(None, event.raise_method());
+  }
+
+  #[test]
+  fn test_missing_rows() {
+    let bytes = build(|md| {
+      md.tables.add_method_def(MethodDefRow::new(
+        0,
+        MethodImplAttributes::empty(),
+        MethodAttributes::STATIC | MethodAttributes::P_INVOKE_IMPL,
+        md.strings.add("M"),
+        md.blobs.add(&[0x00, 0x00, 0x01]),
+        RowId::new(1),
+      ));
+      // The module of the import does not exist.
+      md.tables.add_impl_map(ImplMapRow::new(
+        PInvokeAttributes::empty(),
+        MemberForwarded::MethodDef(RowId::new(1)),
+        md.strings.add("M"),
+        RowId::new(1),
+      ));
+    });
+    let metadata = read(&bytes);
+    let method = metadata.method_def(RowId::new(1)).unwrap();
+
+    assert_eq!(
+      ReadErrorKind::OutOfRange { index: 2, len: 1 },
+      metadata.method_def(RowId::new(2)).unwrap_err().kind()
+    );
+    assert_eq!(
+      ReadErrorKind::OutOfRange { index: 1, len: 0 },
+      method.pinvoke().unwrap_err().kind()
+    );
+  }
+
+  #[test]
+  fn test_constant() {
+    assert_eq!(
+      Some(Constant::R8(1.5)),
+      Constant::from_bytes(ElementType::ELEMENT_TYPE_R8, &1.5f64.to_le_bytes())
+    );
+    assert_eq!(
+      Some(Constant::Boolean(true)),
+      Constant::from_bytes(ElementType::ELEMENT_TYPE_BOOLEAN, &[1])
+    );
+    assert_eq!(
+      None,
+      Constant::from_bytes(ElementType::ELEMENT_TYPE_I4, &[1, 0])
+    );
+    assert_eq!(
+      None,
+      Constant::from_bytes(ElementType::ELEMENT_TYPE_STRING, b"h\0i")
+    );
+    assert_eq!(
+      None,
+      Constant::from_bytes(ElementType::ELEMENT_TYPE_OBJECT, &[])
+    );
+  }
+}
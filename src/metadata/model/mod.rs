@@ -0,0 +1,216 @@
+//! Views of the entities defined by metadata.
+//!
+//! The tables of the `#~` stream refer to each other and to the heaps through ids.  [Metadata]
+//! bundles the streams of a module so that handles such as [TypeDefinition] can follow those ids
+//! and return names, members and related rows directly.
+
+pub mod format;
+pub mod generics;
+pub mod hierarchy;
+pub mod index;
+pub mod layout;
+pub mod lists;
+pub mod members;
+pub mod names;
+pub mod types;
+pub mod vtable;
+
+use super::errors::MetadataStreamReadError;
+use super::streams::blobs::BlobsHeap;
+use super::streams::guids::GuidsHeap;
+use super::streams::strings::StringsHeap;
+use super::streams::tables::id::{HasCustomAttribute, RowId, TypeOrMethodDef};
+use super::streams::tables::rows::{
+  CustomAttributeRow, EventRow, FieldRow, GenericParamRow, MethodDefRow, PropertyRow, TypeDefRow,
+};
+use super::streams::tables::TablesStream;
+use super::streams::user_strings::UserStringsHeap;
+use super::MetadataReader;
+use crate::metadata::errors::ReadError;
+use index::Indexes;
+use members::{EventDefinition, FieldDefinition, MethodDefinition, PropertyDefinition};
+use std::sync::Arc;
+use std::vec::Vec;
+use types::TypeDefinition;
+
+/// The streams of a module's metadata.
+///
+/// Heaps missing from the metadata are treated as empty.  Clones share the indexes built by
+/// lookups.
+#[derive(Debug, Clone)]
+pub struct Metadata<'a> {
+  tables: TablesStream<'a>,
+  strings: StringsHeap<'a>,
+  blobs: BlobsHeap<'a>,
+  guids: GuidsHeap<'a>,
+  user_strings: UserStringsHeap<'a>,
+  indexes: Arc<Indexes>,
+}
+
+impl<'a> Metadata<'a> {
+  /// Creates a [Metadata] from the given streams.
+  pub fn new(
+    tables: TablesStream<'a>,
+    strings: StringsHeap<'a>,
+    blobs: BlobsHeap<'a>,
+    guids: GuidsHeap<'a>,
+    user_strings: UserStringsHeap<'a>,
+  ) -> Self {
+    Self {
+      tables,
+      strings,
+      blobs,
+      guids,
+      user_strings,
+      indexes: Arc::new(Indexes::new()),
+    }
+  }
+
+  /// Reads the streams of the given metadata.
+  ///
+  /// The first stream with each name is used.  Fails with
+  /// [MetadataStreamReadError::MissingStream] if there is no `#~` stream.
+  pub fn from_reader(metadata: &MetadataReader<'a>) -> Result<Self, MetadataStreamReadError<'a>> {
+    let mut tables = None;
+    let mut strings = None;
+    let mut blobs = None;
+    let mut guids = None;
+    let mut user_strings = None;
+
+    for stream in metadata.streams() {
+      let stream = stream?;
+
+      tables = tables.or(stream.as_tables());
+      strings = strings.or(stream.as_strings());
+      blobs = blobs.or(stream.as_blobs());
+      guids = guids.or(stream.as_guids());
+      user_strings = user_strings.or(stream.as_user_strings());
+    }
+
+    Ok(Self::new(
+      tables.ok_or(MetadataStreamReadError::MissingStream { name: "#~" })?,
+      strings.unwrap_or_default(),
+      blobs.unwrap_or_default(),
+      guids.unwrap_or_default(),
+      user_strings.unwrap_or_default(),
+    ))
+  }
+
+  /// Returns the indexes built by lookups, to be shared with another [Metadata] of the same
+  /// streams.
+  #[cfg(feature = "object")]
+  pub(crate) fn indexes(&self) -> &Arc<Indexes> {
+    &self.indexes
+  }
+
+  /// Uses the given indexes, built by lookups of another [Metadata] of the same streams.
+  #[cfg(feature = "object")]
+  pub(crate) fn with_indexes(self, indexes: Arc<Indexes>) -> Self {
+    Self { indexes, ..self }
+  }
+
+  /// Returns the `#~` stream.
+  pub fn tables(&self) -> &TablesStream<'a> {
+    &self.tables
+  }
+
+  /// Returns the `#Strings` heap.
+  pub fn strings(&self) -> StringsHeap<'a> {
+    self.strings
+  }
+
+  /// Returns the `#Blob` heap.
+  pub fn blobs(&self) -> BlobsHeap<'a> {
+    self.blobs
+  }
+
+  /// Returns the `#GUID` heap.
+  pub fn guids(&self) -> GuidsHeap<'a> {
+    self.guids
+  }
+
+  /// Returns the `#US` heap.
+  pub fn user_strings(&self) -> UserStringsHeap<'a> {
+    self.user_strings
+  }
+
+  /// Returns the type with the given [TypeDefRow].
+  pub fn type_def(&self, id: RowId<TypeDefRow>) -> Result<TypeDefinition<'_>, ReadError> {
+    Ok(TypeDefinition::new(self, self.tables.type_defs().get(id)?))
+  }
+
+  /// Returns an iterator over the types defined by the module, including the `<Module>` type.
+  pub fn type_defs(&self) -> impl Iterator<Item = TypeDefinition<'_>> {
+    self
+      .tables
+      .type_defs()
+      .into_iter()
+      .map(|row| TypeDefinition::new(self, row))
+  }
+
+  /// Returns the method with the given [MethodDefRow].
+  pub fn method_def(&self, id: RowId<MethodDefRow>) -> Result<MethodDefinition<'_>, ReadError> {
+    Ok(MethodDefinition::new(
+      self,
+      self.tables.method_defs().get(id)?,
+    ))
+  }
+
+  /// Returns the field with the given [FieldRow].
+  pub fn field(&self, id: RowId<FieldRow>) -> Result<FieldDefinition<'_>, ReadError> {
+    Ok(FieldDefinition::new(self, self.tables.fields().get(id)?))
+  }
+
+  /// Returns the property with the given [PropertyRow].
+  pub fn property(&self, id: RowId<PropertyRow>) -> Result<PropertyDefinition<'_>, ReadError> {
+    Ok(PropertyDefinition::new(
+      self,
+      self.tables.properties().get(id)?,
+    ))
+  }
+
+  /// Returns the event with the given [EventRow].
+  pub fn event(&self, id: RowId<EventRow>) -> Result<EventDefinition<'_>, ReadError> {
+    Ok(EventDefinition::new(self, self.tables.events().get(id)?))
+  }
+
+  /// Returns an iterator over the custom attributes applied to the given row.
+  pub fn custom_attributes(
+    &self,
+    parent: HasCustomAttribute,
+  ) -> impl Iterator<Item = CustomAttributeRow> + '_ {
+    self.owned_rows(parent)
+  }
+
+  /// Returns the generic parameters of the given type or method, ordered by their number.
+  pub fn generic_params(&self, owner: TypeOrMethodDef) -> Vec<GenericParamRow> {
+    let mut params = self
+      .owned_rows::<GenericParamRow>(owner)
+      .collect::<Vec<_>>();
+
+    params.sort_by_key(|row| row.number());
+    params
+  }
+}
+
+/// Helpers shared by the tests of the views.
+#[cfg(all(test, feature = "write"))]
+pub(crate) mod tests {
+  use super::Metadata;
+  use crate::metadata::{MetadataBuilder, MetadataReader};
+
+  /// Returns the bytes of the metadata built by the given function.
+  pub(crate) fn build(f: impl FnOnce(&mut MetadataBuilder)) -> Vec<u8> {
+    let mut builder = MetadataBuilder::new();
+
+    f(&mut builder);
+    builder.finish()
+  }
+
+  /// Reads the metadata returned by [build].
+  pub(crate) fn read(bytes: &[u8]) -> Metadata<'_> {
+    let reader = MetadataReader::from_bytes(bytes).unwrap();
+
+    Metadata::from_reader(&reader).unwrap()
+  }
+}
@@ -0,0 +1,454 @@
+//! Lookups of types and namespaces by name.
+//!
+//! The names of the types defined and exported by a module are indexed the first time one is looked
+//! up, so that later lookups do not read the `TypeDef` and `ExportedType` tables again.
+
+use super::types::TypeDefinition;
+use super::Metadata;
+use crate::metadata::errors::ReadError;
+use crate::metadata::streams::tables::id::{Implementation, RowId};
+use crate::metadata::streams::tables::rows::{ExportedTypeRow, TypeDefRow};
+use std::collections::{BTreeMap, HashMap};
+use std::string::String;
+use std::vec::Vec;
+
+/// A type found by name, either defined by the module or exported by it from another module.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FoundType<'a> {
+  /// A type defined by the module.
+  Defined(TypeDefinition<'a>),
+  /// A type the module exports, either from another module of its assembly or forwarded to
+  /// another assembly.
+  Exported(ExportedTypeRow),
+}
+
+/// The index of the names of the types of a module.
+#[derive(Debug, Default)]
+pub(crate) struct NameIndex {
+  /// Every namespace, including the global namespace and namespaces that only contain other
+  /// namespaces, with the top-level types they contain by name.
+  namespaces: BTreeMap<String, BTreeMap<String, TypeIndex>>,
+  /// The names and indices of the nested types, by the index of their enclosing type.
+  nested: HashMap<TypeIndex, Vec<(String, TypeIndex)>>,
+}
+
+/// The 1-based index of a type defined or exported by a module.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+enum TypeIndex {
+  TypeDef(usize),
+  ExportedType(usize),
+}
+
+impl NameIndex {
+  fn new(metadata: &Metadata<'_>) -> Result<Self, ReadError> {
+    let mut index = Self::default();
+    let tables = metadata.tables();
+    let strings = metadata.strings();
+
+    index.namespaces.insert(String::new(), BTreeMap::new());
+
+    for ty in metadata.type_defs() {
+      if !ty.is_nested() {
+        index.add(
+          ty.namespace()?,
+          ty.name()?,
+          TypeIndex::TypeDef(ty.id().index()),
+        );
+      }
+    }
+
+    for row in tables.nested_classes() {
+      let nested = tables.type_defs().get(row.nested_class())?;
+
+      index
+        .nested
+        .entry(TypeIndex::TypeDef(row.enclosing_class().index()))
+        .or_default()
+        .push((
+          strings.get_str(nested.name())?.into(),
+          TypeIndex::TypeDef(nested.id().index()),
+        ));
+    }
+
+    for row in tables.exported_types() {
+      let name = strings.get_str(row.type_name())?;
+      let ty = TypeIndex::ExportedType(row.id().index());
+
+      match row.implementation() {
+        Implementation::ExportedType(enclosing) => index
+          .nested
+          .entry(TypeIndex::ExportedType(enclosing.index()))
+          .or_default()
+          .push((name.into(), ty)),
+        _ => index.add(strings.get_str(row.type_namespace())?, name, ty),
+      }
+    }
+
+    Ok(index)
+  }
+
+  /// Adds a top-level type, unless a type with the same name was added before.
+  fn add(&mut self, namespace: &str, name: &str, ty: TypeIndex) {
+    let mut parent = namespace;
+
+    while let Some((outer, _)) = parent.rsplit_once('.') {
+      self.namespaces.entry(outer.into()).or_default();
+      parent = outer;
+    }
+
+    self
+      .namespaces
+      .entry(namespace.into())
+      .or_default()
+      .entry(name.into())
+      .or_insert(ty);
+  }
+}
+
+impl<'a> Metadata<'a> {
+  /// Returns the top-level type with the given namespace and name, such as
+  /// `System.Collections.Generic` and ``List`1``.
+  ///
+  /// Types defined by the module are found before types it exports.  Fails if the names of the
+  /// types of the module cannot be read.
+  pub fn find_type(&self, namespace: &str, name: &str) -> Result<Option<FoundType<'_>>, ReadError> {
+    let ty = self
+      .names()?
+      .namespaces
+      .get(namespace)
+      .and_then(|types| types.get(name));
+
+    Ok(ty.and_then(|&ty| self.found_type(ty)))
+  }
+
+  /// Returns the type with the given full name, such as `System.Environment+SpecialFolder`.
+  ///
+  /// The names of nested types follow the name of their enclosing type separated by `+`.
+  pub fn find_type_by_full_name(
+    &self,
+    full_name: &str,
+  ) -> Result<Option<FoundType<'_>>, ReadError> {
+    let mut names = full_name.split('+');
+    let outermost = names.next().unwrap_or_default();
+    let (namespace, name) = outermost.rsplit_once('.').unwrap_or(("", outermost));
+    let mut ty = self.find_type(namespace, name)?;
+
+    for name in names {
+      ty = match ty {
+        Some(ty) => self.find_nested_type(ty, name)?,
+        None => break,
+      };
+    }
+
+    Ok(ty)
+  }
+
+  /// Returns the type nested in the given type with the given name.
+  pub fn find_nested_type(
+    &self,
+    enclosing: FoundType<'_>,
+    name: &str,
+  ) -> Result<Option<FoundType<'_>>, ReadError> {
+    let enclosing = match enclosing {
+      FoundType::Defined(ty) => TypeIndex::TypeDef(ty.id().index()),
+      FoundType::Exported(row) => TypeIndex::ExportedType(row.id().index()),
+    };
+    let mut nested = self.names()?.nested.get(&enclosing).into_iter().flatten();
+    let ty = nested.find(|(nested, _)| nested == name);
+
+    Ok(ty.and_then(|&(_, ty)| self.found_type(ty)))
+  }
+
+  /// Returns all the namespaces containing types, and the namespaces containing those, in order.
+  ///
+  /// The global namespace is not included.
+  pub fn namespaces(&self) -> Result<impl Iterator<Item = &str> + '_, ReadError> {
+    let namespaces = self.names()?.namespaces.keys();
+
+    Ok(
+      namespaces
+        .map(String::as_str)
+        .filter(|namespace| !namespace.is_empty()),
+    )
+  }
+
+  /// Returns the namespaces directly contained in the given namespace, in order.
+  ///
+  /// The namespaces of the global namespace are returned for an empty namespace.
+  pub fn child_namespaces<'s>(
+    &'s self,
+    parent: &'s str,
+  ) -> Result<impl Iterator<Item = &'s str> + 's, ReadError> {
+    let namespaces = self.names()?.namespaces.keys().map(String::as_str);
+
+    Ok(namespaces.filter(move |namespace| {
+      let child = match parent.is_empty() {
+        true => Some(*namespace).filter(|namespace| !namespace.is_empty()),
+        false => namespace
+          .strip_prefix(parent)
+          .and_then(|rest| rest.strip_prefix('.')),
+      };
+
+      child.is_some_and(|child| !child.contains('.'))
+    }))
+  }
+
+  /// Returns the top-level types of the given namespace in order of their names.
+  pub fn types_in_namespace(
+    &self,
+    namespace: &str,
+  ) -> Result<impl Iterator<Item = FoundType<'_>> + '_, ReadError> {
+    let types = self
+      .names()?
+      .namespaces
+      .get(namespace)
+      .into_iter()
+      .flatten();
+
+    Ok(types.filter_map(|(_, &ty)| self.found_type(ty)))
+  }
+
+  /// Returns the ids of the types nested in the given type, in the order of the `NestedClass`
+  /// table.
+  pub(crate) fn nested_type_ids(
+    &self,
+    enclosing: RowId<TypeDefRow>,
+  ) -> Result<impl Iterator<Item = RowId<TypeDefRow>> + '_, ReadError> {
+    let nested = self
+      .names()?
+      .nested
+      .get(&TypeIndex::TypeDef(enclosing.index()));
+
+    Ok(
+      nested
+        .into_iter()
+        .flatten()
+        .filter_map(|&(_, ty)| match ty {
+          TypeIndex::TypeDef(index) => Some(RowId::new(index)),
+          TypeIndex::ExportedType(_) => None,
+        }),
+    )
+  }
+
+  fn names(&self) -> Result<&NameIndex, ReadError> {
+    if let Some(names) = self.indexes.names.get() {
+      return Ok(names);
+    }
+
+    let names = NameIndex::new(self)?;
+
+    Ok(self.indexes.names.get_or_init(|| names))
+  }
+
+  fn found_type(&self, ty: TypeIndex) -> Option<FoundType<'_>> {
+    match ty {
+      TypeIndex::TypeDef(index) => self
+        .type_def(RowId::new(index))
+        .ok()
+        .map(FoundType::Defined),
+      TypeIndex::ExportedType(index) => {
+        let row = self.tables().exported_types().get(RowId::new(index));
+
+        row.ok().map(FoundType::Exported)
+      }
+    }
+  }
+}
+
+#[cfg(all(test, feature = "write"))]
+mod tests {
+  use super::super::tests::{build, read};
+  use super::FoundType;
+  use crate::metadata::errors::ReadErrorKind;
+  use crate::metadata::streams::tables::flags::*;
+  use crate::metadata::streams::tables::id::*;
+  use crate::metadata::streams::tables::rows::*;
+
+  /// `<Module>`, ``System.Collections.Generic.List`1`` and its nested type `Enumerator`, and
+  /// `Program`, with `System.Text.StringBuilder` and its nested type `Chunk` forwarded to another
+  /// assembly.
+  fn fixture() -> Vec<u8> {
+    build(|builder| {
+      let strings = &mut builder.strings;
+      let empty = strings.add("");
+      let generic = strings.add("System.Collections.Generic");
+      let text = strings.add("System.Text");
+      let list = strings.add("List`1");
+      let types = [
+        (TypeAttributes::empty(), strings.add("<Module>"), empty),
+        (TypeAttributes::PUBLIC, list, generic),
+        (
+          TypeAttributes::NESTED_PUBLIC,
+          strings.add("Enumerator"),
+          empty,
+        ),
+        (TypeAttributes::empty(), strings.add("Program"), empty),
+      ];
+      let builder_name = strings.add("StringBuilder");
+      let chunk = strings.add("Chunk");
+      let tables = &mut builder.tables;
+
+      for (flags, name, namespace) in types {
+        tables.add_type_def(TypeDefRow::new(
+          flags,
+          name,
+          namespace,
+          TypeDefOrRef::TypeDef(RowId::new(0)),
+          RowId::new(1),
+          RowId::new(1),
+        ));
+      }
+
+      tables.add_nested_class(NestedClassRow::new(RowId::new(3), RowId::new(2)));
+
+      // A forwarder for a type the module also defines, which is ignored, and a forwarded type with
+      // a nested type.
+      for (flags, name, namespace, implementation) in [
+        (
+          TypeAttributes::IS_TYPE_FORWARDER,
+          list,
+          generic,
+          Implementation::AssemblyRef(RowId::new(1)),
+        ),
+        (
+          TypeAttributes::IS_TYPE_FORWARDER,
+          builder_name,
+          text,
+          Implementation::AssemblyRef(RowId::new(1)),
+        ),
+        (
+          TypeAttributes::NESTED_PUBLIC,
+          chunk,
+          empty,
+          Implementation::ExportedType(RowId::new(2)),
+        ),
+      ] {
+        tables.add_exported_type(ExportedTypeRow::new(
+          flags,
+          RowId::new(0),
+          name,
+          namespace,
+          implementation,
+        ));
+      }
+    })
+  }
+
+  #[test]
+  fn test_find_type() {
+    let bytes = fixture();
+    let metadata = read(&bytes);
+    let list = metadata.type_def(RowId::new(2)).unwrap();
+    let program = metadata.type_def(RowId::new(4)).unwrap();
+    let string_builder = metadata.tables().exported_types().get(RowId::new(2));
+
+    assert_eq!(
+      Ok(Some(FoundType::Defined(list))),
+      metadata.find_type("System.Collections.Generic", "List`1")
+    );
+    assert_eq!(
+      Ok(Some(FoundType::Defined(program))),
+      metadata.find_type("", "Program")
+    );
+    assert_eq!(Ok(None), metadata.find_type("", "Enumerator"));
+    assert_eq!(Ok(None), metadata.find_type("System", "List`1"));
+    assert_eq!(
+      Ok(Some(FoundType::Exported(string_builder.unwrap()))),
+      metadata.find_type("System.Text", "StringBuilder")
+    );
+  }
+
+  #[test]
+  fn test_find_type_by_full_name() {
+    let bytes = fixture();
+    let metadata = read(&bytes);
+    let enumerator = metadata.type_def(RowId::new(3)).unwrap();
+    let chunk = metadata.tables().exported_types().get(RowId::new(3));
+
+    assert_eq!(
+      Ok(Some(FoundType::Defined(enumerator))),
+      metadata.find_type_by_full_name("System.Collections.Generic.List`1+Enumerator")
+    );
+    assert_eq!(
+      Ok(Some(FoundType::Exported(chunk.unwrap()))),
+      metadata.find_type_by_full_name("System.Text.StringBuilder+Chunk")
+    );
+    assert_eq!(
+      Ok(None),
+      metadata.find_type_by_full_name("System.Text.StringBuilder+Chunk+Missing")
+    );
+  }
+
+  #[test]
+  fn test_nested_type() {
+    let bytes = fixture();
+    let metadata = read(&bytes);
+    let list = metadata.type_def(RowId::new(2)).unwrap();
+    let enumerator = metadata.type_def(RowId::new(3)).unwrap();
+
+    assert_eq!(Ok(Some(enumerator)), list.nested_type("Enumerator"));
+    assert_eq!(Ok(None), enumerator.nested_type("Enumerator"));
+    assert_eq!(
+      vec![enumerator],
+      list.nested_types().unwrap().collect::<Vec<_>>()
+    );
+  }
+
+  #[test]
+  fn test_namespaces() {
+    let bytes = fixture();
+    let metadata = read(&bytes);
+
+    assert_eq!(
+      vec![
+        "System",
+        "System.Collections",
+        "System.Collections.Generic",
+        "System.Text"
+      ],
+      metadata.namespaces().unwrap().collect::<Vec<_>>()
+    );
+    assert_eq!(
+      vec!["System"],
+      metadata.child_namespaces("").unwrap().collect::<Vec<_>>()
+    );
+    assert_eq!(
+      vec!["System.Collections", "System.Text"],
+      metadata
+        .child_namespaces("System")
+        .unwrap()
+        .collect::<Vec<_>>()
+    );
+    assert_eq!(0, metadata.child_namespaces("System.Text").unwrap().count());
+    assert_eq!(2, metadata.types_in_namespace("").unwrap().count());
+    assert_eq!(0, metadata.types_in_namespace("System").unwrap().count());
+  }
+
+  #[test]
+  fn test_find_type_with_missing_nested_type() {
+    let bytes = build(|md| {
+      md.tables.add_type_def(TypeDefRow::new(
+        TypeAttributes::PUBLIC,
+        md.strings.add("T"),
+        md.strings.add("N"),
+        TypeDefOrRef::TypeDef(RowId::new(0)),
+        RowId::new(1),
+        RowId::new(1),
+      ));
+      // The nested type does not exist.
+      md.tables
+        .add_nested_class(NestedClassRow::new(RowId::new(2), RowId::new(1)));
+    });
+    let metadata = read(&bytes);
+    let out_of_range = ReadErrorKind::OutOfRange { index: 2, len: 1 };
+
+    assert_eq!(
+      Err(out_of_range),
+      metadata.find_type("N", "T").map_err(|err| err.kind())
+    );
+    assert_eq!(
+      Some(out_of_range),
+      metadata.namespaces().err().map(|err| err.kind())
+    );
+  }
+}
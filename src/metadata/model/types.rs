@@ -0,0 +1,488 @@
+//! Views of the types defined by a module.
+
+use super::members::{EventDefinition, FieldDefinition, MethodDefinition, PropertyDefinition};
+use super::names::FoundType;
+use super::Metadata;
+use crate::metadata::errors::{ReadError, ReadErrorKind, ReadLocation};
+use crate::metadata::streams::tables::flags::TypeAttributes;
+use crate::metadata::streams::tables::id::{
+  HasCustomAttribute, RowId, TypeDefOrRef, TypeOrMethodDef,
+};
+use crate::metadata::streams::tables::rows::{
+  ClassLayoutRow, CustomAttributeRow, GenericParamRow, InterfaceImplRow, NestedClassRow, TypeDefRow,
+};
+use crate::metadata::streams::tables::table::Row;
+use core::fmt;
+use std::string::String;
+use std::vec::Vec;
+
+/// A type defined by a module, a [TypeDefRow] together with the [Metadata] it belongs to.
+#[derive(Clone, Copy)]
+pub struct TypeDefinition<'a> {
+  metadata: &'a Metadata<'a>,
+  row: TypeDefRow,
+}
+
+impl<'a> TypeDefinition<'a> {
+  /// Creates a [TypeDefinition] for a row read from the given metadata.
+  pub fn new(metadata: &'a Metadata<'a>, row: TypeDefRow) -> Self {
+    Self { metadata, row }
+  }
+
+  /// Returns the [Metadata] the type is defined in.
+  pub fn metadata(&self) -> &'a Metadata<'a> {
+    self.metadata
+  }
+
+  /// Returns the id of the [TypeDefRow].
+  pub fn id(&self) -> RowId<TypeDefRow> {
+    self.row.id()
+  }
+
+  /// Returns the [TypeDefRow].
+  pub fn row(&self) -> TypeDefRow {
+    self.row
+  }
+
+  /// Returns the [TypeAttributes] of the type.
+  pub fn flags(&self) -> TypeAttributes {
+    self.row.flags()
+  }
+
+  /// Returns whether the type is nested in another type, based on its visibility.
+  pub fn is_nested(&self) -> bool {
+    (self.flags() & TypeAttributes::VISIBILITY_MASK).bits() >= TypeAttributes::NESTED_PUBLIC.bits()
+  }
+
+  /// Returns whether the type is an interface.
+  pub fn is_interface(&self) -> bool {
+    self.flags() & TypeAttributes::CLASS_SEMANTICS_MASK == TypeAttributes::INTERFACE
+  }
+
+  /// Returns the name of the type.
+  pub fn name(&self) -> Result<&'a str, ReadError> {
+    self.metadata.strings().get_str(self.row.name())
+  }
+
+  /// Returns the namespace of the type, empty for nested types.
+  pub fn namespace(&self) -> Result<&'a str, ReadError> {
+    self.metadata.strings().get_str(self.row.namespace())
+  }
+
+  /// Returns the full name of the type, such as `System.Environment+SpecialFolder`.
+  ///
+  /// The names of enclosing types are separated by `+`, and the namespace of the outermost type
+  /// comes first.
+  pub fn full_name(&self) -> Result<String, ReadError> {
+    let mut names = Vec::from([self.name()?]);
+    let mut outermost = *self;
+
+    while let Some(enclosing) = outermost.declaring_type()? {
+      // Nesting cycles are invalid, and would otherwise never end.
+      if names.len() > self.metadata.tables().type_defs().len() {
+        return Err(ReadError::new(
+          ReadLocation::Table {
+            table: NestedClassRow::NAME,
+          },
+          self.metadata.tables().nested_classes().offset(),
+          ReadErrorKind::Invalid,
+        ));
+      }
+
+      names.push(enclosing.name()?);
+      outermost = enclosing;
+    }
+
+    let mut full_name = String::from(outermost.namespace()?);
+
+    for (i, name) in names.iter().rev().enumerate() {
+      match i {
+        0 if full_name.is_empty() => {}
+        0 => full_name.push('.'),
+        _ => full_name.push('+'),
+      }
+
+      full_name.push_str(name);
+    }
+
+    Ok(full_name)
+  }
+
+  /// Returns the type this type is nested in, or [None] if it is not nested.
+  pub fn declaring_type(&self) -> Result<Option<TypeDefinition<'a>>, ReadError> {
+    self
+      .metadata
+      .owned_row::<NestedClassRow>(self.id())
+      .map(|row| self.metadata.type_def(row.enclosing_class()))
+      .transpose()
+  }
+
+  /// Returns the type this type extends, or [None] for interfaces, `System.Object` and the
+  /// `<Module>` type.
+  pub fn base_type(&self) -> Option<TypeDefOrRef> {
+    match self.row.extends() {
+      TypeDefOrRef::TypeDef(id) if id.index() == 0 => None,
+      TypeDefOrRef::TypeRef(id) if id.index() == 0 => None,
+      TypeDefOrRef::TypeSpec(id) if id.index() == 0 => None,
+      extends => Some(extends),
+    }
+  }
+
+  /// Returns an iterator over the interfaces the type implements.
+  pub fn interfaces(&self) -> impl Iterator<Item = TypeDefOrRef> + 'a {
+    self
+      .metadata
+      .owned_rows::<InterfaceImplRow>(self.id())
+      .map(|row| row.interface())
+  }
+
+  /// Returns an iterator over the fields of the type.
+  pub fn fields(&self) -> impl Iterator<Item = FieldDefinition<'a>> + 'a {
+    let metadata = self.metadata;
+
+    metadata
+      .list(self.id())
+      .map(|row| FieldDefinition::new(metadata, row))
+  }
+
+  /// Returns an iterator over the methods of the type.
+  pub fn methods(&self) -> impl Iterator<Item = MethodDefinition<'a>> + 'a {
+    let metadata = self.metadata;
+
+    metadata
+      .list(self.id())
+      .map(|row| MethodDefinition::new(metadata, row))
+  }
+
+  /// Returns an iterator over the properties of the type.
+  pub fn properties(&self) -> impl Iterator<Item = PropertyDefinition<'a>> + 'a {
+    let metadata = self.metadata;
+    let map = metadata
+      .tables()
+      .property_maps()
+      .into_iter()
+      .find(|row| row.parent() == self.id());
+
+    map
+      .into_iter()
+      .flat_map(|map| metadata.list(map.id()))
+      .map(|row| PropertyDefinition::new(metadata, row))
+  }
+
+  /// Returns an iterator over the events of the type.
+  pub fn events(&self) -> impl Iterator<Item = EventDefinition<'a>> + 'a {
+    let metadata = self.metadata;
+    let map = metadata
+      .tables()
+      .event_maps()
+      .into_iter()
+      .find(|row| row.parent() == self.id());
+
+    map
+      .into_iter()
+      .flat_map(|map| metadata.list(map.id()))
+      .map(|row| EventDefinition::new(metadata, row))
+  }
+
+  /// Returns the generic parameters of the type, ordered by their number.
+  pub fn generic_params(&self) -> Vec<GenericParamRow> {
+    self
+      .metadata
+      .generic_params(TypeOrMethodDef::TypeDef(self.id()))
+  }
+
+  /// Returns an iterator over the types nested in this type.
+  ///
+  /// Fails if the names of the types of the module, which index the nested types, cannot be read.
+  pub fn nested_types(&self) -> Result<impl Iterator<Item = TypeDefinition<'a>> + 'a, ReadError> {
+    let metadata = self.metadata;
+    let ids = metadata.nested_type_ids(self.id())?;
+
+    Ok(ids.filter_map(|id| metadata.type_def(id).ok()))
+  }
+
+  /// Returns the type nested in this type with the given name.
+  pub fn nested_type(&self, name: &str) -> Result<Option<TypeDefinition<'a>>, ReadError> {
+    let nested = self
+      .metadata
+      .find_nested_type(FoundType::Defined(*self), name)?;
+
+    Ok(match nested {
+      Some(FoundType::Defined(ty)) => Some(ty),
+      _ => None,
+    })
+  }
+
+  /// Returns the explicit packing and size of the type, or [None] if it has no [ClassLayoutRow].
+  pub fn layout(&self) -> Option<ClassLayoutRow> {
+    self.metadata.owned_row(self.id())
+  }
+
+  /// Returns an iterator over the custom attributes applied to the type.
+  pub fn custom_attributes(&self) -> impl Iterator<Item = CustomAttributeRow> + 'a {
+    self
+      .metadata
+      .custom_attributes(HasCustomAttribute::TypeDef(self.id()))
+  }
+}
+
+impl fmt::Debug for TypeDefinition<'_> {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    f.debug_struct("TypeDefinition")
+      .field("row", &self.row)
+      .finish_non_exhaustive()
+  }
+}
+
+impl PartialEq for TypeDefinition<'_> {
+  fn eq(&self, other: &Self) -> bool {
+    core::ptr::eq(self.metadata, other.metadata) && self.row == other.row
+  }
+}
+
+impl Eq for TypeDefinition<'_> {}
+
+#[cfg(all(test, feature = "write"))]
+mod tests {
+  use super::super::tests::{build, read};
+  use crate::metadata::errors::{ReadErrorKind, ReadLocation};
+  use crate::metadata::streams::tables::flags::*;
+  use crate::metadata::streams::tables::id::*;
+  use crate::metadata::streams::tables::rows::*;
+
+  /// `<Module>`, `Ns.Outer<T> : Object, Ns.Other` with two fields, a property and its nested type
+  /// `Inner` with a field, and `Ns.Other : Object` with a method and a property.
+  fn fixture() -> Vec<u8> {
+    build(|builder| {
+      let strings = &mut builder.strings;
+      let empty = strings.add("");
+      let names = ["<Module>", "Outer", "Inner", "Other"].map(|name| strings.add(name));
+      let namespace = strings.add("Ns");
+      let member = strings.add("Member");
+      let t = strings.add("T");
+      let signature = builder.blobs.add(&[0x06, 0x08]);
+      let tables = &mut builder.tables;
+      let object = tables.add_type_ref(TypeRefRow::new(
+        ResolutionScope::AssemblyRef(RowId::new(1)),
+        strings.add("Object"),
+        strings.add("System"),
+      ));
+
+      for (i, name) in names.into_iter().enumerate() {
+        let (flags, namespace, extends) = match i {
+          0 => (
+            TypeAttributes::empty(),
+            empty,
+            TypeDefOrRef::TypeDef(RowId::new(0)),
+          ),
+          2 => (
+            TypeAttributes::NESTED_PUBLIC,
+            empty,
+            TypeDefOrRef::TypeRef(object),
+          ),
+          _ => (
+            TypeAttributes::PUBLIC,
+            namespace,
+            TypeDefOrRef::TypeRef(object),
+          ),
+        };
+        // Outer has two fields and a method, Inner has a field, Other has a method.
+        let (fields, methods) = [(1, 1), (1, 1), (3, 2), (4, 2)][i];
+
+        tables.add_type_def(TypeDefRow::new(
+          flags,
+          name,
+          namespace,
+          extends,
+          RowId::new(fields),
+          RowId::new(methods),
+        ));
+      }
+
+      for _ in 0..3 {
+        tables.add_field(FieldRow::new(FieldAttributes::PUBLIC, member, signature));
+      }
+
+      for _ in 0..2 {
+        tables.add_method_def(MethodDefRow::new(
+          0,
+          MethodImplAttributes::empty(),
+          MethodAttributes::PUBLIC,
+          member,
+          signature,
+          RowId::new(1),
+        ));
+      }
+
+      tables.add_interface_impl(InterfaceImplRow::new(
+        RowId::new(2),
+        TypeDefOrRef::TypeDef(RowId::new(4)),
+      ));
+      tables.add_nested_class(NestedClassRow::new(RowId::new(3), RowId::new(2)));
+      tables.add_class_layout(ClassLayoutRow::new(8, 16, RowId::new(2)));
+      tables.add_generic_param(GenericParamRow::new(
+        0,
+        GenericParamAttributes::empty(),
+        TypeOrMethodDef::TypeDef(RowId::new(2)),
+        t,
+      ));
+      tables.add_property_map(PropertyMapRow::new(RowId::new(2), RowId::new(1)));
+      tables.add_property(PropertyRow::new(
+        PropertyAttributes::empty(),
+        member,
+        signature,
+      ));
+      tables.add_property_map(PropertyMapRow::new(RowId::new(4), RowId::new(2)));
+      tables.add_property(PropertyRow::new(
+        PropertyAttributes::empty(),
+        member,
+        signature,
+      ));
+      tables.add_custom_attribute(CustomAttributeRow::new(
+        HasCustomAttribute::TypeDef(RowId::new(2)),
+        CustomAttributeType::MethodDef(RowId::new(2)),
+        builder.blobs.add(&[1, 0, 0, 0]),
+      ));
+    })
+  }
+
+  #[test]
+  fn test_names() {
+    let bytes = fixture();
+    let metadata = read(&bytes);
+    let outer = metadata.type_def(RowId::new(2)).unwrap();
+    let inner = metadata.type_def(RowId::new(3)).unwrap();
+
+    assert_eq!(4, metadata.type_defs().count());
+    assert_eq!(Ok("Outer"), outer.name());
+    assert_eq!(Ok("Ns"), outer.namespace());
+    assert_eq!(Ok("Ns.Outer".into()), outer.full_name());
+    assert_eq!(Ok("Ns.Outer+Inner".into()), inner.full_name());
+    assert_eq!(
+      Ok("<Module>".into()),
+      metadata.type_def(RowId::new(1)).unwrap().full_name()
+    );
+  }
+
+  #[test]
+  fn test_nesting() {
+    let bytes = fixture();
+    let metadata = read(&bytes);
+    let outer = metadata.type_def(RowId::new(2)).unwrap();
+    let inner = metadata.type_def(RowId::new(3)).unwrap();
+
+    assert_eq!(Ok(Some(outer)), inner.declaring_type());
+    assert_eq!(Ok(None), outer.declaring_type());
+    assert!(inner.is_nested());
+    assert!(!outer.is_nested());
+    assert_eq!(
+      [inner],
+      outer.nested_types().unwrap().collect::<Vec<_>>()[..]
+    );
+    assert_eq!(0, inner.nested_types().unwrap().count());
+  }
+
+  #[test]
+  fn test_base_type_and_interfaces() {
+    let bytes = fixture();
+    let metadata = read(&bytes);
+    let outer = metadata.type_def(RowId::new(2)).unwrap();
+    let other = metadata.type_def(RowId::new(4)).unwrap();
+
+    assert_eq!(
+      Some(TypeDefOrRef::TypeRef(RowId::new(1))),
+      outer.base_type()
+    );
+    assert_eq!(None, metadata.type_def(RowId::new(1)).unwrap().base_type());
+    assert_eq!(
+      [TypeDefOrRef::TypeDef(RowId::new(4))],
+      outer.interfaces().collect::<Vec<_>>()[..]
+    );
+    assert_eq!(0, other.interfaces().count());
+  }
+
+  #[test]
+  fn test_members() {
+    let bytes = fixture();
+    let metadata = read(&bytes);
+    let outer = metadata.type_def(RowId::new(2)).unwrap();
+    let inner = metadata.type_def(RowId::new(3)).unwrap();
+    let other = metadata.type_def(RowId::new(4)).unwrap();
+    let ids = |rows: Vec<RowId<FieldRow>>| rows.iter().map(|id| id.index()).collect::<Vec<_>>();
+
+    assert_eq!(
+      [1, 2],
+      ids(outer.fields().map(|row| row.id()).collect())[..]
+    );
+    assert_eq!([3], ids(inner.fields().map(|row| row.id()).collect())[..]);
+    assert_eq!(0, other.fields().count());
+    assert_eq!(1, outer.methods().count());
+    assert_eq!(0, inner.methods().count());
+    assert_eq!(1, other.methods().count());
+    assert_eq!(RowId::new(2), other.methods().next().unwrap().id());
+    assert_eq!(RowId::new(1), outer.properties().next().unwrap().id());
+    assert_eq!(1, outer.properties().count());
+    assert_eq!(RowId::new(2), other.properties().next().unwrap().id());
+    assert_eq!(0, inner.properties().count());
+    assert_eq!(0, outer.events().count());
+  }
+
+  #[test]
+  fn test_generic_params_layout_and_attributes() {
+    let bytes = fixture();
+    let metadata = read(&bytes);
+    let outer = metadata.type_def(RowId::new(2)).unwrap();
+    let inner = metadata.type_def(RowId::new(3)).unwrap();
+    let other = metadata.type_def(RowId::new(4)).unwrap();
+
+    assert_eq!(1, outer.generic_params().len());
+    assert_eq!(0, inner.generic_params().len());
+    assert_eq!(
+      Some((8, 16)),
+      outer.layout().map(|l| (l.packing_size(), l.class_size()))
+    );
+    assert_eq!(None, inner.layout());
+    assert_eq!(1, outer.custom_attributes().count());
+    assert_eq!(0, other.custom_attributes().count());
+  }
+
+  #[test]
+  fn test_missing_type() {
+    let bytes = fixture();
+    let metadata = read(&bytes);
+    let err = metadata.type_def(RowId::new(5)).unwrap_err();
+
+    assert_eq!(ReadErrorKind::OutOfRange { index: 5, len: 4 }, err.kind());
+  }
+
+  #[test]
+  fn test_nested_types_of_missing_type() {
+    let bytes = build(|builder| {
+      let name = builder.strings.add("Outer");
+
+      builder.tables.add_type_def(TypeDefRow::new(
+        TypeAttributes::PUBLIC,
+        name,
+        name,
+        TypeDefOrRef::TypeDef(RowId::new(0)),
+        RowId::new(1),
+        RowId::new(1),
+      ));
+      // The nested type does not exist.
+      builder
+        .tables
+        .add_nested_class(NestedClassRow::new(RowId::new(2), RowId::new(1)));
+    });
+    let metadata = read(&bytes);
+    let outer = metadata.type_def(RowId::new(1)).unwrap();
+    let err = outer.nested_types().err().unwrap();
+
+    assert_eq!(
+      ReadLocation::Table {
+        table: "TypeDefRow"
+      },
+      err.location()
+    );
+    assert!(matches!(err.kind(), ReadErrorKind::OutOfRange { .. }));
+  }
+}
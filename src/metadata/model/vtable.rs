@@ -0,0 +1,597 @@
+//! The virtual method tables of the types defined by a module.
+//!
+//! A type inherits the slots of the table of its base type.  Following ECMA-335 II.10.3, each
+//! virtual method it declares either reuses the slot of the inherited method with the same name and
+//! signature, overriding it, or starts a new slot when it is marked
+//! [MethodAttributes::NEW_SLOT], when no inherited method matches or when the matching method is
+//! [MethodAttributes::STRICT] and not accessible.  `MethodImpl` rows then explicitly override the
+//! slots of the methods they declare.
+//!
+//! Slots are only inherited from base types defined by the module, the slots of other base types
+//! such as `System.Object` are not known.
+
+use super::generics::{defined_type, GenericContext};
+use super::hierarchy::same_signature;
+use super::members::MethodDefinition;
+use super::Metadata;
+use crate::metadata::errors::ReadError;
+use crate::metadata::streams::tables::flags::{MethodAttributes, TypeAttributes};
+use crate::metadata::streams::tables::id::{MethodDefOrRef, RowId, TypeDefOrRef};
+use crate::metadata::streams::tables::rows::{MethodDefRow, MethodImplRow, TypeDefRow};
+use crate::metadata::streams::tables::signatures::{MethodSig, TypeSig};
+use std::vec::Vec;
+
+/// The virtual method table of a type, with the overrides and issues found while laying it out.
+#[derive(Debug, Clone)]
+pub struct VTable<'a> {
+  slots: Vec<VTableSlot<'a>>,
+  overrides: Vec<Override<'a>>,
+  issues: Vec<VTableIssue<'a>>,
+  is_base_known: bool,
+}
+
+/// A slot of a virtual method table.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct VTableSlot<'a> {
+  /// The method that introduced the slot.
+  pub declaration: MethodDefinition<'a>,
+  /// The method called through the slot, the most derived override of the declaration.
+  pub implementation: MethodDefinition<'a>,
+  /// The name of the declaration.
+  pub name: &'a str,
+  /// The signature of the declaration, instantiated with the type arguments of the base type
+  /// declaring it in the type the table belongs to.
+  pub signature: MethodSig,
+}
+
+/// A method of a type overriding an inherited method.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Override<'a> {
+  /// The overriding method.
+  pub method: MethodDefinition<'a>,
+  /// The method previously called through the slot, or the declared method for an explicit
+  /// override of an interface method.
+  pub overridden: MethodDefinition<'a>,
+  /// Whether the override is recorded by a `MethodImpl` row rather than matched by name and
+  /// signature.
+  pub explicit: bool,
+}
+
+/// A possible mistake in the methods a type declares, relative to those it inherits.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum VTableIssue<'a> {
+  /// A method hides an inherited virtual method with the same name, and the same signature for a
+  /// method marked [MethodAttributes::HIDE_BY_SIG], instead of overriding it.
+  Hides {
+    /// The hiding method.
+    method: MethodDefinition<'a>,
+    /// The method called through the slot that is hidden.
+    hidden: MethodDefinition<'a>,
+  },
+  /// A method overrides a method marked [MethodAttributes::FINAL], which the runtime rejects.
+  OverridesFinal {
+    /// The overriding method.
+    method: MethodDefinition<'a>,
+    /// The final method.
+    overridden: MethodDefinition<'a>,
+  },
+  /// A method matches an inherited method marked [MethodAttributes::STRICT] it cannot access, and
+  /// starts a new slot instead of overriding it.
+  InaccessibleOverride {
+    /// The method starting a new slot.
+    method: MethodDefinition<'a>,
+    /// The inaccessible method.
+    overridden: MethodDefinition<'a>,
+  },
+  /// A slot of a type that is not abstract is still implemented by an abstract method.
+  MissingOverride {
+    /// The abstract method called through the slot.
+    method: MethodDefinition<'a>,
+  },
+}
+
+impl<'a> VTable<'a> {
+  /// Returns the slots of the table, the inherited slots first.
+  pub fn slots(&self) -> &[VTableSlot<'a>] {
+    &self.slots
+  }
+
+  /// Returns the slot introduced by the given method, or [None] if it does not introduce one.
+  pub fn slot(&self, declaration: RowId<MethodDefRow>) -> Option<&VTableSlot<'a>> {
+    self
+      .slots
+      .iter()
+      .find(|slot| slot.declaration.id() == declaration)
+  }
+
+  /// Returns the inherited methods overridden by the methods the type declares.
+  pub fn overrides(&self) -> &[Override<'a>] {
+    &self.overrides
+  }
+
+  /// Returns the issues found in the methods the type declares.
+  pub fn issues(&self) -> &[VTableIssue<'a>] {
+    &self.issues
+  }
+
+  /// Returns whether every base type of the type is defined by the module, so that the table
+  /// includes every inherited slot.
+  pub fn is_base_known(&self) -> bool {
+    self.is_base_known
+  }
+}
+
+impl<'a> Metadata<'a> {
+  /// Lays out the virtual method table of the given type.
+  ///
+  /// Fails if the base types of the type form a cycle.
+  pub fn vtable(&self, id: RowId<TypeDefRow>) -> Result<VTable<'_>, ReadError> {
+    let open = TypeSig::Class(TypeDefOrRef::TypeDef(id));
+    let bases = self.base_types(&open)?;
+    let mut table = VTable {
+      slots: Vec::new(),
+      overrides: Vec::new(),
+      issues: Vec::new(),
+      is_base_known: bases.last().is_none_or(|base| defined_type(base).is_some()),
+    };
+    let mut types = bases.iter().filter_map(defined_type).collect::<Vec<_>>();
+
+    // Lay out the table of each base type in turn, starting with the least derived, keeping the
+    // overrides and issues of the type itself only.
+    types.reverse();
+    types.push(id);
+
+    for ty in types {
+      if let Some(base) = self.instantiated_base_type(&TypeSig::Class(TypeDefOrRef::TypeDef(ty)))? {
+        let context = GenericContext::for_type(&base);
+
+        for slot in &mut table.slots {
+          slot.signature = context.substitute_method(&slot.signature);
+        }
+      }
+
+      table.overrides.clear();
+      table.issues.clear();
+      self.add_methods(&mut table, ty)?;
+      self.add_explicit_overrides(&mut table, ty)?;
+    }
+
+    if !self
+      .type_def(id)?
+      .flags()
+      .contains(TypeAttributes::ABSTRACT)
+    {
+      for slot in &table.slots {
+        if slot
+          .implementation
+          .flags()
+          .contains(MethodAttributes::ABSTRACT)
+        {
+          table.issues.push(VTableIssue::MissingOverride {
+            method: slot.implementation,
+          });
+        }
+      }
+    }
+
+    Ok(table)
+  }
+
+  /// Adds the virtual methods of a type to the table of its base type, and records the methods
+  /// hiding inherited ones.
+  fn add_methods<'m>(
+    &'m self,
+    table: &mut VTable<'m>,
+    id: RowId<TypeDefRow>,
+  ) -> Result<(), ReadError> {
+    let inherited = table.slots.len();
+
+    for method in self.type_def(id)?.methods() {
+      let flags = method.flags();
+      let name = method.name()?;
+      let signature = method.signature()?;
+      let matches = |slot: &VTableSlot<'_>| {
+        slot.name == name
+          && (!flags.contains(MethodAttributes::HIDE_BY_SIG)
+            || same_signature(&slot.signature, &signature))
+      };
+
+      if !flags.contains(MethodAttributes::VIRTUAL) || flags.contains(MethodAttributes::NEW_SLOT) {
+        for slot in table.slots[..inherited].iter().filter(|slot| matches(slot)) {
+          table.issues.push(VTableIssue::Hides {
+            method,
+            hidden: slot.implementation,
+          });
+        }
+
+        if flags.contains(MethodAttributes::VIRTUAL) {
+          table.slots.push(VTableSlot::new(method, name, signature));
+        }
+
+        continue;
+      }
+
+      // The most derived inherited slot with the same name and signature is overridden.
+      let slot = table.slots[..inherited]
+        .iter()
+        .rposition(|slot| slot.name == name && same_signature(&slot.signature, &signature));
+
+      match slot.map(|slot| (slot, table.slots[slot].implementation)) {
+        Some((_, overridden)) if overridden.flags().contains(MethodAttributes::FINAL) => {
+          table
+            .issues
+            .push(VTableIssue::OverridesFinal { method, overridden });
+          table.slots.push(VTableSlot::new(method, name, signature));
+        }
+        Some((_, overridden)) if !can_override(overridden) => {
+          table
+            .issues
+            .push(VTableIssue::InaccessibleOverride { method, overridden });
+          table.slots.push(VTableSlot::new(method, name, signature));
+        }
+        Some((slot, overridden)) => {
+          table.slots[slot].implementation = method;
+          table.overrides.push(Override {
+            method,
+            overridden,
+            explicit: false,
+          });
+        }
+        None => table.slots.push(VTableSlot::new(method, name, signature)),
+      }
+    }
+
+    Ok(())
+  }
+
+  /// Applies the `MethodImpl` rows of a type to its table.
+  fn add_explicit_overrides<'m>(
+    &'m self,
+    table: &mut VTable<'m>,
+    id: RowId<TypeDefRow>,
+  ) -> Result<(), ReadError> {
+    for row in self.owned_rows::<MethodImplRow>(id) {
+      let MethodDefOrRef::MethodDef(body) = row.method_body() else {
+        continue;
+      };
+      let Some(declaration) = self.method_def_of(row.method_declaration())? else {
+        continue;
+      };
+      let method = self.method_def(body)?;
+      let slots = table
+        .slots
+        .iter_mut()
+        .filter(|slot| slot.declaration == declaration || slot.implementation == declaration);
+      let mut overridden_slot = false;
+
+      for slot in slots {
+        let overridden = slot.implementation;
+
+        if overridden.flags().contains(MethodAttributes::FINAL) {
+          table
+            .issues
+            .push(VTableIssue::OverridesFinal { method, overridden });
+          continue;
+        }
+
+        slot.implementation = method;
+        overridden_slot = true;
+        table.overrides.push(Override {
+          method,
+          overridden,
+          explicit: true,
+        });
+      }
+
+      // A method of an interface has no slot in the table of the class.
+      if !overridden_slot
+        && declaration
+          .declaring_type()?
+          .is_some_and(|ty| ty.is_interface())
+      {
+        table.overrides.push(Override {
+          method,
+          overridden: declaration,
+          explicit: true,
+        });
+      }
+    }
+
+    Ok(())
+  }
+}
+
+impl<'a> VTableSlot<'a> {
+  fn new(method: MethodDefinition<'a>, name: &'a str, signature: MethodSig) -> Self {
+    Self {
+      declaration: method,
+      implementation: method,
+      name,
+      signature,
+    }
+  }
+}
+
+/// Returns whether a method can be overridden by a method of a type deriving from its declaring
+/// type in the same module.
+///
+/// Methods marked [MethodAttributes::STRICT] can only be overridden where they are accessible,
+/// which are all methods but private ones within a module.
+fn can_override(method: MethodDefinition<'_>) -> bool {
+  let access = method.flags() & MethodAttributes::MEMBER_ACCESS_MASK;
+
+  !method.flags().contains(MethodAttributes::STRICT)
+    || !matches!(
+      access,
+      MethodAttributes::PRIVATE | MethodAttributes::COMPILER_CONTROLLED
+    )
+}
+
+#[cfg(all(test, feature = "write"))]
+mod tests {
+  use super::super::members::MethodDefinition;
+  use super::super::tests::{build, read};
+  use super::super::Metadata;
+  use super::{Override, VTableIssue};
+  use crate::metadata::errors::ReadErrorKind;
+  use crate::metadata::streams::tables::flags::*;
+  use crate::metadata::streams::tables::id::*;
+  use crate::metadata::streams::tables::rows::*;
+
+  /// `abstract class A { abstract F(); virtual G(); sealed H(); private strict P(); }`,
+  /// `class B : A { override F(); new virtual G(); override H(); override P(); virtual Q(); }`,
+  /// `class C : B { void Q() as an explicit override; new G(); }` and `class D : A`.
+  fn fixture() -> Vec<u8> {
+    build(|md| {
+      let empty = md.strings.add("");
+      let signature = md.blobs.add(&[0x20, 0x00, 0x01]);
+      let object = md.tables.add_type_ref(TypeRefRow::new(
+        ResolutionScope::AssemblyRef(RowId::new(1)),
+        md.strings.add("Object"),
+        md.strings.add("System"),
+      ));
+
+      for (name, flags, extends, methods) in [
+        (
+          "<Module>",
+          TypeAttributes::empty(),
+          TypeDefOrRef::TypeDef(RowId::new(0)),
+          1,
+        ),
+        (
+          "A",
+          TypeAttributes::ABSTRACT,
+          TypeDefOrRef::TypeRef(object),
+          1,
+        ),
+        (
+          "B",
+          TypeAttributes::empty(),
+          TypeDefOrRef::TypeDef(RowId::new(2)),
+          5,
+        ),
+        (
+          "C",
+          TypeAttributes::empty(),
+          TypeDefOrRef::TypeDef(RowId::new(3)),
+          10,
+        ),
+        (
+          "D",
+          TypeAttributes::empty(),
+          TypeDefOrRef::TypeDef(RowId::new(2)),
+          12,
+        ),
+      ] {
+        md.tables.add_type_def(TypeDefRow::new(
+          flags | TypeAttributes::PUBLIC,
+          md.strings.add(name),
+          empty,
+          extends,
+          RowId::new(1),
+          RowId::new(methods),
+        ));
+      }
+
+      let public = MethodAttributes::PUBLIC | MethodAttributes::HIDE_BY_SIG;
+      let new_slot = public | MethodAttributes::VIRTUAL | MethodAttributes::NEW_SLOT;
+      let reuse_slot = public | MethodAttributes::VIRTUAL;
+
+      for (name, flags) in [
+        ("F", new_slot | MethodAttributes::ABSTRACT),
+        ("G", new_slot),
+        ("H", new_slot | MethodAttributes::FINAL),
+        (
+          "P",
+          MethodAttributes::PRIVATE
+            | MethodAttributes::VIRTUAL
+            | MethodAttributes::NEW_SLOT
+            | MethodAttributes::STRICT,
+        ),
+        ("F", reuse_slot),
+        ("G", new_slot),
+        ("H", reuse_slot),
+        ("P", reuse_slot),
+        ("Q", new_slot),
+        (
+          "B.Q",
+          MethodAttributes::PRIVATE
+            | MethodAttributes::VIRTUAL
+            | MethodAttributes::NEW_SLOT
+            | MethodAttributes::FINAL,
+        ),
+        ("G", public),
+      ] {
+        md.tables.add_method_def(MethodDefRow::new(
+          0,
+          MethodImplAttributes::empty(),
+          flags,
+          md.strings.add(name),
+          signature,
+          RowId::new(1),
+        ));
+      }
+
+      md.tables.add_method_impl(MethodImplRow::new(
+        RowId::new(4),
+        MethodDefOrRef::MethodDef(RowId::new(10)),
+        MethodDefOrRef::MethodDef(RowId::new(9)),
+      ));
+    })
+  }
+
+  fn method<'a>(metadata: &'a Metadata<'a>, index: usize) -> MethodDefinition<'a> {
+    metadata.method_def(RowId::new(index)).unwrap()
+  }
+
+  /// Returns the indices of the declaration and implementation of each slot of the given type.
+  fn implementations(metadata: &Metadata<'_>, ty: usize) -> Vec<(usize, usize)> {
+    let table = metadata.vtable(RowId::new(ty)).unwrap();
+
+    table
+      .slots()
+      .iter()
+      .map(|slot| {
+        (
+          slot.declaration.id().index(),
+          slot.implementation.id().index(),
+        )
+      })
+      .collect()
+  }
+
+  #[test]
+  fn test_new_slots() {
+    let bytes = fixture();
+    let metadata = read(&bytes);
+    let a = metadata.vtable(RowId::new(2)).unwrap();
+
+    assert!(!a.is_base_known());
+    assert!(a.issues().is_empty());
+    assert_eq!(
+      vec![(1, 1), (2, 2), (3, 3), (4, 4)],
+      implementations(&metadata, 2)
+    );
+  }
+
+  #[test]
+  fn test_overrides_and_issues() {
+    let bytes = fixture();
+    let metadata = read(&bytes);
+    let method = |index| method(&metadata, index);
+    let b = metadata.vtable(RowId::new(3)).unwrap();
+
+    assert_eq!(
+      vec![
+        (1, 5),
+        (2, 2),
+        (3, 3),
+        (4, 4),
+        (6, 6),
+        (7, 7),
+        (8, 8),
+        (9, 9)
+      ],
+      implementations(&metadata, 3)
+    );
+    assert_eq!(
+      [Override {
+        method: method(5),
+        overridden: method(1),
+        explicit: false,
+      }],
+      b.overrides()
+    );
+    assert_eq!(
+      [
+        VTableIssue::Hides {
+          method: method(6),
+          hidden: method(2),
+        },
+        VTableIssue::OverridesFinal {
+          method: method(7),
+          overridden: method(3),
+        },
+        VTableIssue::InaccessibleOverride {
+          method: method(8),
+          overridden: method(4),
+        },
+      ],
+      b.issues()
+    );
+  }
+
+  #[test]
+  fn test_explicit_override() {
+    let bytes = fixture();
+    let metadata = read(&bytes);
+    let method = |index| method(&metadata, index);
+    let c = metadata.vtable(RowId::new(4)).unwrap();
+
+    assert_eq!(
+      Some(method(10)),
+      c.slot(RowId::new(9)).map(|slot| slot.implementation)
+    );
+    assert_eq!(
+      [Override {
+        method: method(10),
+        overridden: method(9),
+        explicit: true,
+      }],
+      c.overrides()
+    );
+    assert_eq!(
+      [
+        VTableIssue::Hides {
+          method: method(11),
+          hidden: method(2),
+        },
+        VTableIssue::Hides {
+          method: method(11),
+          hidden: method(6),
+        },
+      ],
+      c.issues()
+    );
+  }
+
+  #[test]
+  fn test_missing_override() {
+    let bytes = fixture();
+    let metadata = read(&bytes);
+    let d = metadata.vtable(RowId::new(5)).unwrap();
+
+    assert_eq!(
+      [VTableIssue::MissingOverride {
+        method: method(&metadata, 1)
+      }],
+      d.issues()
+    );
+  }
+
+  #[test]
+  fn test_base_type_errors() {
+    // `class X : Y` and `class Y : X`.
+    let bytes = build(|md| {
+      for (name, extends) in [("X", 2), ("Y", 1)] {
+        md.tables.add_type_def(TypeDefRow::new(
+          TypeAttributes::PUBLIC,
+          md.strings.add(name),
+          md.strings.add(""),
+          TypeDefOrRef::TypeDef(RowId::new(extends)),
+          RowId::new(1),
+          RowId::new(1),
+        ));
+      }
+    });
+    let metadata = read(&bytes);
+    let kind = |ty| metadata.vtable(RowId::new(ty)).err().map(|err| err.kind());
+
+    assert_eq!(Some(ReadErrorKind::Invalid), kind(1));
+    assert_eq!(
+      Some(ReadErrorKind::OutOfRange { index: 3, len: 2 }),
+      kind(3)
+    );
+  }
+}
@@ -10,13 +10,14 @@ pub use write::*;
 /// A handle to a blob of bytes in the `#Blob` metadata stream.
 #[repr(transparent)]
 #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
-pub struct BlobId(usize);
+pub struct BlobId(pub(crate) usize);
 
 #[cfg(feature = "read")]
 #[doc(hidden)]
 mod read {
   use super::BlobId;
   use crate::bytes::{ByteSize, ByteSliceExt, CompressedLength, FromBytes};
+  use crate::metadata::errors::{ReadError, ReadErrorKind, ReadLocation};
   use crate::metadata::headers::HeapSizes;
   use core::fmt;
 
@@ -29,16 +30,25 @@ mod read {
   pub struct BlobsHeap<'a>(pub(crate) &'a [u8]);
 
   impl<'a> BlobsHeap<'a> {
-    /// Returns the guid at the given [BlobId].
+    /// Returns the blob at the given [BlobId].
     ///
-    /// Will return `None` if the given id is out of bounds.
-    pub fn get(&self, id: BlobId) -> Option<&'a [u8]> {
+    /// Will return an error if the given id is out of bounds or the blob extends past the end of the
+    /// heap.
+    pub fn get(&self, id: BlobId) -> Result<&'a [u8], ReadError> {
+      let error = |kind| ReadError::new(ReadLocation::Stream("#Blob"), id.0, kind);
       let mut offset = id.0;
 
-      let len = self.0.read_with(&mut offset, CompressedLength)?;
-      let data = self.0.read_with(&mut offset, len)?;
+      if offset >= self.0.len() {
+        return Err(error(ReadErrorKind::OutOfRange {
+          index: offset,
+          len: self.0.len(),
+        }));
+      }
+
+      let len = self.0.read_with(&mut offset, CompressedLength);
+      let data = len.and_then(|len| self.0.read_with(&mut offset, len));
 
-      Some(data)
+      data.ok_or_else(|| error(ReadErrorKind::Truncated))
     }
   }
 
@@ -73,5 +83,151 @@ mod read {
 #[cfg(feature = "write")]
 #[doc(hidden)]
 mod write {
-  // TODO: implement [BlobsHeapBuilder].
+  use super::{BlobId, BlobsHeap};
+  use crate::bytes::{ByteSize, ToBytes};
+  use crate::bytes::{ByteSliceExt, ByteVecExt, CompressedLength};
+  use crate::metadata::headers::HeapSizes;
+  use std::collections::HashMap;
+  use std::vec::Vec;
+
+  /// Builds a `#Blob` heap.
+  ///
+  /// Blobs are deduplicated and prefixed with their compressed length.
+  #[derive(Debug, Clone)]
+  pub struct BlobsHeapBuilder {
+    bytes: Vec<u8>,
+    ids: HashMap<Vec<u8>, BlobId>,
+  }
+
+  impl BlobsHeapBuilder {
+    /// Creates a new [BlobsHeapBuilder] containing only the empty blob.
+    pub fn new() -> Self {
+      Self {
+        bytes: vec![0],
+        ids: HashMap::new(),
+      }
+    }
+
+    /// Creates a [BlobsHeapBuilder] containing the bytes of an existing [BlobsHeap].
+    ///
+    /// The existing bytes are kept as they are, so every existing [BlobId] stays valid.  Blobs are
+    /// deduplicated against the existing blobs up to the first one that cannot be read.
+    pub fn from_heap(heap: BlobsHeap<'_>) -> Self {
+      let mut ids = HashMap::new();
+      let mut offset = 0;
+
+      loop {
+        let id = BlobId(offset);
+        let Some(len) = heap.0.read_with::<usize, _>(&mut offset, CompressedLength) else {
+          break;
+        };
+        let Some(value) = heap.0.get(offset..offset + len) else {
+          break;
+        };
+
+        if len != 0 {
+          ids.entry(value.to_vec()).or_insert(id);
+        }
+
+        offset += len;
+      }
+
+      Self {
+        bytes: heap.0.to_vec(),
+        ids,
+      }
+    }
+
+    /// Adds a blob to the heap and returns its [BlobId].
+    ///
+    /// # Panics
+    ///
+    /// Panics if the blob is larger than `0x1FFFFFFF` bytes.
+    pub fn add(&mut self, value: &[u8]) -> BlobId {
+      if value.is_empty() {
+        return BlobId(0);
+      }
+
+      if let Some(id) = self.ids.get(value) {
+        return *id;
+      }
+
+      let id = BlobId(self.bytes.len());
+
+      self.bytes.write_with(value.len(), CompressedLength);
+      self.bytes.extend_from_slice(value);
+      self.ids.insert(value.to_vec(), id);
+
+      id
+    }
+
+    /// Returns the size of the heap in bytes, including padding.
+    pub fn len(&self) -> usize {
+      (self.bytes.len() + 3) & !3
+    }
+
+    /// Returns `true` if the heap contains only the empty blob.
+    pub fn is_empty(&self) -> bool {
+      self.bytes.len() == 1
+    }
+
+    /// Returns [HeapSizes::WIDE_BLOB_HEAP] if indexes into the heap need to be 4 bytes wide.
+    pub fn heap_sizes(&self) -> HeapSizes {
+      match self.len() > u16::MAX as usize {
+        true => HeapSizes::WIDE_BLOB_HEAP,
+        false => HeapSizes::empty(),
+      }
+    }
+
+    /// Returns the bytes of the heap padded to a 4 byte boundary.
+    pub fn finish(self) -> Vec<u8> {
+      let len = self.len();
+      let mut bytes = self.bytes;
+
+      bytes.resize(len, 0);
+      bytes
+    }
+  }
+
+  impl Default for BlobsHeapBuilder {
+    fn default() -> Self {
+      Self::new()
+    }
+  }
+
+  impl ToBytes<HeapSizes> for BlobId {
+    #[inline]
+    fn to_bytes(&self, buf: &mut Vec<u8>, heap_sizes: HeapSizes) {
+      match Self::byte_size(heap_sizes) {
+        4 => (self.0 as u32).to_bytes(buf, ()),
+        _ => (self.0 as u16).to_bytes(buf, ()),
+      }
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  #[test]
+  #[cfg(feature = "write")]
+  fn test_build() {
+    use super::{BlobId, BlobsHeap, BlobsHeapBuilder};
+
+    let mut builder = BlobsHeapBuilder::new();
+    let short = builder.add(&[0x20, 0x00, 0x01]);
+    let long = builder.add(&[0xab; 0x80]);
+
+    assert_eq!(BlobId(0), builder.add(&[]));
+    assert_eq!(short, builder.add(&[0x20, 0x00, 0x01]));
+    assert_eq!(BlobId(5), long);
+
+    let bytes = builder.finish();
+    let heap = BlobsHeap(&bytes);
+
+    assert_eq!(0, bytes.len() % 4);
+    assert_eq!(&[0x80, 0x80, 0xab], &bytes[5..8]);
+    assert_eq!(Ok(&[][..]), heap.get(BlobId(0)));
+    assert_eq!(Ok(&[0x20, 0x00, 0x01][..]), heap.get(short));
+    assert_eq!(Ok(&[0xab; 0x80][..]), heap.get(long));
+  }
 }
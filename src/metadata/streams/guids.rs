@@ -12,11 +12,19 @@ pub use write::*;
 #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
 pub struct GuidId(usize);
 
+impl GuidId {
+  /// Returns `true` if this id does not refer to a guid.
+  pub fn is_null(self) -> bool {
+    self.0 == 0
+  }
+}
+
 #[cfg(feature = "read")]
 #[doc(hidden)]
 mod read {
   use super::GuidId;
   use crate::bytes::{ByteSize, ByteSliceExt, FromBytes};
+  use crate::metadata::errors::{ReadError, ReadErrorKind, ReadLocation};
   use crate::metadata::headers::HeapSizes;
   use core::fmt;
 
@@ -31,12 +39,26 @@ mod read {
   impl<'a> GuidsHeap<'a> {
     /// Returns the guid from the given [GuidId].
     ///
-    /// Will return `None` if the given id is out of bounds.
-    pub fn get(&self, id: GuidId) -> Option<[u8; 16]> {
-      let beg = id.0;
-      let end = beg + 16;
+    /// Will return an error if the given id is null or out of bounds.
+    pub fn get(&self, id: GuidId) -> Result<[u8; 16], ReadError> {
+      // Unlike the other heaps, guids are addressed by a 1-based index rather than a byte offset.
+      let beg = id.0.saturating_sub(1).saturating_mul(16);
+
+      let guid = match id.0 {
+        0 => None,
+        _ => self.0.peek(&beg),
+      };
 
-      self.0.get(beg..end).and_then(|b| b.try_into().ok())
+      guid.ok_or_else(|| {
+        ReadError::new(
+          ReadLocation::Stream("#GUID"),
+          beg,
+          ReadErrorKind::OutOfRange {
+            index: id.0,
+            len: self.0.len() / 16,
+          },
+        )
+      })
     }
   }
 
@@ -71,5 +93,137 @@ mod read {
 #[cfg(feature = "write")]
 #[doc(hidden)]
 mod write {
-  // TODO: implement [GuidsHeapBuilder].
+  use super::{GuidId, GuidsHeap};
+  use crate::bytes::{ByteSize, ToBytes};
+  use crate::metadata::headers::HeapSizes;
+  use std::collections::HashMap;
+  use std::vec::Vec;
+
+  /// Builds a `#GUID` heap.
+  ///
+  /// Guids are deduplicated and addressed by a 1-based index.
+  #[derive(Debug, Clone, Default)]
+  pub struct GuidsHeapBuilder {
+    bytes: Vec<u8>,
+    ids: HashMap<[u8; 16], GuidId>,
+  }
+
+  impl GuidsHeapBuilder {
+    /// Creates a new empty [GuidsHeapBuilder].
+    pub fn new() -> Self {
+      Self::default()
+    }
+
+    /// Creates a [GuidsHeapBuilder] containing the guids of an existing [GuidsHeap].
+    ///
+    /// Every existing [GuidId] stays valid.
+    pub fn from_heap(heap: GuidsHeap<'_>) -> Self {
+      let mut ids = HashMap::new();
+
+      for (i, guid) in heap.0.chunks_exact(16).enumerate() {
+        ids.entry(guid.try_into().unwrap()).or_insert(GuidId(i + 1));
+      }
+
+      Self {
+        bytes: heap.0.to_vec(),
+        ids,
+      }
+    }
+
+    /// Adds a guid to the heap and returns its [GuidId].
+    ///
+    /// The nil guid is not added to the heap, a null [GuidId] is returned instead.
+    pub fn add(&mut self, value: [u8; 16]) -> GuidId {
+      if value == [0; 16] {
+        return GuidId(0);
+      }
+
+      let next = GuidId(self.bytes.len().div_ceil(16) + 1);
+      let id = *self.ids.entry(value).or_insert(next);
+
+      if id == next {
+        self.bytes.resize((next.0 - 1) * 16, 0);
+        self.bytes.extend_from_slice(&value);
+      }
+
+      id
+    }
+
+    /// Returns the size of the heap in bytes.
+    pub fn len(&self) -> usize {
+      self.bytes.len()
+    }
+
+    /// Returns `true` if the heap contains no guids.
+    pub fn is_empty(&self) -> bool {
+      self.bytes.is_empty()
+    }
+
+    /// Returns [HeapSizes::WIDE_GUID_HEAP] if indexes into the heap need to be 4 bytes wide.
+    pub fn heap_sizes(&self) -> HeapSizes {
+      match self.bytes.len() / 16 > u16::MAX as usize {
+        true => HeapSizes::WIDE_GUID_HEAP,
+        false => HeapSizes::empty(),
+      }
+    }
+
+    /// Returns the bytes of the heap.
+    pub fn finish(self) -> Vec<u8> {
+      self.bytes
+    }
+  }
+
+  impl ToBytes<HeapSizes> for GuidId {
+    #[inline]
+    fn to_bytes(&self, buf: &mut Vec<u8>, heap_sizes: HeapSizes) {
+      match Self::byte_size(heap_sizes) {
+        4 => (self.0 as u32).to_bytes(buf, ()),
+        _ => (self.0 as u16).to_bytes(buf, ()),
+      }
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  #[test]
+  #[cfg(feature = "read")]
+  fn test_get_one_based() {
+    use super::{GuidId, GuidsHeap};
+    use crate::metadata::errors::ReadErrorKind;
+
+    let mut bytes = [0xaa; 32];
+    bytes[16..].fill(0xbb);
+    let heap = GuidsHeap(&bytes);
+
+    assert_eq!(Ok([0xaa; 16]), heap.get(GuidId(1)));
+    assert_eq!(Ok([0xbb; 16]), heap.get(GuidId(2)));
+    assert!(heap.get(GuidId(0)).is_err());
+    assert_eq!(
+      ReadErrorKind::OutOfRange { index: 3, len: 2 },
+      heap.get(GuidId(3)).unwrap_err().kind()
+    );
+  }
+
+  #[test]
+  #[cfg(feature = "write")]
+  fn test_build() {
+    use super::{GuidId, GuidsHeap, GuidsHeapBuilder};
+
+    let mut builder = GuidsHeapBuilder::new();
+    let a = builder.add([0xaa; 16]);
+    let b = builder.add([0xbb; 16]);
+
+    assert_eq!(GuidId(0), builder.add([0; 16]));
+    assert_eq!(GuidId(1), a);
+    assert_eq!(GuidId(2), b);
+    assert_eq!(a, builder.add([0xaa; 16]));
+
+    let bytes = builder.finish();
+    let heap = GuidsHeap(&bytes);
+
+    assert_eq!(32, bytes.len());
+    assert_eq!(Ok([0xaa; 16]), heap.get(a));
+    assert_eq!(Ok([0xbb; 16]), heap.get(b));
+  }
 }
@@ -1,5 +1,6 @@
 pub mod blobs;
 pub mod guids;
+pub mod pdb;
 pub mod strings;
 pub mod tables;
 pub mod user_strings;
@@ -7,20 +8,18 @@ pub mod user_strings;
 #[cfg(feature = "read")]
 #[doc(inline)]
 pub use read::*;
-#[cfg(feature = "write")]
-#[doc(inline)]
-pub use write::*;
 
 #[cfg(feature = "read")]
 #[doc(hidden)]
 mod read {
   use super::blobs::BlobsHeap;
   use super::guids::GuidsHeap;
+  use super::pdb::PdbStream;
   use super::strings::StringsHeap;
   use super::tables::TablesStream;
   use super::user_strings::UserStringsHeap;
   use crate::bytes::ByteSliceExt;
-  use crate::metadata::errors::MetadataStreamReadError;
+  use crate::metadata::errors::{MetadataStreamReadError, ReadError, ReadErrorKind, ReadLocation};
   use crate::metadata::headers::MetadataStreamHeader;
 
   /// Iterates over the metadata streams.
@@ -28,11 +27,17 @@ mod read {
     len: usize,
     bytes: &'a [u8],
     offset: usize,
+    pdb: Option<PdbStream<'a>>,
   }
 
   impl<'a> MetadataStreamIter<'a> {
     pub(crate) fn new(len: usize, offset: usize, bytes: &'a [u8]) -> Self {
-      Self { len, bytes, offset }
+      Self {
+        len,
+        bytes,
+        offset,
+        pdb: None,
+      }
     }
   }
 
@@ -46,17 +51,49 @@ mod read {
 
       self.len -= 1;
 
-      let header = self.bytes.read::<MetadataStreamHeader>(&mut self.offset)?;
+      let offset = self.offset;
+      let header = match self.bytes.read::<MetadataStreamHeader>(&mut self.offset) {
+        Some(header) => header,
+        None => {
+          // The remaining headers cannot be located without this one.
+          self.len = 0;
+
+          let err = ReadError::new(ReadLocation::Root, offset, ReadErrorKind::Truncated);
+
+          return Some(Err(err.into()));
+        }
+      };
       let data = match header.data(self.bytes) {
         Some(data) => data,
         None => return Some(Err(MetadataStreamReadError::MissingData { header })),
       };
 
       Some(Ok(match header.name.to_bytes() {
-        b"#~" => MetadataStream::Tables(match TablesStream::from_bytes(data) {
-          Ok(tables) => tables,
-          Err(err) => return Some(Err(err)),
-        }),
+        // The uncompressed `#-` stream has the same layout, and may contain the `FieldPtr`,
+        // `MethodPtr`, `ParamPtr`, `EventPtr` and `PropertyPtr` tables.
+        b"#~" | b"#-" => {
+          // The `#Pdb` stream precedes the `#~` stream in portable PDBs and provides the row counts
+          // of the type-system tables the debug tables refer to.
+          let tables = match self.pdb.map(|pdb| pdb.type_system_table_rows()) {
+            Some(Ok(rows)) => TablesStream::from_bytes_with_external_rows(data, &rows),
+            Some(Err(err)) => Err(err.into()),
+            None => TablesStream::from_bytes(data),
+          };
+
+          match tables {
+            Ok(mut tables) => {
+              tables.uncompressed = header.name.to_bytes() == b"#-";
+
+              MetadataStream::Tables(tables)
+            }
+            Err(err) => return Some(Err(err)),
+          }
+        }
+        b"#Pdb" => {
+          self.pdb = Some(PdbStream(data));
+
+          MetadataStream::Pdb(PdbStream(data))
+        }
         b"#US" => MetadataStream::UserStrings(UserStringsHeap(data)),
         b"#Blob" => MetadataStream::Blobs(BlobsHeap(data)),
         b"#GUID" => MetadataStream::Guids(GuidsHeap(data)),
@@ -69,12 +106,15 @@ mod read {
   /// A ECMA-335 metadata stream.
   #[derive(Debug)]
   #[non_exhaustive]
+  #[allow(clippy::large_enum_variant)]
   pub enum MetadataStream<'a> {
     /// The `#Blob` metadata stream.
     Blobs(BlobsHeap<'a>),
     /// The `#GUID` metadata stream.
     Guids(GuidsHeap<'a>),
-    /// The `#~` metadata stream.
+    /// The `#Pdb` metadata stream.
+    Pdb(PdbStream<'a>),
+    /// The `#~` metadata stream, or the uncompressed `#-` metadata stream.
     Tables(TablesStream<'a>),
     /// The `#Strings` metadata stream.
     Strings(StringsHeap<'a>),
@@ -106,6 +146,14 @@ mod read {
       }
     }
 
+    /// Returns the [PdbStream] if this is the `#Pdb` metadata stream.
+    pub fn as_pdb(&self) -> Option<PdbStream<'a>> {
+      match self {
+        Self::Pdb(pdb) => Some(*pdb),
+        _ => None,
+      }
+    }
+
     /// Returns the [StringsHeap] if this is the `#Strings` metadata stream.
     pub fn as_strings(&self) -> Option<StringsHeap<'a>> {
       match self {
@@ -131,7 +179,3 @@ mod read {
     }
   }
 }
-
-#[cfg(feature = "write")]
-#[doc(hidden)]
-mod write {}
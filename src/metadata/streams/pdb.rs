@@ -0,0 +1,110 @@
+//! The `#Pdb` metadata stream.
+
+#[cfg(feature = "read")]
+#[doc(inline)]
+pub use read::*;
+
+#[cfg(feature = "read")]
+#[doc(hidden)]
+mod read {
+  use crate::bytes::{ByteSliceExt, FromBytes};
+  use crate::metadata::errors::{ReadError, ReadErrorKind, ReadLocation};
+  use core::fmt;
+
+  /// A `#Pdb` stream.
+  ///
+  /// Only present in portable PDB metadata.  Contains the PDB id, the entry point of the described
+  /// assembly and the row counts of the type-system tables the debug tables refer to.
+  #[repr(transparent)]
+  #[derive(Default, Clone, Copy)]
+  pub struct PdbStream<'a>(pub(crate) &'a [u8]);
+
+  impl<'a> PdbStream<'a> {
+    /// Returns the 20 byte PDB id.
+    ///
+    /// The first 16 bytes are a guid and the last 4 bytes a timestamp, matching the values found in
+    /// the CodeView debug directory entry of the described PE file.
+    pub fn id(&self) -> Result<[u8; 20], ReadError> {
+      self.peek(0)
+    }
+
+    /// Returns the `MethodDef` token of the entry point of the described assembly, or `0`.
+    pub fn entry_point(&self) -> Result<u32, ReadError> {
+      self.peek(20)
+    }
+
+    /// Returns the bit vector of type-system tables referenced by the debug tables.
+    pub fn referenced_type_system_tables(&self) -> Result<u64, ReadError> {
+      self.peek(24)
+    }
+
+    /// Returns the number of rows in each referenced type-system table, indexed by the id of the
+    /// table.
+    ///
+    /// Will return an error if the stream ends before the row count of a referenced table.
+    pub fn type_system_table_rows(&self) -> Result<[u32; 64], ReadError> {
+      let referenced = self.referenced_type_system_tables()?;
+      let mut offset = 32;
+      let mut rows = [0; 64];
+
+      for (i, row) in rows.iter_mut().enumerate() {
+        if referenced & (1 << i) != 0 {
+          *row = self.peek(offset)?;
+          offset += 4;
+        }
+      }
+
+      Ok(rows)
+    }
+
+    fn peek<R: FromBytes<'a>>(&self, offset: usize) -> Result<R, ReadError> {
+      self.0.peek(&offset).ok_or_else(|| {
+        ReadError::new(
+          ReadLocation::Stream("#Pdb"),
+          offset,
+          ReadErrorKind::Truncated,
+        )
+      })
+    }
+  }
+
+  impl fmt::Debug for PdbStream<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+      f.debug_tuple("PdbStream").finish()
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  #[test]
+  #[cfg(feature = "read")]
+  fn test_type_system_table_rows() {
+    use super::PdbStream;
+    use crate::metadata::errors::{ReadErrorKind, ReadLocation};
+
+    // Referencing the `TypeDef` and `MethodDef` tables.
+    let mut bytes = [0; 40];
+    bytes[..20].fill(0xaa);
+    bytes[20..24].copy_from_slice(&0x0600_0001u32.to_le_bytes());
+    bytes[24..32].copy_from_slice(&0x44u64.to_le_bytes());
+    bytes[32..36].copy_from_slice(&3u32.to_le_bytes());
+    bytes[36..40].copy_from_slice(&5u32.to_le_bytes());
+
+    let pdb = PdbStream(&bytes);
+    let rows = pdb.type_system_table_rows().unwrap();
+
+    assert_eq!(Ok([0xaa; 20]), pdb.id());
+    assert_eq!(Ok(0x0600_0001), pdb.entry_point());
+    assert_eq!((3, 5), (rows[0x02], rows[0x06]));
+    assert_eq!(2, rows.iter().filter(|&&count| count != 0).count());
+
+    let err = PdbStream(&bytes[..36])
+      .type_system_table_rows()
+      .unwrap_err();
+
+    assert_eq!(ReadLocation::Stream("#Pdb"), err.location());
+    assert_eq!(36, err.offset());
+    assert_eq!(ReadErrorKind::Truncated, err.kind());
+  }
+}
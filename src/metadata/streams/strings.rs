@@ -17,6 +17,7 @@ pub struct StringId(usize);
 mod read {
   use super::StringId;
   use crate::bytes::{ByteSize, ByteSliceExt, FromBytes};
+  use crate::metadata::errors::{ReadError, ReadErrorKind, ReadLocation};
   use crate::metadata::headers::HeapSizes;
   use core::ffi::CStr;
   use core::fmt;
@@ -33,10 +34,31 @@ mod read {
   impl<'a> StringsHeap<'a> {
     /// Returns the string for the given [StringId].
     ///
-    /// Will return `None` if the given id is out of bounds.  Performs a scan for a `nul` byte to
-    /// determine the length of the string.
-    pub fn get(&self, id: StringId) -> Option<&'a CStr> {
-      CStr::from_bytes_until_nul(self.0.get(id.0..)?).ok()
+    /// Will return an error if the given id is out of bounds or the string is not `nul` terminated.
+    /// Performs a scan for a `nul` byte to determine the length of the string.
+    pub fn get(&self, id: StringId) -> Result<&'a CStr, ReadError> {
+      let error = |kind| ReadError::new(ReadLocation::Stream("#Strings"), id.0, kind);
+      let data = self.0.get(id.0..).ok_or_else(|| {
+        error(ReadErrorKind::OutOfRange {
+          index: id.0,
+          len: self.0.len(),
+        })
+      })?;
+
+      CStr::from_bytes_until_nul(data).map_err(|_| error(ReadErrorKind::Truncated))
+    }
+
+    /// Returns the string for the given [StringId] as a `str`.
+    ///
+    /// Will return an error if [StringsHeap::get] does or the string is not valid utf8.
+    pub fn get_str(&self, id: StringId) -> Result<&'a str, ReadError> {
+      self.get(id)?.to_str().map_err(|err| {
+        ReadError::new(
+          ReadLocation::Stream("#Strings"),
+          id.0 + err.valid_up_to(),
+          ReadErrorKind::BadUtf8(err),
+        )
+      })
     }
   }
 
@@ -106,5 +128,280 @@ mod read {
 #[cfg(feature = "write")]
 #[doc(hidden)]
 mod write {
-  // TODO: implement [StringHeapBuilder].
+  use super::{StringId, StringsHeap};
+  use crate::bytes::{ByteSize, ToBytes};
+  use crate::metadata::headers::HeapSizes;
+  use std::collections::hash_map::RandomState;
+  use std::collections::HashMap;
+  use std::hash::BuildHasher;
+  use std::vec::Vec;
+
+  /// Builds a `#Strings` heap.
+  ///
+  /// Strings are deduplicated and a string that is the suffix of a previously added string shares
+  /// its bytes, so `"Name"` added after `"TypeName"` points inside `"TypeName"`.  Ids are final as
+  /// soon as they are returned.  Use [StringsHeapBuilder::add_all] to add many strings at once,
+  /// which orders them so that every suffix is shared regardless of the order they are given in.
+  #[derive(Debug, Clone)]
+  pub struct StringsHeapBuilder {
+    bytes: Vec<u8>,
+    /// Maps the hash of every suffix of every string in the heap to its first offset.
+    suffixes: HashMap<u64, usize>,
+    state: RandomState,
+  }
+
+  impl StringsHeapBuilder {
+    /// Creates a new [StringsHeapBuilder] containing only the empty string.
+    pub fn new() -> Self {
+      Self {
+        bytes: vec![0],
+        suffixes: HashMap::new(),
+        state: RandomState::new(),
+      }
+    }
+
+    /// Creates a [StringsHeapBuilder] containing the bytes of an existing [StringsHeap].
+    ///
+    /// The existing bytes are kept as they are, including unreferenced strings, so every existing
+    /// [StringId] stays valid.  Added strings share the existing strings where possible.
+    pub fn from_heap(heap: StringsHeap<'_>) -> Self {
+      let mut builder = Self {
+        bytes: heap.0.to_vec(),
+        suffixes: HashMap::new(),
+        state: RandomState::new(),
+      };
+
+      let mut offset = 0;
+
+      for value in heap.0.split(|&b| b == 0) {
+        builder.index(offset, value);
+        offset += value.len() + 1;
+      }
+
+      builder
+    }
+
+    /// Adds a string to the heap and returns its [StringId].
+    ///
+    /// # Panics
+    ///
+    /// Panics if the string contains a `nul` character.
+    pub fn add(&mut self, value: &str) -> StringId {
+      let value = value.as_bytes();
+
+      assert!(
+        !value.contains(&0),
+        "`#Strings` heap entries cannot contain `nul`"
+      );
+
+      if value.is_empty() {
+        return StringId(0);
+      }
+
+      if let Some(offset) = self.find(value) {
+        return StringId(offset);
+      }
+
+      let offset = self.bytes.len();
+
+      self.bytes.extend_from_slice(value);
+      self.bytes.push(0);
+      self.index(offset, value);
+
+      StringId(offset)
+    }
+
+    /// Adds all the given strings to the heap and returns their [StringId]s in the given order.
+    ///
+    /// Strings are added longest first among strings sharing a suffix, the same ordering Roslyn
+    /// uses, so that every string that is the suffix of another is shared.
+    ///
+    /// # Panics
+    ///
+    /// Panics if a string contains a `nul` character.
+    pub fn add_all<'s, I>(&mut self, values: I) -> Vec<StringId>
+    where
+      I: IntoIterator<Item = &'s str>,
+    {
+      let values = values.into_iter().collect::<Vec<_>>();
+      let mut order = (0..values.len()).collect::<Vec<_>>();
+      let mut ids = vec![StringId(0); values.len()];
+
+      // Sorting the reversed strings in descending order places every string after the strings it
+      // is a suffix of.
+      order.sort_by(|&a, &b| {
+        let a = values[a].bytes().rev();
+        let b = values[b].bytes().rev();
+
+        b.cmp(a)
+      });
+
+      for i in order {
+        ids[i] = self.add(values[i]);
+      }
+
+      ids
+    }
+
+    /// Returns the size of the heap in bytes, including padding.
+    pub fn len(&self) -> usize {
+      (self.bytes.len() + 3) & !3
+    }
+
+    /// Returns `true` if the heap contains only the empty string.
+    pub fn is_empty(&self) -> bool {
+      self.bytes.len() == 1
+    }
+
+    /// Returns [HeapSizes::WIDE_STRING_HEAP] if indexes into the heap need to be 4 bytes wide.
+    pub fn heap_sizes(&self) -> HeapSizes {
+      match self.len() > u16::MAX as usize {
+        true => HeapSizes::WIDE_STRING_HEAP,
+        false => HeapSizes::empty(),
+      }
+    }
+
+    /// Returns the bytes of the heap padded to a 4 byte boundary.
+    pub fn finish(self) -> Vec<u8> {
+      let len = self.len();
+      let mut bytes = self.bytes;
+
+      bytes.resize(len, 0);
+      bytes
+    }
+
+    /// Records every suffix of the string at the given offset.
+    fn index(&mut self, offset: usize, value: &[u8]) {
+      for i in 0..value.len() {
+        let hash = self.state.hash_one(&value[i..]);
+
+        self.suffixes.entry(hash).or_insert(offset + i);
+      }
+    }
+
+    /// Returns the offset of an existing string or suffix equal to the given value.
+    fn find(&self, value: &[u8]) -> Option<usize> {
+      let offset = *self.suffixes.get(&self.state.hash_one(value))?;
+      let end = offset + value.len();
+
+      match self.bytes.get(offset..end) == Some(value) && self.bytes.get(end) == Some(&0) {
+        true => Some(offset),
+        false => None,
+      }
+    }
+  }
+
+  impl Default for StringsHeapBuilder {
+    fn default() -> Self {
+      Self::new()
+    }
+  }
+
+  impl ToBytes<HeapSizes> for StringId {
+    #[inline]
+    fn to_bytes(&self, buf: &mut Vec<u8>, heap_sizes: HeapSizes) {
+      match Self::byte_size(heap_sizes) {
+        4 => (self.0 as u32).to_bytes(buf, ()),
+        _ => (self.0 as u16).to_bytes(buf, ()),
+      }
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::{StringId, StringsHeap};
+  use crate::metadata::errors::{ReadErrorKind, ReadLocation};
+  #[cfg(feature = "write")]
+  use {super::StringsHeapBuilder, crate::metadata::headers::HeapSizes};
+
+  #[test]
+  fn test_get_errors() {
+    let heap = StringsHeap(b"\0Foo\0\xffBar\0Baz");
+
+    assert_eq!(Ok("Foo"), heap.get_str(StringId(1)));
+
+    let err = heap.get_str(StringId(5)).unwrap_err();
+    assert_eq!(ReadLocation::Stream("#Strings"), err.location());
+    assert_eq!(5, err.offset());
+    assert!(matches!(err.kind(), ReadErrorKind::BadUtf8(_)));
+
+    let err = heap.get(StringId(10)).unwrap_err();
+    assert_eq!(ReadErrorKind::Truncated, err.kind());
+
+    let err = heap.get(StringId(14)).unwrap_err();
+    assert_eq!(ReadErrorKind::OutOfRange { index: 14, len: 13 }, err.kind());
+    assert_eq!(
+      "Failed to read the `#Strings` stream at offset `0xe`: Index `14` is out of range for length `13`",
+      err.to_string()
+    );
+  }
+
+  #[test]
+  #[cfg(feature = "write")]
+  fn test_build_shares_suffixes() {
+    let mut builder = StringsHeapBuilder::new();
+    let type_name = builder.add("TypeName");
+    let name = builder.add("Name");
+
+    assert_eq!(StringId(0), builder.add(""));
+    assert_eq!(type_name, builder.add("TypeName"));
+    assert_eq!(StringId(type_name.0 + 4), name);
+
+    let bytes = builder.finish();
+    let heap = StringsHeap(&bytes);
+
+    assert_eq!(12, bytes.len());
+    assert_eq!(Ok("TypeName"), heap.get_str(type_name));
+    assert_eq!(Ok("Name"), heap.get_str(name));
+  }
+
+  #[test]
+  #[cfg(feature = "write")]
+  fn test_build_add_all() {
+    let mut builder = StringsHeapBuilder::new();
+    let ids = builder.add_all(["Name", "System", "TypeName", "Name", "Object"]);
+    let bytes = builder.finish();
+    let heap = StringsHeap(&bytes);
+
+    // The shorter "Name" shares the bytes of "TypeName" even though it was given first.
+    assert_eq!(b"\0Object\0System\0TypeName\0", &bytes[..]);
+    assert_eq!(ids[0], ids[3]);
+
+    for (id, value) in ids
+      .into_iter()
+      .zip(["Name", "System", "TypeName", "Name", "Object"])
+    {
+      assert_eq!(Ok(value), heap.get_str(id));
+    }
+  }
+
+  #[test]
+  #[cfg(feature = "write")]
+  fn test_build_heap_sizes() {
+    let mut builder = StringsHeapBuilder::new();
+
+    assert!(builder.heap_sizes().is_empty());
+
+    for i in 0..10_000 {
+      builder.add(&format!("String{i}"));
+    }
+
+    assert_eq!(HeapSizes::WIDE_STRING_HEAP, builder.heap_sizes());
+  }
+
+  #[test]
+  #[cfg(feature = "write")]
+  fn test_build_from_heap() {
+    let heap = StringsHeap(b"\0TypeName\0\0\0");
+    let mut builder = StringsHeapBuilder::from_heap(heap);
+
+    assert_eq!(StringId(5), builder.add("Name"));
+
+    let object = builder.add("Object");
+    let bytes = builder.finish();
+
+    assert_eq!(b"\0TypeName\0\0\0Object\0\0", &bytes[..]);
+    assert_eq!(Ok("Object"), StringsHeap(&bytes).get_str(object));
+  }
 }
@@ -1,5 +1,6 @@
 //! The `#~` metadata stream.
 
+pub mod debug;
 pub mod flags;
 pub mod id;
 pub mod rows;
@@ -19,7 +20,7 @@ mod read {
   use super::rows::{ModuleRow, *};
   use super::table::{TableBytes, TableReader};
   use crate::bytes::ByteSliceExt;
-  use crate::metadata::errors::MetadataStreamReadError;
+  use crate::metadata::errors::{MetadataStreamReadError, ReadError, ReadErrorKind, ReadLocation};
   use crate::metadata::headers::MetadataTablesHeader;
   use core::fmt;
 
@@ -31,6 +32,10 @@ mod read {
   pub struct TablesStream<'a> {
     bytes: &'a [u8],
     header: MetadataTablesHeader,
+    /// The offset of the end of the table data.
+    end: usize,
+    /// Whether the stream was read from an uncompressed `#-` stream.
+    pub(crate) uncompressed: bool,
     assemblies: TableBytes<'a, AssemblyRow>,
     assembly_oses: TableBytes<'a, AssemblyOsRow>,
     assembly_processors: TableBytes<'a, AssemblyProcessorRow>,
@@ -43,10 +48,12 @@ mod read {
     decl_securities: TableBytes<'a, DeclSecurityRow>,
     events: TableBytes<'a, EventRow>,
     event_maps: TableBytes<'a, EventMapRow>,
+    event_ptrs: TableBytes<'a, EventPtrRow>,
     exported_types: TableBytes<'a, ExportedTypeRow>,
     fields: TableBytes<'a, FieldRow>,
     field_layouts: TableBytes<'a, FieldLayoutRow>,
     field_marshals: TableBytes<'a, FieldMarshalRow>,
+    field_ptrs: TableBytes<'a, FieldPtrRow>,
     field_rvas: TableBytes<'a, FieldRvaRow>,
     files: TableBytes<'a, FileRow>,
     generic_params: TableBytes<'a, GenericParamRow>,
@@ -57,18 +64,29 @@ mod read {
     member_refs: TableBytes<'a, MemberRefRow>,
     method_defs: TableBytes<'a, MethodDefRow>,
     method_impls: TableBytes<'a, MethodImplRow>,
+    method_ptrs: TableBytes<'a, MethodPtrRow>,
     method_semantics: TableBytes<'a, MethodSemanticsRow>,
     method_specs: TableBytes<'a, MethodSpecRow>,
     modules: TableBytes<'a, ModuleRow>,
     module_refs: TableBytes<'a, ModuleRefRow>,
     nested_classes: TableBytes<'a, NestedClassRow>,
     params: TableBytes<'a, ParamRow>,
+    param_ptrs: TableBytes<'a, ParamPtrRow>,
     properties: TableBytes<'a, PropertyRow>,
     property_maps: TableBytes<'a, PropertyMapRow>,
+    property_ptrs: TableBytes<'a, PropertyPtrRow>,
     stand_alone_sigs: TableBytes<'a, StandAloneSigRow>,
     type_defs: TableBytes<'a, TypeDefRow>,
     type_refs: TableBytes<'a, TypeRefRow>,
     type_specs: TableBytes<'a, TypeSpecRow>,
+    documents: TableBytes<'a, DocumentRow>,
+    method_debug_informations: TableBytes<'a, MethodDebugInformationRow>,
+    local_scopes: TableBytes<'a, LocalScopeRow>,
+    local_variables: TableBytes<'a, LocalVariableRow>,
+    local_constants: TableBytes<'a, LocalConstantRow>,
+    import_scopes: TableBytes<'a, ImportScopeRow>,
+    state_machine_methods: TableBytes<'a, StateMachineMethodRow>,
+    custom_debug_informations: TableBytes<'a, CustomDebugInformationRow>,
   }
 
   impl<'a> TablesStream<'a> {
@@ -76,10 +94,31 @@ mod read {
     ///
     /// Attempts to parse and verify the tables stream header before returning.
     pub fn from_bytes(bytes: &'a [u8]) -> Result<Self, MetadataStreamReadError<'a>> {
+      Self::from_bytes_with_external_rows(bytes, &[0; 64])
+    }
+
+    /// Creates a [TablesStream] from the given bytes, using `external_rows` as the number of rows
+    /// for tables that are referenced by the stream but not present in it.
+    ///
+    /// The debug tables of a portable PDB refer to the type-system tables of the assembly it
+    /// describes, whose row counts are found in the `#Pdb` stream.
+    ///
+    /// See [PdbStream::type_system_table_rows](crate::metadata::streams::pdb::PdbStream::type_system_table_rows).
+    pub fn from_bytes_with_external_rows(
+      bytes: &'a [u8],
+      external_rows: &[u32; 64],
+    ) -> Result<Self, MetadataStreamReadError<'a>> {
       let offset = &mut 0;
-      let header = bytes
+      let mut header = bytes
         .read::<MetadataTablesHeader>(offset)
-        .ok_or(MetadataStreamReadError::NotEnough)?;
+        .ok_or_else(|| ReadError::new(ReadLocation::Stream("#~"), 0, ReadErrorKind::Truncated))?;
+
+      for (table, rows) in header.rows.iter_mut().enumerate() {
+        if header.valid & (1 << table) == 0 {
+          *rows = external_rows[table];
+        }
+      }
+
       let mut assemblies = TableBytes::default();
       let mut assembly_oses = TableBytes::default();
       let mut assembly_processors = TableBytes::default();
@@ -92,10 +131,12 @@ mod read {
       let mut decl_securities = TableBytes::default();
       let mut events = TableBytes::default();
       let mut event_maps = TableBytes::default();
+      let mut event_ptrs = TableBytes::default();
       let mut exported_types = TableBytes::default();
       let mut fields = TableBytes::default();
       let mut field_layouts = TableBytes::default();
       let mut field_marshals = TableBytes::default();
+      let mut field_ptrs = TableBytes::default();
       let mut field_rvas = TableBytes::default();
       let mut files = TableBytes::default();
       let mut generic_params = TableBytes::default();
@@ -106,20 +147,35 @@ mod read {
       let mut member_refs = TableBytes::default();
       let mut method_defs = TableBytes::default();
       let mut method_impls = TableBytes::default();
+      let mut method_ptrs = TableBytes::default();
       let mut method_semantics = TableBytes::default();
       let mut method_specs = TableBytes::default();
       let mut modules = TableBytes::default();
       let mut module_refs = TableBytes::default();
       let mut nested_classes = TableBytes::default();
       let mut params = TableBytes::default();
+      let mut param_ptrs = TableBytes::default();
       let mut properties = TableBytes::default();
       let mut property_maps = TableBytes::default();
+      let mut property_ptrs = TableBytes::default();
       let mut stand_alone_sigs = TableBytes::default();
       let mut type_defs = TableBytes::default();
       let mut type_refs = TableBytes::default();
       let mut type_specs = TableBytes::default();
+      let mut documents = TableBytes::default();
+      let mut method_debug_informations = TableBytes::default();
+      let mut local_scopes = TableBytes::default();
+      let mut local_variables = TableBytes::default();
+      let mut local_constants = TableBytes::default();
+      let mut import_scopes = TableBytes::default();
+      let mut state_machine_methods = TableBytes::default();
+      let mut custom_debug_informations = TableBytes::default();
 
       for table in 0..header.rows.len() {
+        if header.valid & (1 << table) == 0 {
+          continue;
+        }
+
         match table {
           AssemblyRow::ID => assemblies = TableBytes::from_bytes(bytes, offset, &header)?,
           AssemblyOsRow::ID => assembly_oses = TableBytes::from_bytes(bytes, offset, &header)?,
@@ -141,10 +197,12 @@ mod read {
           DeclSecurityRow::ID => decl_securities = TableBytes::from_bytes(bytes, offset, &header)?,
           EventRow::ID => events = TableBytes::from_bytes(bytes, offset, &header)?,
           EventMapRow::ID => event_maps = TableBytes::from_bytes(bytes, offset, &header)?,
+          EventPtrRow::ID => event_ptrs = TableBytes::from_bytes(bytes, offset, &header)?,
           ExportedTypeRow::ID => exported_types = TableBytes::from_bytes(bytes, offset, &header)?,
           FieldRow::ID => fields = TableBytes::from_bytes(bytes, offset, &header)?,
           FieldLayoutRow::ID => field_layouts = TableBytes::from_bytes(bytes, offset, &header)?,
           FieldMarshalRow::ID => field_marshals = TableBytes::from_bytes(bytes, offset, &header)?,
+          FieldPtrRow::ID => field_ptrs = TableBytes::from_bytes(bytes, offset, &header)?,
           FieldRvaRow::ID => field_rvas = TableBytes::from_bytes(bytes, offset, &header)?,
           FileRow::ID => files = TableBytes::from_bytes(bytes, offset, &header)?,
           GenericParamRow::ID => generic_params = TableBytes::from_bytes(bytes, offset, &header)?,
@@ -159,6 +217,7 @@ mod read {
           MemberRefRow::ID => member_refs = TableBytes::from_bytes(bytes, offset, &header)?,
           MethodDefRow::ID => method_defs = TableBytes::from_bytes(bytes, offset, &header)?,
           MethodImplRow::ID => method_impls = TableBytes::from_bytes(bytes, offset, &header)?,
+          MethodPtrRow::ID => method_ptrs = TableBytes::from_bytes(bytes, offset, &header)?,
           MethodSemanticsRow::ID => {
             method_semantics = TableBytes::from_bytes(bytes, offset, &header)?
           }
@@ -167,14 +226,30 @@ mod read {
           ModuleRefRow::ID => module_refs = TableBytes::from_bytes(bytes, offset, &header)?,
           NestedClassRow::ID => nested_classes = TableBytes::from_bytes(bytes, offset, &header)?,
           ParamRow::ID => params = TableBytes::from_bytes(bytes, offset, &header)?,
+          ParamPtrRow::ID => param_ptrs = TableBytes::from_bytes(bytes, offset, &header)?,
           PropertyRow::ID => properties = TableBytes::from_bytes(bytes, offset, &header)?,
           PropertyMapRow::ID => property_maps = TableBytes::from_bytes(bytes, offset, &header)?,
+          PropertyPtrRow::ID => property_ptrs = TableBytes::from_bytes(bytes, offset, &header)?,
           StandAloneSigRow::ID => {
             stand_alone_sigs = TableBytes::from_bytes(bytes, offset, &header)?
           }
           TypeDefRow::ID => type_defs = TableBytes::from_bytes(bytes, offset, &header)?,
           TypeRefRow::ID => type_refs = TableBytes::from_bytes(bytes, offset, &header)?,
           TypeSpecRow::ID => type_specs = TableBytes::from_bytes(bytes, offset, &header)?,
+          DocumentRow::ID => documents = TableBytes::from_bytes(bytes, offset, &header)?,
+          MethodDebugInformationRow::ID => {
+            method_debug_informations = TableBytes::from_bytes(bytes, offset, &header)?
+          }
+          LocalScopeRow::ID => local_scopes = TableBytes::from_bytes(bytes, offset, &header)?,
+          LocalVariableRow::ID => local_variables = TableBytes::from_bytes(bytes, offset, &header)?,
+          LocalConstantRow::ID => local_constants = TableBytes::from_bytes(bytes, offset, &header)?,
+          ImportScopeRow::ID => import_scopes = TableBytes::from_bytes(bytes, offset, &header)?,
+          StateMachineMethodRow::ID => {
+            state_machine_methods = TableBytes::from_bytes(bytes, offset, &header)?
+          }
+          CustomDebugInformationRow::ID => {
+            custom_debug_informations = TableBytes::from_bytes(bytes, offset, &header)?
+          }
           _ => {}
         }
       }
@@ -182,6 +257,8 @@ mod read {
       Ok(Self {
         bytes,
         header,
+        end: *offset,
+        uncompressed: false,
         assemblies,
         assembly_oses,
         assembly_processors,
@@ -194,10 +271,12 @@ mod read {
         decl_securities,
         events,
         event_maps,
+        event_ptrs,
         exported_types,
         fields,
         field_layouts,
         field_marshals,
+        field_ptrs,
         field_rvas,
         files,
         generic_params,
@@ -208,27 +287,66 @@ mod read {
         member_refs,
         method_defs,
         method_impls,
+        method_ptrs,
         method_semantics,
         method_specs,
         modules,
         module_refs,
         nested_classes,
         params,
+        param_ptrs,
         properties,
         property_maps,
+        property_ptrs,
         stand_alone_sigs,
         type_defs,
         type_refs,
         type_specs,
+        documents,
+        method_debug_informations,
+        local_scopes,
+        local_variables,
+        local_constants,
+        import_scopes,
+        state_machine_methods,
+        custom_debug_informations,
       })
     }
 
+    /// Returns the [MetadataTablesHeader].
+    #[inline]
+    pub fn header(&self) -> &MetadataTablesHeader {
+      &self.header
+    }
+
     /// Returns the bytes used to create this [TablesStream].
     #[inline]
     pub fn bytes(&self) -> &'a [u8] {
       self.bytes
     }
 
+    /// Returns the offset of this stream within the given buffer, such as the file it was read from.
+    ///
+    /// Returns [None] if the bytes of this stream were not borrowed from the given buffer.
+    pub fn offset_in(&self, buf: &[u8]) -> Option<usize> {
+      let offset = (self.bytes.as_ptr() as usize).checked_sub(buf.as_ptr() as usize)?;
+
+      (offset + self.bytes.len() <= buf.len()).then_some(offset)
+    }
+
+    /// Returns the bytes following the table data, usually padding.
+    #[inline]
+    pub fn trailer(&self) -> &'a [u8] {
+      &self.bytes[self.end..]
+    }
+
+    /// Returns `true` if this is an uncompressed `#-` stream, whose tables may be unsorted and may
+    /// be accessed through `Ptr` tables.
+    #[inline]
+    pub fn is_uncompressed(&self) -> bool {
+      self.uncompressed
+    }
+
     /// Returns a reader for [AssemblyRow]s.
     #[inline]
     pub fn assemblies(&self) -> TableReader<'a, '_, AssemblyRow> {
@@ -301,6 +419,11 @@ mod read {
       self.event_maps.reader(&self.header)
     }
 
+    /// Returns a reader for [EventPtrRow]s.
+    pub fn event_ptrs(&self) -> TableReader<'a, '_, EventPtrRow> {
+      self.event_ptrs.reader(&self.header)
+    }
+
     /// Returns a reader for [ExportedTypeRow]s.
     #[inline]
     pub fn exported_types(&self) -> TableReader<'a, '_, ExportedTypeRow> {
@@ -325,6 +448,11 @@ mod read {
       self.field_marshals.reader(&self.header)
     }
 
+    /// Returns a reader for [FieldPtrRow]s.
+    pub fn field_ptrs(&self) -> TableReader<'a, '_, FieldPtrRow> {
+      self.field_ptrs.reader(&self.header)
+    }
+
     /// Returns a reader for [FieldRvaRow]s.
     #[inline]
     pub fn field_rvas(&self) -> TableReader<'a, '_, FieldRvaRow> {
@@ -385,6 +513,11 @@ mod read {
       self.method_impls.reader(&self.header)
     }
 
+    /// Returns a reader for [MethodPtrRow]s.
+    pub fn method_ptrs(&self) -> TableReader<'a, '_, MethodPtrRow> {
+      self.method_ptrs.reader(&self.header)
+    }
+
     /// Returns a reader for [MethodSemanticsRow]s.
     #[inline]
     pub fn method_semantics(&self) -> TableReader<'a, '_, MethodSemanticsRow> {
@@ -421,6 +554,11 @@ mod read {
       self.params.reader(&self.header)
     }
 
+    /// Returns a reader for [ParamPtrRow]s.
+    pub fn param_ptrs(&self) -> TableReader<'a, '_, ParamPtrRow> {
+      self.param_ptrs.reader(&self.header)
+    }
+
     /// Returns a reader for [PropertyRow]s.
     #[inline]
     pub fn properties(&self) -> TableReader<'a, '_, PropertyRow> {
@@ -433,6 +571,11 @@ mod read {
       self.property_maps.reader(&self.header)
     }
 
+    /// Returns a reader for [PropertyPtrRow]s.
+    pub fn property_ptrs(&self) -> TableReader<'a, '_, PropertyPtrRow> {
+      self.property_ptrs.reader(&self.header)
+    }
+
     /// Returns a reader for [StandAloneSigRow]s.
     #[inline]
     pub fn stand_alone_sigs(&self) -> TableReader<'a, '_, StandAloneSigRow> {
@@ -456,6 +599,54 @@ mod read {
     pub fn type_specs(&self) -> TableReader<'a, '_, TypeSpecRow> {
       self.type_specs.reader(&self.header)
     }
+
+    /// Returns a reader for [DocumentRow]s.
+    #[inline]
+    pub fn documents(&self) -> TableReader<'a, '_, DocumentRow> {
+      self.documents.reader(&self.header)
+    }
+
+    /// Returns a reader for [MethodDebugInformationRow]s.
+    #[inline]
+    pub fn method_debug_informations(&self) -> TableReader<'a, '_, MethodDebugInformationRow> {
+      self.method_debug_informations.reader(&self.header)
+    }
+
+    /// Returns a reader for [LocalScopeRow]s.
+    #[inline]
+    pub fn local_scopes(&self) -> TableReader<'a, '_, LocalScopeRow> {
+      self.local_scopes.reader(&self.header)
+    }
+
+    /// Returns a reader for [LocalVariableRow]s.
+    #[inline]
+    pub fn local_variables(&self) -> TableReader<'a, '_, LocalVariableRow> {
+      self.local_variables.reader(&self.header)
+    }
+
+    /// Returns a reader for [LocalConstantRow]s.
+    #[inline]
+    pub fn local_constants(&self) -> TableReader<'a, '_, LocalConstantRow> {
+      self.local_constants.reader(&self.header)
+    }
+
+    /// Returns a reader for [ImportScopeRow]s.
+    #[inline]
+    pub fn import_scopes(&self) -> TableReader<'a, '_, ImportScopeRow> {
+      self.import_scopes.reader(&self.header)
+    }
+
+    /// Returns a reader for [StateMachineMethodRow]s.
+    #[inline]
+    pub fn state_machine_methods(&self) -> TableReader<'a, '_, StateMachineMethodRow> {
+      self.state_machine_methods.reader(&self.header)
+    }
+
+    /// Returns a reader for [CustomDebugInformationRow]s.
+    #[inline]
+    pub fn custom_debug_informations(&self) -> TableReader<'a, '_, CustomDebugInformationRow> {
+      self.custom_debug_informations.reader(&self.header)
+    }
   }
 
   impl fmt::Debug for TablesStream<'_> {
@@ -469,4 +660,1539 @@ mod read {
 
 #[cfg(feature = "write")]
 #[doc(hidden)]
-mod write {}
+mod write {
+  use super::rows::*;
+  use super::table::{RowWrite, TableBuilder};
+  use super::TablesStream;
+  use crate::bytes::ToBytes;
+  use crate::metadata::errors::ReadError;
+  use crate::metadata::headers::{HeapSizes, MetadataTablesHeader};
+  use crate::metadata::streams::tables::id::RowId;
+  use core::cmp::Reverse;
+  use std::vec::Vec;
+
+  /// Builds a `#~` metadata stream.
+  ///
+  /// The inverse of [TablesStream](super::TablesStream).  Rows are added to each table in order and
+  /// the widths of heap, table and coded indexes are derived from the number of rows in each table
+  /// and the given [HeapSizes] when the stream is written.
+  ///
+  /// Tables that are required to be sorted and are not referenced by other tables are sorted when
+  /// the stream is written, so adding rows to them does not return a [RowId].  The `GenericParam`,
+  /// `GenericParamConstraint`, `InterfaceImpl`, `DeclSecurity` and `LocalScope` tables are referenced
+  /// by other tables and must be added in sorted order to be flagged as sorted.
+  #[derive(Debug, Clone, Default)]
+  pub struct TablesBuilder {
+    heap_sizes: HeapSizes,
+    sorted: Option<u64>,
+    uncompressed: bool,
+    external_rows: Option<[u32; 64]>,
+    extra_data: u32,
+    trailer: Vec<u8>,
+    modules: TableBuilder<ModuleRow>,
+    type_refs: TableBuilder<TypeRefRow>,
+    type_defs: TableBuilder<TypeDefRow>,
+    field_ptrs: TableBuilder<FieldPtrRow>,
+    fields: TableBuilder<FieldRow>,
+    method_ptrs: TableBuilder<MethodPtrRow>,
+    method_defs: TableBuilder<MethodDefRow>,
+    param_ptrs: TableBuilder<ParamPtrRow>,
+    params: TableBuilder<ParamRow>,
+    interface_impls: TableBuilder<InterfaceImplRow>,
+    member_refs: TableBuilder<MemberRefRow>,
+    constants: TableBuilder<ConstantRow>,
+    custom_attributes: TableBuilder<CustomAttributeRow>,
+    field_marshals: TableBuilder<FieldMarshalRow>,
+    decl_securities: TableBuilder<DeclSecurityRow>,
+    class_layouts: TableBuilder<ClassLayoutRow>,
+    field_layouts: TableBuilder<FieldLayoutRow>,
+    stand_alone_sigs: TableBuilder<StandAloneSigRow>,
+    event_maps: TableBuilder<EventMapRow>,
+    event_ptrs: TableBuilder<EventPtrRow>,
+    events: TableBuilder<EventRow>,
+    property_maps: TableBuilder<PropertyMapRow>,
+    property_ptrs: TableBuilder<PropertyPtrRow>,
+    properties: TableBuilder<PropertyRow>,
+    method_semantics: TableBuilder<MethodSemanticsRow>,
+    method_impls: TableBuilder<MethodImplRow>,
+    module_refs: TableBuilder<ModuleRefRow>,
+    type_specs: TableBuilder<TypeSpecRow>,
+    impl_maps: TableBuilder<ImplMapRow>,
+    field_rvas: TableBuilder<FieldRvaRow>,
+    assemblies: TableBuilder<AssemblyRow>,
+    assembly_processors: TableBuilder<AssemblyProcessorRow>,
+    assembly_oses: TableBuilder<AssemblyOsRow>,
+    assembly_refs: TableBuilder<AssemblyRefRow>,
+    assembly_ref_processors: TableBuilder<AssemblyRefProcessorRow>,
+    assembly_ref_oses: TableBuilder<AssemblyRefOsRow>,
+    files: TableBuilder<FileRow>,
+    exported_types: TableBuilder<ExportedTypeRow>,
+    manifest_resources: TableBuilder<ManifestResourceRow>,
+    nested_classes: TableBuilder<NestedClassRow>,
+    generic_params: TableBuilder<GenericParamRow>,
+    method_specs: TableBuilder<MethodSpecRow>,
+    generic_param_constraints: TableBuilder<GenericParamConstraintRow>,
+    documents: TableBuilder<DocumentRow>,
+    method_debug_informations: TableBuilder<MethodDebugInformationRow>,
+    local_scopes: TableBuilder<LocalScopeRow>,
+    local_variables: TableBuilder<LocalVariableRow>,
+    local_constants: TableBuilder<LocalConstantRow>,
+    import_scopes: TableBuilder<ImportScopeRow>,
+    state_machine_methods: TableBuilder<StateMachineMethodRow>,
+    custom_debug_informations: TableBuilder<CustomDebugInformationRow>,
+  }
+
+  impl TablesBuilder {
+    /// Creates a new empty [TablesBuilder].
+    pub fn new() -> Self {
+      Self::default()
+    }
+
+    /// Creates a [TablesBuilder] containing the rows of an existing [TablesStream].
+    ///
+    /// The [HeapSizes], the bit vector of sorted tables, whether the stream is uncompressed, the
+    /// row counts of tables referenced by the stream but not present in it and any extra data and
+    /// bytes following the table data are kept, so that finishing the builder without changes
+    /// reproduces the stream exactly.
+    pub fn from_stream(tables: &TablesStream<'_>) -> Result<Self, ReadError> {
+      let header = tables.header();
+      let mut external_rows = [0; 64];
+
+      for (table, rows) in external_rows.iter_mut().enumerate() {
+        if header.valid & (1 << table) == 0 {
+          *rows = header.rows[table];
+        }
+      }
+
+      Ok(Self {
+        heap_sizes: header.heap_sizes,
+        sorted: Some(header.sorted),
+        uncompressed: tables.is_uncompressed(),
+        extra_data: header.extra_data,
+        external_rows: Some(external_rows).filter(|rows| rows.iter().any(|rows| *rows > 0)),
+        trailer: tables.trailer().to_vec(),
+        modules: TableBuilder::from_reader(tables.modules())?,
+        type_refs: TableBuilder::from_reader(tables.type_refs())?,
+        type_defs: TableBuilder::from_reader(tables.type_defs())?,
+        field_ptrs: TableBuilder::from_reader(tables.field_ptrs())?,
+        fields: TableBuilder::from_reader(tables.fields())?,
+        method_ptrs: TableBuilder::from_reader(tables.method_ptrs())?,
+        method_defs: TableBuilder::from_reader(tables.method_defs())?,
+        param_ptrs: TableBuilder::from_reader(tables.param_ptrs())?,
+        params: TableBuilder::from_reader(tables.params())?,
+        interface_impls: TableBuilder::from_reader(tables.interface_impls())?,
+        member_refs: TableBuilder::from_reader(tables.member_refs())?,
+        constants: TableBuilder::from_reader(tables.constants())?,
+        custom_attributes: TableBuilder::from_reader(tables.custom_attributes())?,
+        field_marshals: TableBuilder::from_reader(tables.field_marshals())?,
+        decl_securities: TableBuilder::from_reader(tables.decl_securities())?,
+        class_layouts: TableBuilder::from_reader(tables.class_layouts())?,
+        field_layouts: TableBuilder::from_reader(tables.field_layouts())?,
+        stand_alone_sigs: TableBuilder::from_reader(tables.stand_alone_sigs())?,
+        event_maps: TableBuilder::from_reader(tables.event_maps())?,
+        event_ptrs: TableBuilder::from_reader(tables.event_ptrs())?,
+        events: TableBuilder::from_reader(tables.events())?,
+        property_maps: TableBuilder::from_reader(tables.property_maps())?,
+        property_ptrs: TableBuilder::from_reader(tables.property_ptrs())?,
+        properties: TableBuilder::from_reader(tables.properties())?,
+        method_semantics: TableBuilder::from_reader(tables.method_semantics())?,
+        method_impls: TableBuilder::from_reader(tables.method_impls())?,
+        module_refs: TableBuilder::from_reader(tables.module_refs())?,
+        type_specs: TableBuilder::from_reader(tables.type_specs())?,
+        impl_maps: TableBuilder::from_reader(tables.impl_maps())?,
+        field_rvas: TableBuilder::from_reader(tables.field_rvas())?,
+        assemblies: TableBuilder::from_reader(tables.assemblies())?,
+        assembly_processors: TableBuilder::from_reader(tables.assembly_processors())?,
+        assembly_oses: TableBuilder::from_reader(tables.assembly_oses())?,
+        assembly_refs: TableBuilder::from_reader(tables.assembly_refs())?,
+        assembly_ref_processors: TableBuilder::from_reader(tables.assembly_ref_processors())?,
+        assembly_ref_oses: TableBuilder::from_reader(tables.assembly_ref_oses())?,
+        files: TableBuilder::from_reader(tables.files())?,
+        exported_types: TableBuilder::from_reader(tables.exported_types())?,
+        manifest_resources: TableBuilder::from_reader(tables.manifest_resources())?,
+        nested_classes: TableBuilder::from_reader(tables.nested_classes())?,
+        generic_params: TableBuilder::from_reader(tables.generic_params())?,
+        method_specs: TableBuilder::from_reader(tables.method_specs())?,
+        generic_param_constraints: TableBuilder::from_reader(tables.generic_param_constraints())?,
+        documents: TableBuilder::from_reader(tables.documents())?,
+        method_debug_informations: TableBuilder::from_reader(tables.method_debug_informations())?,
+        local_scopes: TableBuilder::from_reader(tables.local_scopes())?,
+        local_variables: TableBuilder::from_reader(tables.local_variables())?,
+        local_constants: TableBuilder::from_reader(tables.local_constants())?,
+        import_scopes: TableBuilder::from_reader(tables.import_scopes())?,
+        state_machine_methods: TableBuilder::from_reader(tables.state_machine_methods())?,
+        custom_debug_informations: TableBuilder::from_reader(tables.custom_debug_informations())?,
+      })
+    }
+
+    /// Returns the [HeapSizes] used to determine the width of heap indexes.
+    pub fn heap_sizes(&self) -> HeapSizes {
+      self.heap_sizes
+    }
+
+    /// Sets the [HeapSizes] used to determine the width of heap indexes.
+    ///
+    /// Use the heap sizes reported by the heap builders of the metadata.
+    pub fn set_heap_sizes(&mut self, heap_sizes: HeapSizes) {
+      self.heap_sizes = heap_sizes;
+    }
+
+    /// Overrides the bit vector of sorted tables, which is otherwise derived from the tables.
+    ///
+    /// Useful to reproduce an existing stream exactly.
+    pub fn set_sorted(&mut self, sorted: u64) {
+      self.sorted = Some(sorted);
+    }
+
+    /// Returns `true` if the stream is written as an uncompressed `#-` stream.
+    pub fn is_uncompressed(&self) -> bool {
+      self.uncompressed
+    }
+
+    /// Sets whether the stream is written as an uncompressed `#-` stream.
+    ///
+    /// The rows of an uncompressed stream are written in the order they were added, tables that
+    /// are required to be sorted in a `#~` stream are only flagged as sorted if they are.
+    pub fn set_uncompressed(&mut self, uncompressed: bool) {
+      self.uncompressed = uncompressed;
+    }
+
+    /// Sets the number of rows of tables that are referenced by the stream but not present in it,
+    /// indexed by the id of the table.
+    ///
+    /// The debug tables of a portable PDB refer to the type-system tables of the assembly it
+    /// describes, so the width of those indexes depends on the row counts of the `#Pdb` stream.
+    /// Tables that have rows in the builder use their own row count instead.
+    ///
+    /// See [PdbStream::type_system_table_rows](crate::metadata::streams::pdb::PdbStream::type_system_table_rows).
+    pub fn set_external_rows(&mut self, rows: [u32; 64]) {
+      self.external_rows = Some(rows);
+    }
+
+    /// Adds a [ModuleRow] and returns its [RowId].
+    pub fn add_module(&mut self, row: ModuleRow) -> RowId<ModuleRow> {
+      self.modules.add(row)
+    }
+
+    /// Returns the [ModuleRow]s added so far.
+    pub fn modules(&self) -> &[ModuleRow] {
+      self.modules.rows()
+    }
+
+    /// Replaces the [ModuleRow] with the given [RowId] and returns the previous row.
+    pub fn set_module(&mut self, id: RowId<ModuleRow>, row: ModuleRow) -> Option<ModuleRow> {
+      self.modules.set(id, row)
+    }
+
+    /// Adds a [TypeRefRow] and returns its [RowId].
+    pub fn add_type_ref(&mut self, row: TypeRefRow) -> RowId<TypeRefRow> {
+      self.type_refs.add(row)
+    }
+
+    /// Returns the [TypeRefRow]s added so far.
+    pub fn type_refs(&self) -> &[TypeRefRow] {
+      self.type_refs.rows()
+    }
+
+    /// Replaces the [TypeRefRow] with the given [RowId] and returns the previous row.
+    pub fn set_type_ref(&mut self, id: RowId<TypeRefRow>, row: TypeRefRow) -> Option<TypeRefRow> {
+      self.type_refs.set(id, row)
+    }
+
+    /// Adds a [TypeDefRow] and returns its [RowId].
+    pub fn add_type_def(&mut self, row: TypeDefRow) -> RowId<TypeDefRow> {
+      self.type_defs.add(row)
+    }
+
+    /// Returns the [TypeDefRow]s added so far.
+    pub fn type_defs(&self) -> &[TypeDefRow] {
+      self.type_defs.rows()
+    }
+
+    /// Replaces the [TypeDefRow] with the given [RowId] and returns the previous row.
+    pub fn set_type_def(&mut self, id: RowId<TypeDefRow>, row: TypeDefRow) -> Option<TypeDefRow> {
+      self.type_defs.set(id, row)
+    }
+
+    /// Adds a [FieldPtrRow] and returns its [RowId].
+    pub fn add_field_ptr(&mut self, row: FieldPtrRow) -> RowId<FieldPtrRow> {
+      self.field_ptrs.add(row)
+    }
+
+    /// Returns the [FieldPtrRow]s added so far.
+    pub fn field_ptrs(&self) -> &[FieldPtrRow] {
+      self.field_ptrs.rows()
+    }
+
+    /// Replaces the [FieldPtrRow] with the given [RowId] and returns the previous row.
+    pub fn set_field_ptr(
+      &mut self,
+      id: RowId<FieldPtrRow>,
+      row: FieldPtrRow,
+    ) -> Option<FieldPtrRow> {
+      self.field_ptrs.set(id, row)
+    }
+
+    /// Adds a [FieldRow] and returns its [RowId].
+    pub fn add_field(&mut self, row: FieldRow) -> RowId<FieldRow> {
+      self.fields.add(row)
+    }
+
+    /// Returns the [FieldRow]s added so far.
+    pub fn fields(&self) -> &[FieldRow] {
+      self.fields.rows()
+    }
+
+    /// Replaces the [FieldRow] with the given [RowId] and returns the previous row.
+    pub fn set_field(&mut self, id: RowId<FieldRow>, row: FieldRow) -> Option<FieldRow> {
+      self.fields.set(id, row)
+    }
+
+    /// Adds a [MethodPtrRow] and returns its [RowId].
+    pub fn add_method_ptr(&mut self, row: MethodPtrRow) -> RowId<MethodPtrRow> {
+      self.method_ptrs.add(row)
+    }
+
+    /// Returns the [MethodPtrRow]s added so far.
+    pub fn method_ptrs(&self) -> &[MethodPtrRow] {
+      self.method_ptrs.rows()
+    }
+
+    /// Replaces the [MethodPtrRow] with the given [RowId] and returns the previous row.
+    pub fn set_method_ptr(
+      &mut self,
+      id: RowId<MethodPtrRow>,
+      row: MethodPtrRow,
+    ) -> Option<MethodPtrRow> {
+      self.method_ptrs.set(id, row)
+    }
+
+    /// Adds a [MethodDefRow] and returns its [RowId].
+    pub fn add_method_def(&mut self, row: MethodDefRow) -> RowId<MethodDefRow> {
+      self.method_defs.add(row)
+    }
+
+    /// Returns the [MethodDefRow]s added so far.
+    pub fn method_defs(&self) -> &[MethodDefRow] {
+      self.method_defs.rows()
+    }
+
+    /// Replaces the [MethodDefRow] with the given [RowId] and returns the previous row.
+    pub fn set_method_def(
+      &mut self,
+      id: RowId<MethodDefRow>,
+      row: MethodDefRow,
+    ) -> Option<MethodDefRow> {
+      self.method_defs.set(id, row)
+    }
+
+    /// Adds a [ParamPtrRow] and returns its [RowId].
+    pub fn add_param_ptr(&mut self, row: ParamPtrRow) -> RowId<ParamPtrRow> {
+      self.param_ptrs.add(row)
+    }
+
+    /// Returns the [ParamPtrRow]s added so far.
+    pub fn param_ptrs(&self) -> &[ParamPtrRow] {
+      self.param_ptrs.rows()
+    }
+
+    /// Replaces the [ParamPtrRow] with the given [RowId] and returns the previous row.
+    pub fn set_param_ptr(
+      &mut self,
+      id: RowId<ParamPtrRow>,
+      row: ParamPtrRow,
+    ) -> Option<ParamPtrRow> {
+      self.param_ptrs.set(id, row)
+    }
+
+    /// Adds a [ParamRow] and returns its [RowId].
+    pub fn add_param(&mut self, row: ParamRow) -> RowId<ParamRow> {
+      self.params.add(row)
+    }
+
+    /// Returns the [ParamRow]s added so far.
+    pub fn params(&self) -> &[ParamRow] {
+      self.params.rows()
+    }
+
+    /// Replaces the [ParamRow] with the given [RowId] and returns the previous row.
+    pub fn set_param(&mut self, id: RowId<ParamRow>, row: ParamRow) -> Option<ParamRow> {
+      self.params.set(id, row)
+    }
+
+    /// Adds an [InterfaceImplRow] and returns its [RowId].
+    ///
+    /// The table is only flagged as sorted if rows are added in sorted order.
+    pub fn add_interface_impl(&mut self, row: InterfaceImplRow) -> RowId<InterfaceImplRow> {
+      self.interface_impls.add(row)
+    }
+
+    /// Returns the [InterfaceImplRow]s added so far.
+    pub fn interface_impls(&self) -> &[InterfaceImplRow] {
+      self.interface_impls.rows()
+    }
+
+    /// Replaces the [InterfaceImplRow] with the given [RowId] and returns the previous row.
+    pub fn set_interface_impl(
+      &mut self,
+      id: RowId<InterfaceImplRow>,
+      row: InterfaceImplRow,
+    ) -> Option<InterfaceImplRow> {
+      self.interface_impls.set(id, row)
+    }
+
+    /// Adds a [MemberRefRow] and returns its [RowId].
+    pub fn add_member_ref(&mut self, row: MemberRefRow) -> RowId<MemberRefRow> {
+      self.member_refs.add(row)
+    }
+
+    /// Returns the [MemberRefRow]s added so far.
+    pub fn member_refs(&self) -> &[MemberRefRow] {
+      self.member_refs.rows()
+    }
+
+    /// Replaces the [MemberRefRow] with the given [RowId] and returns the previous row.
+    pub fn set_member_ref(
+      &mut self,
+      id: RowId<MemberRefRow>,
+      row: MemberRefRow,
+    ) -> Option<MemberRefRow> {
+      self.member_refs.set(id, row)
+    }
+
+    /// Adds a [ConstantRow], the table is sorted when the stream is written.
+    pub fn add_constant(&mut self, row: ConstantRow) {
+      self.constants.add(row);
+    }
+
+    /// Returns the [ConstantRow]s added so far.
+    pub fn constants(&self) -> &[ConstantRow] {
+      self.constants.rows()
+    }
+
+    /// Replaces the [ConstantRow] with the given [RowId] and returns the previous row.
+    pub fn set_constant(
+      &mut self,
+      id: RowId<ConstantRow>,
+      row: ConstantRow,
+    ) -> Option<ConstantRow> {
+      self.constants.set(id, row)
+    }
+
+    /// Adds a [CustomAttributeRow], the table is sorted when the stream is written.
+    pub fn add_custom_attribute(&mut self, row: CustomAttributeRow) {
+      self.custom_attributes.add(row);
+    }
+
+    /// Returns the [CustomAttributeRow]s added so far.
+    pub fn custom_attributes(&self) -> &[CustomAttributeRow] {
+      self.custom_attributes.rows()
+    }
+
+    /// Replaces the [CustomAttributeRow] with the given [RowId] and returns the previous row.
+    pub fn set_custom_attribute(
+      &mut self,
+      id: RowId<CustomAttributeRow>,
+      row: CustomAttributeRow,
+    ) -> Option<CustomAttributeRow> {
+      self.custom_attributes.set(id, row)
+    }
+
+    /// Adds a [FieldMarshalRow], the table is sorted when the stream is written.
+    pub fn add_field_marshal(&mut self, row: FieldMarshalRow) {
+      self.field_marshals.add(row);
+    }
+
+    /// Returns the [FieldMarshalRow]s added so far.
+    pub fn field_marshals(&self) -> &[FieldMarshalRow] {
+      self.field_marshals.rows()
+    }
+
+    /// Replaces the [FieldMarshalRow] with the given [RowId] and returns the previous row.
+    pub fn set_field_marshal(
+      &mut self,
+      id: RowId<FieldMarshalRow>,
+      row: FieldMarshalRow,
+    ) -> Option<FieldMarshalRow> {
+      self.field_marshals.set(id, row)
+    }
+
+    /// Adds a [DeclSecurityRow] and returns its [RowId].
+    ///
+    /// The table is only flagged as sorted if rows are added in sorted order.
+    pub fn add_decl_security(&mut self, row: DeclSecurityRow) -> RowId<DeclSecurityRow> {
+      self.decl_securities.add(row)
+    }
+
+    /// Returns the [DeclSecurityRow]s added so far.
+    pub fn decl_securities(&self) -> &[DeclSecurityRow] {
+      self.decl_securities.rows()
+    }
+
+    /// Replaces the [DeclSecurityRow] with the given [RowId] and returns the previous row.
+    pub fn set_decl_security(
+      &mut self,
+      id: RowId<DeclSecurityRow>,
+      row: DeclSecurityRow,
+    ) -> Option<DeclSecurityRow> {
+      self.decl_securities.set(id, row)
+    }
+
+    /// Adds a [ClassLayoutRow], the table is sorted when the stream is written.
+    pub fn add_class_layout(&mut self, row: ClassLayoutRow) {
+      self.class_layouts.add(row);
+    }
+
+    /// Returns the [ClassLayoutRow]s added so far.
+    pub fn class_layouts(&self) -> &[ClassLayoutRow] {
+      self.class_layouts.rows()
+    }
+
+    /// Replaces the [ClassLayoutRow] with the given [RowId] and returns the previous row.
+    pub fn set_class_layout(
+      &mut self,
+      id: RowId<ClassLayoutRow>,
+      row: ClassLayoutRow,
+    ) -> Option<ClassLayoutRow> {
+      self.class_layouts.set(id, row)
+    }
+
+    /// Adds a [FieldLayoutRow], the table is sorted when the stream is written.
+    pub fn add_field_layout(&mut self, row: FieldLayoutRow) {
+      self.field_layouts.add(row);
+    }
+
+    /// Returns the [FieldLayoutRow]s added so far.
+    pub fn field_layouts(&self) -> &[FieldLayoutRow] {
+      self.field_layouts.rows()
+    }
+
+    /// Replaces the [FieldLayoutRow] with the given [RowId] and returns the previous row.
+    pub fn set_field_layout(
+      &mut self,
+      id: RowId<FieldLayoutRow>,
+      row: FieldLayoutRow,
+    ) -> Option<FieldLayoutRow> {
+      self.field_layouts.set(id, row)
+    }
+
+    /// Adds a [StandAloneSigRow] and returns its [RowId].
+    pub fn add_stand_alone_sig(&mut self, row: StandAloneSigRow) -> RowId<StandAloneSigRow> {
+      self.stand_alone_sigs.add(row)
+    }
+
+    /// Returns the [StandAloneSigRow]s added so far.
+    pub fn stand_alone_sigs(&self) -> &[StandAloneSigRow] {
+      self.stand_alone_sigs.rows()
+    }
+
+    /// Replaces the [StandAloneSigRow] with the given [RowId] and returns the previous row.
+    pub fn set_stand_alone_sig(
+      &mut self,
+      id: RowId<StandAloneSigRow>,
+      row: StandAloneSigRow,
+    ) -> Option<StandAloneSigRow> {
+      self.stand_alone_sigs.set(id, row)
+    }
+
+    /// Adds an [EventMapRow] and returns its [RowId].
+    pub fn add_event_map(&mut self, row: EventMapRow) -> RowId<EventMapRow> {
+      self.event_maps.add(row)
+    }
+
+    /// Returns the [EventMapRow]s added so far.
+    pub fn event_maps(&self) -> &[EventMapRow] {
+      self.event_maps.rows()
+    }
+
+    /// Replaces the [EventMapRow] with the given [RowId] and returns the previous row.
+    pub fn set_event_map(
+      &mut self,
+      id: RowId<EventMapRow>,
+      row: EventMapRow,
+    ) -> Option<EventMapRow> {
+      self.event_maps.set(id, row)
+    }
+
+    /// Adds an [EventPtrRow] and returns its [RowId].
+    pub fn add_event_ptr(&mut self, row: EventPtrRow) -> RowId<EventPtrRow> {
+      self.event_ptrs.add(row)
+    }
+
+    /// Returns the [EventPtrRow]s added so far.
+    pub fn event_ptrs(&self) -> &[EventPtrRow] {
+      self.event_ptrs.rows()
+    }
+
+    /// Replaces the [EventPtrRow] with the given [RowId] and returns the previous row.
+    pub fn set_event_ptr(
+      &mut self,
+      id: RowId<EventPtrRow>,
+      row: EventPtrRow,
+    ) -> Option<EventPtrRow> {
+      self.event_ptrs.set(id, row)
+    }
+
+    /// Adds an [EventRow] and returns its [RowId].
+    pub fn add_event(&mut self, row: EventRow) -> RowId<EventRow> {
+      self.events.add(row)
+    }
+
+    /// Returns the [EventRow]s added so far.
+    pub fn events(&self) -> &[EventRow] {
+      self.events.rows()
+    }
+
+    /// Replaces the [EventRow] with the given [RowId] and returns the previous row.
+    pub fn set_event(&mut self, id: RowId<EventRow>, row: EventRow) -> Option<EventRow> {
+      self.events.set(id, row)
+    }
+
+    /// Adds a [PropertyMapRow] and returns its [RowId].
+    pub fn add_property_map(&mut self, row: PropertyMapRow) -> RowId<PropertyMapRow> {
+      self.property_maps.add(row)
+    }
+
+    /// Returns the [PropertyMapRow]s added so far.
+    pub fn property_maps(&self) -> &[PropertyMapRow] {
+      self.property_maps.rows()
+    }
+
+    /// Replaces the [PropertyMapRow] with the given [RowId] and returns the previous row.
+    pub fn set_property_map(
+      &mut self,
+      id: RowId<PropertyMapRow>,
+      row: PropertyMapRow,
+    ) -> Option<PropertyMapRow> {
+      self.property_maps.set(id, row)
+    }
+
+    /// Adds a [PropertyPtrRow] and returns its [RowId].
+    pub fn add_property_ptr(&mut self, row: PropertyPtrRow) -> RowId<PropertyPtrRow> {
+      self.property_ptrs.add(row)
+    }
+
+    /// Returns the [PropertyPtrRow]s added so far.
+    pub fn property_ptrs(&self) -> &[PropertyPtrRow] {
+      self.property_ptrs.rows()
+    }
+
+    /// Replaces the [PropertyPtrRow] with the given [RowId] and returns the previous row.
+    pub fn set_property_ptr(
+      &mut self,
+      id: RowId<PropertyPtrRow>,
+      row: PropertyPtrRow,
+    ) -> Option<PropertyPtrRow> {
+      self.property_ptrs.set(id, row)
+    }
+
+    /// Adds a [PropertyRow] and returns its [RowId].
+    pub fn add_property(&mut self, row: PropertyRow) -> RowId<PropertyRow> {
+      self.properties.add(row)
+    }
+
+    /// Returns the [PropertyRow]s added so far.
+    pub fn properties(&self) -> &[PropertyRow] {
+      self.properties.rows()
+    }
+
+    /// Replaces the [PropertyRow] with the given [RowId] and returns the previous row.
+    pub fn set_property(
+      &mut self,
+      id: RowId<PropertyRow>,
+      row: PropertyRow,
+    ) -> Option<PropertyRow> {
+      self.properties.set(id, row)
+    }
+
+    /// Adds a [MethodSemanticsRow], the table is sorted when the stream is written.
+    pub fn add_method_semantics(&mut self, row: MethodSemanticsRow) {
+      self.method_semantics.add(row);
+    }
+
+    /// Returns the [MethodSemanticsRow]s added so far.
+    pub fn method_semantics(&self) -> &[MethodSemanticsRow] {
+      self.method_semantics.rows()
+    }
+
+    /// Replaces the [MethodSemanticsRow] with the given [RowId] and returns the previous row.
+    pub fn set_method_semantics(
+      &mut self,
+      id: RowId<MethodSemanticsRow>,
+      row: MethodSemanticsRow,
+    ) -> Option<MethodSemanticsRow> {
+      self.method_semantics.set(id, row)
+    }
+
+    /// Adds a [MethodImplRow], the table is sorted when the stream is written.
+    pub fn add_method_impl(&mut self, row: MethodImplRow) {
+      self.method_impls.add(row);
+    }
+
+    /// Returns the [MethodImplRow]s added so far.
+    pub fn method_impls(&self) -> &[MethodImplRow] {
+      self.method_impls.rows()
+    }
+
+    /// Replaces the [MethodImplRow] with the given [RowId] and returns the previous row.
+    pub fn set_method_impl(
+      &mut self,
+      id: RowId<MethodImplRow>,
+      row: MethodImplRow,
+    ) -> Option<MethodImplRow> {
+      self.method_impls.set(id, row)
+    }
+
+    /// Adds a [ModuleRefRow] and returns its [RowId].
+    pub fn add_module_ref(&mut self, row: ModuleRefRow) -> RowId<ModuleRefRow> {
+      self.module_refs.add(row)
+    }
+
+    /// Returns the [ModuleRefRow]s added so far.
+    pub fn module_refs(&self) -> &[ModuleRefRow] {
+      self.module_refs.rows()
+    }
+
+    /// Replaces the [ModuleRefRow] with the given [RowId] and returns the previous row.
+    pub fn set_module_ref(
+      &mut self,
+      id: RowId<ModuleRefRow>,
+      row: ModuleRefRow,
+    ) -> Option<ModuleRefRow> {
+      self.module_refs.set(id, row)
+    }
+
+    /// Adds a [TypeSpecRow] and returns its [RowId].
+    pub fn add_type_spec(&mut self, row: TypeSpecRow) -> RowId<TypeSpecRow> {
+      self.type_specs.add(row)
+    }
+
+    /// Returns the [TypeSpecRow]s added so far.
+    pub fn type_specs(&self) -> &[TypeSpecRow] {
+      self.type_specs.rows()
+    }
+
+    /// Replaces the [TypeSpecRow] with the given [RowId] and returns the previous row.
+    pub fn set_type_spec(
+      &mut self,
+      id: RowId<TypeSpecRow>,
+      row: TypeSpecRow,
+    ) -> Option<TypeSpecRow> {
+      self.type_specs.set(id, row)
+    }
+
+    /// Adds an [ImplMapRow], the table is sorted when the stream is written.
+    pub fn add_impl_map(&mut self, row: ImplMapRow) {
+      self.impl_maps.add(row);
+    }
+
+    /// Returns the [ImplMapRow]s added so far.
+    pub fn impl_maps(&self) -> &[ImplMapRow] {
+      self.impl_maps.rows()
+    }
+
+    /// Replaces the [ImplMapRow] with the given [RowId] and returns the previous row.
+    pub fn set_impl_map(&mut self, id: RowId<ImplMapRow>, row: ImplMapRow) -> Option<ImplMapRow> {
+      self.impl_maps.set(id, row)
+    }
+
+    /// Adds a [FieldRvaRow], the table is sorted when the stream is written.
+    pub fn add_field_rva(&mut self, row: FieldRvaRow) {
+      self.field_rvas.add(row);
+    }
+
+    /// Returns the [FieldRvaRow]s added so far.
+    pub fn field_rvas(&self) -> &[FieldRvaRow] {
+      self.field_rvas.rows()
+    }
+
+    /// Replaces the [FieldRvaRow] with the given [RowId] and returns the previous row.
+    pub fn set_field_rva(
+      &mut self,
+      id: RowId<FieldRvaRow>,
+      row: FieldRvaRow,
+    ) -> Option<FieldRvaRow> {
+      self.field_rvas.set(id, row)
+    }
+
+    /// Adds an [AssemblyRow] and returns its [RowId].
+    pub fn add_assembly(&mut self, row: AssemblyRow) -> RowId<AssemblyRow> {
+      self.assemblies.add(row)
+    }
+
+    /// Returns the [AssemblyRow]s added so far.
+    pub fn assemblies(&self) -> &[AssemblyRow] {
+      self.assemblies.rows()
+    }
+
+    /// Replaces the [AssemblyRow] with the given [RowId] and returns the previous row.
+    pub fn set_assembly(
+      &mut self,
+      id: RowId<AssemblyRow>,
+      row: AssemblyRow,
+    ) -> Option<AssemblyRow> {
+      self.assemblies.set(id, row)
+    }
+
+    /// Adds an [AssemblyProcessorRow] and returns its [RowId].
+    pub fn add_assembly_processor(
+      &mut self,
+      row: AssemblyProcessorRow,
+    ) -> RowId<AssemblyProcessorRow> {
+      self.assembly_processors.add(row)
+    }
+
+    /// Returns the [AssemblyProcessorRow]s added so far.
+    pub fn assembly_processors(&self) -> &[AssemblyProcessorRow] {
+      self.assembly_processors.rows()
+    }
+
+    /// Replaces the [AssemblyProcessorRow] with the given [RowId] and returns the previous row.
+    pub fn set_assembly_processor(
+      &mut self,
+      id: RowId<AssemblyProcessorRow>,
+      row: AssemblyProcessorRow,
+    ) -> Option<AssemblyProcessorRow> {
+      self.assembly_processors.set(id, row)
+    }
+
+    /// Adds an [AssemblyOsRow] and returns its [RowId].
+    pub fn add_assembly_os(&mut self, row: AssemblyOsRow) -> RowId<AssemblyOsRow> {
+      self.assembly_oses.add(row)
+    }
+
+    /// Returns the [AssemblyOsRow]s added so far.
+    pub fn assembly_oses(&self) -> &[AssemblyOsRow] {
+      self.assembly_oses.rows()
+    }
+
+    /// Replaces the [AssemblyOsRow] with the given [RowId] and returns the previous row.
+    pub fn set_assembly_os(
+      &mut self,
+      id: RowId<AssemblyOsRow>,
+      row: AssemblyOsRow,
+    ) -> Option<AssemblyOsRow> {
+      self.assembly_oses.set(id, row)
+    }
+
+    /// Adds an [AssemblyRefRow] and returns its [RowId].
+    pub fn add_assembly_ref(&mut self, row: AssemblyRefRow) -> RowId<AssemblyRefRow> {
+      self.assembly_refs.add(row)
+    }
+
+    /// Returns the [AssemblyRefRow]s added so far.
+    pub fn assembly_refs(&self) -> &[AssemblyRefRow] {
+      self.assembly_refs.rows()
+    }
+
+    /// Replaces the [AssemblyRefRow] with the given [RowId] and returns the previous row.
+    pub fn set_assembly_ref(
+      &mut self,
+      id: RowId<AssemblyRefRow>,
+      row: AssemblyRefRow,
+    ) -> Option<AssemblyRefRow> {
+      self.assembly_refs.set(id, row)
+    }
+
+    /// Adds an [AssemblyRefProcessorRow] and returns its [RowId].
+    pub fn add_assembly_ref_processor(
+      &mut self,
+      row: AssemblyRefProcessorRow,
+    ) -> RowId<AssemblyRefProcessorRow> {
+      self.assembly_ref_processors.add(row)
+    }
+
+    /// Returns the [AssemblyRefProcessorRow]s added so far.
+    pub fn assembly_ref_processors(&self) -> &[AssemblyRefProcessorRow] {
+      self.assembly_ref_processors.rows()
+    }
+
+    /// Replaces the [AssemblyRefProcessorRow] with the given [RowId] and returns the previous row.
+    pub fn set_assembly_ref_processor(
+      &mut self,
+      id: RowId<AssemblyRefProcessorRow>,
+      row: AssemblyRefProcessorRow,
+    ) -> Option<AssemblyRefProcessorRow> {
+      self.assembly_ref_processors.set(id, row)
+    }
+
+    /// Adds an [AssemblyRefOsRow] and returns its [RowId].
+    pub fn add_assembly_ref_os(&mut self, row: AssemblyRefOsRow) -> RowId<AssemblyRefOsRow> {
+      self.assembly_ref_oses.add(row)
+    }
+
+    /// Returns the [AssemblyRefOsRow]s added so far.
+    pub fn assembly_ref_oses(&self) -> &[AssemblyRefOsRow] {
+      self.assembly_ref_oses.rows()
+    }
+
+    /// Replaces the [AssemblyRefOsRow] with the given [RowId] and returns the previous row.
+    pub fn set_assembly_ref_os(
+      &mut self,
+      id: RowId<AssemblyRefOsRow>,
+      row: AssemblyRefOsRow,
+    ) -> Option<AssemblyRefOsRow> {
+      self.assembly_ref_oses.set(id, row)
+    }
+
+    /// Adds a [FileRow] and returns its [RowId].
+    pub fn add_file(&mut self, row: FileRow) -> RowId<FileRow> {
+      self.files.add(row)
+    }
+
+    /// Returns the [FileRow]s added so far.
+    pub fn files(&self) -> &[FileRow] {
+      self.files.rows()
+    }
+
+    /// Replaces the [FileRow] with the given [RowId] and returns the previous row.
+    pub fn set_file(&mut self, id: RowId<FileRow>, row: FileRow) -> Option<FileRow> {
+      self.files.set(id, row)
+    }
+
+    /// Adds an [ExportedTypeRow] and returns its [RowId].
+    pub fn add_exported_type(&mut self, row: ExportedTypeRow) -> RowId<ExportedTypeRow> {
+      self.exported_types.add(row)
+    }
+
+    /// Returns the [ExportedTypeRow]s added so far.
+    pub fn exported_types(&self) -> &[ExportedTypeRow] {
+      self.exported_types.rows()
+    }
+
+    /// Replaces the [ExportedTypeRow] with the given [RowId] and returns the previous row.
+    pub fn set_exported_type(
+      &mut self,
+      id: RowId<ExportedTypeRow>,
+      row: ExportedTypeRow,
+    ) -> Option<ExportedTypeRow> {
+      self.exported_types.set(id, row)
+    }
+
+    /// Adds a [ManifestResourceRow] and returns its [RowId].
+    pub fn add_manifest_resource(
+      &mut self,
+      row: ManifestResourceRow,
+    ) -> RowId<ManifestResourceRow> {
+      self.manifest_resources.add(row)
+    }
+
+    /// Returns the [ManifestResourceRow]s added so far.
+    pub fn manifest_resources(&self) -> &[ManifestResourceRow] {
+      self.manifest_resources.rows()
+    }
+
+    /// Replaces the [ManifestResourceRow] with the given [RowId] and returns the previous row.
+    pub fn set_manifest_resource(
+      &mut self,
+      id: RowId<ManifestResourceRow>,
+      row: ManifestResourceRow,
+    ) -> Option<ManifestResourceRow> {
+      self.manifest_resources.set(id, row)
+    }
+
+    /// Adds a [NestedClassRow], the table is sorted when the stream is written.
+    pub fn add_nested_class(&mut self, row: NestedClassRow) {
+      self.nested_classes.add(row);
+    }
+
+    /// Returns the [NestedClassRow]s added so far.
+    pub fn nested_classes(&self) -> &[NestedClassRow] {
+      self.nested_classes.rows()
+    }
+
+    /// Replaces the [NestedClassRow] with the given [RowId] and returns the previous row.
+    pub fn set_nested_class(
+      &mut self,
+      id: RowId<NestedClassRow>,
+      row: NestedClassRow,
+    ) -> Option<NestedClassRow> {
+      self.nested_classes.set(id, row)
+    }
+
+    /// Adds a [GenericParamRow] and returns its [RowId].
+    ///
+    /// The table is only flagged as sorted if rows are added in sorted order.
+    pub fn add_generic_param(&mut self, row: GenericParamRow) -> RowId<GenericParamRow> {
+      self.generic_params.add(row)
+    }
+
+    /// Returns the [GenericParamRow]s added so far.
+    pub fn generic_params(&self) -> &[GenericParamRow] {
+      self.generic_params.rows()
+    }
+
+    /// Replaces the [GenericParamRow] with the given [RowId] and returns the previous row.
+    pub fn set_generic_param(
+      &mut self,
+      id: RowId<GenericParamRow>,
+      row: GenericParamRow,
+    ) -> Option<GenericParamRow> {
+      self.generic_params.set(id, row)
+    }
+
+    /// Adds a [MethodSpecRow] and returns its [RowId].
+    pub fn add_method_spec(&mut self, row: MethodSpecRow) -> RowId<MethodSpecRow> {
+      self.method_specs.add(row)
+    }
+
+    /// Returns the [MethodSpecRow]s added so far.
+    pub fn method_specs(&self) -> &[MethodSpecRow] {
+      self.method_specs.rows()
+    }
+
+    /// Replaces the [MethodSpecRow] with the given [RowId] and returns the previous row.
+    pub fn set_method_spec(
+      &mut self,
+      id: RowId<MethodSpecRow>,
+      row: MethodSpecRow,
+    ) -> Option<MethodSpecRow> {
+      self.method_specs.set(id, row)
+    }
+
+    /// Adds a [GenericParamConstraintRow] and returns its [RowId].
+    ///
+    /// The table is only flagged as sorted if rows are added in sorted order.
+    pub fn add_generic_param_constraint(
+      &mut self,
+      row: GenericParamConstraintRow,
+    ) -> RowId<GenericParamConstraintRow> {
+      self.generic_param_constraints.add(row)
+    }
+
+    /// Returns the [GenericParamConstraintRow]s added so far.
+    pub fn generic_param_constraints(&self) -> &[GenericParamConstraintRow] {
+      self.generic_param_constraints.rows()
+    }
+
+    /// Replaces the [GenericParamConstraintRow] with the given [RowId] and returns the previous row.
+    pub fn set_generic_param_constraint(
+      &mut self,
+      id: RowId<GenericParamConstraintRow>,
+      row: GenericParamConstraintRow,
+    ) -> Option<GenericParamConstraintRow> {
+      self.generic_param_constraints.set(id, row)
+    }
+
+    /// Adds a [DocumentRow] and returns its [RowId].
+    pub fn add_document(&mut self, row: DocumentRow) -> RowId<DocumentRow> {
+      self.documents.add(row)
+    }
+
+    /// Returns the [DocumentRow]s added so far.
+    pub fn documents(&self) -> &[DocumentRow] {
+      self.documents.rows()
+    }
+
+    /// Replaces the [DocumentRow] with the given [RowId] and returns the previous row.
+    pub fn set_document(
+      &mut self,
+      id: RowId<DocumentRow>,
+      row: DocumentRow,
+    ) -> Option<DocumentRow> {
+      self.documents.set(id, row)
+    }
+
+    /// Adds a [MethodDebugInformationRow] and returns its [RowId].
+    pub fn add_method_debug_information(
+      &mut self,
+      row: MethodDebugInformationRow,
+    ) -> RowId<MethodDebugInformationRow> {
+      self.method_debug_informations.add(row)
+    }
+
+    /// Returns the [MethodDebugInformationRow]s added so far.
+    pub fn method_debug_informations(&self) -> &[MethodDebugInformationRow] {
+      self.method_debug_informations.rows()
+    }
+
+    /// Replaces the [MethodDebugInformationRow] with the given [RowId] and returns the previous row.
+    pub fn set_method_debug_information(
+      &mut self,
+      id: RowId<MethodDebugInformationRow>,
+      row: MethodDebugInformationRow,
+    ) -> Option<MethodDebugInformationRow> {
+      self.method_debug_informations.set(id, row)
+    }
+
+    /// Adds a [LocalScopeRow] and returns its [RowId].
+    ///
+    /// The table is only flagged as sorted if rows are added in sorted order.
+    pub fn add_local_scope(&mut self, row: LocalScopeRow) -> RowId<LocalScopeRow> {
+      self.local_scopes.add(row)
+    }
+
+    /// Returns the [LocalScopeRow]s added so far.
+    pub fn local_scopes(&self) -> &[LocalScopeRow] {
+      self.local_scopes.rows()
+    }
+
+    /// Replaces the [LocalScopeRow] with the given [RowId] and returns the previous row.
+    pub fn set_local_scope(
+      &mut self,
+      id: RowId<LocalScopeRow>,
+      row: LocalScopeRow,
+    ) -> Option<LocalScopeRow> {
+      self.local_scopes.set(id, row)
+    }
+
+    /// Adds a [LocalVariableRow] and returns its [RowId].
+    pub fn add_local_variable(&mut self, row: LocalVariableRow) -> RowId<LocalVariableRow> {
+      self.local_variables.add(row)
+    }
+
+    /// Returns the [LocalVariableRow]s added so far.
+    pub fn local_variables(&self) -> &[LocalVariableRow] {
+      self.local_variables.rows()
+    }
+
+    /// Replaces the [LocalVariableRow] with the given [RowId] and returns the previous row.
+    pub fn set_local_variable(
+      &mut self,
+      id: RowId<LocalVariableRow>,
+      row: LocalVariableRow,
+    ) -> Option<LocalVariableRow> {
+      self.local_variables.set(id, row)
+    }
+
+    /// Adds a [LocalConstantRow] and returns its [RowId].
+    pub fn add_local_constant(&mut self, row: LocalConstantRow) -> RowId<LocalConstantRow> {
+      self.local_constants.add(row)
+    }
+
+    /// Returns the [LocalConstantRow]s added so far.
+    pub fn local_constants(&self) -> &[LocalConstantRow] {
+      self.local_constants.rows()
+    }
+
+    /// Replaces the [LocalConstantRow] with the given [RowId] and returns the previous row.
+    pub fn set_local_constant(
+      &mut self,
+      id: RowId<LocalConstantRow>,
+      row: LocalConstantRow,
+    ) -> Option<LocalConstantRow> {
+      self.local_constants.set(id, row)
+    }
+
+    /// Adds an [ImportScopeRow] and returns its [RowId].
+    pub fn add_import_scope(&mut self, row: ImportScopeRow) -> RowId<ImportScopeRow> {
+      self.import_scopes.add(row)
+    }
+
+    /// Returns the [ImportScopeRow]s added so far.
+    pub fn import_scopes(&self) -> &[ImportScopeRow] {
+      self.import_scopes.rows()
+    }
+
+    /// Replaces the [ImportScopeRow] with the given [RowId] and returns the previous row.
+    pub fn set_import_scope(
+      &mut self,
+      id: RowId<ImportScopeRow>,
+      row: ImportScopeRow,
+    ) -> Option<ImportScopeRow> {
+      self.import_scopes.set(id, row)
+    }
+
+    /// Adds a [StateMachineMethodRow], the table is sorted when the stream is written.
+    pub fn add_state_machine_method(&mut self, row: StateMachineMethodRow) {
+      self.state_machine_methods.add(row);
+    }
+
+    /// Returns the [StateMachineMethodRow]s added so far.
+    pub fn state_machine_methods(&self) -> &[StateMachineMethodRow] {
+      self.state_machine_methods.rows()
+    }
+
+    /// Replaces the [StateMachineMethodRow] with the given [RowId] and returns the previous row.
+    pub fn set_state_machine_method(
+      &mut self,
+      id: RowId<StateMachineMethodRow>,
+      row: StateMachineMethodRow,
+    ) -> Option<StateMachineMethodRow> {
+      self.state_machine_methods.set(id, row)
+    }
+
+    /// Adds a [CustomDebugInformationRow], the table is sorted when the stream is written.
+    pub fn add_custom_debug_information(&mut self, row: CustomDebugInformationRow) {
+      self.custom_debug_informations.add(row);
+    }
+
+    /// Returns the [CustomDebugInformationRow]s added so far.
+    pub fn custom_debug_informations(&self) -> &[CustomDebugInformationRow] {
+      self.custom_debug_informations.rows()
+    }
+
+    /// Replaces the [CustomDebugInformationRow] with the given [RowId] and returns the previous row.
+    pub fn set_custom_debug_information(
+      &mut self,
+      id: RowId<CustomDebugInformationRow>,
+      row: CustomDebugInformationRow,
+    ) -> Option<CustomDebugInformationRow> {
+      self.custom_debug_informations.set(id, row)
+    }
+
+    /// Sorts the tables that are required to be sorted and returns the bit vector of sorted tables.
+    ///
+    /// The tables of an uncompressed stream are left in the order the rows were added.
+    fn sort(&mut self) -> u64 {
+      let keep = self.uncompressed;
+      let mut sorted = 0;
+
+      for (id, is_sorted, len) in [
+        (
+          ConstantRow::ID,
+          sort_rows(&mut self.constants, keep, |row| row.parent().to_tag()),
+          self.constants.len(),
+        ),
+        (
+          CustomAttributeRow::ID,
+          sort_rows(&mut self.custom_attributes, keep, |row| {
+            row.parent().to_tag()
+          }),
+          self.custom_attributes.len(),
+        ),
+        (
+          FieldMarshalRow::ID,
+          sort_rows(&mut self.field_marshals, keep, |row| row.parent().to_tag()),
+          self.field_marshals.len(),
+        ),
+        (
+          ClassLayoutRow::ID,
+          sort_rows(&mut self.class_layouts, keep, |row| row.parent().index()),
+          self.class_layouts.len(),
+        ),
+        (
+          FieldLayoutRow::ID,
+          sort_rows(&mut self.field_layouts, keep, |row| row.field().index()),
+          self.field_layouts.len(),
+        ),
+        (
+          MethodSemanticsRow::ID,
+          sort_rows(&mut self.method_semantics, keep, |row| {
+            row.association().to_tag()
+          }),
+          self.method_semantics.len(),
+        ),
+        (
+          MethodImplRow::ID,
+          sort_rows(&mut self.method_impls, keep, |row| row.class().index()),
+          self.method_impls.len(),
+        ),
+        (
+          ImplMapRow::ID,
+          sort_rows(&mut self.impl_maps, keep, |row| {
+            row.member_forwarded().to_tag()
+          }),
+          self.impl_maps.len(),
+        ),
+        (
+          FieldRvaRow::ID,
+          sort_rows(&mut self.field_rvas, keep, |row| row.field().index()),
+          self.field_rvas.len(),
+        ),
+        (
+          NestedClassRow::ID,
+          sort_rows(&mut self.nested_classes, keep, |row| {
+            row.nested_class().index()
+          }),
+          self.nested_classes.len(),
+        ),
+        (
+          StateMachineMethodRow::ID,
+          sort_rows(&mut self.state_machine_methods, keep, |row| {
+            row.move_next_method().index()
+          }),
+          self.state_machine_methods.len(),
+        ),
+        (
+          CustomDebugInformationRow::ID,
+          sort_rows(&mut self.custom_debug_informations, keep, |row| {
+            row.parent().to_tag()
+          }),
+          self.custom_debug_informations.len(),
+        ),
+      ] {
+        // The type-system tables are always flagged, like Roslyn does, the debug tables only when
+        // present.
+        if is_sorted && (id < DocumentRow::ID || len > 0) {
+          sorted |= 1 << id;
+        }
+      }
+
+      if self
+        .interface_impls
+        .is_sorted_by_key(|row| (row.class().index(), row.interface().to_tag()))
+      {
+        sorted |= 1 << InterfaceImplRow::ID;
+      }
+
+      if self
+        .decl_securities
+        .is_sorted_by_key(|row| row.parent().to_tag())
+      {
+        sorted |= 1 << DeclSecurityRow::ID;
+      }
+
+      if self
+        .generic_params
+        .is_sorted_by_key(|row| (row.owner().to_tag(), row.number()))
+      {
+        sorted |= 1 << GenericParamRow::ID;
+      }
+
+      if self
+        .generic_param_constraints
+        .is_sorted_by_key(|row| row.owner().index())
+      {
+        sorted |= 1 << GenericParamConstraintRow::ID;
+      }
+
+      if self.local_scopes.is_sorted_by_key(|row| {
+        (
+          row.method().index(),
+          row.start_offset(),
+          Reverse(row.length()),
+        )
+      }) {
+        sorted |= 1 << LocalScopeRow::ID;
+      }
+
+      sorted
+    }
+
+    /// Sorts the tables that are required to be sorted and returns the bytes of the stream padded to
+    /// a 4 byte boundary.
+    pub fn finish(mut self) -> Vec<u8> {
+      let sorted = self.sort();
+      let mut header = MetadataTablesHeader {
+        _reserved_0: 0,
+        major_version: 2,
+        minor_version: 0,
+        heap_sizes: self.heap_sizes,
+        _reserved_1: 1,
+        valid: 0,
+        sorted: self.sorted.unwrap_or(sorted),
+        rows: [0; 64],
+        extra_data: self.extra_data,
+      };
+
+      header.rows[ModuleRow::ID] = self.modules.len() as u32;
+      header.rows[TypeRefRow::ID] = self.type_refs.len() as u32;
+      header.rows[TypeDefRow::ID] = self.type_defs.len() as u32;
+      header.rows[FieldPtrRow::ID] = self.field_ptrs.len() as u32;
+      header.rows[FieldRow::ID] = self.fields.len() as u32;
+      header.rows[MethodPtrRow::ID] = self.method_ptrs.len() as u32;
+      header.rows[MethodDefRow::ID] = self.method_defs.len() as u32;
+      header.rows[ParamPtrRow::ID] = self.param_ptrs.len() as u32;
+      header.rows[ParamRow::ID] = self.params.len() as u32;
+      header.rows[InterfaceImplRow::ID] = self.interface_impls.len() as u32;
+      header.rows[MemberRefRow::ID] = self.member_refs.len() as u32;
+      header.rows[ConstantRow::ID] = self.constants.len() as u32;
+      header.rows[CustomAttributeRow::ID] = self.custom_attributes.len() as u32;
+      header.rows[FieldMarshalRow::ID] = self.field_marshals.len() as u32;
+      header.rows[DeclSecurityRow::ID] = self.decl_securities.len() as u32;
+      header.rows[ClassLayoutRow::ID] = self.class_layouts.len() as u32;
+      header.rows[FieldLayoutRow::ID] = self.field_layouts.len() as u32;
+      header.rows[StandAloneSigRow::ID] = self.stand_alone_sigs.len() as u32;
+      header.rows[EventMapRow::ID] = self.event_maps.len() as u32;
+      header.rows[EventPtrRow::ID] = self.event_ptrs.len() as u32;
+      header.rows[EventRow::ID] = self.events.len() as u32;
+      header.rows[PropertyMapRow::ID] = self.property_maps.len() as u32;
+      header.rows[PropertyPtrRow::ID] = self.property_ptrs.len() as u32;
+      header.rows[PropertyRow::ID] = self.properties.len() as u32;
+      header.rows[MethodSemanticsRow::ID] = self.method_semantics.len() as u32;
+      header.rows[MethodImplRow::ID] = self.method_impls.len() as u32;
+      header.rows[ModuleRefRow::ID] = self.module_refs.len() as u32;
+      header.rows[TypeSpecRow::ID] = self.type_specs.len() as u32;
+      header.rows[ImplMapRow::ID] = self.impl_maps.len() as u32;
+      header.rows[FieldRvaRow::ID] = self.field_rvas.len() as u32;
+      header.rows[AssemblyRow::ID] = self.assemblies.len() as u32;
+      header.rows[AssemblyProcessorRow::ID] = self.assembly_processors.len() as u32;
+      header.rows[AssemblyOsRow::ID] = self.assembly_oses.len() as u32;
+      header.rows[AssemblyRefRow::ID] = self.assembly_refs.len() as u32;
+      header.rows[AssemblyRefProcessorRow::ID] = self.assembly_ref_processors.len() as u32;
+      header.rows[AssemblyRefOsRow::ID] = self.assembly_ref_oses.len() as u32;
+      header.rows[FileRow::ID] = self.files.len() as u32;
+      header.rows[ExportedTypeRow::ID] = self.exported_types.len() as u32;
+      header.rows[ManifestResourceRow::ID] = self.manifest_resources.len() as u32;
+      header.rows[NestedClassRow::ID] = self.nested_classes.len() as u32;
+      header.rows[GenericParamRow::ID] = self.generic_params.len() as u32;
+      header.rows[MethodSpecRow::ID] = self.method_specs.len() as u32;
+      header.rows[GenericParamConstraintRow::ID] = self.generic_param_constraints.len() as u32;
+      header.rows[DocumentRow::ID] = self.documents.len() as u32;
+      header.rows[MethodDebugInformationRow::ID] = self.method_debug_informations.len() as u32;
+      header.rows[LocalScopeRow::ID] = self.local_scopes.len() as u32;
+      header.rows[LocalVariableRow::ID] = self.local_variables.len() as u32;
+      header.rows[LocalConstantRow::ID] = self.local_constants.len() as u32;
+      header.rows[ImportScopeRow::ID] = self.import_scopes.len() as u32;
+      header.rows[StateMachineMethodRow::ID] = self.state_machine_methods.len() as u32;
+      header.rows[CustomDebugInformationRow::ID] = self.custom_debug_informations.len() as u32;
+
+      for (id, rows) in header.rows.iter().enumerate() {
+        if *rows > 0 {
+          header.valid |= 1 << id;
+        }
+      }
+
+      let mut buf = Vec::new();
+
+      header.to_bytes(&mut buf, ());
+
+      // External row counts are not written, but determine the width of indexes into them.
+      if let Some(external_rows) = self.external_rows {
+        for (id, rows) in header.rows.iter_mut().enumerate() {
+          if header.valid & (1 << id) == 0 {
+            *rows = external_rows[id];
+          }
+        }
+      }
+
+      self.modules.write(&mut buf, &header);
+      self.type_refs.write(&mut buf, &header);
+      self.type_defs.write(&mut buf, &header);
+      self.field_ptrs.write(&mut buf, &header);
+      self.fields.write(&mut buf, &header);
+      self.method_ptrs.write(&mut buf, &header);
+      self.method_defs.write(&mut buf, &header);
+      self.param_ptrs.write(&mut buf, &header);
+      self.params.write(&mut buf, &header);
+      self.interface_impls.write(&mut buf, &header);
+      self.member_refs.write(&mut buf, &header);
+      self.constants.write(&mut buf, &header);
+      self.custom_attributes.write(&mut buf, &header);
+      self.field_marshals.write(&mut buf, &header);
+      self.decl_securities.write(&mut buf, &header);
+      self.class_layouts.write(&mut buf, &header);
+      self.field_layouts.write(&mut buf, &header);
+      self.stand_alone_sigs.write(&mut buf, &header);
+      self.event_maps.write(&mut buf, &header);
+      self.event_ptrs.write(&mut buf, &header);
+      self.events.write(&mut buf, &header);
+      self.property_maps.write(&mut buf, &header);
+      self.property_ptrs.write(&mut buf, &header);
+      self.properties.write(&mut buf, &header);
+      self.method_semantics.write(&mut buf, &header);
+      self.method_impls.write(&mut buf, &header);
+      self.module_refs.write(&mut buf, &header);
+      self.type_specs.write(&mut buf, &header);
+      self.impl_maps.write(&mut buf, &header);
+      self.field_rvas.write(&mut buf, &header);
+      self.assemblies.write(&mut buf, &header);
+      self.assembly_processors.write(&mut buf, &header);
+      self.assembly_oses.write(&mut buf, &header);
+      self.assembly_refs.write(&mut buf, &header);
+      self.assembly_ref_processors.write(&mut buf, &header);
+      self.assembly_ref_oses.write(&mut buf, &header);
+      self.files.write(&mut buf, &header);
+      self.exported_types.write(&mut buf, &header);
+      self.manifest_resources.write(&mut buf, &header);
+      self.nested_classes.write(&mut buf, &header);
+      self.generic_params.write(&mut buf, &header);
+      self.method_specs.write(&mut buf, &header);
+      self.generic_param_constraints.write(&mut buf, &header);
+      self.documents.write(&mut buf, &header);
+      self.method_debug_informations.write(&mut buf, &header);
+      self.local_scopes.write(&mut buf, &header);
+      self.local_variables.write(&mut buf, &header);
+      self.local_constants.write(&mut buf, &header);
+      self.import_scopes.write(&mut buf, &header);
+      self.state_machine_methods.write(&mut buf, &header);
+      self.custom_debug_informations.write(&mut buf, &header);
+
+      buf.extend_from_slice(&self.trailer);
+      buf.resize((buf.len() + 3) & !3, 0);
+      buf
+    }
+  }
+
+  /// Sorts the rows of the table by the given key, or only checks whether they are sorted if the
+  /// order of the rows is kept.
+  fn sort_rows<R: RowWrite, K: Ord>(
+    table: &mut TableBuilder<R>,
+    keep: bool,
+    key: impl FnMut(&R) -> K,
+  ) -> bool {
+    match keep {
+      true => table.is_sorted_by_key(key),
+      false => {
+        table.sort_by_key(key);
+        true
+      }
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  #[test]
+  #[cfg(feature = "write")]
+  fn test_build_sorts_tables() {
+    use super::rows::*;
+    use super::{TablesBuilder, TablesStream};
+    use crate::metadata::streams::blobs::BlobsHeapBuilder;
+    use crate::metadata::streams::strings::StringsHeapBuilder;
+    use crate::metadata::streams::tables::flags::{
+      MethodAttributes, MethodImplAttributes, TypeAttributes,
+    };
+    use crate::metadata::streams::tables::id::{
+      CustomAttributeType, HasCustomAttribute, RowId, TypeDefOrRef,
+    };
+
+    let mut strings = StringsHeapBuilder::new();
+    let mut blobs = BlobsHeapBuilder::new();
+    let mut builder = TablesBuilder::new();
+    let ctor = builder.add_method_def(MethodDefRow::new(
+      0,
+      MethodImplAttributes::empty(),
+      MethodAttributes::PUBLIC,
+      strings.add(".ctor"),
+      blobs.add(&[0x20, 0x00, 0x01]),
+      RowId::new(1),
+    ));
+
+    for i in 0..0x4000 {
+      builder.add_type_def(TypeDefRow::new(
+        TypeAttributes::PUBLIC,
+        strings.add(&format!("Type{i}")),
+        strings.add(""),
+        TypeDefOrRef::TypeDef(RowId::new(i)),
+        RowId::new(1),
+        RowId::new(2),
+      ));
+    }
+
+    for parent in [3, 1, 2] {
+      builder.add_custom_attribute(CustomAttributeRow::new(
+        HasCustomAttribute::TypeDef(RowId::new(parent)),
+        CustomAttributeType::MethodDef(ctor),
+        blobs.add(&[0x01, 0x00, 0x00, 0x00]),
+      ));
+    }
+
+    builder.set_heap_sizes(strings.heap_sizes() | blobs.heap_sizes());
+
+    let bytes = builder.finish();
+    let tables = TablesStream::from_bytes(&bytes).unwrap();
+    let parents = tables
+      .custom_attributes()
+      .into_iter()
+      .map(|row| row.parent())
+      .collect::<Vec<_>>();
+
+    assert_eq!(0, bytes.len() % 4);
+    assert_ne!(0, tables.header().sorted & (1 << CustomAttributeRow::ID));
+    assert_eq!(0x4000, tables.type_defs().len());
+    assert_eq!(
+      TypeDefOrRef::TypeDef(RowId::new(0x3fff)),
+      tables.type_defs().into_iter().last().unwrap().extends()
+    );
+    assert_eq!(
+      vec![
+        HasCustomAttribute::TypeDef(RowId::new(1)),
+        HasCustomAttribute::TypeDef(RowId::new(2)),
+        HasCustomAttribute::TypeDef(RowId::new(3)),
+      ],
+      parents
+    );
+  }
+}
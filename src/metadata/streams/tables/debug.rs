@@ -0,0 +1,827 @@
+//! Portable PDB custom debug information.
+//!
+//! The format of the `value` blob of a [CustomDebugInformationRow] is identified by the guid in its
+//! `kind` column.  This module decodes the well-known kinds emitted by the C# and VB compilers.
+//!
+//! [CustomDebugInformationRow]: super::rows::CustomDebugInformationRow
+
+#[cfg(feature = "read")]
+#[doc(inline)]
+pub use read::*;
+
+/// Creates the little-endian byte representation of a guid, as stored in the `#GUID` heap.
+const fn guid(a: u32, b: u16, c: u16, d: [u8; 8]) -> [u8; 16] {
+  let a = a.to_le_bytes();
+  let b = b.to_le_bytes();
+  let c = c.to_le_bytes();
+
+  [
+    a[0], a[1], a[2], a[3], b[0], b[1], c[0], c[1], d[0], d[1], d[2], d[3], d[4], d[5], d[6], d[7],
+  ]
+}
+
+/// The well-known kinds of custom debug information.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum CustomDebugInformationKind {
+  /// Scopes of local variables hoisted to fields of a state machine.
+  StateMachineHoistedLocalScopes,
+  /// Which parts of the types of local variables and constants are `dynamic`.
+  DynamicLocalVariables,
+  /// The tuple element names of local variables and constants.
+  TupleElementNames,
+  /// The default namespace of a VB project.
+  DefaultNamespace,
+  /// Edit and Continue local slot map.
+  EncLocalSlotMap,
+  /// Edit and Continue lambda and closure map.
+  EncLambdaAndClosureMap,
+  /// The source of a document embedded in the PDB.
+  EmbeddedSource,
+  /// The Source Link JSON document.
+  SourceLink,
+  /// The options the compilation was created with.
+  CompilationOptions,
+  /// The metadata references of the compilation.
+  CompilationMetadataReferences,
+}
+
+impl CustomDebugInformationKind {
+  /// The kind guid of [CustomDebugInformationKind::StateMachineHoistedLocalScopes].
+  pub const STATE_MACHINE_HOISTED_LOCAL_SCOPES: [u8; 16] = guid(
+    0x6da9a61e,
+    0xf8c7,
+    0x4874,
+    [0xbe, 0x62, 0x68, 0xbc, 0x56, 0x30, 0xdf, 0x71],
+  );
+  /// The kind guid of [CustomDebugInformationKind::DynamicLocalVariables].
+  pub const DYNAMIC_LOCAL_VARIABLES: [u8; 16] = guid(
+    0x83c563c4,
+    0xb4f3,
+    0x47d5,
+    [0xb8, 0x24, 0xba, 0x54, 0x41, 0x47, 0x7e, 0xa8],
+  );
+  /// The kind guid of [CustomDebugInformationKind::TupleElementNames].
+  pub const TUPLE_ELEMENT_NAMES: [u8; 16] = guid(
+    0xed9fdf71,
+    0x8879,
+    0x4747,
+    [0x8e, 0xd3, 0xfe, 0x5e, 0xde, 0x3c, 0xe7, 0x10],
+  );
+  /// The kind guid of [CustomDebugInformationKind::DefaultNamespace].
+  pub const DEFAULT_NAMESPACE: [u8; 16] = guid(
+    0x58b2eab6,
+    0x209f,
+    0x4e4e,
+    [0xa2, 0x2c, 0xb2, 0xd0, 0xf9, 0x10, 0xc7, 0x82],
+  );
+  /// The kind guid of [CustomDebugInformationKind::EncLocalSlotMap].
+  pub const ENC_LOCAL_SLOT_MAP: [u8; 16] = guid(
+    0x755f52a8,
+    0x91c5,
+    0x45be,
+    [0xb4, 0xb8, 0x20, 0x95, 0x71, 0xe5, 0x52, 0xbd],
+  );
+  /// The kind guid of [CustomDebugInformationKind::EncLambdaAndClosureMap].
+  pub const ENC_LAMBDA_AND_CLOSURE_MAP: [u8; 16] = guid(
+    0xa643004c,
+    0x0240,
+    0x496f,
+    [0xa7, 0x83, 0x30, 0xd6, 0x4f, 0x49, 0x79, 0xde],
+  );
+  /// The kind guid of [CustomDebugInformationKind::EmbeddedSource].
+  pub const EMBEDDED_SOURCE: [u8; 16] = guid(
+    0x0e8a571b,
+    0x6926,
+    0x466e,
+    [0xb4, 0xad, 0x8a, 0xb0, 0x46, 0x11, 0xf5, 0xfe],
+  );
+  /// The kind guid of [CustomDebugInformationKind::SourceLink].
+  pub const SOURCE_LINK: [u8; 16] = guid(
+    0xcc110556,
+    0xa091,
+    0x4d38,
+    [0x9f, 0xec, 0x25, 0xab, 0x9a, 0x35, 0x1a, 0x6a],
+  );
+  /// The kind guid of [CustomDebugInformationKind::CompilationOptions].
+  pub const COMPILATION_OPTIONS: [u8; 16] = guid(
+    0xb5feec05,
+    0x8cd0,
+    0x4a83,
+    [0x96, 0xda, 0x46, 0x62, 0x84, 0xbb, 0x4b, 0xd8],
+  );
+  /// The kind guid of [CustomDebugInformationKind::CompilationMetadataReferences].
+  pub const COMPILATION_METADATA_REFERENCES: [u8; 16] = guid(
+    0x7e4d4708,
+    0x096e,
+    0x4c5c,
+    [0xae, 0xda, 0xcb, 0x10, 0xba, 0x6a, 0x74, 0x0d],
+  );
+
+  /// Returns the kind identified by the given guid.
+  ///
+  /// Returns `None` if the guid is not a well-known kind.
+  pub fn from_guid(guid: [u8; 16]) -> Option<Self> {
+    Some(match guid {
+      Self::STATE_MACHINE_HOISTED_LOCAL_SCOPES => Self::StateMachineHoistedLocalScopes,
+      Self::DYNAMIC_LOCAL_VARIABLES => Self::DynamicLocalVariables,
+      Self::TUPLE_ELEMENT_NAMES => Self::TupleElementNames,
+      Self::DEFAULT_NAMESPACE => Self::DefaultNamespace,
+      Self::ENC_LOCAL_SLOT_MAP => Self::EncLocalSlotMap,
+      Self::ENC_LAMBDA_AND_CLOSURE_MAP => Self::EncLambdaAndClosureMap,
+      Self::EMBEDDED_SOURCE => Self::EmbeddedSource,
+      Self::SOURCE_LINK => Self::SourceLink,
+      Self::COMPILATION_OPTIONS => Self::CompilationOptions,
+      Self::COMPILATION_METADATA_REFERENCES => Self::CompilationMetadataReferences,
+      _ => return None,
+    })
+  }
+
+  /// Returns the guid identifying this kind.
+  pub const fn guid(self) -> [u8; 16] {
+    match self {
+      Self::StateMachineHoistedLocalScopes => Self::STATE_MACHINE_HOISTED_LOCAL_SCOPES,
+      Self::DynamicLocalVariables => Self::DYNAMIC_LOCAL_VARIABLES,
+      Self::TupleElementNames => Self::TUPLE_ELEMENT_NAMES,
+      Self::DefaultNamespace => Self::DEFAULT_NAMESPACE,
+      Self::EncLocalSlotMap => Self::ENC_LOCAL_SLOT_MAP,
+      Self::EncLambdaAndClosureMap => Self::ENC_LAMBDA_AND_CLOSURE_MAP,
+      Self::EmbeddedSource => Self::EMBEDDED_SOURCE,
+      Self::SourceLink => Self::SOURCE_LINK,
+      Self::CompilationOptions => Self::COMPILATION_OPTIONS,
+      Self::CompilationMetadataReferences => Self::COMPILATION_METADATA_REFERENCES,
+    }
+  }
+}
+
+#[cfg(feature = "read")]
+#[doc(hidden)]
+mod read {
+  use super::CustomDebugInformationKind;
+  use crate::bytes::{ByteSliceExt, CompressedLength, NulTerminated};
+  use core::ffi::CStr;
+
+  /// Decoded custom debug information.
+  #[derive(Debug, Clone, Copy)]
+  #[non_exhaustive]
+  pub enum CustomDebugInformation<'a> {
+    /// See [CustomDebugInformationKind::StateMachineHoistedLocalScopes].
+    StateMachineHoistedLocalScopes(HoistedLocalScopes<'a>),
+    /// See [CustomDebugInformationKind::DynamicLocalVariables].
+    DynamicLocalVariables(DynamicLocalVariables<'a>),
+    /// See [CustomDebugInformationKind::TupleElementNames].
+    TupleElementNames(TupleElementNames<'a>),
+    /// See [CustomDebugInformationKind::DefaultNamespace].
+    DefaultNamespace(&'a str),
+    /// See [CustomDebugInformationKind::EncLocalSlotMap].
+    EncLocalSlotMap(EncLocalSlotMap<'a>),
+    /// See [CustomDebugInformationKind::EncLambdaAndClosureMap].
+    EncLambdaAndClosureMap(EncLambdaAndClosureMap<'a>),
+    /// See [CustomDebugInformationKind::EmbeddedSource].
+    EmbeddedSource(EmbeddedSource<'a>),
+    /// See [CustomDebugInformationKind::SourceLink].
+    ///
+    /// Contains the UTF-8 encoded JSON document.
+    SourceLink(&'a str),
+    /// See [CustomDebugInformationKind::CompilationOptions].
+    CompilationOptions(CompilationOptions<'a>),
+    /// See [CustomDebugInformationKind::CompilationMetadataReferences].
+    CompilationMetadataReferences(MetadataReferences<'a>),
+    /// The kind guid was not recognized.
+    Unrecognized {
+      /// The kind guid.
+      kind: [u8; 16],
+      /// The undecoded value blob.
+      value: &'a [u8],
+    },
+  }
+
+  impl<'a> CustomDebugInformation<'a> {
+    /// Decodes the given value blob using the format identified by the given kind guid.
+    ///
+    /// Returns `None` if the blob is malformed.  Blobs made of repeated records are decoded lazily,
+    /// and their iterators stop at the first malformed record.
+    pub fn decode(kind: [u8; 16], value: &'a [u8]) -> Option<Self> {
+      let kind = match CustomDebugInformationKind::from_guid(kind) {
+        Some(kind) => kind,
+        None => return Some(Self::Unrecognized { kind, value }),
+      };
+
+      Some(match kind {
+        CustomDebugInformationKind::StateMachineHoistedLocalScopes => {
+          Self::StateMachineHoistedLocalScopes(HoistedLocalScopes(value))
+        }
+        CustomDebugInformationKind::DynamicLocalVariables => {
+          Self::DynamicLocalVariables(DynamicLocalVariables(value))
+        }
+        CustomDebugInformationKind::TupleElementNames => {
+          Self::TupleElementNames(TupleElementNames(value))
+        }
+        CustomDebugInformationKind::DefaultNamespace => {
+          Self::DefaultNamespace(core::str::from_utf8(value).ok()?)
+        }
+        CustomDebugInformationKind::EncLocalSlotMap => {
+          Self::EncLocalSlotMap(EncLocalSlotMap(value))
+        }
+        CustomDebugInformationKind::EncLambdaAndClosureMap => {
+          Self::EncLambdaAndClosureMap(EncLambdaAndClosureMap::from_bytes(value)?)
+        }
+        CustomDebugInformationKind::EmbeddedSource => {
+          Self::EmbeddedSource(EmbeddedSource::from_bytes(value)?)
+        }
+        CustomDebugInformationKind::SourceLink => {
+          Self::SourceLink(core::str::from_utf8(value).ok()?)
+        }
+        CustomDebugInformationKind::CompilationOptions => {
+          Self::CompilationOptions(CompilationOptions(value))
+        }
+        CustomDebugInformationKind::CompilationMetadataReferences => {
+          Self::CompilationMetadataReferences(MetadataReferences(value))
+        }
+      })
+    }
+  }
+
+  /// Reads a nul-terminated UTF-8 string.
+  fn read_str<'a>(buf: &'a [u8], offset: &mut usize) -> Option<&'a str> {
+    buf
+      .read_with::<&CStr, _>(offset, NulTerminated)?
+      .to_str()
+      .ok()
+  }
+
+  /// Reads a compressed unsigned integer.
+  fn read_compressed(buf: &[u8], offset: &mut usize) -> Option<i32> {
+    buf
+      .read_with::<usize, _>(offset, CompressedLength)
+      .map(|val| val as i32)
+  }
+
+  /// The scopes of local variables hoisted to fields of a state machine, in the order of the fields.
+  #[derive(Debug, Clone, Copy)]
+  pub struct HoistedLocalScopes<'a>(&'a [u8]);
+
+  /// The IL range a hoisted local variable is in scope.
+  ///
+  /// A scope with both a zero offset and length indicates the variable has no scope.
+  #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+  pub struct HoistedLocalScope {
+    /// The IL offset of the start of the scope.
+    pub start_offset: u32,
+    /// The length of the scope in bytes.
+    pub length: u32,
+  }
+
+  impl<'a> IntoIterator for HoistedLocalScopes<'a> {
+    type Item = HoistedLocalScope;
+    type IntoIter = HoistedLocalScopesIter<'a>;
+
+    fn into_iter(self) -> Self::IntoIter {
+      HoistedLocalScopesIter {
+        data: self.0,
+        offset: 0,
+      }
+    }
+  }
+
+  /// An iterator over [HoistedLocalScope]s.
+  #[derive(Clone, Copy)]
+  pub struct HoistedLocalScopesIter<'a> {
+    data: &'a [u8],
+    offset: usize,
+  }
+
+  impl<'a> Iterator for HoistedLocalScopesIter<'a> {
+    type Item = HoistedLocalScope;
+
+    fn next(&mut self) -> Option<Self::Item> {
+      Some(HoistedLocalScope {
+        start_offset: self.data.read(&mut self.offset)?,
+        length: self.data.read(&mut self.offset)?,
+      })
+    }
+  }
+
+  /// A bit sequence indicating which types in the signature of a local variable or constant are
+  /// `dynamic` rather than `object`.
+  #[derive(Debug, Clone, Copy)]
+  pub struct DynamicLocalVariables<'a>(&'a [u8]);
+
+  impl<'a> DynamicLocalVariables<'a> {
+    /// Returns whether the type at the given position in the pre-order traversal of the signature is
+    /// `dynamic`.
+    pub fn get(&self, index: usize) -> bool {
+      match self.0.get(index / 8) {
+        Some(byte) => byte & (1 << (index % 8)) != 0,
+        None => false,
+      }
+    }
+
+    /// Returns the number of flags in the sequence, including trailing padding bits.
+    pub fn len(&self) -> usize {
+      self.0.len() * 8
+    }
+
+    /// Returns `true` if the sequence contains no flags.
+    pub fn is_empty(&self) -> bool {
+      self.0.is_empty()
+    }
+  }
+
+  /// The tuple element names of the tuple types in the signature of a local variable or constant.
+  #[derive(Debug, Clone, Copy)]
+  pub struct TupleElementNames<'a>(&'a [u8]);
+
+  impl<'a> IntoIterator for TupleElementNames<'a> {
+    type Item = Option<&'a str>;
+    type IntoIter = TupleElementNamesIter<'a>;
+
+    fn into_iter(self) -> Self::IntoIter {
+      TupleElementNamesIter {
+        data: self.0,
+        offset: 0,
+      }
+    }
+  }
+
+  /// An iterator over tuple element names.
+  ///
+  /// Yields `None` for unnamed elements.
+  #[derive(Clone, Copy)]
+  pub struct TupleElementNamesIter<'a> {
+    data: &'a [u8],
+    offset: usize,
+  }
+
+  impl<'a> Iterator for TupleElementNamesIter<'a> {
+    type Item = Option<&'a str>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+      let name = read_str(self.data, &mut self.offset)?;
+
+      Some((!name.is_empty()).then_some(name))
+    }
+  }
+
+  /// Maps the local variable slots of a method to the syntax that declared them, used to preserve
+  /// slots across Edit and Continue generations.
+  #[derive(Debug, Clone, Copy)]
+  pub struct EncLocalSlotMap<'a>(&'a [u8]);
+
+  /// A local variable slot of an [EncLocalSlotMap].
+  #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+  pub enum EncLocalSlot {
+    /// A short-lived temporary that is not preserved across generations.
+    Temporary,
+    /// A long-lived local variable.
+    LongLived {
+      /// The compiler specific kind of the local, `0` for user-defined variables.
+      kind: u8,
+      /// The offset of the declaring syntax node from the start of the method body syntax.
+      syntax_offset: i32,
+      /// Distinguishes locals declared by the same syntax node.
+      ordinal: i32,
+    },
+  }
+
+  impl<'a> IntoIterator for EncLocalSlotMap<'a> {
+    type Item = EncLocalSlot;
+    type IntoIter = EncLocalSlotMapIter<'a>;
+
+    fn into_iter(self) -> Self::IntoIter {
+      EncLocalSlotMapIter {
+        data: self.0,
+        offset: 0,
+        syntax_offset_baseline: -1,
+      }
+    }
+  }
+
+  /// An iterator over [EncLocalSlot]s.
+  #[derive(Clone, Copy)]
+  pub struct EncLocalSlotMapIter<'a> {
+    data: &'a [u8],
+    offset: usize,
+    syntax_offset_baseline: i32,
+  }
+
+  impl<'a> Iterator for EncLocalSlotMapIter<'a> {
+    type Item = EncLocalSlot;
+
+    fn next(&mut self) -> Option<Self::Item> {
+      let mut kind = self.data.read::<u8>(&mut self.offset)?;
+
+      // A leading `0xff` byte introduces a negative syntax offset baseline.
+      if kind == 0xff {
+        self.syntax_offset_baseline = -read_compressed(self.data, &mut self.offset)?;
+        kind = self.data.read::<u8>(&mut self.offset)?;
+      }
+
+      if kind == 0 {
+        return Some(EncLocalSlot::Temporary);
+      }
+
+      // The kind is stored biased by one, a zero kind of a long lived slot is malformed.
+      let Some(slot_kind) = (kind & 0x3f).checked_sub(1) else {
+        self.offset = self.data.len();
+        return None;
+      };
+
+      let syntax_offset = read_compressed(self.data, &mut self.offset)?;
+      let ordinal = match kind & 0x80 != 0 {
+        true => read_compressed(self.data, &mut self.offset)?,
+        false => 0,
+      };
+
+      Some(EncLocalSlot::LongLived {
+        kind: slot_kind,
+        syntax_offset: syntax_offset.wrapping_add(self.syntax_offset_baseline),
+        ordinal,
+      })
+    }
+  }
+
+  /// Maps the lambdas and closures of a method to the syntax that declared them, used to preserve
+  /// them across Edit and Continue generations.
+  #[derive(Debug, Clone, Copy)]
+  pub struct EncLambdaAndClosureMap<'a> {
+    method_ordinal: i32,
+    syntax_offset_baseline: i32,
+    closures: &'a [u8],
+    lambdas: &'a [u8],
+  }
+
+  /// A closure of an [EncLambdaAndClosureMap].
+  #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+  pub struct EncClosure {
+    /// The offset of the syntax node the closure is created for.
+    pub syntax_offset: i32,
+  }
+
+  /// A lambda of an [EncLambdaAndClosureMap].
+  #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+  pub struct EncLambda {
+    /// The offset of the syntax node of the lambda.
+    pub syntax_offset: i32,
+    /// The index of the closure the lambda is emitted to, `-1` for static lambdas and `-2` for
+    /// lambdas only capturing `this`.
+    pub closure_ordinal: i32,
+  }
+
+  impl<'a> EncLambdaAndClosureMap<'a> {
+    /// Decodes the header of the map and locates its closures and lambdas.
+    pub fn from_bytes(buf: &'a [u8]) -> Option<Self> {
+      let offset = &mut 0;
+      let method_ordinal = read_compressed(buf, offset)? - 1;
+      let syntax_offset_baseline = -read_compressed(buf, offset)?;
+      let closure_count = read_compressed(buf, offset)?;
+      let beg = *offset;
+
+      for _ in 0..closure_count {
+        read_compressed(buf, offset)?;
+      }
+
+      Some(Self {
+        method_ordinal,
+        syntax_offset_baseline,
+        closures: buf.get(beg..*offset)?,
+        lambdas: buf.remaining(offset),
+      })
+    }
+
+    /// Returns the ordinal of the method among the methods generated from the same syntax, or `-1`.
+    pub fn method_ordinal(&self) -> i32 {
+      self.method_ordinal
+    }
+
+    /// Returns an iterator over the closures of the method.
+    pub fn closures(&self) -> impl Iterator<Item = EncClosure> + 'a {
+      let data = self.closures;
+      let baseline = self.syntax_offset_baseline;
+      let mut offset = 0;
+
+      core::iter::from_fn(move || {
+        Some(EncClosure {
+          syntax_offset: read_compressed(data, &mut offset)?.wrapping_add(baseline),
+        })
+      })
+    }
+
+    /// Returns an iterator over the lambdas of the method.
+    pub fn lambdas(&self) -> impl Iterator<Item = EncLambda> + 'a {
+      let data = self.lambdas;
+      let baseline = self.syntax_offset_baseline;
+      let mut offset = 0;
+
+      core::iter::from_fn(move || {
+        Some(EncLambda {
+          syntax_offset: read_compressed(data, &mut offset)?.wrapping_add(baseline),
+          closure_ordinal: read_compressed(data, &mut offset)? - 2,
+        })
+      })
+    }
+  }
+
+  /// The source of a document embedded in the PDB.
+  #[derive(Debug, Clone, Copy)]
+  pub struct EmbeddedSource<'a> {
+    format: i32,
+    content: &'a [u8],
+  }
+
+  impl<'a> EmbeddedSource<'a> {
+    /// Decodes the format header of the embedded source.
+    pub fn from_bytes(buf: &'a [u8]) -> Option<Self> {
+      let offset = &mut 0;
+
+      Some(Self {
+        format: buf.read(offset)?,
+        content: buf.remaining(offset),
+      })
+    }
+
+    /// Returns `true` if the content is deflate compressed.
+    pub fn is_compressed(&self) -> bool {
+      self.format > 0
+    }
+
+    /// Returns the size of the source after decompression.
+    pub fn len(&self) -> usize {
+      match self.is_compressed() {
+        true => self.format as usize,
+        false => self.content.len(),
+      }
+    }
+
+    /// Returns `true` if the source is empty.
+    pub fn is_empty(&self) -> bool {
+      self.len() == 0
+    }
+
+    /// Returns the raw, possibly compressed, content.
+    pub fn content(&self) -> &'a [u8] {
+      self.content
+    }
+
+    /// Returns the source, decompressing it if necessary.
+    ///
+    /// Returns `None` if the content could not be decompressed to the expected size.
+    #[cfg(feature = "deflate")]
+    pub fn decompress(&self) -> Option<std::borrow::Cow<'a, [u8]>> {
+      if !self.is_compressed() {
+        return Some(self.content.into());
+      }
+
+      let data =
+        miniz_oxide::inflate::decompress_to_vec_with_limit(self.content, self.len()).ok()?;
+
+      (data.len() == self.len()).then_some(data.into())
+    }
+  }
+
+  /// The options a compilation was created with, as `key` and `value` pairs.
+  #[derive(Debug, Clone, Copy)]
+  pub struct CompilationOptions<'a>(&'a [u8]);
+
+  impl<'a> CompilationOptions<'a> {
+    /// Returns the value of the option with the given key.
+    pub fn get(&self, key: &str) -> Option<&'a str> {
+      self
+        .into_iter()
+        .find_map(|(k, value)| (k == key).then_some(value))
+    }
+  }
+
+  impl<'a> IntoIterator for CompilationOptions<'a> {
+    type Item = (&'a str, &'a str);
+    type IntoIter = CompilationOptionsIter<'a>;
+
+    fn into_iter(self) -> Self::IntoIter {
+      CompilationOptionsIter {
+        data: self.0,
+        offset: 0,
+      }
+    }
+  }
+
+  /// An iterator over the `key` and `value` pairs of [CompilationOptions].
+  #[derive(Clone, Copy)]
+  pub struct CompilationOptionsIter<'a> {
+    data: &'a [u8],
+    offset: usize,
+  }
+
+  impl<'a> Iterator for CompilationOptionsIter<'a> {
+    type Item = (&'a str, &'a str);
+
+    fn next(&mut self) -> Option<Self::Item> {
+      let key = read_str(self.data, &mut self.offset)?;
+      let value = read_str(self.data, &mut self.offset)?;
+
+      Some((key, value))
+    }
+  }
+
+  /// The metadata references of a compilation.
+  #[derive(Debug, Clone, Copy)]
+  pub struct MetadataReferences<'a>(&'a [u8]);
+
+  /// A metadata reference of a compilation.
+  #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+  pub struct MetadataReference<'a> {
+    /// The file name of the reference.
+    pub file_name: &'a str,
+    /// The comma separated extern aliases of the reference.
+    pub extern_aliases: &'a str,
+    /// Bit `0` is set if the reference is an assembly rather than a module, bit `1` if interop
+    /// types are embedded.
+    pub flags: u8,
+    /// The `TimeDateStamp` of the COFF header of the referenced file.
+    pub timestamp: u32,
+    /// The `SizeOfImage` of the optional header of the referenced file.
+    pub image_size: u32,
+    /// The module version id of the referenced file.
+    pub mvid: [u8; 16],
+  }
+
+  impl<'a> MetadataReference<'a> {
+    /// Returns `true` if the reference is an assembly, `false` if it is a module.
+    pub fn is_assembly(&self) -> bool {
+      self.flags & 0x01 != 0
+    }
+
+    /// Returns `true` if interop types are embedded from the reference.
+    pub fn embed_interop_types(&self) -> bool {
+      self.flags & 0x02 != 0
+    }
+
+    /// Returns an iterator over the extern aliases of the reference.
+    pub fn aliases(&self) -> impl Iterator<Item = &'a str> {
+      self
+        .extern_aliases
+        .split(',')
+        .filter(|alias| !alias.is_empty())
+    }
+  }
+
+  impl<'a> IntoIterator for MetadataReferences<'a> {
+    type Item = MetadataReference<'a>;
+    type IntoIter = MetadataReferencesIter<'a>;
+
+    fn into_iter(self) -> Self::IntoIter {
+      MetadataReferencesIter {
+        data: self.0,
+        offset: 0,
+      }
+    }
+  }
+
+  /// An iterator over [MetadataReference]s.
+  #[derive(Clone, Copy)]
+  pub struct MetadataReferencesIter<'a> {
+    data: &'a [u8],
+    offset: usize,
+  }
+
+  impl<'a> Iterator for MetadataReferencesIter<'a> {
+    type Item = MetadataReference<'a>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+      Some(MetadataReference {
+        file_name: read_str(self.data, &mut self.offset)?,
+        extern_aliases: read_str(self.data, &mut self.offset)?,
+        flags: self.data.read(&mut self.offset)?,
+        timestamp: self.data.read(&mut self.offset)?,
+        image_size: self.data.read(&mut self.offset)?,
+        mvid: self.data.read(&mut self.offset)?,
+      })
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::{CustomDebugInformation, CustomDebugInformationKind, EncLocalSlot};
+
+  #[test]
+  fn test_kind_guid() {
+    // {b5feec05-8cd0-4a83-96da-466284bb4bd8}
+    let guid = [
+      0x05, 0xec, 0xfe, 0xb5, 0xd0, 0x8c, 0x83, 0x4a, 0x96, 0xda, 0x46, 0x62, 0x84, 0xbb, 0x4b,
+      0xd8,
+    ];
+
+    assert_eq!(
+      Some(CustomDebugInformationKind::CompilationOptions),
+      CustomDebugInformationKind::from_guid(guid)
+    );
+  }
+
+  #[test]
+  fn test_decode_compilation_options() {
+    let value = b"language\0C#\0optimization\0release\0";
+    let kind = CustomDebugInformationKind::COMPILATION_OPTIONS;
+    let Some(CustomDebugInformation::CompilationOptions(options)) =
+      CustomDebugInformation::decode(kind, value)
+    else {
+      panic!("expected compilation options");
+    };
+
+    assert_eq!(Some("release"), options.get("optimization"));
+    assert_eq!(2, options.into_iter().count());
+  }
+
+  #[test]
+  fn test_decode_metadata_references() {
+    let mut value = b"System.Runtime.dll\0a,b\0\x01".to_vec();
+
+    value.extend_from_slice(&0x1234u32.to_le_bytes());
+    value.extend_from_slice(&0x8000u32.to_le_bytes());
+    value.extend_from_slice(&[0xaa; 16]);
+
+    let kind = CustomDebugInformationKind::COMPILATION_METADATA_REFERENCES;
+    let Some(CustomDebugInformation::CompilationMetadataReferences(references)) =
+      CustomDebugInformation::decode(kind, &value)
+    else {
+      panic!("expected metadata references");
+    };
+
+    let reference = references.into_iter().next().unwrap();
+
+    assert_eq!("System.Runtime.dll", reference.file_name);
+    assert_eq!(vec!["a", "b"], reference.aliases().collect::<Vec<_>>());
+    assert!(reference.is_assembly());
+    assert!(!reference.embed_interop_types());
+    assert_eq!(0x1234, reference.timestamp);
+    assert_eq!(0x8000, reference.image_size);
+    assert_eq!([0xaa; 16], reference.mvid);
+    assert_eq!(1, references.into_iter().count());
+  }
+
+  #[test]
+  fn test_decode_enc_local_slot_map() {
+    let value = [0xff, 0x05, 0x01, 0x07, 0x00, 0x83, 0x02, 0x01];
+    let kind = CustomDebugInformationKind::ENC_LOCAL_SLOT_MAP;
+    let Some(CustomDebugInformation::EncLocalSlotMap(map)) =
+      CustomDebugInformation::decode(kind, &value)
+    else {
+      panic!("expected local slot map");
+    };
+
+    let expected = [
+      EncLocalSlot::LongLived {
+        kind: 0,
+        syntax_offset: 2,
+        ordinal: 0,
+      },
+      EncLocalSlot::Temporary,
+      EncLocalSlot::LongLived {
+        kind: 2,
+        syntax_offset: -3,
+        ordinal: 1,
+      },
+    ];
+
+    assert_eq!(&expected[..], &map.into_iter().collect::<Vec<_>>());
+  }
+
+  #[test]
+  fn test_decode_malformed_enc_local_slot_map() {
+    let kind = CustomDebugInformationKind::ENC_LOCAL_SLOT_MAP;
+
+    for value in [[0x01, 0x02, 0x40, 0x02], [0x01, 0x02, 0x80, 0x02]] {
+      let Some(CustomDebugInformation::EncLocalSlotMap(map)) =
+        CustomDebugInformation::decode(kind, &value)
+      else {
+        panic!("expected local slot map");
+      };
+      let mut slots = map.into_iter();
+
+      assert!(matches!(
+        slots.next(),
+        Some(EncLocalSlot::LongLived { kind: 0, .. })
+      ));
+      assert_eq!(None, slots.next());
+      assert_eq!(None, slots.next());
+    }
+  }
+
+  #[cfg(feature = "deflate")]
+  #[test]
+  fn test_decompress_embedded_source() {
+    let source = b"class C { static void Main() { } }";
+    let mut value = (source.len() as i32).to_le_bytes().to_vec();
+
+    value.extend(miniz_oxide::deflate::compress_to_vec(source, 6));
+
+    let kind = CustomDebugInformationKind::EMBEDDED_SOURCE;
+    let Some(CustomDebugInformation::EmbeddedSource(embedded)) =
+      CustomDebugInformation::decode(kind, &value)
+    else {
+      panic!("expected embedded source");
+    };
+
+    assert!(embedded.is_compressed());
+    assert_eq!(&source[..], &*embedded.decompress().unwrap());
+  }
+}
@@ -1,3 +1,4 @@
+use super::table::{Column, ColumnValue};
 use crate::bytes;
 
 bytes::bitflags! {
@@ -19,6 +20,8 @@ bytes::bitflags! {
     /// The implementation of this assembly used at runtime is not expected to match the version
     /// seen at compile time.
     const RETARGETABLE =  0x0100;
+    /// The assembly contains Windows Runtime types, the only content type other than the default.
+    const WINDOWS_RUNTIME = 0x0200;
     /// Reserved (a conforming implementation of the CLI can ignore this setting on read; some
     /// implementations might use this bit to indicate that a CIL-to-native-code compiler should not
     /// should not generate optimized code)
@@ -434,3 +437,81 @@ bytes::bitflags! {
     const ELEMENT_TYPE_PINNED =  0x45; //  Denotes a local variable that points at a pinned object
   }
 }
+
+bytes::bitflags! {
+  /// The first byte of a method, field, property or local variable signature.
+  #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+  pub struct CallingConvention : u8 {
+    /// The low 4 bits contain one of the following values:
+    const KIND_MASK = 0x0f;
+    /// A managed method.
+    const DEFAULT = 0x00;
+    /// An unmanaged method using the C calling convention.
+    const C = 0x01;
+    /// An unmanaged method using the standard calling convention.
+    const STDCALL = 0x02;
+    /// An unmanaged method using the this calling convention.
+    const THISCALL = 0x03;
+    /// An unmanaged method using the fast calling convention.
+    const FASTCALL = 0x04;
+    /// A managed method with a variable number of arguments.
+    const VARARG = 0x05;
+    /// A field signature.
+    const FIELD = 0x06;
+    /// A local variable signature.
+    const LOCAL_SIG = 0x07;
+    /// A property signature.
+    const PROPERTY = 0x08;
+    /// An unmanaged method whose calling convention is given by modifiers.
+    const UNMANAGED = 0x09;
+    /// A generic method instantiation.
+    const GENERIC_INST = 0x0a;
+    /// An unmanaged method with a variable number of arguments.
+    const NATIVE_VARARG = 0x0b;
+    /// The method has generic parameters, whose count follows.
+    const GENERIC = 0x10;
+    /// The method or property has a `this` pointer.
+    const HAS_THIS = 0x20;
+    /// The `this` pointer is the first parameter of the signature.
+    const EXPLICIT_THIS = 0x40;
+  }
+}
+
+bytes::bitflags! {
+  #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+  pub struct LocalVariableAttributes : u16 {
+    /// Variables with this attribute shall be hidden in a debugger variables window.
+    const DEBUGGER_HIDDEN = 0x0001;
+  }
+}
+
+macro_rules! flags_column {
+  ($($ty:ty),+ $(,)?) => {
+    $(
+      impl Column for $ty {
+        fn value(&self) -> ColumnValue {
+          ColumnValue::Constant(self.bits() as u32)
+        }
+      }
+    )+
+  };
+}
+
+flags_column!(
+  AssemblyHashAlgorithm,
+  AssemblyFlags,
+  EventAttributes,
+  FieldAttributes,
+  FileAttributes,
+  GenericParamAttributes,
+  PInvokeAttributes,
+  ManifestResourceAttributes,
+  MethodImplAttributes,
+  MethodAttributes,
+  MethodSemanticsAttributes,
+  ParamAttributes,
+  PropertyAttributes,
+  TypeAttributes,
+  ElementType,
+  LocalVariableAttributes,
+);
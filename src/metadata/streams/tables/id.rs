@@ -1,12 +1,6 @@
 use super::rows::*;
 use core::fmt;
 use core::marker::PhantomData;
-#[cfg(feature = "read")]
-#[doc(inline)]
-pub use read::*;
-#[cfg(feature = "write")]
-#[doc(inline)]
-pub use write::*;
 
 /// A handle to a row in a table with the given row type.
 #[repr(transparent)]
@@ -16,16 +10,16 @@ pub struct RowId<R> {
 }
 
 impl<R> RowId<R> {
-  /// Creates a new [RowId] with the given index.
+  /// Creates a new [RowId] with the given 1-based index, `0` denotes a null reference.
   #[inline]
-  pub(crate) fn new(index: usize) -> Self {
+  pub fn new(index: usize) -> Self {
     Self {
       row: PhantomData,
       index,
     }
   }
 
-  /// Returns the index of the row.
+  /// Returns the 1-based index of the row, `0` denotes a null reference.
   #[inline]
   pub fn index(self) -> usize {
     self.index
@@ -97,6 +91,32 @@ macro_rules! coded_id {
           _ => None
         }
       }
+
+      /// Returns the coded id value of this coded id.
+      #[inline]
+      pub fn to_tag(self) -> usize {
+        match self {
+          $(
+            Self::$variant(id) => (id.index() << $bits) | $tag,
+          )*
+        }
+      }
+    }
+
+    impl $crate::metadata::streams::tables::table::Column for $name {
+      fn value(&self) -> $crate::metadata::streams::tables::table::ColumnValue {
+        use $crate::metadata::streams::tables::table::{ColumnValue, Row};
+
+        match *self {
+          $(
+            Self::$variant(id) => ColumnValue::Coded {
+              table: <$table as Row>::ID,
+              index: id.index(),
+              value: self.to_tag(),
+            },
+          )*
+        }
+      }
     }
 
     #[cfg(feature = "read")]
@@ -114,6 +134,22 @@ macro_rules! coded_id {
       }
     }
 
+    #[cfg(feature = "write")]
+    impl $crate::bytes::ToBytes<&$crate::metadata::headers::MetadataTablesHeader> for $name {
+      fn to_bytes(
+        &self,
+        buf: &mut std::vec::Vec<u8>,
+        header: &$crate::metadata::headers::MetadataTablesHeader,
+      ) {
+        use $crate::bytes::ByteSize;
+
+        match Self::byte_size(header) {
+          4 => (self.to_tag() as u32).to_bytes(buf, ()),
+          _ => (self.to_tag() as u16).to_bytes(buf, ()),
+        }
+      }
+    }
+
     #[cfg(feature = "read")]
     impl $crate::bytes::ByteSize<&$crate::metadata::headers::MetadataTablesHeader> for $name {
       fn byte_size(header: &$crate::metadata::headers::MetadataTablesHeader) -> usize {
@@ -251,6 +287,39 @@ coded_id! {
   }
 }
 
+coded_id! {
+  /// The parent of a portable PDB `CustomDebugInformation` row.
+  enum HasCustomDebugInformation : 5 {
+    MethodDef(MethodDefRow) = 0,
+    Field(FieldRow) = 1,
+    TypeRef(TypeRefRow) = 2,
+    TypeDef(TypeDefRow) = 3,
+    Param(ParamRow) = 4,
+    InterfaceImpl(InterfaceImplRow) = 5,
+    MemberRef(MemberRefRow) = 6,
+    Module(ModuleRow) = 7,
+    DeclSecurity(DeclSecurityRow) = 8,
+    Property(PropertyRow) = 9,
+    Event(EventRow) = 10,
+    StandAloneSig(StandAloneSigRow) = 11,
+    ModuleRef(ModuleRefRow) = 12,
+    TypeSpec(TypeSpecRow) = 13,
+    Assembly(AssemblyRow) = 14,
+    AssemblyRef(AssemblyRefRow) = 15,
+    File(FileRow) = 16,
+    ExportedType(ExportedTypeRow) = 17,
+    ManifestResource(ManifestResourceRow) = 18,
+    GenericParam(GenericParamRow) = 19,
+    GenericParamConstraint(GenericParamConstraintRow) = 20,
+    MethodSpec(MethodSpecRow) = 21,
+    Document(DocumentRow) = 22,
+    LocalScope(LocalScopeRow) = 23,
+    LocalVariable(LocalVariableRow) = 24,
+    LocalConstant(LocalConstantRow) = 25,
+    ImportScope(ImportScopeRow) = 26
+  }
+}
+
 #[cfg(feature = "read")]
 #[doc(hidden)]
 mod read {
@@ -282,4 +351,19 @@ mod read {
 
 #[cfg(feature = "write")]
 #[doc(hidden)]
-mod write {}
+mod write {
+  use super::RowId;
+  use crate::bytes::{ByteSize, ToBytes};
+  use crate::metadata::headers::MetadataTablesHeader;
+  use crate::metadata::streams::tables::table::RowRead;
+  use std::vec::Vec;
+
+  impl<R: RowRead> ToBytes<&MetadataTablesHeader> for RowId<R> {
+    fn to_bytes(&self, buf: &mut Vec<u8>, header: &MetadataTablesHeader) {
+      match Self::byte_size(header) {
+        4 => (self.index as u32).to_bytes(buf, ()),
+        _ => (self.index as u16).to_bytes(buf, ()),
+      }
+    }
+  }
+}
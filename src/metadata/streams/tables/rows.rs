@@ -170,6 +170,12 @@ table::row! {
   }
 }
 
+table::row! {
+  struct EventPtrRow : 0x13 {
+    event: RowId<EventRow>,
+  }
+}
+
 table::row! {
   struct ExportedTypeRow : 0x27 {
     flags: TypeAttributes,
@@ -194,6 +200,12 @@ table::row! {
   }
 }
 
+table::row! {
+  struct FieldPtrRow : 0x03 {
+    field: RowId<FieldRow>,
+  }
+}
+
 table::row! {
   struct FieldRvaRow : 0x1d {
     rva: u32,
@@ -270,6 +282,12 @@ table::row! {
   }
 }
 
+table::row! {
+  struct MethodPtrRow : 0x05 {
+    method: RowId<MethodDefRow>,
+  }
+}
+
 table::row! {
   struct MethodSemanticsRow : 0x18 {
     semantics: MethodSemanticsAttributes,
@@ -306,6 +324,12 @@ table::row! {
   }
 }
 
+table::row! {
+  struct ParamPtrRow : 0x07 {
+    param: RowId<ParamRow>,
+  }
+}
+
 table::row! {
   struct PropertyRow : 0x17 {
     flags: PropertyAttributes,
@@ -321,6 +345,12 @@ table::row! {
   }
 }
 
+table::row! {
+  struct PropertyPtrRow : 0x16 {
+    property: RowId<PropertyRow>,
+  }
+}
+
 table::row! {
   struct StandAloneSigRow : 0x11 {
     signature: BlobId,
@@ -332,3 +362,73 @@ table::row! {
     signature: BlobId,
   }
 }
+
+table::row! {
+  /// A source document referenced by a portable PDB.
+  struct DocumentRow : 0x30 {
+    name: BlobId,
+    hash_algorithm: GuidId,
+    hash: BlobId,
+    language: GuidId,
+  }
+}
+
+table::row! {
+  /// Debug information for a method, such as its sequence points.
+  struct MethodDebugInformationRow : 0x31 {
+    document: RowId<DocumentRow>,
+    sequence_points: BlobId,
+  }
+}
+
+table::row! {
+  /// A lexical scope within a method body in which local variables and constants are declared.
+  struct LocalScopeRow : 0x32 {
+    method: RowId<MethodDefRow>,
+    import_scope: RowId<ImportScopeRow>,
+    variable_list: RowId<LocalVariableRow>, // List
+    constant_list: RowId<LocalConstantRow>, // List
+    start_offset: u32,
+    length: u32,
+  }
+}
+
+table::row! {
+  struct LocalVariableRow : 0x33 {
+    attributes: LocalVariableAttributes,
+    index: u16,
+    name: StringId,
+  }
+}
+
+table::row! {
+  struct LocalConstantRow : 0x34 {
+    name: StringId,
+    signature: BlobId,
+  }
+}
+
+table::row! {
+  struct ImportScopeRow : 0x35 {
+    parent: RowId<ImportScopeRow>,
+    imports: BlobId,
+  }
+}
+
+table::row! {
+  /// Maps the `MoveNext` method of a compiler generated state machine to the method it was lowered
+  /// from.
+  struct StateMachineMethodRow : 0x36 {
+    move_next_method: RowId<MethodDefRow>,
+    kickoff_method: RowId<MethodDefRow>,
+  }
+}
+
+table::row! {
+  /// Custom debug information whose format is identified by the `kind` guid.
+  struct CustomDebugInformationRow : 0x37 {
+    parent: HasCustomDebugInformation,
+    kind: GuidId,
+    value: BlobId,
+  }
+}
@@ -1,14 +1,466 @@
-#[cfg(feature = "read")]
+//! Signatures stored in the `#Blob` heap.
+//!
+//! The signatures of methods, fields and properties, and the types of `TypeSpec` rows, are decoded
+//! into trees of [TypeSig]s referring to rows of the `TypeDef`, `TypeRef` and `TypeSpec` tables.
+
+#[cfg(all(feature = "read", feature = "std"))]
 #[doc(inline)]
 pub use read::*;
-#[cfg(feature = "write")]
-#[doc(inline)]
-pub use write::*;
 
-#[cfg(feature = "read")]
+#[cfg(all(feature = "read", feature = "std"))]
 #[doc(hidden)]
-mod read {}
+mod read {
+  use super::super::flags::{CallingConvention, ElementType};
+  use super::super::id::TypeDefOrRef;
+  use crate::bytes::{ByteSliceExt, CompressedLength, FromBytes};
+  use std::boxed::Box;
+  use std::vec::Vec;
 
-#[cfg(feature = "write")]
-#[doc(hidden)]
-mod write {}
+  /// The deepest nesting of types decoded, which bounds the recursion on malformed signatures.
+  const MAX_DEPTH: usize = 64;
+
+  /// A type in a signature.
+  #[derive(Debug, Clone, PartialEq, Eq)]
+  pub enum TypeSig {
+    /// `void`, only valid as a return type or pointed to.
+    Void,
+    /// `System.Boolean`.
+    Boolean,
+    /// `System.Char`.
+    Char,
+    /// `System.SByte`.
+    I1,
+    /// `System.Byte`.
+    U1,
+    /// `System.Int16`.
+    I2,
+    /// `System.UInt16`.
+    U2,
+    /// `System.Int32`.
+    I4,
+    /// `System.UInt32`.
+    U4,
+    /// `System.Int64`.
+    I8,
+    /// `System.UInt64`.
+    U8,
+    /// `System.Single`.
+    R4,
+    /// `System.Double`.
+    R8,
+    /// `System.String`.
+    String,
+    /// `System.IntPtr`.
+    I,
+    /// `System.UIntPtr`.
+    U,
+    /// `System.Object`.
+    Object,
+    /// `System.TypedReference`.
+    TypedByRef,
+    /// A reference type.
+    Class(TypeDefOrRef),
+    /// A value type.
+    ValueType(TypeDefOrRef),
+    /// A generic parameter of the enclosing type, by number.
+    Var(u32),
+    /// A generic parameter of the enclosing method, by number.
+    MVar(u32),
+    /// An unmanaged pointer.
+    Ptr(Box<TypeSig>),
+    /// A managed pointer, only valid for parameters, return types and locals.
+    ByRef(Box<TypeSig>),
+    /// A single-dimensional array with a lower bound of zero.
+    SzArray(Box<TypeSig>),
+    /// A general array.
+    Array(Box<TypeSig>, ArrayShape),
+    /// An instantiation of a generic type.
+    GenericInst {
+      /// Whether the generic type is a value type.
+      value_type: bool,
+      /// The generic type.
+      generic: TypeDefOrRef,
+      /// The type arguments.
+      args: Vec<TypeSig>,
+    },
+    /// A pointer to a method.
+    FnPtr(Box<MethodSig>),
+    /// A type with a custom modifier.
+    Modified(CustomModifier, Box<TypeSig>),
+  }
+
+  /// The shape of a general array.
+  #[derive(Debug, Clone, PartialEq, Eq)]
+  pub struct ArrayShape {
+    /// The number of dimensions.
+    pub rank: u32,
+    /// The sizes of the first dimensions, dimensions without a size are unbounded.
+    pub sizes: Vec<u32>,
+    /// The lower bounds of the first dimensions, dimensions without a lower bound start at zero.
+    pub lower_bounds: Vec<i32>,
+  }
+
+  /// A custom modifier, `modreq` or `modopt`, applied to a type.
+  #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+  pub struct CustomModifier {
+    /// Whether the modifier is required, `modreq`, rather than optional.
+    pub required: bool,
+    /// The type of the modifier.
+    pub modifier: TypeDefOrRef,
+  }
+
+  /// The signature of a method, a `MethodDefSig`, `MethodRefSig` or `StandAloneMethodSig`.
+  #[derive(Debug, Clone, PartialEq, Eq)]
+  pub struct MethodSig {
+    /// The calling convention and flags of the method.
+    pub calling_convention: CallingConvention,
+    /// The number of generic parameters of the method.
+    pub generic_param_count: u32,
+    /// The return type.
+    pub return_type: TypeSig,
+    /// The types of the parameters, not including the `this` pointer unless it is explicit.
+    pub params: Vec<TypeSig>,
+    /// The index of the first parameter following the sentinel, which starts the variable
+    /// arguments of a call site of a vararg method.
+    pub sentinel: Option<usize>,
+  }
+
+  impl MethodSig {
+    /// Returns whether the method has a `this` pointer.
+    pub fn has_this(&self) -> bool {
+      self
+        .calling_convention
+        .contains(CallingConvention::HAS_THIS)
+    }
+
+    /// Returns whether the `this` pointer is the first of the parameters.
+    pub fn explicit_this(&self) -> bool {
+      self
+        .calling_convention
+        .contains(CallingConvention::EXPLICIT_THIS)
+    }
+
+    /// Returns the kind of calling convention, such as [CallingConvention::VARARG].
+    pub fn kind(&self) -> CallingConvention {
+      self.calling_convention & CallingConvention::KIND_MASK
+    }
+  }
+
+  /// The signature of a field.
+  #[derive(Debug, Clone, PartialEq, Eq)]
+  pub struct FieldSig {
+    /// The type of the field, including its custom modifiers.
+    pub ty: TypeSig,
+  }
+
+  /// The signature of a property.
+  #[derive(Debug, Clone, PartialEq, Eq)]
+  pub struct PropertySig {
+    /// Whether the property is an instance property.
+    pub has_this: bool,
+    /// The type of the property, including its custom modifiers.
+    pub ty: TypeSig,
+    /// The types of the parameters of an indexed property.
+    pub params: Vec<TypeSig>,
+  }
+
+  /// The instantiation of a generic method, the signature of a `MethodSpec` row.
+  #[derive(Debug, Clone, PartialEq, Eq)]
+  pub struct MethodSpecSig {
+    /// The type arguments.
+    pub args: Vec<TypeSig>,
+  }
+
+  impl FromBytes<'_> for TypeSig {
+    /// Reads the type of a `TypeSpec` row, or a type within a signature.
+    fn from_bytes(buf: &[u8], offset: &mut usize, _: ()) -> Option<Self> {
+      read_type(buf, offset, 0)
+    }
+  }
+
+  impl FromBytes<'_> for MethodSig {
+    fn from_bytes(buf: &[u8], offset: &mut usize, _: ()) -> Option<Self> {
+      read_method(buf, offset, 0)
+    }
+  }
+
+  impl FromBytes<'_> for FieldSig {
+    fn from_bytes(buf: &[u8], offset: &mut usize, _: ()) -> Option<Self> {
+      if buf.read::<CallingConvention>(offset)? != CallingConvention::FIELD {
+        return None;
+      }
+
+      Some(Self {
+        ty: read_type(buf, offset, 0)?,
+      })
+    }
+  }
+
+  impl FromBytes<'_> for PropertySig {
+    fn from_bytes(buf: &[u8], offset: &mut usize, _: ()) -> Option<Self> {
+      let convention = buf.read::<CallingConvention>(offset)?;
+
+      if convention & CallingConvention::KIND_MASK != CallingConvention::PROPERTY {
+        return None;
+      }
+
+      let count = read_compressed(buf, offset)?;
+      let ty = read_type(buf, offset, 0)?;
+      let params = read_types(buf, offset, count, 0)?;
+
+      Some(Self {
+        has_this: convention.contains(CallingConvention::HAS_THIS),
+        ty,
+        params,
+      })
+    }
+  }
+
+  impl FromBytes<'_> for MethodSpecSig {
+    fn from_bytes(buf: &[u8], offset: &mut usize, _: ()) -> Option<Self> {
+      if buf.read::<CallingConvention>(offset)? != CallingConvention::GENERIC_INST {
+        return None;
+      }
+
+      let count = read_compressed(buf, offset)?;
+
+      Some(Self {
+        args: read_types(buf, offset, count, 0)?,
+      })
+    }
+  }
+
+  fn read_method(buf: &[u8], offset: &mut usize, depth: usize) -> Option<MethodSig> {
+    let calling_convention = buf.read::<CallingConvention>(offset)?;
+    let generic_param_count = match calling_convention.contains(CallingConvention::GENERIC) {
+      true => read_compressed(buf, offset)?,
+      false => 0,
+    };
+    let count = read_compressed(buf, offset)? as usize;
+    let return_type = read_type(buf, offset, depth)?;
+    let mut params = Vec::with_capacity(count.min(buf.len()));
+    let mut sentinel = None;
+
+    for i in 0..count {
+      if buf.peek::<u8>(offset)? == ElementType::ELEMENT_TYPE_SENTINEL.bits() {
+        *offset += 1;
+        sentinel = Some(i);
+      }
+
+      params.push(read_type(buf, offset, depth)?);
+    }
+
+    Some(MethodSig {
+      calling_convention,
+      generic_param_count,
+      return_type,
+      params,
+      sentinel,
+    })
+  }
+
+  fn read_types(buf: &[u8], offset: &mut usize, count: u32, depth: usize) -> Option<Vec<TypeSig>> {
+    let mut types = Vec::with_capacity((count as usize).min(buf.len()));
+
+    for _ in 0..count {
+      types.push(read_type(buf, offset, depth)?);
+    }
+
+    Some(types)
+  }
+
+  fn read_type(buf: &[u8], offset: &mut usize, depth: usize) -> Option<TypeSig> {
+    if depth == MAX_DEPTH {
+      return None;
+    }
+
+    let depth = depth + 1;
+    let inner = |offset: &mut usize| read_type(buf, offset, depth).map(Box::new);
+
+    Some(match ElementType::from_bits_retain(buf.read(offset)?) {
+      ElementType::ELEMENT_TYPE_VOID => TypeSig::Void,
+      ElementType::ELEMENT_TYPE_BOOLEAN => TypeSig::Boolean,
+      ElementType::ELEMENT_TYPE_CHAR => TypeSig::Char,
+      ElementType::ELEMENT_TYPE_I1 => TypeSig::I1,
+      ElementType::ELEMENT_TYPE_U1 => TypeSig::U1,
+      ElementType::ELEMENT_TYPE_I2 => TypeSig::I2,
+      ElementType::ELEMENT_TYPE_U2 => TypeSig::U2,
+      ElementType::ELEMENT_TYPE_I4 => TypeSig::I4,
+      ElementType::ELEMENT_TYPE_U4 => TypeSig::U4,
+      ElementType::ELEMENT_TYPE_I8 => TypeSig::I8,
+      ElementType::ELEMENT_TYPE_U8 => TypeSig::U8,
+      ElementType::ELEMENT_TYPE_R4 => TypeSig::R4,
+      ElementType::ELEMENT_TYPE_R8 => TypeSig::R8,
+      ElementType::ELEMENT_TYPE_STRING => TypeSig::String,
+      ElementType::ELEMENT_TYPE_I => TypeSig::I,
+      ElementType::ELEMENT_TYPE_U => TypeSig::U,
+      ElementType::ELEMENT_TYPE_OBJECT => TypeSig::Object,
+      ElementType::ELEMENT_TYPE_TYPEDBYREF => TypeSig::TypedByRef,
+      ElementType::ELEMENT_TYPE_CLASS => TypeSig::Class(read_type_def_or_ref(buf, offset)?),
+      ElementType::ELEMENT_TYPE_VALUETYPE => TypeSig::ValueType(read_type_def_or_ref(buf, offset)?),
+      ElementType::ELEMENT_TYPE_VAR => TypeSig::Var(read_compressed(buf, offset)?),
+      ElementType::ELEMENT_TYPE_MVAR => TypeSig::MVar(read_compressed(buf, offset)?),
+      ElementType::ELEMENT_TYPE_PTR => TypeSig::Ptr(inner(offset)?),
+      ElementType::ELEMENT_TYPE_BYREF => TypeSig::ByRef(inner(offset)?),
+      ElementType::ELEMENT_TYPE_SZARRAY => TypeSig::SzArray(inner(offset)?),
+      ElementType::ELEMENT_TYPE_ARRAY => {
+        let element = inner(offset)?;
+        let rank = read_compressed(buf, offset)?;
+        let count = read_compressed(buf, offset)?;
+        let sizes = (0..count)
+          .map(|_| read_compressed(buf, offset))
+          .collect::<Option<_>>()?;
+        let count = read_compressed(buf, offset)?;
+        let lower_bounds = (0..count)
+          .map(|_| read_compressed_signed(buf, offset))
+          .collect::<Option<_>>()?;
+
+        TypeSig::Array(
+          element,
+          ArrayShape {
+            rank,
+            sizes,
+            lower_bounds,
+          },
+        )
+      }
+      ElementType::ELEMENT_TYPE_GENERICINST => {
+        let value_type = match ElementType::from_bits_retain(buf.read(offset)?) {
+          ElementType::ELEMENT_TYPE_CLASS => false,
+          ElementType::ELEMENT_TYPE_VALUETYPE => true,
+          _ => return None,
+        };
+        let generic = read_type_def_or_ref(buf, offset)?;
+        let count = read_compressed(buf, offset)?;
+
+        TypeSig::GenericInst {
+          value_type,
+          generic,
+          args: read_types(buf, offset, count, depth)?,
+        }
+      }
+      ElementType::ELEMENT_TYPE_FNPTR => TypeSig::FnPtr(Box::new(read_method(buf, offset, depth)?)),
+      element @ (ElementType::ELEMENT_TYPE_CMOD_REQD | ElementType::ELEMENT_TYPE_CMOD_OPT) => {
+        let modifier = CustomModifier {
+          required: element == ElementType::ELEMENT_TYPE_CMOD_REQD,
+          modifier: read_type_def_or_ref(buf, offset)?,
+        };
+
+        TypeSig::Modified(modifier, inner(offset)?)
+      }
+      _ => return None,
+    })
+  }
+
+  /// Reads a `TypeDefOrRefOrSpecEncoded` value.
+  fn read_type_def_or_ref(buf: &[u8], offset: &mut usize) -> Option<TypeDefOrRef> {
+    TypeDefOrRef::from_tag(buf.read_with(offset, CompressedLength)?)
+  }
+
+  /// Reads a compressed unsigned integer.
+  fn read_compressed(buf: &[u8], offset: &mut usize) -> Option<u32> {
+    buf
+      .read_with::<usize, _>(offset, CompressedLength)
+      .map(|val| val as u32)
+  }
+
+  /// Reads a compressed signed integer, whose sign bit is rotated to the lowest bit.
+  fn read_compressed_signed(buf: &[u8], offset: &mut usize) -> Option<i32> {
+    let start = *offset;
+    let val = read_compressed(buf, offset)?;
+    let bits = match *offset - start {
+      1 => 7,
+      2 => 14,
+      _ => 29,
+    };
+
+    Some(match val & 1 {
+      0 => (val >> 1) as i32,
+      _ => (val >> 1) as i32 - (1 << (bits - 1)),
+    })
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  #[test]
+  #[cfg(feature = "std")]
+  fn test_method_sig() {
+    use super::*;
+    use crate::bytes::ByteSliceExt;
+    use crate::metadata::streams::tables::flags::CallingConvention;
+    use crate::metadata::streams::tables::id::{RowId, TypeDefOrRef};
+
+    // instance void <T>(int32&, class List`1<!!0>, string[0...,-2...])
+    let data: &[u8] = &[
+      0x30, 0x01, 0x03, 0x01, 0x10, 0x08, 0x15, 0x12, 0x05, 0x01, 0x1e, 0x00, 0x14, 0x0e, 0x02,
+      0x00, 0x02, 0x00, 0x7d,
+    ];
+    let sig = data.read::<MethodSig>(&mut 0).unwrap();
+
+    assert!(sig.has_this());
+    assert_eq!(CallingConvention::DEFAULT, sig.kind());
+    assert_eq!(1, sig.generic_param_count);
+    assert_eq!(TypeSig::Void, sig.return_type);
+    assert_eq!(
+      [
+        TypeSig::ByRef(Box::new(TypeSig::I4)),
+        TypeSig::GenericInst {
+          value_type: false,
+          generic: TypeDefOrRef::TypeRef(RowId::new(1)),
+          args: vec![TypeSig::MVar(0)],
+        },
+        TypeSig::Array(
+          Box::new(TypeSig::String),
+          ArrayShape {
+            rank: 2,
+            sizes: vec![],
+            lower_bounds: vec![0, -2],
+          },
+        ),
+      ],
+      sig.params[..]
+    );
+
+    // A truncated signature, and one nested too deeply.
+    assert_eq!(None, (&data[..6]).read::<MethodSig>(&mut 0));
+    assert_eq!(None, [0x0f; 100].as_slice().read::<TypeSig>(&mut 0));
+  }
+
+  #[test]
+  #[cfg(feature = "std")]
+  fn test_field_and_property_sig() {
+    use super::*;
+    use crate::bytes::ByteSliceExt;
+    use crate::metadata::streams::tables::id::{RowId, TypeDefOrRef};
+
+    // modreq(IsVolatile) int32
+    let data: &[u8] = &[0x06, 0x1f, 0x05, 0x08];
+    let field = data.read::<FieldSig>(&mut 0).unwrap();
+    let modifier = CustomModifier {
+      required: true,
+      modifier: TypeDefOrRef::TypeRef(RowId::new(1)),
+    };
+
+    assert_eq!(TypeSig::Modified(modifier, Box::new(TypeSig::I4)), field.ty);
+    assert_eq!(None, [0x07, 0x08].as_slice().read::<FieldSig>(&mut 0));
+
+    // instance string Item(int32)
+    let data: &[u8] = &[0x28, 0x01, 0x0e, 0x08];
+    let property = data.read::<PropertySig>(&mut 0).unwrap();
+
+    assert!(property.has_this);
+    assert_eq!(TypeSig::String, property.ty);
+    assert_eq!([TypeSig::I4], property.params[..]);
+
+    // <int32, !0>
+    let data: &[u8] = &[0x0a, 0x02, 0x08, 0x13, 0x00];
+    let spec = data.read::<MethodSpecSig>(&mut 0).unwrap();
+
+    assert_eq!([TypeSig::I4, TypeSig::Var(0)], spec.args[..]);
+    assert_eq!(
+      None,
+      [0x00, 0x01, 0x08].as_slice().read::<MethodSpecSig>(&mut 0)
+    );
+  }
+}
@@ -5,22 +5,114 @@ pub use read::*;
 #[doc(inline)]
 pub use write::*;
 
-pub trait Row: Sized {}
+use crate::metadata::streams::blobs::BlobId;
+use crate::metadata::streams::guids::GuidId;
+use crate::metadata::streams::strings::StringId;
+use crate::metadata::streams::tables::id::RowId;
+
+/// A row of a metadata table.
+pub trait Row: Sized {
+  /// The numerical sequence of the table in the metadata stream.
+  const ID: usize;
+  /// The name of the row type, used when reporting errors.
+  const NAME: &'static str;
+
+  /// Calls the given function with the name and value of each column, in declaration order.
+  fn for_each_column(&self, f: &mut dyn FnMut(&'static str, ColumnValue));
+}
+
+/// The value of a column of a metadata table row.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum ColumnValue {
+  /// A constant, bit mask or offset.
+  Constant(u32),
+  /// An index into the `#Strings` heap.
+  String(StringId),
+  /// An index into the `#Blob` heap.
+  Blob(BlobId),
+  /// An index into the `#GUID` heap.
+  Guid(GuidId),
+  /// An index into the table with the given id, `0` is null.
+  Row {
+    /// The id of the table.
+    table: usize,
+    /// The 1-based index of the row.
+    index: usize,
+  },
+  /// A coded index into the table with the given id, `0` is null.
+  Coded {
+    /// The id of the table.
+    table: usize,
+    /// The 1-based index of the row.
+    index: usize,
+    /// The encoded value of the coded index, used as the key of sorted tables.
+    value: usize,
+  },
+}
+
+/// A type that can be stored in a column of a metadata table.
+pub trait Column {
+  /// Returns the [ColumnValue] of this column.
+  fn value(&self) -> ColumnValue;
+}
+
+macro_rules! constant_column {
+  ($($ty:ty),+) => {
+    $(
+      impl Column for $ty {
+        fn value(&self) -> ColumnValue {
+          ColumnValue::Constant(*self as u32)
+        }
+      }
+    )+
+  };
+}
+
+constant_column!(u8, u16, u32);
+
+impl Column for StringId {
+  fn value(&self) -> ColumnValue {
+    ColumnValue::String(*self)
+  }
+}
+
+impl Column for BlobId {
+  fn value(&self) -> ColumnValue {
+    ColumnValue::Blob(*self)
+  }
+}
+
+impl Column for GuidId {
+  fn value(&self) -> ColumnValue {
+    ColumnValue::Guid(*self)
+  }
+}
+
+impl<R: Row> Column for RowId<R> {
+  fn value(&self) -> ColumnValue {
+    ColumnValue::Row {
+      table: R::ID,
+      index: self.index(),
+    }
+  }
+}
 
 #[cfg(feature = "read")]
 #[doc(hidden)]
 mod read {
   use super::Row;
-  use crate::metadata::errors::MetadataStreamReadError;
+  use crate::metadata::errors::{MetadataStreamReadError, ReadError, ReadErrorKind, ReadLocation};
   use crate::metadata::headers::{HeapSizes, MetadataTablesHeader};
   use crate::metadata::streams::tables::id::RowId;
   use core::marker::PhantomData;
+  use core::ops::Range;
 
   /// A slice of bytes containing the data for a metadata table.
-  #[repr(transparent)]
   pub struct TableBytes<'a, R> {
     row: PhantomData<R>,
     bytes: &'a [u8],
+    offset: usize,
   }
 
   impl<'a, R: RowRead> TableBytes<'a, R> {
@@ -30,18 +122,24 @@ mod read {
       offset: &mut usize,
       header: &MetadataTablesHeader,
     ) -> Result<Self, MetadataStreamReadError<'a>> {
+      let beg = *offset;
       let len = R::table_len(header);
       let size = R::row_size(header);
       let size = len.saturating_mul(size);
-      let bytes = bytes
-        .get(*offset..*offset + size)
-        .ok_or(MetadataStreamReadError::NotEnough)?;
+      let bytes = bytes.get(beg..beg.saturating_add(size)).ok_or_else(|| {
+        ReadError::new(
+          ReadLocation::Table { table: R::NAME },
+          beg,
+          ReadErrorKind::Truncated,
+        )
+      })?;
 
       *offset += size;
 
       Ok(Self {
         row: PhantomData,
         bytes,
+        offset: beg,
       })
     }
 
@@ -50,6 +148,7 @@ mod read {
       TableReader {
         row: PhantomData,
         bytes: self.bytes,
+        offset: self.offset,
         header,
       }
     }
@@ -60,6 +159,7 @@ mod read {
       Self {
         row: PhantomData,
         bytes: &[],
+        offset: 0,
       }
     }
   }
@@ -74,17 +174,93 @@ mod read {
 
   /// Reads rows from a metadata table.
   pub struct TableReader<'a, 'h, R> {
-    row: PhantomData<R>,
-    bytes: &'a [u8],
-    header: &'h MetadataTablesHeader,
+    pub(super) row: PhantomData<R>,
+    pub(super) bytes: &'a [u8],
+    pub(super) offset: usize,
+    pub(super) header: &'h MetadataTablesHeader,
   }
 
   impl<'a, 'h, R: RowRead> TableReader<'a, 'h, R> {
     /// Gets the row from the given [RowId].
-    pub fn get(&self, id: RowId<R>) -> Option<R> {
-      let mut offset = id.index() * R::row_size(self.header);
+    ///
+    /// Row ids are 1-based, a null or out of bounds id returns [ReadErrorKind::OutOfRange].
+    pub fn get(&self, id: RowId<R>) -> Result<R, ReadError> {
+      let len = self.len();
+      let index = id.index();
+
+      if index == 0 || index > len {
+        return Err(ReadError::new(
+          ReadLocation::Table { table: R::NAME },
+          self.offset,
+          ReadErrorKind::OutOfRange { index, len },
+        ));
+      }
+
+      let mut offset = (index - 1) * R::row_size(self.header);
 
       R::from_bytes(self.bytes, &mut offset, id, self.header)
+        .map_err(|err| err.offset_by(self.offset))
+    }
+
+    /// Returns the number of rows in the table.
+    pub fn len(&self) -> usize {
+      // Tables of a portable PDB referring to the type-system tables of another assembly have a row
+      // count but no data, so the length is taken from the data rather than the header.
+      self
+        .bytes
+        .len()
+        .checked_div(R::row_size(self.header))
+        .unwrap_or_default()
+    }
+
+    /// Returns `true` if the table has no rows.
+    pub fn is_empty(&self) -> bool {
+      self.len() == 0
+    }
+
+    /// Returns the offset of the table, relative to the start of the `#~` stream.
+    pub fn offset(&self) -> usize {
+      self.offset
+    }
+
+    /// Returns `true` if the table is flagged as sorted in the [MetadataTablesHeader].
+    pub fn is_sorted(&self) -> bool {
+      R::ID < 64 && self.header.sorted & (1 << R::ID) != 0
+    }
+
+    /// Returns the 1-based indices of the rows whose key is equal to the given key, using a binary
+    /// search.
+    ///
+    /// The rows must be sorted by the key, such as a table flagged as sorted by its primary key
+    /// column.
+    pub fn equal_range<K: Ord>(
+      &self,
+      key: &K,
+      mut key_of: impl FnMut(&R) -> K,
+    ) -> Result<Range<usize>, ReadError> {
+      let start = self.partition_point(|row| key_of(row) < *key)?;
+      let end = self.partition_point(|row| key_of(row) <= *key)?;
+
+      Ok(start..end.max(start))
+    }
+
+    /// Returns the 1-based index of the first row for which the predicate is `false`, given that it
+    /// is `true` for all the rows before it, using a binary search.
+    ///
+    /// Returns one past the last row if the predicate is `true` for every row.
+    pub fn partition_point(&self, mut pred: impl FnMut(&R) -> bool) -> Result<usize, ReadError> {
+      let (mut lo, mut hi) = (1, self.len() + 1);
+
+      while lo < hi {
+        let mid = lo + (hi - lo) / 2;
+
+        match pred(&self.get(RowId::new(mid))?) {
+          true => lo = mid + 1,
+          false => hi = mid,
+        }
+      }
+
+      Ok(lo)
     }
   }
 
@@ -102,28 +278,25 @@ mod read {
 
     fn into_iter(self) -> Self::IntoIter {
       TableReaderIter {
-        row: self.row,
-        id: RowId::new(0),
-        bytes: self.bytes,
-        header: self.header,
+        id: RowId::new(1),
+        reader: self,
       }
     }
   }
 
   /// Iterates over rows in a metadata table.
+  ///
+  /// Iteration stops at the first row that cannot be read, use [TableReader::get] to find out why.
   pub struct TableReaderIter<'a, 'h, R> {
-    row: PhantomData<R>,
     id: RowId<R>,
-    bytes: &'a [u8],
-    header: &'h MetadataTablesHeader,
+    reader: TableReader<'a, 'h, R>,
   }
 
   impl<'a, 'h, R: RowRead> Iterator for TableReaderIter<'a, 'h, R> {
     type Item = R;
 
     fn next(&mut self) -> Option<Self::Item> {
-      let mut offset = self.id.index() * R::row_size(self.header);
-      let row = R::from_bytes(self.bytes, &mut offset, self.id, self.header)?;
+      let row = self.reader.get(self.id).ok()?;
 
       self.id = self.id.next();
 
@@ -137,12 +310,14 @@ mod read {
     /// Returns the number of rows in the table using the given [MetadataTablesHeader].
     fn table_len(header: &MetadataTablesHeader) -> usize;
     /// Reads the row with the given [RowId] from the given buffer and [MetadataTablesHeader].
+    ///
+    /// Offsets of returned errors are relative to the start of the given buffer.
     fn from_bytes(
       buf: &[u8],
       offset: &mut usize,
       id: RowId<Self>,
       header: &MetadataTablesHeader,
-    ) -> Option<Self>;
+    ) -> Result<Self, ReadError>;
   }
 
   impl From<&MetadataTablesHeader> for HeapSizes {
@@ -158,7 +333,209 @@ mod read {
 
 #[cfg(feature = "write")]
 #[doc(hidden)]
-mod write {}
+mod write {
+  use super::{RowRead, TableReader};
+  use crate::bytes::ToBytes;
+  use crate::metadata::errors::{PatchError, ReadError, ReadLocation};
+  use crate::metadata::headers::MetadataTablesHeader;
+  use crate::metadata::streams::tables::id::RowId;
+  use core::marker::PhantomData;
+  use std::vec::Vec;
+
+  pub trait RowWrite: RowRead + Copy + for<'h> ToBytes<&'h MetadataTablesHeader> {
+    /// Returns this row with the given [RowId].
+    fn with_id(self, id: RowId<Self>) -> Self;
+  }
+
+  /// The rows of a metadata table being built.
+  #[derive(Debug, Clone)]
+  pub struct TableBuilder<R> {
+    rows: Vec<R>,
+  }
+
+  impl<R: RowWrite> TableBuilder<R> {
+    /// Creates a [TableBuilder] containing the rows read by the given [TableReader].
+    pub fn from_reader(reader: TableReader<'_, '_, R>) -> Result<Self, ReadError> {
+      let rows = (1..=reader.len())
+        .map(|index| reader.get(RowId::new(index)))
+        .collect::<Result<_, _>>()?;
+
+      Ok(Self { rows })
+    }
+
+    /// Replaces the row with the given [RowId] and returns the previous row.
+    ///
+    /// Returns [None] and leaves the table unchanged if there is no such row.
+    pub fn set(&mut self, id: RowId<R>, row: R) -> Option<R> {
+      let prev = self.rows.get_mut(id.index().checked_sub(1)?)?;
+
+      Some(core::mem::replace(prev, row.with_id(id)))
+    }
+
+    /// Adds a row to the end of the table and returns its [RowId].
+    pub fn add(&mut self, row: R) -> RowId<R> {
+      let id = RowId::new(self.rows.len() + 1);
+
+      self.rows.push(row.with_id(id));
+
+      id
+    }
+
+    /// Returns the rows of the table.
+    pub fn rows(&self) -> &[R] {
+      &self.rows
+    }
+
+    /// Returns the number of rows in the table.
+    pub fn len(&self) -> usize {
+      self.rows.len()
+    }
+
+    /// Returns `true` if the table has no rows.
+    pub fn is_empty(&self) -> bool {
+      self.rows.is_empty()
+    }
+
+    /// Returns `true` if the rows are in ascending order of the given key.
+    pub fn is_sorted_by_key<K: Ord>(&self, mut key: impl FnMut(&R) -> K) -> bool {
+      self
+        .rows
+        .windows(2)
+        .all(|rows| key(&rows[0]) <= key(&rows[1]))
+    }
+
+    /// Sorts the rows by the given key, keeping the order of rows with equal keys.
+    ///
+    /// Row ids are reassigned to match the new order.
+    pub fn sort_by_key<K: Ord>(&mut self, key: impl FnMut(&R) -> K) {
+      self.rows.sort_by_key(key);
+
+      for (i, row) in self.rows.iter_mut().enumerate() {
+        *row = row.with_id(RowId::new(i + 1));
+      }
+    }
+
+    /// Writes the rows of the table using the column widths of the given [MetadataTablesHeader].
+    pub fn write(&self, buf: &mut Vec<u8>, header: &MetadataTablesHeader) {
+      for row in &self.rows {
+        row.to_bytes(buf, header);
+      }
+    }
+  }
+
+  impl<R> Default for TableBuilder<R> {
+    fn default() -> Self {
+      Self { rows: Vec::new() }
+    }
+  }
+
+  /// Overwrites rows of a metadata table in place, keeping the current column widths.
+  ///
+  /// Useful for small fixes to an existing file, such as changing flags or pointing a column at
+  /// another existing heap entry, without rebuilding the metadata.
+  pub struct TableBytesMut<'a, R> {
+    row: PhantomData<R>,
+    bytes: &'a mut [u8],
+    offset: usize,
+    header: MetadataTablesHeader,
+  }
+
+  impl<'a, R: RowWrite> TableBytesMut<'a, R> {
+    /// Creates a [TableBytesMut] over the table read by the given [TableReader].
+    ///
+    /// The given buffer must start at the same `#~` stream the [TableReader] was read from, usually
+    /// a copy of the stream or of the file sliced at [TablesStream::offset_in].
+    ///
+    /// Returns [None] if the buffer is too short to contain the table.
+    ///
+    /// [TablesStream::offset_in]: crate::metadata::streams::tables::TablesStream::offset_in
+    pub fn new(stream: &'a mut [u8], table: TableReader<'_, '_, R>) -> Option<Self> {
+      let end = table.offset.checked_add(table.bytes.len())?;
+
+      Some(Self {
+        row: PhantomData,
+        bytes: stream.get_mut(table.offset..end)?,
+        offset: table.offset,
+        header: *table.header,
+      })
+    }
+
+    fn reader(&self) -> TableReader<'_, '_, R> {
+      TableReader {
+        row: PhantomData,
+        bytes: self.bytes,
+        offset: self.offset,
+        header: &self.header,
+      }
+    }
+
+    /// Gets the row from the given [RowId].
+    pub fn get(&self, id: RowId<R>) -> Result<R, ReadError> {
+      self.reader().get(id)
+    }
+
+    /// Returns the number of rows in the table.
+    pub fn len(&self) -> usize {
+      self.reader().len()
+    }
+
+    /// Returns `true` if the table has no rows.
+    pub fn is_empty(&self) -> bool {
+      self.len() == 0
+    }
+
+    /// Overwrites the row with the given [RowId] and returns the previous row.
+    ///
+    /// Only the bytes of columns whose value changed are written, so unchanged columns keep their
+    /// exact bytes. Returns [PatchError::TooWide] and leaves the row unchanged if a value does not
+    /// fit in its column, such as a heap index past the current index width.
+    pub fn set(&mut self, id: RowId<R>, row: R) -> Result<R, PatchError> {
+      let prev = self.get(id)?;
+      let row = row.with_id(id);
+      let size = R::row_size(&self.header);
+      let start = (id.index() - 1) * size;
+      let saved = self.bytes[start..start + size].to_vec();
+
+      let mut old = Vec::with_capacity(size);
+      let mut new = Vec::with_capacity(size);
+      prev.to_bytes(&mut old, &self.header);
+      row.to_bytes(&mut new, &self.header);
+
+      for (i, (old, new)) in old.iter().zip(&new).enumerate() {
+        if old != new {
+          self.bytes[start + i] = *new;
+        }
+      }
+
+      let mut expected = Vec::new();
+      row.for_each_column(&mut |_, value| expected.push(value));
+
+      let mut column = None;
+      let mut i = 0;
+      self.get(id)?.for_each_column(&mut |name, value| {
+        if column.is_none() && expected[i] != value {
+          column = Some(name);
+        }
+        i += 1;
+      });
+
+      if let Some(column) = column {
+        self.bytes[start..start + size].copy_from_slice(&saved);
+
+        return Err(PatchError::TooWide {
+          location: ReadLocation::Column {
+            table: R::NAME,
+            row: id.index(),
+            column,
+          },
+          offset: self.offset + start,
+        });
+      }
+
+      Ok(prev)
+    }
+  }
+}
 
 /// Defines a metadata table row.
 macro_rules! row {
@@ -182,6 +559,15 @@ macro_rules! row {
       /// The numerical sequence of the table in the metadata stream.
       pub const ID: usize = $id;
 
+      /// Creates a new row, the id of the row is assigned when it is added to a table.
+      #[allow(clippy::too_many_arguments)]
+      pub fn new($($field: $field_ty),+) -> Self {
+        Self {
+          id: RowId::new(0),
+          $($field,)+
+        }
+      }
+
       pub fn id(self) -> RowId<Self> {
         self.id
       }
@@ -194,7 +580,21 @@ macro_rules! row {
       )+
     }
 
-    impl Row for $name {}
+    impl Row for $name {
+      const ID: usize = $id;
+      const NAME: &'static str = stringify!($name);
+
+      fn for_each_column(
+        &self,
+        f: &mut dyn FnMut(&'static str, $crate::metadata::streams::tables::table::ColumnValue),
+      ) {
+        use $crate::metadata::streams::tables::table::Column;
+
+        $(
+          f(stringify!($field), self.$field.value());
+        )+
+      }
+    }
 
     #[cfg(feature = "read")]
     impl $crate::metadata::streams::tables::table::RowRead for $name {
@@ -220,17 +620,58 @@ macro_rules! row {
         offset: &mut usize,
         id: RowId<Self>,
         header: &$crate::metadata::headers::MetadataTablesHeader,
-      ) -> Option<Self> {
-        use $crate::bytes::FromBytes;
+      ) -> Result<Self, $crate::metadata::errors::ReadError> {
+        use $crate::bytes::{ByteSize, FromBytes};
+        use $crate::metadata::errors::{ReadError, ReadErrorKind, ReadLocation};
 
-        Some(Self {
+        Ok(Self {
           id,
           $(
-            $field: <$field_ty>::from_bytes(buf, offset, header.into())?,
+            $field: {
+              let beg = *offset;
+
+              <$field_ty>::from_bytes(buf, offset, header.into()).ok_or_else(|| {
+                let end = beg.saturating_add(<$field_ty>::byte_size(header.into()));
+                let kind = match end > buf.len() {
+                  true => ReadErrorKind::Truncated,
+                  false => ReadErrorKind::Invalid,
+                };
+
+                ReadError::new(
+                  ReadLocation::Column {
+                    table: stringify!($name),
+                    row: id.index(),
+                    column: stringify!($field),
+                  },
+                  beg,
+                  kind,
+                )
+              })?
+            },
           )+
         })
       }
     }
+
+    #[cfg(feature = "write")]
+    impl $crate::bytes::ToBytes<&$crate::metadata::headers::MetadataTablesHeader> for $name {
+      fn to_bytes(
+        &self,
+        buf: &mut std::vec::Vec<u8>,
+        header: &$crate::metadata::headers::MetadataTablesHeader,
+      ) {
+        $(
+          self.$field.to_bytes(buf, header.into());
+        )+
+      }
+    }
+
+    #[cfg(feature = "write")]
+    impl $crate::metadata::streams::tables::table::RowWrite for $name {
+      fn with_id(self, id: RowId<Self>) -> Self {
+        Self { id, ..self }
+      }
+    }
   };
 }
 
@@ -1,4 +1,4 @@
-//! The `#GUID` metadata stream.
+//! The `#US` metadata stream.
 
 #[cfg(feature = "read")]
 #[doc(inline)]
@@ -7,7 +7,7 @@ pub use read::*;
 #[doc(inline)]
 pub use write::*;
 
-/// A handle to a guid in the `#GUID` metadata stream.
+/// A handle to a string in the `#US` metadata stream.
 #[repr(transparent)]
 #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
 pub struct UserStringId(usize);
@@ -17,6 +17,7 @@ pub struct UserStringId(usize);
 mod read {
   use super::UserStringId;
   use crate::bytes::{ByteSliceExt, CompressedLength};
+  use crate::metadata::errors::{ReadError, ReadErrorKind, ReadLocation};
   use core::fmt;
 
   /// A `#US` heap.
@@ -30,14 +31,23 @@ mod read {
   impl<'a> UserStringsHeap<'a> {
     /// Returns the UTF-16 encoded string from the given [UserStringId].
     ///
-    /// Will return `None` if the given id is out of bounds.
-    pub fn get(&self, id: UserStringId) -> Option<&'a [u8]> {
+    /// Will return an error if the given id is out of bounds or the string extends past the end of
+    /// the heap.
+    pub fn get(&self, id: UserStringId) -> Result<&'a [u8], ReadError> {
+      let error = |kind| ReadError::new(ReadLocation::Stream("#US"), id.0, kind);
       let mut offset = id.0;
 
-      let len = self.0.read_with(&mut offset, CompressedLength)?;
-      let data = self.0.read_with(&mut offset, len)?;
+      if offset >= self.0.len() {
+        return Err(error(ReadErrorKind::OutOfRange {
+          index: offset,
+          len: self.0.len(),
+        }));
+      }
 
-      Some(data)
+      let len = self.0.read_with(&mut offset, CompressedLength);
+      let data = len.and_then(|len| self.0.read_with(&mut offset, len));
+
+      data.ok_or_else(|| error(ReadErrorKind::Truncated))
     }
   }
 
@@ -51,5 +61,164 @@ mod read {
 #[cfg(feature = "write")]
 #[doc(hidden)]
 mod write {
-  // TODO: implement [UserStringsHeapBuilder].
+  use super::{UserStringId, UserStringsHeap};
+  use crate::bytes::{ByteSliceExt, ByteVecExt, CompressedLength};
+  use std::collections::HashMap;
+  use std::string::String;
+  use std::vec::Vec;
+
+  /// Builds a `#US` heap.
+  ///
+  /// Strings are deduplicated and encoded as UTF-16 followed by a byte that is `1` if the string
+  /// contains characters that need special handling.  Tables never index the `#US` heap, strings
+  /// are referenced by `ldstr` tokens whose 24-bit index limits the heap to `0xFFFFFF` bytes, so
+  /// the heap has no [HeapSizes](crate::metadata::headers::HeapSizes) flag.
+  #[derive(Debug, Clone)]
+  pub struct UserStringsHeapBuilder {
+    bytes: Vec<u8>,
+    ids: HashMap<String, UserStringId>,
+  }
+
+  impl UserStringsHeapBuilder {
+    /// The largest offset a `ldstr` token can refer to.
+    pub const MAX_OFFSET: usize = 0xFF_FFFF;
+
+    /// Creates a new [UserStringsHeapBuilder] containing only the empty string.
+    pub fn new() -> Self {
+      Self {
+        bytes: vec![0],
+        ids: HashMap::new(),
+      }
+    }
+
+    /// Creates a [UserStringsHeapBuilder] containing the bytes of an existing [UserStringsHeap].
+    ///
+    /// The existing bytes are kept as they are, so every existing [UserStringId] stays valid.
+    /// Strings are deduplicated against the existing strings up to the first one that cannot be
+    /// read.
+    pub fn from_heap(heap: UserStringsHeap<'_>) -> Self {
+      let mut ids = HashMap::new();
+      let mut offset = 0;
+
+      loop {
+        let id = UserStringId(offset);
+        let Some(len) = heap.0.read_with::<usize, _>(&mut offset, CompressedLength) else {
+          break;
+        };
+        let Some(value) = heap.0.get(offset..offset + len) else {
+          break;
+        };
+
+        // The UTF-16 code units are followed by the special character byte.
+        if len % 2 == 1 {
+          let value = value[..len - 1]
+            .chunks_exact(2)
+            .map(|c| u16::from_le_bytes([c[0], c[1]]));
+
+          if let Ok(value) = char::decode_utf16(value).collect::<Result<String, _>>() {
+            ids.entry(value).or_insert(id);
+          }
+        }
+
+        offset += len;
+      }
+
+      Self {
+        bytes: heap.0.to_vec(),
+        ids,
+      }
+    }
+
+    /// Adds a string to the heap and returns its [UserStringId].
+    ///
+    /// Returns `None` if the string would start past [UserStringsHeapBuilder::MAX_OFFSET].
+    pub fn add(&mut self, value: &str) -> Option<UserStringId> {
+      if let Some(id) = self.ids.get(value) {
+        return Some(*id);
+      }
+
+      let id = UserStringId(self.bytes.len());
+
+      if id.0 > Self::MAX_OFFSET {
+        return None;
+      }
+
+      let mut special = false;
+      let mut len = 0;
+      let mut data = Vec::with_capacity(value.len() * 2 + 1);
+
+      for c in value.encode_utf16() {
+        special |= is_special(c);
+        len += 2;
+        data.write(c);
+      }
+
+      data.push(special as u8);
+
+      self.bytes.write_with(len + 1, CompressedLength);
+      self.bytes.extend_from_slice(&data);
+      self.ids.insert(value.into(), id);
+
+      Some(id)
+    }
+
+    /// Returns the size of the heap in bytes, including padding.
+    pub fn len(&self) -> usize {
+      (self.bytes.len() + 3) & !3
+    }
+
+    /// Returns `true` if the heap contains only the empty string.
+    pub fn is_empty(&self) -> bool {
+      self.bytes.len() == 1
+    }
+
+    /// Returns the bytes of the heap padded to a 4 byte boundary.
+    pub fn finish(self) -> Vec<u8> {
+      let len = self.len();
+      let mut bytes = self.bytes;
+
+      bytes.resize(len, 0);
+      bytes
+    }
+  }
+
+  impl Default for UserStringsHeapBuilder {
+    fn default() -> Self {
+      Self::new()
+    }
+  }
+
+  /// Returns `true` if the given UTF-16 code unit requires the terminal byte of a `#US` string to
+  /// be set.
+  ///
+  /// See ECMA-335 II.24.2.4.
+  fn is_special(c: u16) -> bool {
+    matches!(c, 0x01..=0x08 | 0x0e..=0x1f | 0x27 | 0x2d | 0x7f) || c > 0xff
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  #[test]
+  #[cfg(feature = "write")]
+  fn test_build() {
+    use super::{UserStringId, UserStringsHeap, UserStringsHeapBuilder};
+
+    let mut builder = UserStringsHeapBuilder::new();
+    let hello = builder.add("Hi").unwrap();
+    let quote = builder.add("'").unwrap();
+    let snowman = builder.add("\u{2603}").unwrap();
+    let empty = builder.add("").unwrap();
+
+    assert_eq!(UserStringId(1), hello);
+    assert_eq!(hello, builder.add("Hi").unwrap());
+
+    let bytes = builder.finish();
+    let heap = UserStringsHeap(&bytes);
+
+    assert_eq!(Ok(&[b'H', 0, b'i', 0, 0][..]), heap.get(hello));
+    assert_eq!(Ok(&[b'\'', 0, 1][..]), heap.get(quote));
+    assert_eq!(Ok(&[0x03, 0x26, 1][..]), heap.get(snowman));
+    assert_eq!(Ok(&[0][..]), heap.get(empty));
+  }
 }
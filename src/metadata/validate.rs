@@ -0,0 +1,633 @@
+//! Validation of metadata against the ECMA-335 Partition II table rules.
+
+use crate::metadata::errors::{MetadataStreamReadError, ReadLocation};
+use crate::metadata::streams::blobs::BlobsHeap;
+use crate::metadata::streams::guids::GuidsHeap;
+use crate::metadata::streams::strings::StringsHeap;
+use crate::metadata::streams::tables::flags::{FieldAttributes, MethodAttributes, TypeAttributes};
+use crate::metadata::streams::tables::id::RowId;
+use crate::metadata::streams::tables::rows::*;
+use crate::metadata::streams::tables::table::{ColumnValue, Row, RowRead, TableReader};
+use crate::metadata::streams::tables::TablesStream;
+use crate::metadata::streams::MetadataStream;
+use crate::metadata::MetadataReader;
+use core::fmt;
+use std::collections::HashMap;
+
+/// The tables that are required to be sorted and the columns they are sorted by, primary key first.
+const SORTED_TABLES: &[(usize, &[&str])] = &[
+  (ClassLayoutRow::ID, &["parent"]),
+  (ConstantRow::ID, &["parent"]),
+  (CustomAttributeRow::ID, &["parent"]),
+  (DeclSecurityRow::ID, &["parent"]),
+  (FieldLayoutRow::ID, &["field"]),
+  (FieldMarshalRow::ID, &["parent"]),
+  (FieldRvaRow::ID, &["field"]),
+  (GenericParamRow::ID, &["owner", "number"]),
+  (GenericParamConstraintRow::ID, &["owner"]),
+  (ImplMapRow::ID, &["member_forwarded"]),
+  (InterfaceImplRow::ID, &["class", "interface"]),
+  (MethodImplRow::ID, &["class"]),
+  (MethodSemanticsRow::ID, &["association"]),
+  (NestedClassRow::ID, &["nested_class"]),
+  (LocalScopeRow::ID, &["method", "start_offset"]),
+  (StateMachineMethodRow::ID, &["move_next_method"]),
+  (CustomDebugInformationRow::ID, &["parent"]),
+];
+
+/// The columns that mark the start of a run of rows owned by the row, ending at the start of the
+/// run of the next row.
+const LIST_COLUMNS: &[(usize, &str)] = &[
+  (TypeDefRow::ID, "field_list"),
+  (TypeDefRow::ID, "method_list"),
+  (MethodDefRow::ID, "param_list"),
+  (EventMapRow::ID, "event_list"),
+  (PropertyMapRow::ID, "property_list"),
+  (LocalScopeRow::ID, "variable_list"),
+  (LocalScopeRow::ID, "constant_list"),
+];
+
+/// Validates the given metadata against the ECMA-335 Partition II table rules.
+///
+/// Validation does not stop at the first problem, every rule is checked and all findings are
+/// collected into the returned [ValidationReport].
+pub fn validate(metadata: &MetadataReader<'_>) -> ValidationReport {
+  let mut findings = Vec::new();
+  let mut tables = None;
+  let mut strings = None;
+  let mut blobs = None;
+  let mut guids = None;
+  let mut pdb = false;
+
+  for stream in metadata.streams() {
+    match stream {
+      Ok(MetadataStream::Tables(stream)) => tables = tables.or(Some(stream)),
+      Ok(MetadataStream::Strings(stream)) => strings = strings.or(Some(stream)),
+      Ok(MetadataStream::Blobs(stream)) => blobs = blobs.or(Some(stream)),
+      Ok(MetadataStream::Guids(stream)) => guids = guids.or(Some(stream)),
+      Ok(MetadataStream::Pdb(_)) => pdb = true,
+      Ok(_) => {}
+      Err(MetadataStreamReadError::Read(err)) => findings.push(Finding::error(
+        Rule::MalformedStream,
+        err.location(),
+        err.to_string(),
+      )),
+      Err(err) => findings.push(Finding::error(
+        Rule::MalformedStream,
+        ReadLocation::Root,
+        err.to_string(),
+      )),
+    }
+  }
+
+  let Some(tables) = tables else {
+    findings.push(Finding::error(
+      Rule::MissingTables,
+      ReadLocation::Root,
+      "The `#~` stream is missing".to_string(),
+    ));
+
+    return ValidationReport { findings };
+  };
+
+  let mut validator = Validator {
+    tables,
+    strings: strings.unwrap_or_default(),
+    blobs: blobs.unwrap_or_default(),
+    guids: guids.unwrap_or_default(),
+    findings,
+  };
+
+  validator.check_tables();
+
+  // Portable PDBs describe the module of another file and have no module of their own.
+  if !pdb {
+    validator.check_module_count();
+  }
+
+  validator.check_type_defs();
+  validator.check_member_flags();
+
+  ValidationReport {
+    findings: validator.findings,
+  }
+}
+
+/// The findings of [validate].
+#[derive(Debug, Clone, Default)]
+pub struct ValidationReport {
+  findings: Vec<Finding>,
+}
+
+impl ValidationReport {
+  /// Returns all findings in the order they were found.
+  pub fn findings(&self) -> &[Finding] {
+    &self.findings
+  }
+
+  /// Returns an iterator over findings with [Severity::Error].
+  pub fn errors(&self) -> impl Iterator<Item = &Finding> {
+    self
+      .findings
+      .iter()
+      .filter(|finding| finding.severity == Severity::Error)
+  }
+
+  /// Returns an iterator over findings with [Severity::Warning].
+  pub fn warnings(&self) -> impl Iterator<Item = &Finding> {
+    self
+      .findings
+      .iter()
+      .filter(|finding| finding.severity == Severity::Warning)
+  }
+
+  /// Returns `true` if no findings have [Severity::Error].
+  pub fn is_valid(&self) -> bool {
+    self.errors().next().is_none()
+  }
+}
+
+impl fmt::Display for ValidationReport {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    for finding in &self.findings {
+      writeln!(f, "{finding}")?;
+    }
+
+    Ok(())
+  }
+}
+
+/// A single problem found by [validate].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Finding {
+  /// How severe the problem is.
+  pub severity: Severity,
+  /// The rule that was broken.
+  pub rule: Rule,
+  /// Where the problem was found.
+  pub location: ReadLocation,
+  /// A description of the problem.
+  pub message: String,
+}
+
+impl Finding {
+  fn error(rule: Rule, location: ReadLocation, message: String) -> Self {
+    Self {
+      severity: Severity::Error,
+      rule,
+      location,
+      message,
+    }
+  }
+
+  fn warning(rule: Rule, location: ReadLocation, message: String) -> Self {
+    Self {
+      severity: Severity::Warning,
+      rule,
+      location,
+      message,
+    }
+  }
+}
+
+impl fmt::Display for Finding {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    write!(
+      f,
+      "{} {} in {}: {}",
+      self.severity,
+      self.rule.id(),
+      self.location,
+      self.message
+    )
+  }
+}
+
+/// The severity of a [Finding].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Severity {
+  /// The metadata is unusual but can be read by the CLI.
+  Warning,
+  /// The metadata breaks a rule the CLI relies on.
+  Error,
+}
+
+impl fmt::Display for Severity {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match self {
+      Self::Warning => write!(f, "warning"),
+      Self::Error => write!(f, "error"),
+    }
+  }
+}
+
+/// A rule checked by [validate].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum Rule {
+  /// A metadata stream could not be read.
+  MalformedStream,
+  /// The `#~` stream is missing.
+  MissingTables,
+  /// A row could not be read, such as a coded index with an unknown tag.
+  MalformedRow,
+  /// The `Module` table does not contain exactly one row.
+  ModuleCount,
+  /// A table that is required to be sorted is not flagged as sorted.
+  SortedFlagMissing,
+  /// A table flagged as sorted is not sorted by its key.
+  UnsortedTable,
+  /// An index into a table is out of range.
+  RowIdOutOfRange,
+  /// An index into a heap is out of range or refers to malformed data.
+  InvalidHeapIndex,
+  /// A name is empty.
+  EmptyName,
+  /// A list column is less than the same column of the previous row.
+  ListNotMonotonic,
+  /// Two `TypeDef` rows share a namespace, name and enclosing type.
+  DuplicateTypeDef,
+  /// A flags column has an invalid combination of values.
+  InvalidFlags,
+}
+
+impl Rule {
+  /// Returns the stable identifier of the rule.
+  pub fn id(self) -> &'static str {
+    match self {
+      Self::MalformedStream => "MD0001",
+      Self::MissingTables => "MD0002",
+      Self::MalformedRow => "MD0003",
+      Self::ModuleCount => "MD0004",
+      Self::SortedFlagMissing => "MD0005",
+      Self::UnsortedTable => "MD0006",
+      Self::RowIdOutOfRange => "MD0007",
+      Self::InvalidHeapIndex => "MD0008",
+      Self::EmptyName => "MD0009",
+      Self::ListNotMonotonic => "MD0010",
+      Self::DuplicateTypeDef => "MD0011",
+      Self::InvalidFlags => "MD0012",
+    }
+  }
+}
+
+impl fmt::Display for Rule {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    f.write_str(self.id())
+  }
+}
+
+struct Validator<'a> {
+  tables: TablesStream<'a>,
+  strings: StringsHeap<'a>,
+  blobs: BlobsHeap<'a>,
+  guids: GuidsHeap<'a>,
+  findings: Vec<Finding>,
+}
+
+impl<'a> Validator<'a> {
+  fn check_tables(&mut self) {
+    let tables = self.tables;
+
+    self.check_table(tables.modules());
+    self.check_table(tables.type_refs());
+    self.check_table(tables.type_defs());
+    self.check_table(tables.field_ptrs());
+    self.check_table(tables.fields());
+    self.check_table(tables.method_ptrs());
+    self.check_table(tables.method_defs());
+    self.check_table(tables.param_ptrs());
+    self.check_table(tables.params());
+    self.check_table(tables.interface_impls());
+    self.check_table(tables.member_refs());
+    self.check_table(tables.constants());
+    self.check_table(tables.custom_attributes());
+    self.check_table(tables.field_marshals());
+    self.check_table(tables.decl_securities());
+    self.check_table(tables.class_layouts());
+    self.check_table(tables.field_layouts());
+    self.check_table(tables.stand_alone_sigs());
+    self.check_table(tables.event_maps());
+    self.check_table(tables.event_ptrs());
+    self.check_table(tables.events());
+    self.check_table(tables.property_maps());
+    self.check_table(tables.property_ptrs());
+    self.check_table(tables.properties());
+    self.check_table(tables.method_semantics());
+    self.check_table(tables.method_impls());
+    self.check_table(tables.module_refs());
+    self.check_table(tables.type_specs());
+    self.check_table(tables.impl_maps());
+    self.check_table(tables.field_rvas());
+    self.check_table(tables.assemblies());
+    self.check_table(tables.assembly_processors());
+    self.check_table(tables.assembly_oses());
+    self.check_table(tables.assembly_refs());
+    self.check_table(tables.assembly_ref_processors());
+    self.check_table(tables.assembly_ref_oses());
+    self.check_table(tables.files());
+    self.check_table(tables.exported_types());
+    self.check_table(tables.manifest_resources());
+    self.check_table(tables.nested_classes());
+    self.check_table(tables.generic_params());
+    self.check_table(tables.method_specs());
+    self.check_table(tables.generic_param_constraints());
+    self.check_table(tables.documents());
+    self.check_table(tables.method_debug_informations());
+    self.check_table(tables.local_scopes());
+    self.check_table(tables.local_variables());
+    self.check_table(tables.local_constants());
+    self.check_table(tables.import_scopes());
+    self.check_table(tables.state_machine_methods());
+    self.check_table(tables.custom_debug_informations());
+  }
+
+  /// Checks the rows, columns and sort order of a single table.
+  fn check_table<R: RowRead>(&mut self, reader: TableReader<'a, '_, R>) {
+    let header = self.tables.header();
+    let table = ReadLocation::Table { table: R::NAME };
+    let sort_key = SORTED_TABLES
+      .iter()
+      .find(|(id, _)| *id == R::ID)
+      .map(|(_, key)| *key);
+
+    let sorted = match sort_key {
+      Some(_) => header.sorted & (1 << R::ID) != 0,
+      None => false,
+    };
+
+    if sort_key.is_some() && !sorted && !reader.is_empty() {
+      self.findings.push(Finding::warning(
+        Rule::SortedFlagMissing,
+        table,
+        "The table is required to be sorted but is not flagged as sorted".to_string(),
+      ));
+    }
+
+    let mut prev_key = Vec::new();
+    let mut prev_lists = Vec::new();
+
+    for index in 1..=reader.len() {
+      let row = match reader.get(RowId::new(index)) {
+        Ok(row) => row,
+        Err(err) => {
+          self.findings.push(Finding::error(
+            Rule::MalformedRow,
+            err.location(),
+            err.to_string(),
+          ));
+
+          continue;
+        }
+      };
+
+      let mut key = Vec::new();
+      let mut lists = Vec::new();
+
+      row.for_each_column(&mut |column, value| {
+        self.check_column::<R>(index, column, value);
+
+        if let Some(key_column) = sort_key.and_then(|key| key.iter().position(|c| *c == column)) {
+          key.push((key_column, sort_value(value)));
+        }
+
+        if LIST_COLUMNS.contains(&(R::ID, column)) {
+          lists.push((column, sort_value(value)));
+        }
+      });
+
+      key.sort_unstable();
+
+      if sorted && index > 1 && key < prev_key {
+        self.findings.push(Finding::error(
+          Rule::UnsortedTable,
+          ReadLocation::Row {
+            table: R::NAME,
+            row: index,
+          },
+          "The row precedes the previous row in sort order".to_string(),
+        ));
+      }
+
+      for (&(column, list), &(_, prev)) in lists.iter().zip(prev_lists.iter()) {
+        if list < prev {
+          self.findings.push(Finding::error(
+            Rule::ListNotMonotonic,
+            ReadLocation::Column {
+              table: R::NAME,
+              row: index,
+              column,
+            },
+            format!("The list starts at `{list}` which precedes `{prev}` of the previous row"),
+          ));
+        }
+      }
+
+      prev_key = key;
+      prev_lists = lists;
+    }
+  }
+
+  /// Checks that the index stored in a column refers to an existing row or heap entry.
+  fn check_column<R: Row>(&mut self, row: usize, column: &'static str, value: ColumnValue) {
+    let header = self.tables.header();
+    let location = ReadLocation::Column {
+      table: R::NAME,
+      row,
+      column,
+    };
+
+    match value {
+      ColumnValue::Row { table, index } | ColumnValue::Coded { table, index, .. } => {
+        let mut len = header.rows[table] as usize;
+        let (min, max) = match LIST_COLUMNS.contains(&(R::ID, column)) {
+          true => {
+            // Lists index the table of pointers to the rows instead when it is present.
+            if let Some(ptrs) = ptr_table(table).filter(|&ptrs| header.rows[ptrs] > 0) {
+              len = header.rows[ptrs] as usize;
+            }
+
+            // A list may be empty, in which case it starts one past the end of the table.
+            (1, len + 1)
+          }
+          false => (0, len),
+        };
+
+        if index < min || index > max {
+          self.findings.push(Finding::error(
+            Rule::RowIdOutOfRange,
+            location,
+            format!("Row `{index}` is outside of the table of `{len}` rows"),
+          ));
+        }
+      }
+      ColumnValue::String(id) => match self.strings.get_str(id) {
+        Ok("") if column == "name" && R::ID != ParamRow::ID => {
+          self.findings.push(Finding::error(
+            Rule::EmptyName,
+            location,
+            "The name is empty".to_string(),
+          ));
+        }
+        Ok(_) => {}
+        Err(err) => self.findings.push(Finding::error(
+          Rule::InvalidHeapIndex,
+          location,
+          err.to_string(),
+        )),
+      },
+      ColumnValue::Blob(id) => {
+        if let Err(err) = self.blobs.get(id) {
+          self.findings.push(Finding::error(
+            Rule::InvalidHeapIndex,
+            location,
+            err.to_string(),
+          ));
+        }
+      }
+      ColumnValue::Guid(id) if !id.is_null() => {
+        if let Err(err) = self.guids.get(id) {
+          self.findings.push(Finding::error(
+            Rule::InvalidHeapIndex,
+            location,
+            err.to_string(),
+          ));
+        }
+      }
+      _ => {}
+    }
+  }
+
+  fn check_module_count(&mut self) {
+    let len = self.tables.modules().len();
+
+    if len != 1 {
+      self.findings.push(Finding::error(
+        Rule::ModuleCount,
+        ReadLocation::Table {
+          table: ModuleRow::NAME,
+        },
+        format!("Expected exactly one row, found `{len}`"),
+      ));
+    }
+  }
+
+  /// Checks for duplicate type definitions and flags that are inconsistent with nesting.
+  fn check_type_defs(&mut self) {
+    let tables = self.tables;
+    let enclosing = tables
+      .nested_classes()
+      .into_iter()
+      .map(|row| (row.nested_class().index(), row.enclosing_class().index()))
+      .collect::<HashMap<_, _>>();
+
+    let mut names = HashMap::new();
+
+    for row in tables.type_defs() {
+      let index = row.id().index();
+      let location = ReadLocation::Row {
+        table: TypeDefRow::NAME,
+        row: index,
+      };
+
+      let flags = row.flags();
+      let enclosing = enclosing.get(&index).copied();
+      let visibility = (flags & TypeAttributes::VISIBILITY_MASK).bits();
+      let nested_visibility = visibility > TypeAttributes::PUBLIC.bits();
+
+      if enclosing.is_some() != nested_visibility {
+        let message = match enclosing {
+          Some(_) => "A nested type has top-level visibility",
+          None => "A top-level type has nested visibility",
+        };
+
+        self.invalid_flags(location, message);
+      }
+
+      if flags & TypeAttributes::LAYOUT_MASK == TypeAttributes::LAYOUT_MASK {
+        self.invalid_flags(location, "The type has an invalid layout");
+      }
+
+      if flags.contains(TypeAttributes::INTERFACE) && !flags.contains(TypeAttributes::ABSTRACT) {
+        self.invalid_flags(location, "An interface is not abstract");
+      }
+
+      let (Ok(namespace), Ok(name)) = (
+        self.strings.get(row.namespace()),
+        self.strings.get(row.name()),
+      ) else {
+        continue;
+      };
+
+      if let Some(first) = names.insert((namespace, name, enclosing), index) {
+        self.findings.push(Finding::error(
+          Rule::DuplicateTypeDef,
+          location,
+          format!("The type has the same namespace, name and enclosing type as row `{first}`"),
+        ));
+      }
+    }
+  }
+
+  /// Checks that the member access of fields and methods is valid.
+  fn check_member_flags(&mut self) {
+    let tables = self.tables;
+
+    for row in tables.fields() {
+      let access = row.flags() & FieldAttributes::FIELD_ACCESS_MASK;
+
+      if access == FieldAttributes::FIELD_ACCESS_MASK {
+        let location = ReadLocation::Row {
+          table: FieldRow::NAME,
+          row: row.id().index(),
+        };
+
+        self.invalid_flags(location, "The field has an invalid access");
+      }
+    }
+
+    for row in tables.method_defs() {
+      let access = row.flags() & MethodAttributes::MEMBER_ACCESS_MASK;
+
+      if access == MethodAttributes::MEMBER_ACCESS_MASK {
+        let location = ReadLocation::Row {
+          table: MethodDefRow::NAME,
+          row: row.id().index(),
+        };
+
+        self.invalid_flags(location, "The method has an invalid access");
+      }
+    }
+  }
+
+  fn invalid_flags(&mut self, location: ReadLocation, message: &str) {
+    self.findings.push(Finding::error(
+      Rule::InvalidFlags,
+      location,
+      message.to_string(),
+    ));
+  }
+}
+
+/// Returns the id of the table of pointers to the rows of the given table, for the tables whose
+/// lists may be indirected.
+fn ptr_table(table: usize) -> Option<usize> {
+  match table {
+    FieldRow::ID => Some(FieldPtrRow::ID),
+    MethodDefRow::ID => Some(MethodPtrRow::ID),
+    ParamRow::ID => Some(ParamPtrRow::ID),
+    EventRow::ID => Some(EventPtrRow::ID),
+    PropertyRow::ID => Some(PropertyPtrRow::ID),
+    _ => None,
+  }
+}
+
+/// Returns the value of a column as it is compared when sorting.
+fn sort_value(value: ColumnValue) -> usize {
+  match value {
+    ColumnValue::Constant(value) => value as usize,
+    ColumnValue::Row { index, .. } => index,
+    ColumnValue::Coded { value, .. } => value,
+    _ => 0,
+  }
+}
@@ -0,0 +1,330 @@
+//! CIL method bodies.
+
+#[cfg(feature = "read")]
+#[doc(inline)]
+pub use read::*;
+
+bitflags::bitflags! {
+  /// The flags of a method body header.
+  #[derive(Debug, Default, Copy, Clone, PartialEq, Eq)]
+  pub struct MethodBodyFlags : u16 {
+    /// The method body uses the tiny header format.
+    ///
+    /// The format is stored in the two lowest bits, compare them instead of using `contains` since
+    /// [MethodBodyFlags::FAT_FORMAT] includes this bit.
+    const TINY_FORMAT = 0x2;
+    /// The method body uses the fat header format.
+    const FAT_FORMAT = 0x3;
+    /// More sections follow after the code.
+    const MORE_SECTS = 0x8;
+    /// Call the default constructor on all local variables.
+    const INIT_LOCALS = 0x10;
+  }
+}
+
+bitflags::bitflags! {
+  /// The kind of an exception handling clause.
+  #[derive(Debug, Default, Copy, Clone, PartialEq, Eq)]
+  pub struct ExceptionClauseFlags : u32 {
+    /// A typed exception clause.
+    const EXCEPTION = 0x0000;
+    /// An exception filter and handler clause.
+    const FILTER = 0x0001;
+    /// A finally clause.
+    const FINALLY = 0x0002;
+    /// A fault clause, a finally clause that is only executed on exceptions.
+    const FAULT = 0x0004;
+  }
+}
+
+bitflags::bitflags! {
+  /// The kind of a method body data section.
+  #[derive(Debug, Default, Copy, Clone, PartialEq, Eq)]
+  pub struct MethodDataSectionFlags : u8 {
+    /// The section contains exception handling clauses.
+    const EH_TABLE = 0x1;
+    /// Reserved, shall be 0.
+    const OPT_IL_TABLE = 0x2;
+    /// The section uses the fat format.
+    const FAT_FORMAT = 0x40;
+    /// Another data section follows this one.
+    const MORE_SECTS = 0x80;
+  }
+}
+
+/// An exception handling clause of a method body.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ExceptionClause {
+  /// The kind of the clause.
+  pub flags: ExceptionClauseFlags,
+  /// The offset of the try block in bytes from the start of the code.
+  pub try_offset: u32,
+  /// The length of the try block in bytes.
+  pub try_length: u32,
+  /// The offset of the handler in bytes from the start of the code.
+  pub handler_offset: u32,
+  /// The length of the handler in bytes.
+  pub handler_length: u32,
+  /// The metadata token of the caught type for typed exception clauses, or the offset of the
+  /// filter in bytes from the start of the code for filter clauses.
+  pub class_token_or_filter_offset: u32,
+}
+
+#[cfg(feature = "read")]
+#[doc(hidden)]
+mod read {
+  use super::{ExceptionClause, ExceptionClauseFlags, MethodBodyFlags, MethodDataSectionFlags};
+  use crate::bytes::{ByteSliceExt, FromBytes};
+
+  /// A CIL method body.
+  #[derive(Debug, Clone, Copy)]
+  pub struct MethodBody<'a> {
+    /// The flags of the method body header.
+    pub flags: MethodBodyFlags,
+    /// The maximum number of items on the operand stack, 8 for tiny method bodies.
+    pub max_stack: u16,
+    /// The `StandAloneSig` token of the local variables signature, or 0.
+    pub local_var_sig_token: u32,
+    /// The CIL instructions.
+    pub code: &'a [u8],
+    /// The data sections following the code.
+    pub sections: &'a [u8],
+  }
+
+  impl<'a> MethodBody<'a> {
+    /// Returns `true` if the method body uses the tiny header format.
+    pub fn is_tiny(&self) -> bool {
+      self.flags.bits() & 0x3 == MethodBodyFlags::TINY_FORMAT.bits()
+    }
+
+    /// Returns `true` if the method body uses the fat header format.
+    pub fn is_fat(&self) -> bool {
+      self.flags.bits() & 0x3 == MethodBodyFlags::FAT_FORMAT.bits()
+    }
+
+    /// Returns `true` if local variables are zero initialized.
+    pub fn init_locals(&self) -> bool {
+      self.flags.contains(MethodBodyFlags::INIT_LOCALS)
+    }
+
+    /// Returns an iterator over the exception handling clauses of all data sections.
+    pub fn exception_clauses(&self) -> ExceptionClauseIter<'a> {
+      ExceptionClauseIter {
+        data: self.sections,
+        section: 0,
+        offset: 0,
+        end: 0,
+        fat: false,
+        more: !self.sections.is_empty(),
+      }
+    }
+  }
+
+  impl<'a> FromBytes<'a> for MethodBody<'a> {
+    fn from_bytes(buf: &'a [u8], offset: &mut usize, _: ()) -> Option<Self> {
+      let beg = *offset;
+      let first = buf.read::<u8>(offset)?;
+
+      if first & 0x3 == MethodBodyFlags::TINY_FORMAT.bits() as u8 {
+        return Some(Self {
+          flags: MethodBodyFlags::TINY_FORMAT,
+          max_stack: 8,
+          local_var_sig_token: 0,
+          code: buf.read_with(offset, (first >> 2) as usize)?,
+          sections: &[],
+        });
+      }
+
+      *offset = beg;
+
+      let flags_and_size = buf.read::<u16>(offset)?;
+      let flags = MethodBodyFlags::from_bits_truncate(flags_and_size & 0x0fff);
+      let size = (flags_and_size >> 12) as usize * 4;
+      let max_stack = buf.read(offset)?;
+      let code_size = buf.read::<u32>(offset)? as usize;
+      let local_var_sig_token = buf.read(offset)?;
+
+      if flags_and_size & 0x3 != MethodBodyFlags::FAT_FORMAT.bits() {
+        return None;
+      }
+
+      *offset = beg.saturating_add(size);
+
+      let code = buf.read_with(offset, code_size)?;
+      let sections = match flags.contains(MethodBodyFlags::MORE_SECTS) {
+        true => {
+          // Data sections are aligned to a 4 byte boundary.
+          *offset = (*offset + 3) & !3;
+          buf.get(*offset..)?
+        }
+        false => &[],
+      };
+
+      Some(Self {
+        flags,
+        max_stack,
+        local_var_sig_token,
+        code,
+        sections,
+      })
+    }
+  }
+
+  /// Iterates over the exception handling clauses of a [MethodBody].
+  #[derive(Clone, Copy)]
+  pub struct ExceptionClauseIter<'a> {
+    data: &'a [u8],
+    section: usize,
+    offset: usize,
+    end: usize,
+    fat: bool,
+    more: bool,
+  }
+
+  impl<'a> ExceptionClauseIter<'a> {
+    /// Moves to the next data section containing exception handling clauses.
+    fn next_section(&mut self) -> Option<()> {
+      while self.more {
+        let offset = &mut self.section;
+        let flags = MethodDataSectionFlags::from_bits_truncate(self.data.read(offset)?);
+        let size = match flags.contains(MethodDataSectionFlags::FAT_FORMAT) {
+          true => u32::from_le_bytes([
+            self.data.read(offset)?,
+            self.data.read(offset)?,
+            self.data.read(offset)?,
+            0,
+          ]) as usize,
+          false => {
+            let size = self.data.read::<u8>(offset)? as usize;
+
+            *offset += 2;
+            size
+          }
+        };
+
+        let beg = *offset - 4;
+
+        // A section is at least as large as its header, anything smaller would never advance.
+        if size < 4 {
+          self.more = false;
+          return None;
+        }
+
+        self.offset = *offset;
+        self.end = beg.saturating_add(size);
+        self.fat = flags.contains(MethodDataSectionFlags::FAT_FORMAT);
+        self.more = flags.contains(MethodDataSectionFlags::MORE_SECTS);
+        self.section = (self.end + 3) & !3;
+
+        if flags.contains(MethodDataSectionFlags::EH_TABLE) {
+          return Some(());
+        }
+      }
+
+      None
+    }
+  }
+
+  impl<'a> Iterator for ExceptionClauseIter<'a> {
+    type Item = ExceptionClause;
+
+    fn next(&mut self) -> Option<Self::Item> {
+      let clause_size = if self.fat { 24 } else { 12 };
+
+      if self.offset + clause_size > self.end {
+        self.next_section()?;
+      }
+
+      let data = self.data.get(..self.end)?;
+      let offset = &mut self.offset;
+
+      Some(match self.fat {
+        true => ExceptionClause {
+          flags: ExceptionClauseFlags::from_bits_truncate(data.read(offset)?),
+          try_offset: data.read(offset)?,
+          try_length: data.read(offset)?,
+          handler_offset: data.read(offset)?,
+          handler_length: data.read(offset)?,
+          class_token_or_filter_offset: data.read(offset)?,
+        },
+        false => ExceptionClause {
+          flags: ExceptionClauseFlags::from_bits_truncate(data.read::<u16>(offset)? as u32),
+          try_offset: data.read::<u16>(offset)? as u32,
+          try_length: data.read::<u8>(offset)? as u32,
+          handler_offset: data.read::<u16>(offset)? as u32,
+          handler_length: data.read::<u8>(offset)? as u32,
+          class_token_or_filter_offset: data.read(offset)?,
+        },
+      })
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::{ExceptionClauseFlags, MethodBody};
+  use crate::bytes::ByteSliceExt;
+
+  #[test]
+  fn test_read_tiny_method_body() {
+    // ldstr, call, ret
+    let bytes = &[
+      0x2e, 0x72, 0x01, 0x00, 0x00, 0x70, 0x28, 0x01, 0x00, 0x00, 0x0a, 0x2a,
+    ][..];
+    let body = bytes.read::<MethodBody>(&mut 0).unwrap();
+
+    assert!(body.is_tiny());
+    assert!(!body.is_fat());
+    assert_eq!(8, body.max_stack);
+    assert_eq!(&bytes[1..], body.code);
+    assert_eq!(0, body.exception_clauses().count());
+  }
+
+  #[test]
+  fn test_read_fat_method_body() {
+    let mut bytes = vec![
+      0x1b, 0x30, 0x02, 0x00, 0x06, 0x00, 0x00, 0x00, 0x01, 0x00, 0x00, 0x11,
+    ];
+
+    // nop, leave.s 2, nop, endfinally, ret
+    bytes.extend_from_slice(&[0x00, 0xde, 0x02, 0x00, 0xdc, 0x2a]);
+    bytes.extend_from_slice(&[0x00, 0x00]);
+    // A small exception handling section with a single finally clause.
+    bytes.extend_from_slice(&[0x01, 0x10, 0x00, 0x00]);
+    bytes.extend_from_slice(&[
+      0x02, 0x00, 0x00, 0x00, 0x03, 0x03, 0x00, 0x02, 0x00, 0x00, 0x00, 0x00,
+    ]);
+
+    let body = (&bytes[..]).read::<MethodBody>(&mut 0).unwrap();
+    let clauses = body.exception_clauses().collect::<Vec<_>>();
+
+    assert!(body.is_fat());
+    assert!(!body.is_tiny());
+    assert!(body.init_locals());
+    assert_eq!(2, body.max_stack);
+    assert_eq!(0x11000001, body.local_var_sig_token);
+    assert_eq!(6, body.code.len());
+    assert_eq!(1, clauses.len());
+    assert_eq!(ExceptionClauseFlags::FINALLY, clauses[0].flags);
+    assert_eq!(0, clauses[0].try_offset);
+    assert_eq!(3, clauses[0].try_length);
+    assert_eq!(3, clauses[0].handler_offset);
+    assert_eq!(2, clauses[0].handler_length);
+  }
+
+  #[test]
+  fn test_read_undersized_data_section() {
+    let mut bytes = vec![
+      0x0b, 0x30, 0x02, 0x00, 0x01, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+    ];
+
+    // ret
+    bytes.extend_from_slice(&[0x2a, 0x00, 0x00, 0x00]);
+    // A small section claiming more sections, but too small to hold its own header.
+    bytes.extend_from_slice(&[0x80, 0x00, 0x00, 0x00]);
+
+    let body = (&bytes[..]).read::<MethodBody>(&mut 0).unwrap();
+
+    assert_eq!(0, body.exception_clauses().count());
+  }
+}
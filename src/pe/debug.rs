@@ -0,0 +1,350 @@
+//! The PE debug directory.
+
+#[cfg(feature = "read")]
+#[doc(inline)]
+pub use read::*;
+
+/// The index of the debug directory in the optional header data directories.
+pub const IMAGE_DIRECTORY_ENTRY_DEBUG: usize = 6;
+
+/// The associated PDB file is described by a CodeView entry.
+pub const IMAGE_DEBUG_TYPE_CODEVIEW: u32 = 2;
+/// The PE file was produced by a deterministic compiler.
+pub const IMAGE_DEBUG_TYPE_REPRO: u32 = 16;
+/// The PE file contains an embedded, compressed portable PDB.
+pub const IMAGE_DEBUG_TYPE_EMBEDDED_PORTABLE_PDB: u32 = 17;
+/// The entry contains the checksum of the associated PDB file.
+pub const IMAGE_DEBUG_TYPE_PDB_CHECKSUM: u32 = 19;
+
+/// The magic signature of a CodeView `RSDS` entry.
+pub const CODE_VIEW_MAGIC: u32 = 0x53445352;
+/// The magic signature of an embedded portable PDB entry.
+pub const EMBEDDED_PORTABLE_PDB_MAGIC: u32 = 0x4244504d;
+/// The minor version of CodeView entries that refer to a portable PDB.
+pub const PORTABLE_CODE_VIEW_MINOR_VERSION: u16 = 0x504d;
+
+/// An `IMAGE_DEBUG_DIRECTORY` entry.
+#[derive(Debug, Clone, Copy)]
+#[repr(C)]
+pub struct DebugDirectory {
+  /// Reserved, shall be 0.
+  pub characteristics: u32,
+  /// The time and date the debug data was created, or the PDB stamp of portable PDB entries.
+  pub time_date_stamp: u32,
+  /// The major version of the debug data format.
+  pub major_version: u16,
+  /// The minor version of the debug data format.
+  pub minor_version: u16,
+  /// The format of the debug data.
+  pub kind: u32,
+  /// The size of the debug data, not including the directory itself.
+  pub size_of_data: u32,
+  /// The RVA of the debug data when loaded, or 0.
+  pub address_of_raw_data: u32,
+  /// The file offset of the debug data.
+  pub pointer_to_raw_data: u32,
+}
+
+impl DebugDirectory {
+  /// Returns `true` if this is a CodeView entry referring to a portable PDB.
+  pub fn is_portable_code_view(&self) -> bool {
+    self.kind == IMAGE_DEBUG_TYPE_CODEVIEW && self.minor_version == PORTABLE_CODE_VIEW_MINOR_VERSION
+  }
+}
+
+#[cfg(feature = "read")]
+#[doc(hidden)]
+mod read {
+  use super::*;
+  use crate::bytes::{ByteSliceExt, FromBytes, NulTerminated};
+  use core::ffi::CStr;
+
+  impl<'a> FromBytes<'a> for DebugDirectory {
+    fn from_bytes(buf: &'a [u8], offset: &mut usize, _: ()) -> Option<Self> {
+      Some(Self {
+        characteristics: buf.read(offset)?,
+        time_date_stamp: buf.read(offset)?,
+        major_version: buf.read(offset)?,
+        minor_version: buf.read(offset)?,
+        kind: buf.read(offset)?,
+        size_of_data: buf.read(offset)?,
+        address_of_raw_data: buf.read(offset)?,
+        pointer_to_raw_data: buf.read(offset)?,
+      })
+    }
+  }
+
+  /// Iterates over the entries of a debug directory.
+  #[derive(Clone, Copy)]
+  pub struct DebugDirectoryIter<'a> {
+    directory: &'a [u8],
+    data: &'a [u8],
+    offset: usize,
+  }
+
+  impl<'a> DebugDirectoryIter<'a> {
+    /// Creates a [DebugDirectoryIter] from the bytes of the debug directory and the bytes of the PE
+    /// file the entries' `pointer_to_raw_data` is relative to.
+    pub fn new(directory: &'a [u8], data: &'a [u8]) -> Self {
+      Self {
+        directory,
+        data,
+        offset: 0,
+      }
+    }
+  }
+
+  impl<'a> Iterator for DebugDirectoryIter<'a> {
+    type Item = DebugDirectoryEntry<'a>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+      let directory = self.directory.read::<DebugDirectory>(&mut self.offset)?;
+      let beg = directory.pointer_to_raw_data as usize;
+      let end = beg.saturating_add(directory.size_of_data as usize);
+
+      Some(DebugDirectoryEntry {
+        directory,
+        data: self.data.get(beg..end).unwrap_or_default(),
+      })
+    }
+  }
+
+  /// A debug directory entry and its data.
+  #[derive(Debug, Clone, Copy)]
+  pub struct DebugDirectoryEntry<'a> {
+    /// The debug directory entry.
+    pub directory: DebugDirectory,
+    /// The debug data the entry points to.
+    ///
+    /// Empty if the entry has no data or points outside the file.
+    pub data: &'a [u8],
+  }
+
+  impl<'a> DebugDirectoryEntry<'a> {
+    /// Decodes the debug data using the format indicated by the entry.
+    ///
+    /// Returns `None` if the data is malformed.
+    pub fn decode(&self) -> Option<DebugData<'a>> {
+      Some(match self.directory.kind {
+        IMAGE_DEBUG_TYPE_CODEVIEW => {
+          DebugData::CodeView(CodeView::from_bytes(self.data, &mut 0, ())?)
+        }
+        IMAGE_DEBUG_TYPE_REPRO => DebugData::Deterministic,
+        IMAGE_DEBUG_TYPE_EMBEDDED_PORTABLE_PDB => {
+          DebugData::EmbeddedPortablePdb(EmbeddedPortablePdb::from_bytes(self.data, &mut 0, ())?)
+        }
+        IMAGE_DEBUG_TYPE_PDB_CHECKSUM => {
+          DebugData::PdbChecksum(PdbChecksum::from_bytes(self.data, &mut 0, ())?)
+        }
+        kind => DebugData::Unrecognized {
+          kind,
+          data: self.data,
+        },
+      })
+    }
+
+    /// Returns the 20 byte id of the portable PDB referred to by this CodeView entry.
+    ///
+    /// The id is made of the CodeView guid followed by the entry's time stamp, and equals
+    /// [PdbStream::id](crate::metadata::streams::pdb::PdbStream::id) of the matching PDB.
+    pub fn pdb_id(&self) -> Option<[u8; 20]> {
+      if !self.directory.is_portable_code_view() {
+        return None;
+      }
+
+      let DebugData::CodeView(code_view) = self.decode()? else {
+        return None;
+      };
+
+      let mut id = [0; 20];
+
+      id[..16].copy_from_slice(&code_view.guid);
+      id[16..].copy_from_slice(&self.directory.time_date_stamp.to_le_bytes());
+
+      Some(id)
+    }
+  }
+
+  /// Decoded debug data.
+  #[derive(Debug, Clone, Copy)]
+  #[non_exhaustive]
+  pub enum DebugData<'a> {
+    /// A CodeView `RSDS` entry describing the associated PDB file.
+    CodeView(CodeView<'a>),
+    /// The PE file was produced deterministically and its time stamps are content hashes.
+    Deterministic,
+    /// The checksum of the associated PDB file.
+    PdbChecksum(PdbChecksum<'a>),
+    /// An embedded portable PDB.
+    EmbeddedPortablePdb(EmbeddedPortablePdb<'a>),
+    /// The debug data format was not recognized.
+    Unrecognized {
+      /// The debug data format.
+      kind: u32,
+      /// The undecoded debug data.
+      data: &'a [u8],
+    },
+  }
+
+  /// A CodeView `RSDS` entry.
+  #[derive(Debug, Clone, Copy)]
+  pub struct CodeView<'a> {
+    /// The guid of the associated PDB file.
+    pub guid: [u8; 16],
+    /// The age of the associated PDB file, always 1 for portable PDBs.
+    pub age: u32,
+    /// The path of the associated PDB file.
+    pub path: &'a CStr,
+  }
+
+  impl<'a> FromBytes<'a> for CodeView<'a> {
+    fn from_bytes(buf: &'a [u8], offset: &mut usize, _: ()) -> Option<Self> {
+      if buf.read::<u32>(offset)? != CODE_VIEW_MAGIC {
+        return None;
+      }
+
+      Some(Self {
+        guid: buf.read(offset)?,
+        age: buf.read(offset)?,
+        path: buf.read_with(offset, NulTerminated)?,
+      })
+    }
+  }
+
+  /// The checksum of the associated PDB file.
+  #[derive(Debug, Clone, Copy)]
+  pub struct PdbChecksum<'a> {
+    /// The name of the hash algorithm, such as `SHA256`.
+    pub algorithm: &'a CStr,
+    /// The checksum.
+    pub checksum: &'a [u8],
+  }
+
+  impl<'a> FromBytes<'a> for PdbChecksum<'a> {
+    fn from_bytes(buf: &'a [u8], offset: &mut usize, _: ()) -> Option<Self> {
+      Some(Self {
+        algorithm: buf.read_with(offset, NulTerminated)?,
+        checksum: buf.remaining(offset),
+      })
+    }
+  }
+
+  /// A deflate compressed portable PDB embedded in the PE file.
+  #[derive(Debug, Clone, Copy)]
+  pub struct EmbeddedPortablePdb<'a> {
+    /// The size of the portable PDB after decompression.
+    pub uncompressed_size: u32,
+    /// The deflate compressed portable PDB.
+    pub compressed: &'a [u8],
+  }
+
+  impl<'a> EmbeddedPortablePdb<'a> {
+    /// Decompresses the portable PDB.
+    ///
+    /// The returned bytes can be read with [MetadataReader](crate::metadata::MetadataReader).
+    /// Returns `None` if the data could not be decompressed to the expected size.
+    #[cfg(feature = "deflate")]
+    pub fn decompress(&self) -> Option<std::vec::Vec<u8>> {
+      let size = self.uncompressed_size as usize;
+      let data = miniz_oxide::inflate::decompress_to_vec_with_limit(self.compressed, size).ok()?;
+
+      (data.len() == size).then_some(data)
+    }
+  }
+
+  impl<'a> FromBytes<'a> for EmbeddedPortablePdb<'a> {
+    fn from_bytes(buf: &'a [u8], offset: &mut usize, _: ()) -> Option<Self> {
+      if buf.read::<u32>(offset)? != EMBEDDED_PORTABLE_PDB_MAGIC {
+        return None;
+      }
+
+      Some(Self {
+        uncompressed_size: buf.read(offset)?,
+        compressed: buf.remaining(offset),
+      })
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_decode_code_view() {
+    let mut file = Vec::new();
+
+    file.extend_from_slice(&0u32.to_le_bytes());
+    file.extend_from_slice(&0x1234u32.to_le_bytes());
+    file.extend_from_slice(&0x0100u16.to_le_bytes());
+    file.extend_from_slice(&PORTABLE_CODE_VIEW_MINOR_VERSION.to_le_bytes());
+    file.extend_from_slice(&IMAGE_DEBUG_TYPE_CODEVIEW.to_le_bytes());
+    file.extend_from_slice(&34u32.to_le_bytes());
+    file.extend_from_slice(&0u32.to_le_bytes());
+    file.extend_from_slice(&28u32.to_le_bytes());
+    file.extend_from_slice(&CODE_VIEW_MAGIC.to_le_bytes());
+    file.extend_from_slice(&[0xab; 16]);
+    file.extend_from_slice(&1u32.to_le_bytes());
+    file.extend_from_slice(b"empty.pdb\0");
+
+    let mut entries = DebugDirectoryIter::new(&file[..28], &file);
+    let entry = entries.next().unwrap();
+
+    assert!(entries.next().is_none());
+
+    let Some(DebugData::CodeView(code_view)) = entry.decode() else {
+      panic!("expected CodeView entry");
+    };
+
+    assert_eq!([0xab; 16], code_view.guid);
+    assert_eq!(1, code_view.age);
+    assert_eq!(b"empty.pdb", code_view.path.to_bytes());
+
+    let id = entry.pdb_id().unwrap();
+
+    assert_eq!([0xab; 16], id[..16]);
+    assert_eq!(0x1234u32.to_le_bytes(), id[16..]);
+  }
+
+  #[test]
+  fn test_decode_pdb_checksum() {
+    let entry = DebugDirectoryEntry {
+      directory: DebugDirectory {
+        characteristics: 0,
+        time_date_stamp: 0,
+        major_version: 1,
+        minor_version: 0,
+        kind: IMAGE_DEBUG_TYPE_PDB_CHECKSUM,
+        size_of_data: 11,
+        address_of_raw_data: 0,
+        pointer_to_raw_data: 0,
+      },
+      data: b"SHA256\0\x01\x02\x03\x04",
+    };
+
+    let Some(DebugData::PdbChecksum(checksum)) = entry.decode() else {
+      panic!("expected PdbChecksum entry");
+    };
+
+    assert_eq!(b"SHA256", checksum.algorithm.to_bytes());
+    assert_eq!(&[1, 2, 3, 4], checksum.checksum);
+  }
+
+  #[cfg(feature = "deflate")]
+  #[test]
+  fn test_decompress_embedded_portable_pdb() {
+    use crate::bytes::FromBytes;
+
+    let pdb = b"BSJB not really a pdb";
+    let mut data = EMBEDDED_PORTABLE_PDB_MAGIC.to_le_bytes().to_vec();
+
+    data.extend_from_slice(&(pdb.len() as u32).to_le_bytes());
+    data.extend(miniz_oxide::deflate::compress_to_vec(pdb, 6));
+
+    let Some(embedded) = EmbeddedPortablePdb::from_bytes(&data, &mut 0, ()) else {
+      panic!("expected embedded portable pdb");
+    };
+
+    assert_eq!(&pdb[..], &embedded.decompress().unwrap()[..]);
+  }
+}
@@ -1,3 +1,6 @@
+#[cfg(feature = "crypto")]
+#[doc(inline)]
+pub use crypto::*;
 #[cfg(feature = "read")]
 #[doc(inline)]
 pub use read::*;
@@ -8,22 +11,142 @@ pub use write::*;
 #[cfg(feature = "read")]
 #[doc(hidden)]
 mod read {
+  use core::fmt;
+
+  /// An error that occurred while reading a managed PE file.
   #[derive(Debug)]
+  #[non_exhaustive]
   pub enum ReadManagedPeError {
     /// The pe file was not valid.
-    InvalidPeFile,
+    #[cfg(feature = "object")]
+    InvalidPeFile(object::Error),
+    /// The file was not a PE32 or PE32+ file.
+    NotPeFile,
     /// The CLI header was not found.
     MissingCliHeader,
+    /// The CLI data directory points to data outside the file, or to a truncated CLI header.
+    InvalidCliHeader {
+      /// The relative virtual address of the CLI header.
+      virtual_address: u32,
+      /// The size of the CLI header in bytes.
+      size: u32,
+    },
+  }
+
+  impl fmt::Display for ReadManagedPeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+      match self {
+        #[cfg(feature = "object")]
+        Self::InvalidPeFile(err) => write!(f, "Invalid PE file: {err}"),
+        Self::NotPeFile => write!(f, "Not a PE32 or PE32+ file"),
+        Self::MissingCliHeader => write!(f, "The CLI header was not found"),
+        Self::InvalidCliHeader {
+          virtual_address,
+          size,
+        } => write!(
+          f,
+          "The CLI header at `{virtual_address:#x}` of size `{size:#x}` could not be read"
+        ),
+      }
+    }
+  }
+
+  #[cfg(any(feature = "std", test))]
+  impl std::error::Error for ReadManagedPeError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+      match self {
+        // `object::Error` only implements `std::error::Error` with the `std` feature of `object`.
+        #[cfg(all(feature = "object", feature = "std"))]
+        Self::InvalidPeFile(err) => Some(err),
+        _ => None,
+      }
+    }
   }
 
   #[cfg(feature = "object")]
   impl From<object::Error> for ReadManagedPeError {
-    fn from(_: object::Error) -> Self {
-      Self::InvalidPeFile
+    fn from(err: object::Error) -> Self {
+      Self::InvalidPeFile(err)
     }
   }
 }
 
 #[cfg(feature = "write")]
 #[doc(hidden)]
-mod write {}
+mod write {
+  use core::fmt;
+
+  /// An error that occurred while writing a managed PE file.
+  #[derive(Debug)]
+  #[non_exhaustive]
+  pub enum WriteManagedPeError {
+    /// The headers have no room for another section header.
+    NoRoomForSection,
+    /// The metadata does not fit in a section of the file.
+    MetadataTooLarge,
+    /// The CLI data directory points to data outside the file.
+    MissingCliHeader,
+    /// The metadata directory of the CLI header points to data outside the file.
+    MissingMetadata {
+      /// The relative virtual address of the metadata.
+      virtual_address: u32,
+      /// The size of the metadata in bytes.
+      size: u32,
+    },
+  }
+
+  impl fmt::Display for WriteManagedPeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+      match self {
+        Self::NoRoomForSection => write!(f, "The headers have no room for another section"),
+        Self::MetadataTooLarge => write!(f, "The metadata does not fit in a section"),
+        Self::MissingCliHeader => write!(f, "The CLI header is outside the file"),
+        Self::MissingMetadata {
+          virtual_address,
+          size,
+        } => write!(
+          f,
+          "The metadata at `{virtual_address:#x}` of size `{size:#x}` is outside the file"
+        ),
+      }
+    }
+  }
+
+  impl std::error::Error for WriteManagedPeError {}
+}
+
+#[cfg(feature = "crypto")]
+#[doc(hidden)]
+mod crypto {
+  use crate::metadata::streams::tables::flags::AssemblyHashAlgorithm;
+  use core::fmt;
+
+  /// An error that occurred while verifying a strong name signature.
+  #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+  #[non_exhaustive]
+  pub enum StrongNameError {
+    /// The file has no strong name signature.
+    NotSigned,
+    /// The public key is not an RSA public key, such as the ECMA standard public key.
+    InvalidPublicKey,
+    /// The public key uses a hash algorithm that is not supported.
+    UnsupportedHashAlgorithm(AssemblyHashAlgorithm),
+    /// The signature does not match the hash of the file.
+    BadSignature,
+  }
+
+  impl fmt::Display for StrongNameError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+      match self {
+        Self::NotSigned => write!(f, "The file has no strong name signature"),
+        Self::InvalidPublicKey => write!(f, "The public key is not an RSA public key"),
+        Self::UnsupportedHashAlgorithm(algorithm) => {
+          write!(f, "Unsupported hash algorithm `{:#x}`", algorithm.bits())
+        }
+        Self::BadSignature => write!(f, "The strong name signature does not match"),
+      }
+    }
+  }
+
+  impl std::error::Error for StrongNameError {}
+}
@@ -1,10 +1,3 @@
-#[cfg(feature = "read")]
-#[doc(inline)]
-pub use read::*;
-#[cfg(feature = "write")]
-#[doc(inline)]
-pub use write::*;
-
 /// The [CliHeader] contains all of the runtime-specific data entries and other information. The
 /// header should be placed in a read-only, sharable section of the image.
 #[derive(Copy, Clone)]
@@ -37,7 +30,7 @@ pub struct CliHeader {
 }
 
 /// Represents a data directory.
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Default, Clone, Copy)]
 #[repr(C)]
 pub struct DataDirectory {
   /// The relative virtual address of the table.
@@ -49,7 +42,7 @@ pub struct DataDirectory {
 bitflags::bitflags! {
   /// The following [CliRuntimeFlags] describe this runtime image and are used by the loader. All
   /// unspecified bits should be zero.
-  #[derive(Copy, Clone)]
+  #[derive(Debug, Copy, Clone)]
   pub struct CliRuntimeFlags : u32 {
     const COMIMAGE_FLAGS_ILONLY = 0x00000001;
     /// Image can only be loaded into a 32-bit process, for instance if there are 32-bit vtable
@@ -106,4 +99,38 @@ mod read {
 
 #[cfg(feature = "write")]
 #[doc(hidden)]
-mod write {}
+mod write {
+  use super::{CliHeader, CliRuntimeFlags, DataDirectory};
+  use crate::bytes::{ByteVecExt, ToBytes};
+  use std::vec::Vec;
+
+  impl ToBytes for CliHeader {
+    fn to_bytes(&self, buf: &mut Vec<u8>, _: ()) {
+      buf.write(self.cb);
+      buf.write(self.major_runtime_version);
+      buf.write(self.minor_runtime_version);
+      buf.write(self.metadata);
+      buf.write(self.flags);
+      buf.write(self.entry_point_token);
+      buf.write(self.resources);
+      buf.write(self.strong_name_signature);
+      buf.write(self.code_manager_table);
+      buf.write(self.vtable_fixups);
+      buf.write(self.export_address_table_jumps);
+      buf.write(self.managed_native_header);
+    }
+  }
+
+  impl ToBytes for DataDirectory {
+    fn to_bytes(&self, buf: &mut Vec<u8>, _: ()) {
+      buf.write(self.virtual_address);
+      buf.write(self.size);
+    }
+  }
+
+  impl ToBytes for CliRuntimeFlags {
+    fn to_bytes(&self, buf: &mut Vec<u8>, _: ()) {
+      buf.write(self.bits());
+    }
+  }
+}
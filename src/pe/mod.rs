@@ -2,8 +2,11 @@
 #[cfg(feature = "object")]
 pub use self::object::*;
 
+pub mod body;
+pub mod debug;
 pub mod errors;
 pub mod headers;
+pub mod strong_name;
 
 #[doc(hidden)]
 #[cfg(feature = "object")]
@@ -13,10 +13,13 @@ mod read {
   use crate::bytes::ByteSliceExt;
   use crate::metadata::errors::MetadataReadError;
   use crate::metadata::MetadataReader;
+  use crate::pe::body::MethodBody;
+  use crate::pe::debug::{DebugDirectoryIter, IMAGE_DIRECTORY_ENTRY_DEBUG};
   use crate::pe::errors::ReadManagedPeError;
   use crate::pe::headers::CliHeader;
-  use object::pe::{ImageNtHeaders32, ImageNtHeaders64};
-  use object::read::pe::{ImageNtHeaders, PeFile};
+  use object::pe::{ImageNtHeaders32, ImageNtHeaders64, IMAGE_DIRECTORY_ENTRY_SECURITY};
+  use object::read::pe::{ImageNtHeaders, ImageOptionalHeader, PeFile};
+  use object::{FileKind, LittleEndian as LE};
 
   pub type ManagedPeFile32<'a> = ManagedPeFile<'a, ImageNtHeaders32>;
   pub type ManagedPeFile64<'a> = ManagedPeFile<'a, ImageNtHeaders64>;
@@ -26,8 +29,8 @@ mod read {
   where
     Pe: ImageNtHeaders,
   {
-    pe: PeFile<'a, Pe, &'a [u8]>,
-    header: CliHeader,
+    pub(super) pe: PeFile<'a, Pe, &'a [u8]>,
+    pub(super) header: CliHeader,
   }
 
   impl<'a, Pe> ManagedPeFile<'a, Pe>
@@ -44,35 +47,1413 @@ mod read {
       let directory = pe
         .data_directories()
         .get(14)
+        .filter(|directory| directory.virtual_address.get(LE) != 0)
         .ok_or(ReadManagedPeError::MissingCliHeader)?;
 
-      let data = directory
+      let header = directory
         .data(pe.data(), &pe.section_table())
-        .map_err(|_| ReadManagedPeError::MissingCliHeader)?;
-
-      let header = data
-        .read::<CliHeader>(&mut 0)
-        .ok_or(ReadManagedPeError::MissingCliHeader)?;
+        .ok()
+        .and_then(|data| data.read::<CliHeader>(&mut 0))
+        .ok_or(ReadManagedPeError::InvalidCliHeader {
+          virtual_address: directory.virtual_address.get(LE),
+          size: directory.size.get(LE),
+        })?;
 
       Ok(Self { pe, header })
     }
 
+    /// Returns the [CliHeader].
+    pub fn cli_header(&self) -> &CliHeader {
+      &self.header
+    }
+
+    /// Returns the data at the given relative virtual address up to the end of its section.
+    pub fn data_at(&self, rva: u32) -> Option<&'a [u8]> {
+      self.pe.section_table().pe_data_at(self.pe.data(), rva)
+    }
+
     /// Returns the [MetadataReader] for this PE file.
-    pub fn metadata(&self) -> Result<MetadataReader, MetadataReadError> {
+    pub fn metadata(&self) -> Result<MetadataReader<'a>, MetadataReadError> {
       let metadata = self.header.metadata;
       let data = self
-        .pe
-        .section_table()
-        .pe_data_at(self.pe.data(), metadata.virtual_address)
-        .ok_or(MetadataReadError::NotEnough)?
-        .get(..metadata.size as _)
-        .ok_or(MetadataReadError::NotEnough)?;
+        .data_at(metadata.virtual_address)
+        .and_then(|data| data.get(..metadata.size as _))
+        .ok_or(MetadataReadError::MissingData {
+          virtual_address: metadata.virtual_address,
+          size: metadata.size,
+        })?;
 
       MetadataReader::from_bytes(data)
     }
+
+    /// Returns the [MethodBody] at the given relative virtual address.
+    ///
+    /// The address is found in the `rva` column of a `MethodDef` row.
+    pub fn method_body(&self, rva: u32) -> Option<MethodBody<'a>> {
+      self.data_at(rva)?.read(&mut 0)
+    }
+
+    /// Returns the managed resource at the given offset into the CLI resources.
+    ///
+    /// The offset is found in the `offset` column of a `ManifestResource` row whose
+    /// implementation is null.
+    pub fn resource(&self, offset: u32) -> Option<&'a [u8]> {
+      let resources = self.header.resources;
+      let data = self
+        .data_at(resources.virtual_address)?
+        .get(..resources.size as _)?;
+
+      let offset = &mut (offset as usize);
+      let len = data.read::<u32>(offset)? as usize;
+
+      data.read_with(offset, len)
+    }
+
+    /// Returns an iterator over the entries of the debug directory.
+    ///
+    /// The iterator is empty if the PE file has no debug directory.
+    pub fn debug_directory(&self) -> DebugDirectoryIter<'a> {
+      let directory = self
+        .pe
+        .data_directories()
+        .get(IMAGE_DIRECTORY_ENTRY_DEBUG)
+        .and_then(|directory| {
+          directory
+            .data(self.pe.data(), &self.pe.section_table())
+            .ok()
+        })
+        .unwrap_or_default();
+
+      DebugDirectoryIter::new(directory, self.pe.data())
+    }
+
+    /// Returns the strong name signature, or [None] if the file has no strong name signature.
+    pub fn strong_name_signature(&self) -> Option<&'a [u8]> {
+      let signature = self.header.strong_name_signature;
+
+      if signature.virtual_address == 0 || signature.size == 0 {
+        return None;
+      }
+
+      self
+        .data_at(signature.virtual_address)?
+        .get(..signature.size as _)
+    }
+
+    /// Calls the given function with each part of the file covered by the strong name hash, in
+    /// order.
+    ///
+    /// The hash covers the headers and the data of each section, with the checksum and the
+    /// certificate table entry zeroed and the strong name signature itself left out.  Certificates
+    /// are appended after the sections, so they are left out as well.
+    pub fn strong_name_hash_data(&self, mut update: impl FnMut(&[u8])) {
+      let data = self.pe.data();
+      let file_header = self.pe.nt_headers().file_header();
+      let header = self.pe.nt_headers().optional_header();
+      let optional_header = self.pe.dos_header().nt_headers_offset() as usize + 24;
+      let optional_header_size = file_header.size_of_optional_header.get(LE) as usize;
+      let headers_end = optional_header
+        + optional_header_size
+        + 40 * file_header.number_of_sections.get(LE) as usize;
+
+      let checksum = optional_header + 64;
+      let directories = optional_header + core::mem::size_of::<Pe::ImageOptionalHeader>();
+      let security = if IMAGE_DIRECTORY_ENTRY_SECURITY < header.number_of_rva_and_sizes() as usize {
+        directories + 8 * IMAGE_DIRECTORY_ENTRY_SECURITY
+      } else {
+        headers_end
+      };
+
+      update(&data[..checksum]);
+      update(&[0; 4]);
+      update(&data[checksum + 4..security]);
+
+      if security < headers_end {
+        update(&[0; 8]);
+        update(&data[security + 8..headers_end]);
+      }
+
+      let sections = self.pe.section_table();
+      let signature = self.header.strong_name_signature;
+      let signature = sections
+        .pe_file_range_at(signature.virtual_address)
+        .filter(|_| signature.virtual_address != 0)
+        .map(|(offset, _)| offset as usize..offset as usize + signature.size as usize)
+        .unwrap_or_default();
+
+      for section in sections.iter() {
+        let start = section.pointer_to_raw_data.get(LE) as usize;
+        let end = (start + section.size_of_raw_data.get(LE) as usize).min(data.len());
+
+        if start >= end {
+          continue;
+        }
+
+        if signature.start >= start && signature.end <= end && !signature.is_empty() {
+          update(&data[start..signature.start]);
+          update(&data[signature.end..end]);
+        } else {
+          update(&data[start..end]);
+        }
+      }
+    }
+  }
+
+  /// A [ManagedPeFile] of either bitness.
+  ///
+  /// Provides the same API as [ManagedPeFile] for both PE32 and PE32+ files.
+  pub enum ManagedPe<'a> {
+    /// A PE32 file.
+    Pe32(ManagedPeFile32<'a>),
+    /// A PE32+ file.
+    Pe64(ManagedPeFile64<'a>),
+  }
+
+  impl<'a> ManagedPe<'a> {
+    /// Returns the [ManagedPe] from the given data.
+    ///
+    /// Inspects the optional header magic to determine whether the file is a PE32 or PE32+ file.
+    pub fn parse(data: &'a [u8]) -> Result<Self, ReadManagedPeError> {
+      match FileKind::parse(data)? {
+        FileKind::Pe32 => Ok(Self::Pe32(ManagedPeFile32::from_data(data)?)),
+        FileKind::Pe64 => Ok(Self::Pe64(ManagedPeFile64::from_data(data)?)),
+        _ => Err(ReadManagedPeError::NotPeFile),
+      }
+    }
+
+    /// Returns `true` if this is a PE32+ file.
+    pub fn is_64(&self) -> bool {
+      matches!(self, Self::Pe64(_))
+    }
+
+    /// Returns the [CliHeader].
+    pub fn cli_header(&self) -> &CliHeader {
+      match self {
+        Self::Pe32(pe) => pe.cli_header(),
+        Self::Pe64(pe) => pe.cli_header(),
+      }
+    }
+
+    /// Returns the data at the given relative virtual address up to the end of its section.
+    pub fn data_at(&self, rva: u32) -> Option<&'a [u8]> {
+      match self {
+        Self::Pe32(pe) => pe.data_at(rva),
+        Self::Pe64(pe) => pe.data_at(rva),
+      }
+    }
+
+    /// Returns the [MetadataReader] for this PE file.
+    pub fn metadata(&self) -> Result<MetadataReader<'a>, MetadataReadError> {
+      match self {
+        Self::Pe32(pe) => pe.metadata(),
+        Self::Pe64(pe) => pe.metadata(),
+      }
+    }
+
+    /// Returns the [MethodBody] at the given relative virtual address.
+    pub fn method_body(&self, rva: u32) -> Option<MethodBody<'a>> {
+      match self {
+        Self::Pe32(pe) => pe.method_body(rva),
+        Self::Pe64(pe) => pe.method_body(rva),
+      }
+    }
+
+    /// Returns the managed resource at the given offset into the CLI resources.
+    pub fn resource(&self, offset: u32) -> Option<&'a [u8]> {
+      match self {
+        Self::Pe32(pe) => pe.resource(offset),
+        Self::Pe64(pe) => pe.resource(offset),
+      }
+    }
+
+    /// Returns an iterator over the entries of the debug directory.
+    pub fn debug_directory(&self) -> DebugDirectoryIter<'a> {
+      match self {
+        Self::Pe32(pe) => pe.debug_directory(),
+        Self::Pe64(pe) => pe.debug_directory(),
+      }
+    }
+
+    /// Returns the strong name signature, or [None] if the file has no strong name signature.
+    pub fn strong_name_signature(&self) -> Option<&'a [u8]> {
+      match self {
+        Self::Pe32(pe) => pe.strong_name_signature(),
+        Self::Pe64(pe) => pe.strong_name_signature(),
+      }
+    }
+
+    /// Calls the given function with each part of the file covered by the strong name hash.
+    ///
+    /// See [ManagedPeFile::strong_name_hash_data].
+    pub fn strong_name_hash_data(&self, update: impl FnMut(&[u8])) {
+      match self {
+        Self::Pe32(pe) => pe.strong_name_hash_data(update),
+        Self::Pe64(pe) => pe.strong_name_hash_data(update),
+      }
+    }
   }
 }
 
 #[cfg(feature = "write")]
 #[doc(hidden)]
-mod write {}
+mod write {
+  use super::{ManagedPe, ManagedPeFile};
+  use crate::bytes::{ByteSliceExt, ByteVecExt};
+  use crate::pe::errors::WriteManagedPeError;
+  use crate::pe::headers::{CliHeader, CliRuntimeFlags, DataDirectory};
+  use object::pe::*;
+  use object::read::pe::{ImageNtHeaders, ImageOptionalHeader};
+  use object::LittleEndian as LE;
+  use std::vec::Vec;
+
+  const SECTION_ALIGNMENT: u32 = 0x2000;
+  const FILE_ALIGNMENT: u32 = 0x200;
+
+  /// The MS-DOS header and stub program that prints `This program cannot be run in DOS mode.`
+  const DOS_HEADER: [u8; 0x80] = [
+    0x4d, 0x5a, 0x90, 0x00, 0x03, 0x00, 0x00, 0x00, 0x04, 0x00, 0x00, 0x00, 0xff, 0xff, 0x00, 0x00,
+    0xb8, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x40, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+    0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+    0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x80, 0x00, 0x00, 0x00,
+    0x0e, 0x1f, 0xba, 0x0e, 0x00, 0xb4, 0x09, 0xcd, 0x21, 0xb8, 0x01, 0x4c, 0xcd, 0x21, 0x54, 0x68,
+    0x69, 0x73, 0x20, 0x70, 0x72, 0x6f, 0x67, 0x72, 0x61, 0x6d, 0x20, 0x63, 0x61, 0x6e, 0x6e, 0x6f,
+    0x74, 0x20, 0x62, 0x65, 0x20, 0x72, 0x75, 0x6e, 0x20, 0x69, 0x6e, 0x20, 0x44, 0x4f, 0x53, 0x20,
+    0x6d, 0x6f, 0x64, 0x65, 0x2e, 0x0d, 0x0d, 0x0a, 0x24, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+  ];
+
+  impl<'a, Pe> ManagedPeFile<'a, Pe>
+  where
+    Pe: ImageNtHeaders,
+  {
+    /// Returns a copy of the file with its metadata replaced by the given metadata.
+    ///
+    /// Everything but the metadata and the headers pointing to it is kept as it is.  The metadata
+    /// is written in place if it is not larger than the existing metadata, otherwise it is written
+    /// to a new `.cormeta` section at the end of the file.  A strong name signature is not updated.
+    ///
+    /// Returns [WriteManagedPeError::MissingMetadata] if the metadata would be written in place but
+    /// the existing metadata runs past the end of its section or of the file.
+    pub fn with_metadata(&self, metadata: &[u8]) -> Result<Vec<u8>, WriteManagedPeError> {
+      let data = self.pe.data();
+      let sections = self.pe.section_table();
+      let cli_header = self
+        .pe
+        .data_directory(IMAGE_DIRECTORY_ENTRY_COM_DESCRIPTOR)
+        .and_then(|dir| sections.pe_file_range_at(dir.virtual_address.get(LE)))
+        .map(|(offset, _)| offset as usize)
+        .filter(|&offset| offset + 72 <= data.len())
+        .ok_or(WriteManagedPeError::MissingCliHeader)?;
+
+      let mut buf = data.to_vec();
+      let old = self.header.metadata;
+
+      if metadata.len() <= old.size as usize {
+        // The size of the metadata is never checked when reading it, so it may run past the end of
+        // its section or of the file.
+        let offset = sections
+          .pe_file_range_at(old.virtual_address)
+          .map(|(offset, size)| (offset as usize, size as usize))
+          .filter(|&(offset, size)| {
+            old.size as usize <= size.min(data.len().saturating_sub(offset))
+          })
+          .map(|(offset, _)| offset)
+          .ok_or(WriteManagedPeError::MissingMetadata {
+            virtual_address: old.virtual_address,
+            size: old.size,
+          })?;
+
+        buf[offset..offset + old.size as usize].fill(0);
+        buf[offset..offset + metadata.len()].copy_from_slice(metadata);
+        put(&mut buf, cli_header + 12, metadata.len() as u32);
+
+        return Ok(buf);
+      }
+
+      let nt_headers = self.pe.dos_header().nt_headers_offset() as usize;
+      let optional_header = nt_headers + 24;
+      let file_header = self.pe.nt_headers().file_header();
+      let section_table = optional_header + file_header.size_of_optional_header.get(LE) as usize;
+      let count = file_header.number_of_sections.get(LE) as usize;
+      let section = section_table + 40 * count;
+
+      let header = self.pe.nt_headers().optional_header();
+      let section_alignment = header.section_alignment();
+      let file_alignment = header.file_alignment();
+      let headers_end = sections
+        .iter()
+        .map(|s| s.pointer_to_raw_data.get(LE))
+        .filter(|&offset| offset != 0)
+        .fold(header.size_of_headers(), u32::min);
+
+      if section + 40 > headers_end as usize || buf[section..section + 40].iter().any(|&b| b != 0) {
+        return Err(WriteManagedPeError::NoRoomForSection);
+      }
+
+      let rva = sections
+        .iter()
+        .map(|s| {
+          let size = s.virtual_size.get(LE).max(s.size_of_raw_data.get(LE));
+
+          s.virtual_address.get(LE) + size
+        })
+        .fold(header.size_of_headers(), u32::max);
+      let rva = align(rva, section_alignment);
+      let size =
+        u32::try_from(metadata.len()).map_err(|_| WriteManagedPeError::MetadataTooLarge)?;
+      let raw_size = align(size, file_alignment);
+      let offset = align(buf.len() as u32, file_alignment);
+
+      buf.resize(offset as usize, 0);
+      buf.extend_from_slice(metadata);
+      buf.resize((offset + raw_size) as usize, 0);
+
+      // IMAGE_SECTION_HEADER
+      buf[section..section + 8].copy_from_slice(b".cormeta");
+      put(&mut buf, section + 8, size);
+      put(&mut buf, section + 12, rva);
+      put(&mut buf, section + 16, raw_size);
+      put(&mut buf, section + 20, offset);
+      put(
+        &mut buf,
+        section + 36,
+        IMAGE_SCN_CNT_INITIALIZED_DATA | IMAGE_SCN_MEM_READ,
+      );
+
+      buf[nt_headers + 6..nt_headers + 8].copy_from_slice(&(count as u16 + 1).to_le_bytes());
+      put(
+        &mut buf,
+        optional_header + 8,
+        header.size_of_initialized_data() + raw_size,
+      );
+      put(
+        &mut buf,
+        optional_header + 56,
+        align(rva + size, section_alignment),
+      );
+
+      put(&mut buf, cli_header + 8, rva);
+      put(&mut buf, cli_header + 12, size);
+
+      Ok(buf)
+    }
+  }
+
+  impl<'a> ManagedPe<'a> {
+    /// Returns a copy of the file with its metadata replaced by the given metadata.
+    ///
+    /// See [ManagedPeFile::with_metadata].
+    pub fn with_metadata(&self, metadata: &[u8]) -> Result<Vec<u8>, WriteManagedPeError> {
+      match self {
+        Self::Pe32(pe) => pe.with_metadata(metadata),
+        Self::Pe64(pe) => pe.with_metadata(metadata),
+      }
+    }
+  }
+
+  /// Builds a PE file containing CIL metadata that the CLR and Mono can load.
+  ///
+  /// The inverse of [ManagedPe](super::ManagedPe).  Method bodies and mapped field data are added
+  /// first because the `MethodDef` and `FieldRva` tables refer to their RVAs, which stay the same
+  /// no matter what is added afterwards.  The metadata is given to [ManagedPeBuilder::finish], which
+  /// lays out the following sections.
+  ///
+  /// * `.text` containing the [CliHeader], method bodies, field data, metadata, managed resources,
+  ///   the strong name signature and, for x86 files, the import address table, the import table
+  ///   for `mscoree.dll` and the native entry point stub.
+  /// * `.rsrc` containing the Win32 resources, if there are any.
+  /// * `.reloc` containing the base relocation of the entry point stub, for x86 files.
+  #[derive(Debug, Clone)]
+  pub struct ManagedPeBuilder {
+    machine: u16,
+    dll: bool,
+    subsystem: u16,
+    time_date_stamp: u32,
+    image_base: Option<u64>,
+    flags: CliRuntimeFlags,
+    entry_point_token: u32,
+    code: Vec<u8>,
+    resources: Vec<u8>,
+    strong_name_signature_size: u32,
+    win32_resources: Vec<u8>,
+    win32_resource_entries: Vec<usize>,
+  }
+
+  impl ManagedPeBuilder {
+    /// Creates a new [ManagedPeBuilder] for a dll targeting the given `IMAGE_FILE_MACHINE_*`.
+    ///
+    /// `IMAGE_FILE_MACHINE_AMD64`, `IMAGE_FILE_MACHINE_ARM64` and `IMAGE_FILE_MACHINE_IA64`
+    /// produce a PE32+ file, any other machine produces a PE32 file.  Platform neutral assemblies
+    /// use `IMAGE_FILE_MACHINE_I386` with the default [CliRuntimeFlags::COMIMAGE_FLAGS_ILONLY].
+    pub fn new(machine: u16) -> Self {
+      Self {
+        machine,
+        dll: true,
+        subsystem: IMAGE_SUBSYSTEM_WINDOWS_CUI,
+        time_date_stamp: 0,
+        image_base: None,
+        flags: CliRuntimeFlags::COMIMAGE_FLAGS_ILONLY,
+        entry_point_token: 0,
+        code: Vec::new(),
+        resources: Vec::new(),
+        strong_name_signature_size: 0,
+        win32_resources: Vec::new(),
+        win32_resource_entries: Vec::new(),
+      }
+    }
+
+    /// Returns `true` if a PE32+ file is built.
+    pub fn is_64(&self) -> bool {
+      matches!(
+        self.machine,
+        IMAGE_FILE_MACHINE_AMD64 | IMAGE_FILE_MACHINE_ARM64 | IMAGE_FILE_MACHINE_IA64
+      )
+    }
+
+    /// Returns `true` if the file has a native entry point stub, which is only the case for
+    /// `IMAGE_FILE_MACHINE_I386`.
+    fn has_entry_point_stub(&self) -> bool {
+      self.machine == IMAGE_FILE_MACHINE_I386
+    }
+
+    /// Sets whether a dll or an executable is built, a dll by default.
+    pub fn set_dll(&mut self, dll: bool) {
+      self.dll = dll;
+    }
+
+    /// Sets the `IMAGE_SUBSYSTEM_*` of the file, `IMAGE_SUBSYSTEM_WINDOWS_CUI` by default.
+    pub fn set_subsystem(&mut self, subsystem: u16) {
+      self.subsystem = subsystem;
+    }
+
+    /// Sets the time and date the file was created, 0 by default.
+    pub fn set_time_date_stamp(&mut self, time_date_stamp: u32) {
+      self.time_date_stamp = time_date_stamp;
+    }
+
+    /// Sets the preferred address of the image when loaded.
+    ///
+    /// Defaults to the address the C# compiler uses for the kind of file.
+    pub fn set_image_base(&mut self, image_base: u64) {
+      self.image_base = Some(image_base);
+    }
+
+    /// Sets the [CliRuntimeFlags], [CliRuntimeFlags::COMIMAGE_FLAGS_ILONLY] by default.
+    pub fn set_flags(&mut self, flags: CliRuntimeFlags) {
+      self.flags = flags;
+    }
+
+    /// Sets the `MethodDef` or `File` token of the entry point.
+    pub fn set_entry_point_token(&mut self, token: u32) {
+      self.entry_point_token = token;
+    }
+
+    /// Reserves the given number of zeroed bytes for the strong name signature.
+    pub fn set_strong_name_signature_size(&mut self, size: u32) {
+      self.strong_name_signature_size = size;
+    }
+
+    /// Sets the contents of the `.rsrc` section.
+    ///
+    /// The `OffsetToData` of every resource data entry is relative to the start of the given data
+    /// and is relocated once the RVA of the section is known.  Returns [None] if the resource
+    /// directory tree is malformed or deeper than the 3 levels of type, name and language.
+    pub fn set_win32_resources(&mut self, data: Vec<u8>) -> Option<()> {
+      let mut entries = Vec::new();
+
+      if !data.is_empty() {
+        resource_data_entries(&data, 0, 0, &mut entries)?;
+      }
+
+      self.win32_resources = data;
+      self.win32_resource_entries = entries;
+
+      Some(())
+    }
+
+    /// Adds a method body and returns its RVA.
+    ///
+    /// Fat method bodies are aligned to a 4 byte boundary.
+    pub fn add_method_body(&mut self, body: &[u8]) -> u32 {
+      if body.first().is_some_and(|first| first & 0x3 != 0x2) {
+        self.align_code(4);
+      }
+
+      self.add_code(body)
+    }
+
+    /// Adds the initial data of a field with an RVA and returns its RVA.
+    ///
+    /// The data is aligned to an 8 byte boundary.
+    pub fn add_field_data(&mut self, data: &[u8]) -> u32 {
+      self.align_code(8);
+      self.add_code(data)
+    }
+
+    /// Adds a managed resource and returns its offset.
+    ///
+    /// The offset is stored in the `offset` column of the `ManifestResource` row.
+    pub fn add_resource(&mut self, data: &[u8]) -> u32 {
+      self.resources.resize((self.resources.len() + 7) & !7, 0);
+
+      let offset = self.resources.len() as u32;
+
+      self.resources.write(data.len() as u32);
+      self.resources.extend_from_slice(data);
+
+      offset
+    }
+
+    /// Returns the RVA of the first method body.
+    fn code_rva(&self) -> u32 {
+      let iat = if self.has_entry_point_stub() { 8 } else { 0 };
+
+      SECTION_ALIGNMENT + iat + 72
+    }
+
+    fn align_code(&mut self, alignment: u32) {
+      let rva = self.code_rva() + self.code.len() as u32;
+      let pad = align(rva, alignment) - rva;
+
+      self.code.resize(self.code.len() + pad as usize, 0);
+    }
+
+    fn add_code(&mut self, data: &[u8]) -> u32 {
+      let rva = self.code_rva() + self.code.len() as u32;
+
+      self.code.extend_from_slice(data);
+      rva
+    }
+
+    /// Returns the bytes of the PE file containing the given metadata.
+    pub fn finish(self, metadata: &[u8]) -> Vec<u8> {
+      let is_64 = self.is_64();
+      let image_base = self.image_base.unwrap_or(match (is_64, self.dll) {
+        (false, false) => 0x0040_0000,
+        (false, true) => 0x1000_0000,
+        (true, false) => 0x0001_4000_0000,
+        (true, true) => 0x0001_8000_0000,
+      });
+
+      // The import address table and the CLI header are written once everything is laid out.
+      let text_rva = SECTION_ALIGNMENT;
+      let cli_header_rva = self.code_rva() - 72;
+      let mut text = vec![0; (self.code_rva() - text_rva) as usize];
+
+      text.extend_from_slice(&self.code);
+
+      let rva = |text: &Vec<u8>| text_rva + text.len() as u32;
+
+      pad(&mut text, 4);
+
+      let metadata_rva = rva(&text);
+
+      text.extend_from_slice(metadata);
+
+      let metadata = DataDirectory {
+        virtual_address: metadata_rva,
+        size: metadata.len() as u32,
+      };
+      let mut resources = DataDirectory::default();
+
+      if !self.resources.is_empty() {
+        pad(&mut text, 8);
+        resources.virtual_address = rva(&text);
+        resources.size = self.resources.len() as u32;
+        text.extend_from_slice(&self.resources);
+      }
+
+      let mut strong_name_signature = DataDirectory::default();
+
+      if self.strong_name_signature_size != 0 {
+        pad(&mut text, 4);
+        strong_name_signature.virtual_address = rva(&text);
+        strong_name_signature.size = self.strong_name_signature_size;
+        text.resize(text.len() + self.strong_name_signature_size as usize, 0);
+      }
+
+      // x86 files import `_CorDllMain` or `_CorExeMain` from `mscoree.dll` and jump to it from the
+      // native entry point, which loads the CLR on operating systems that do not know about the CLI
+      // header.  Other machines have no entry point and are only loaded by the CLR itself.
+      let mut import = None;
+
+      if self.has_entry_point_stub() {
+        pad(&mut text, 16);
+
+        let import_rva = rva(&text);
+        let lookup_rva = import_rva + 40;
+        let hint_name_rva = lookup_rva + 8;
+        let name_rva = hint_name_rva + 14;
+
+        text.write(lookup_rva);
+        text.write(0u32);
+        text.write(0u32);
+        text.write(name_rva);
+        text.write(text_rva);
+        text.resize(text.len() + 20, 0);
+        text.write(hint_name_rva);
+        text.write(0u32);
+        text.write(0u16);
+        text.extend_from_slice(match self.dll {
+          true => b"_CorDllMain\0",
+          false => b"_CorExeMain\0",
+        });
+        text.extend_from_slice(b"mscoree.dll\0");
+
+        let import_size = rva(&text) - import_rva;
+
+        // The address operand of `jmp [iat]` is aligned to a 4 byte boundary.
+        pad(&mut text, 4);
+        text.resize(text.len() + 2, 0);
+
+        let entry_rva = rva(&text);
+
+        text.extend_from_slice(&[0xff, 0x25]);
+        text.write((image_base as u32).wrapping_add(text_rva));
+        text[..4].copy_from_slice(&hint_name_rva.to_le_bytes());
+
+        import = Some((import_rva, import_size, entry_rva));
+      }
+
+      let mut header = Vec::new();
+
+      header.write(CliHeader {
+        cb: 72,
+        major_runtime_version: 2,
+        minor_runtime_version: 0,
+        metadata,
+        flags: self.flags,
+        entry_point_token: self.entry_point_token,
+        resources,
+        strong_name_signature,
+        code_manager_table: DataDirectory::default(),
+        vtable_fixups: DataDirectory::default(),
+        export_address_table_jumps: DataDirectory::default(),
+        managed_native_header: DataDirectory::default(),
+      });
+
+      let cli_header_offset = (cli_header_rva - text_rva) as usize;
+
+      text[cli_header_offset..cli_header_offset + 72].copy_from_slice(&header);
+
+      let mut sections = Vec::new();
+      let mut next_rva = align(rva(&text), SECTION_ALIGNMENT);
+
+      sections.push(Section {
+        name: *b".text\0\0\0",
+        rva: text_rva,
+        data: text,
+        characteristics: IMAGE_SCN_CNT_CODE | IMAGE_SCN_MEM_EXECUTE | IMAGE_SCN_MEM_READ,
+      });
+
+      if !self.win32_resources.is_empty() {
+        let mut data = self.win32_resources;
+
+        for &entry in &self.win32_resource_entries {
+          let rva = (&data[..]).read::<u32>(&mut { entry }).unwrap_or_default() + next_rva;
+
+          data[entry..entry + 4].copy_from_slice(&rva.to_le_bytes());
+        }
+
+        sections.push(Section {
+          name: *b".rsrc\0\0\0",
+          rva: next_rva,
+          characteristics: IMAGE_SCN_CNT_INITIALIZED_DATA | IMAGE_SCN_MEM_READ,
+          data,
+        });
+
+        next_rva = align(next_rva + sections[1].data.len() as u32, SECTION_ALIGNMENT);
+      }
+
+      if let Some((_, _, entry_rva)) = import {
+        // A single `IMAGE_REL_BASED_HIGHLOW` relocation of the address operand of the stub, padded
+        // with an `IMAGE_REL_BASED_ABSOLUTE` entry.
+        let target = entry_rva + 2;
+        let mut data = Vec::new();
+
+        data.write(target & !0xfff);
+        data.write(12u32);
+        data.write((IMAGE_REL_BASED_HIGHLOW << 12) | (target & 0xfff) as u16);
+        data.write(0u16);
+
+        sections.push(Section {
+          name: *b".reloc\0\0",
+          rva: next_rva,
+          characteristics: IMAGE_SCN_CNT_INITIALIZED_DATA
+            | IMAGE_SCN_MEM_DISCARDABLE
+            | IMAGE_SCN_MEM_READ,
+          data,
+        });
+      }
+
+      let optional_header_size: u16 = if is_64 { 240 } else { 224 };
+      let headers_size =
+        DOS_HEADER.len() + 24 + optional_header_size as usize + 40 * sections.len();
+      let headers_size = align(headers_size as u32, FILE_ALIGNMENT);
+      let last = sections
+        .last()
+        .map_or(text_rva, |s| s.rva + s.data.len() as u32);
+
+      let mut directories = [DataDirectory::default(); 16];
+
+      if let Some((import_rva, import_size, _)) = import {
+        directories[IMAGE_DIRECTORY_ENTRY_IMPORT] = DataDirectory {
+          virtual_address: import_rva,
+          size: import_size,
+        };
+        directories[IMAGE_DIRECTORY_ENTRY_IAT] = DataDirectory {
+          virtual_address: text_rva,
+          size: 8,
+        };
+      }
+
+      for section in &sections[1..] {
+        let index = match &section.name {
+          b".rsrc\0\0\0" => IMAGE_DIRECTORY_ENTRY_RESOURCE,
+          _ => IMAGE_DIRECTORY_ENTRY_BASERELOC,
+        };
+
+        directories[index] = DataDirectory {
+          virtual_address: section.rva,
+          size: section.data.len() as u32,
+        };
+      }
+
+      directories[IMAGE_DIRECTORY_ENTRY_COM_DESCRIPTOR] = DataDirectory {
+        virtual_address: cli_header_rva,
+        size: 72,
+      };
+
+      let raw_size = |kind: u32| -> u32 {
+        sections
+          .iter()
+          .filter(|s| s.characteristics & kind != 0)
+          .map(|s| align(s.data.len() as u32, FILE_ALIGNMENT))
+          .sum()
+      };
+
+      let mut characteristics = IMAGE_FILE_EXECUTABLE_IMAGE;
+
+      characteristics |= match is_64 {
+        true => IMAGE_FILE_LARGE_ADDRESS_AWARE,
+        false => IMAGE_FILE_32BIT_MACHINE,
+      };
+
+      if self.dll {
+        characteristics |= IMAGE_FILE_DLL;
+      }
+
+      let mut dll_characteristics = IMAGE_DLLCHARACTERISTICS_DYNAMIC_BASE
+        | IMAGE_DLLCHARACTERISTICS_NX_COMPAT
+        | IMAGE_DLLCHARACTERISTICS_NO_SEH
+        | IMAGE_DLLCHARACTERISTICS_TERMINAL_SERVER_AWARE;
+
+      if is_64 {
+        dll_characteristics |= IMAGE_DLLCHARACTERISTICS_HIGH_ENTROPY_VA;
+      }
+
+      let mut buf = Vec::new();
+
+      buf.extend_from_slice(&DOS_HEADER);
+      buf.write(IMAGE_NT_SIGNATURE);
+
+      // IMAGE_FILE_HEADER
+      buf.write(self.machine);
+      buf.write(sections.len() as u16);
+      buf.write(self.time_date_stamp);
+      buf.write(0u32);
+      buf.write(0u32);
+      buf.write(optional_header_size);
+      buf.write(characteristics);
+
+      // IMAGE_OPTIONAL_HEADER
+      buf.write(match is_64 {
+        true => IMAGE_NT_OPTIONAL_HDR64_MAGIC,
+        false => IMAGE_NT_OPTIONAL_HDR32_MAGIC,
+      });
+      buf.write(48u8);
+      buf.write(0u8);
+      buf.write(raw_size(IMAGE_SCN_CNT_CODE));
+      buf.write(raw_size(IMAGE_SCN_CNT_INITIALIZED_DATA));
+      buf.write(0u32);
+      buf.write(import.map_or(0, |(_, _, entry_rva)| entry_rva));
+      buf.write(text_rva);
+
+      match is_64 {
+        true => buf.write(image_base),
+        false => {
+          buf.write(sections.get(1).map_or(0, |s| s.rva));
+          buf.write(image_base as u32);
+        }
+      }
+
+      buf.write(SECTION_ALIGNMENT);
+      buf.write(FILE_ALIGNMENT);
+      buf.write(4u16);
+      buf.write(0u16);
+      buf.write(0u16);
+      buf.write(0u16);
+      buf.write(4u16);
+      buf.write(0u16);
+      buf.write(0u32);
+      buf.write(align(last, SECTION_ALIGNMENT));
+      buf.write(headers_size);
+      buf.write(0u32);
+      buf.write(self.subsystem);
+      buf.write(dll_characteristics);
+
+      for size in [0x10_0000u64, 0x1000, 0x10_0000, 0x1000] {
+        match is_64 {
+          true => buf.write(size),
+          false => buf.write(size as u32),
+        }
+      }
+
+      buf.write(0u32);
+      buf.write(directories.len() as u32);
+
+      for directory in directories {
+        buf.write(directory);
+      }
+
+      // IMAGE_SECTION_HEADER
+      let mut offset = headers_size;
+
+      for section in &sections {
+        let raw_size = align(section.data.len() as u32, FILE_ALIGNMENT);
+
+        buf.extend_from_slice(&section.name);
+        buf.write(section.data.len() as u32);
+        buf.write(section.rva);
+        buf.write(raw_size);
+        buf.write(offset);
+        buf.write(0u32);
+        buf.write(0u32);
+        buf.write(0u16);
+        buf.write(0u16);
+        buf.write(section.characteristics);
+
+        offset += raw_size;
+      }
+
+      buf.resize(headers_size as usize, 0);
+
+      for section in &sections {
+        buf.extend_from_slice(&section.data);
+        pad(&mut buf, FILE_ALIGNMENT);
+      }
+
+      buf
+    }
+  }
+
+  /// A section of the PE file being built.
+  struct Section {
+    name: [u8; 8],
+    rva: u32,
+    data: Vec<u8>,
+    characteristics: u32,
+  }
+
+  /// Collects the offsets of the `OffsetToData` fields of the resource data entries in the
+  /// directory at the given offset.
+  fn resource_data_entries(
+    data: &[u8],
+    offset: usize,
+    depth: usize,
+    entries: &mut Vec<usize>,
+  ) -> Option<()> {
+    if depth == 3 {
+      return None;
+    }
+
+    let named = data.read::<u16>(&mut (offset + 12))? as usize;
+    let ids = data.read::<u16>(&mut (offset + 14))? as usize;
+
+    for i in 0..named + ids {
+      let target = data.read::<u32>(&mut (offset + 16 + 8 * i + 4))?;
+
+      match target & 0x8000_0000 != 0 {
+        true => resource_data_entries(data, (target & 0x7fff_ffff) as usize, depth + 1, entries)?,
+        false => {
+          // IMAGE_RESOURCE_DATA_ENTRY
+          data.get(target as usize..target as usize + 16)?;
+          entries.push(target as usize);
+        }
+      }
+    }
+
+    Some(())
+  }
+
+  fn align(value: u32, alignment: u32) -> u32 {
+    (value + alignment - 1) & !(alignment - 1)
+  }
+
+  /// Overwrites the `u32` at the given offset.
+  fn put(buf: &mut [u8], offset: usize, value: u32) {
+    buf[offset..offset + 4].copy_from_slice(&value.to_le_bytes());
+  }
+
+  fn pad(buf: &mut Vec<u8>, alignment: u32) {
+    buf.resize(align(buf.len() as u32, alignment) as usize, 0);
+  }
+}
+
+#[cfg(feature = "crypto")]
+#[doc(hidden)]
+mod crypto {
+  use super::{ManagedPe, ManagedPeFile};
+  use crate::metadata::streams::tables::flags::AssemblyHashAlgorithm;
+  use crate::pe::errors::StrongNameError;
+  use crate::pe::strong_name::{StrongNameHasher, StrongNamePublicKey};
+  use object::read::pe::ImageNtHeaders;
+  use std::vec::Vec;
+
+  impl<'a, Pe> ManagedPeFile<'a, Pe>
+  where
+    Pe: ImageNtHeaders,
+  {
+    /// Returns the strong name hash of the file using the given hash algorithm.
+    ///
+    /// Returns [None] if the hash algorithm is not supported.
+    pub fn strong_name_hash(&self, algorithm: AssemblyHashAlgorithm) -> Option<Vec<u8>> {
+      let mut hasher = StrongNameHasher::new(algorithm)?;
+
+      self.strong_name_hash_data(|data| hasher.update(data));
+
+      Some(hasher.finish())
+    }
+
+    /// Checks the strong name signature of the file against the given public key.
+    ///
+    /// The public key is the blob of the `public_key` column of the `AssemblyRow`.
+    pub fn verify_strong_name(&self, public_key: &[u8]) -> Result<(), StrongNameError> {
+      let signature = self
+        .strong_name_signature()
+        .ok_or(StrongNameError::NotSigned)?;
+      let public_key = StrongNamePublicKey::parse(public_key)
+        .filter(|key| key.rsa().is_some())
+        .ok_or(StrongNameError::InvalidPublicKey)?;
+      let hash = self.strong_name_hash(public_key.hash_algorithm).ok_or(
+        StrongNameError::UnsupportedHashAlgorithm(public_key.hash_algorithm),
+      )?;
+
+      public_key.verify(&hash, signature)
+    }
+  }
+
+  impl<'a> ManagedPe<'a> {
+    /// Returns the strong name hash of the file using the given hash algorithm.
+    pub fn strong_name_hash(&self, algorithm: AssemblyHashAlgorithm) -> Option<Vec<u8>> {
+      match self {
+        Self::Pe32(pe) => pe.strong_name_hash(algorithm),
+        Self::Pe64(pe) => pe.strong_name_hash(algorithm),
+      }
+    }
+
+    /// Checks the strong name signature of the file against the given public key.
+    ///
+    /// See [ManagedPeFile::verify_strong_name].
+    pub fn verify_strong_name(&self, public_key: &[u8]) -> Result<(), StrongNameError> {
+      match self {
+        Self::Pe32(pe) => pe.verify_strong_name(public_key),
+        Self::Pe64(pe) => pe.verify_strong_name(public_key),
+      }
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  #[test]
+  #[cfg(feature = "write")]
+  fn test_build_pe64() {
+    use super::{ManagedPe, ManagedPeBuilder};
+    use crate::bytes::ByteSliceExt;
+    use crate::metadata::streams::tables::id::RowId;
+    use crate::metadata::streams::tables::rows::ModuleRow;
+    use crate::metadata::MetadataBuilder;
+    use object::pe::{IMAGE_DIRECTORY_ENTRY_RESOURCE, IMAGE_FILE_MACHINE_AMD64};
+    use object::read::pe::PeFile64;
+
+    let mut builder = ManagedPeBuilder::new(IMAGE_FILE_MACHINE_AMD64);
+    // A tiny `ret` followed by a fat body of `nop, ret`.
+    let tiny = builder.add_method_body(&[0x06, 0x2a]);
+    let fat = builder.add_method_body(&[
+      0x03, 0x30, 0x08, 0x00, 0x02, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x2a,
+    ]);
+    let field = builder.add_field_data(&[1, 2, 3]);
+    let first = builder.add_resource(b"first");
+    let second = builder.add_resource(b"second");
+
+    // A single `RT_VERSION` data entry directly below the root directory.
+    let mut rsrc = vec![0; 16];
+
+    rsrc[14] = 1;
+    rsrc.extend_from_slice(&[16, 0, 0, 0, 24, 0, 0, 0]);
+    rsrc.extend_from_slice(&[40, 0, 0, 0, 4, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0]);
+    rsrc.extend_from_slice(&[0xde, 0xad, 0xbe, 0xef]);
+
+    assert_eq!(None, builder.set_win32_resources(vec![0; 15]));
+    assert_eq!(Some(()), builder.set_win32_resources(rsrc));
+
+    builder.set_entry_point_token(0x0600_0001);
+
+    let mut metadata = MetadataBuilder::new();
+    let name = metadata.strings.add("test.dll");
+    let mvid = metadata.guids.add([1; 16]);
+
+    metadata.tables.add_module(ModuleRow::new(
+      0,
+      name,
+      mvid,
+      metadata.guids.add([0; 16]),
+      metadata.guids.add([0; 16]),
+    ));
+
+    let data = builder.finish(&metadata.finish());
+    let pe = ManagedPe::parse(&data).unwrap();
+
+    assert!(pe.is_64());
+    assert_eq!(0x2048, tiny);
+    assert_eq!(0, fat % 4);
+    assert_eq!(0, field % 8);
+    assert_eq!(&[0x2a], pe.method_body(tiny).unwrap().code);
+    assert_eq!(&[0x00, 0x2a], pe.method_body(fat).unwrap().code);
+    assert_eq!(&[1, 2, 3], &pe.data_at(field).unwrap()[..3]);
+    assert_eq!(Some(&b"first"[..]), pe.resource(first));
+    assert_eq!(Some(&b"second"[..]), pe.resource(second));
+    assert_eq!(0x0600_0001, pe.cli_header().entry_point_token);
+
+    let md = pe.metadata().unwrap();
+    let tables = md.streams().find_map(|s| s.ok()?.as_tables()).unwrap();
+    let strings = md.streams().find_map(|s| s.ok()?.as_strings()).unwrap();
+    let module = tables.modules().get(RowId::new(1)).unwrap();
+
+    assert_eq!(Ok("test.dll"), strings.get_str(module.name()));
+
+    let file = PeFile64::parse(&data[..]).unwrap();
+    let rsrc = file
+      .data_directories()
+      .get(IMAGE_DIRECTORY_ENTRY_RESOURCE)
+      .unwrap();
+    let rsrc_rva = rsrc.virtual_address.get(object::LittleEndian);
+    let entry = pe
+      .data_at(rsrc_rva + 24)
+      .unwrap()
+      .read::<u32>(&mut 0)
+      .unwrap();
+
+    assert_eq!(rsrc_rva + 40, entry);
+    assert_eq!(&[0xde, 0xad, 0xbe, 0xef], &pe.data_at(entry).unwrap()[..4]);
+  }
+
+  #[test]
+  #[cfg(feature = "write")]
+  fn test_build_pe32_armnt() {
+    use super::{ManagedPe, ManagedPeBuilder};
+    use crate::metadata::MetadataBuilder;
+    use object::pe::{
+      IMAGE_DIRECTORY_ENTRY_BASERELOC, IMAGE_DIRECTORY_ENTRY_IAT, IMAGE_DIRECTORY_ENTRY_IMPORT,
+      IMAGE_FILE_MACHINE_ARMNT,
+    };
+    use object::read::pe::{ImageNtHeaders, ImageOptionalHeader, PeFile32};
+
+    let mut builder = ManagedPeBuilder::new(IMAGE_FILE_MACHINE_ARMNT);
+    let body = builder.add_method_body(&[0x06, 0x2a]);
+
+    let data = builder.finish(&MetadataBuilder::new().finish());
+    let pe = ManagedPe::parse(&data).unwrap();
+
+    assert!(!pe.is_64());
+    assert_eq!(0x2048, body);
+    assert_eq!(&[0x2a], pe.method_body(body).unwrap().code);
+    assert!(pe.metadata().is_ok());
+
+    // Only x86 files get the `mscoree.dll` import, the native entry point stub and its relocation.
+    let file = PeFile32::parse(&data[..]).unwrap();
+    let directories = file.data_directories();
+
+    assert_eq!(
+      0,
+      file.nt_headers().optional_header().address_of_entry_point()
+    );
+    assert_eq!(
+      vec![&b".text"[..]],
+      file
+        .section_table()
+        .iter()
+        .map(|s| s.raw_name())
+        .collect::<Vec<_>>()
+    );
+
+    for index in [
+      IMAGE_DIRECTORY_ENTRY_IMPORT,
+      IMAGE_DIRECTORY_ENTRY_IAT,
+      IMAGE_DIRECTORY_ENTRY_BASERELOC,
+    ] {
+      assert!(directories.get(index).is_none());
+    }
+  }
+
+  #[test]
+  #[cfg(feature = "write")]
+  fn test_read_out_of_bounds_directories() {
+    use super::{ManagedPe, ManagedPeBuilder};
+    use crate::metadata::errors::MetadataReadError;
+    use crate::metadata::MetadataBuilder;
+    use crate::pe::errors::ReadManagedPeError;
+    use object::pe::IMAGE_FILE_MACHINE_I386;
+    use object::read::pe::PeFile32;
+
+    let mut data =
+      ManagedPeBuilder::new(IMAGE_FILE_MACHINE_I386).finish(&MetadataBuilder::new().finish());
+    let file = PeFile32::parse(&data[..]).unwrap();
+    let cli = file.data_directories().get(14).unwrap();
+    let (cli_offset, _) = file
+      .section_table()
+      .pe_file_range_at(cli.virtual_address.get(object::LittleEndian))
+      .unwrap();
+    let metadata_rva = ManagedPe::parse(&data)
+      .unwrap()
+      .cli_header()
+      .metadata
+      .virtual_address;
+
+    // Grow the metadata size of the CLI header past the end of its section.
+    let size = cli_offset as usize + 12;
+    data[size..size + 4].copy_from_slice(&0x00ff_0000u32.to_le_bytes());
+
+    assert!(matches!(
+      ManagedPe::parse(&data).unwrap().metadata(),
+      Err(MetadataReadError::MissingData { virtual_address, size: 0x00ff_0000 })
+        if virtual_address == metadata_rva
+    ));
+
+    // Move the CLI header itself outside the file.
+    let nt_headers = u32::from_le_bytes(data[0x3c..0x40].try_into().unwrap()) as usize;
+    let directory = nt_headers + 24 + 96 + 14 * 8;
+    data[directory..directory + 4].copy_from_slice(&0x00f0_0000u32.to_le_bytes());
+
+    assert!(matches!(
+      ManagedPe::parse(&data),
+      Err(ReadManagedPeError::InvalidCliHeader {
+        virtual_address: 0x00f0_0000,
+        size: 72
+      })
+    ));
+  }
+
+  #[test]
+  #[cfg(feature = "write")]
+  fn test_with_metadata_out_of_bounds() {
+    use super::{ManagedPe, ManagedPeBuilder};
+    use crate::metadata::MetadataBuilder;
+    use crate::pe::errors::WriteManagedPeError;
+    use object::pe::IMAGE_FILE_MACHINE_I386;
+    use object::read::pe::PeFile32;
+
+    let metadata = MetadataBuilder::new().finish();
+    let mut data = ManagedPeBuilder::new(IMAGE_FILE_MACHINE_I386).finish(&metadata);
+    let file = PeFile32::parse(&data[..]).unwrap();
+    let cli = file.data_directories().get(14).unwrap();
+    let (cli_offset, _) = file
+      .section_table()
+      .pe_file_range_at(cli.virtual_address.get(object::LittleEndian))
+      .unwrap();
+    let metadata_rva = ManagedPe::parse(&data)
+      .unwrap()
+      .cli_header()
+      .metadata
+      .virtual_address;
+
+    // Grow the metadata size of the CLI header past the end of the file, so that the new metadata
+    // is not larger than the size of the existing metadata.
+    let size = cli_offset as usize + 12;
+    data[size..size + 4].copy_from_slice(&0x00ff_0000u32.to_le_bytes());
+
+    assert!(matches!(
+      ManagedPe::parse(&data).unwrap().with_metadata(&metadata),
+      Err(WriteManagedPeError::MissingMetadata { virtual_address, size: 0x00ff_0000 })
+        if virtual_address == metadata_rva
+    ));
+  }
+
+  #[test]
+  #[cfg(feature = "write")]
+  fn test_strong_name_hash_data_large_optional_header() {
+    use super::{ManagedPe, ManagedPeBuilder};
+    use crate::metadata::MetadataBuilder;
+    use object::pe::{
+      IMAGE_DIRECTORY_ENTRY_DEBUG, IMAGE_DIRECTORY_ENTRY_SECURITY, IMAGE_FILE_MACHINE_I386,
+    };
+
+    fn hash_data(data: &[u8]) -> Vec<u8> {
+      let mut hashed = Vec::new();
+
+      ManagedPe::parse(data)
+        .unwrap()
+        .strong_name_hash_data(|data| hashed.extend_from_slice(data));
+
+      hashed
+    }
+
+    let mut data =
+      ManagedPeBuilder::new(IMAGE_FILE_MACHINE_I386).finish(&MetadataBuilder::new().finish());
+    let nt_headers = u32::from_le_bytes(data[0x3c..0x40].try_into().unwrap()) as usize;
+    let sections = u16::from_le_bytes(data[nt_headers + 6..nt_headers + 8].try_into().unwrap());
+    let optional_header = nt_headers + 24;
+    let section_table = optional_header + 224;
+
+    // Move the section table 16 bytes further into the header padding, leaving unused bytes
+    // between the data directories and the section table.
+    data.copy_within(
+      section_table..section_table + 40 * sections as usize,
+      section_table + 16,
+    );
+    data[section_table..section_table + 16].fill(0);
+    data[nt_headers + 20..nt_headers + 22].copy_from_slice(&240u16.to_le_bytes());
+
+    let directory = |index: usize| optional_header + 96 + 8 * index;
+    let expected = hash_data(&data);
+
+    let security = directory(IMAGE_DIRECTORY_ENTRY_SECURITY);
+    data[security..security + 8].fill(0xff);
+
+    assert_eq!(expected, hash_data(&data));
+
+    let debug = directory(IMAGE_DIRECTORY_ENTRY_DEBUG);
+    data[debug..debug + 8].fill(0xff);
+
+    assert_ne!(expected, hash_data(&data));
+  }
+
+  #[test]
+  #[cfg(all(feature = "write", feature = "crypto"))]
+  fn test_verify_strong_name() {
+    use super::{ManagedPe, ManagedPeBuilder};
+    use crate::metadata::streams::tables::flags::AssemblyHashAlgorithm;
+    use crate::metadata::MetadataBuilder;
+    use crate::pe::errors::StrongNameError;
+    use crate::pe::strong_name::{digest_info_prefix, CALG_RSA_SIGN, ECMA_PUBLIC_KEY, RSA1_MAGIC};
+    use object::pe::IMAGE_FILE_MACHINE_I386;
+    use rsa::{BigUint, Pkcs1v15Sign, RsaPrivateKey};
+
+    // A 512 bit test key, big-endian.
+    const MODULUS: [u8; 64] = [
+      0xcd, 0xb9, 0xc9, 0x15, 0x5e, 0xda, 0xcd, 0xc2, 0x34, 0x26, 0x7d, 0xe0, 0xcc, 0x37, 0xf3,
+      0xa9, 0x2d, 0xca, 0xd1, 0x0a, 0xbd, 0x20, 0x09, 0x58, 0x45, 0xa6, 0x4c, 0x6e, 0xe1, 0x02,
+      0xa6, 0xe4, 0xba, 0xe8, 0xdc, 0x8c, 0x32, 0x67, 0xaf, 0xad, 0x86, 0xcf, 0xd2, 0x64, 0x0a,
+      0xe9, 0xa8, 0x33, 0x01, 0xac, 0x87, 0xe7, 0xc2, 0xf5, 0xbb, 0x7c, 0x21, 0x7c, 0x48, 0x06,
+      0x89, 0x01, 0x6b, 0x95,
+    ];
+    const PRIVATE_EXPONENT: [u8; 64] = [
+      0x48, 0x0b, 0x83, 0x0f, 0x17, 0xdb, 0xda, 0x68, 0xe3, 0x68, 0x83, 0x76, 0xc1, 0x2d, 0xeb,
+      0x07, 0xd9, 0x40, 0x88, 0xba, 0x91, 0x06, 0xf2, 0x1e, 0xd6, 0xad, 0x69, 0x60, 0xff, 0x2d,
+      0xb5, 0x92, 0x0c, 0xfc, 0x7b, 0xa7, 0x81, 0x1e, 0xc9, 0x3f, 0xbe, 0xd0, 0xa9, 0x89, 0x29,
+      0xdd, 0x9d, 0x67, 0x73, 0x3a, 0xc3, 0xfc, 0x66, 0xa8, 0xaf, 0xde, 0x00, 0x3b, 0x8c, 0x0b,
+      0x64, 0xa3, 0xa9, 0x61,
+    ];
+    const PRIMES: [[u8; 32]; 2] = [
+      [
+        0xff, 0x0d, 0x3c, 0x10, 0x09, 0xcf, 0x04, 0xc2, 0xfc, 0xd8, 0x02, 0x22, 0xed, 0xe6, 0x27,
+        0xa4, 0xe1, 0xd5, 0x58, 0x50, 0x2f, 0xde, 0x8a, 0x0d, 0x53, 0x68, 0xa9, 0x42, 0x5c, 0x5d,
+        0x7d, 0x1d,
+      ],
+      [
+        0xce, 0x7d, 0x99, 0xdc, 0x0a, 0xe6, 0x90, 0x7f, 0x41, 0xd4, 0x4b, 0x6b, 0xc3, 0x33, 0xda,
+        0x44, 0x85, 0x9b, 0xeb, 0xd6, 0xf2, 0x0e, 0x74, 0x18, 0x03, 0x57, 0xcd, 0x7d, 0xed, 0xc3,
+        0x76, 0xd9,
+      ],
+    ];
+
+    let key = RsaPrivateKey::from_components(
+      BigUint::from_bytes_be(&MODULUS),
+      BigUint::from(65537u32),
+      BigUint::from_bytes_be(&PRIVATE_EXPONENT),
+      PRIMES.iter().map(|p| BigUint::from_bytes_be(p)).collect(),
+    )
+    .unwrap();
+
+    // The signature and hash algorithms followed by a `PUBLICKEYBLOB` with a little-endian modulus.
+    let mut public_key = Vec::new();
+    public_key.extend_from_slice(&CALG_RSA_SIGN.to_le_bytes());
+    public_key.extend_from_slice(&AssemblyHashAlgorithm::SHA1.bits().to_le_bytes());
+    public_key.extend_from_slice(&84u32.to_le_bytes());
+    public_key.extend_from_slice(&[0x06, 0x02, 0x00, 0x00]);
+    public_key.extend_from_slice(&CALG_RSA_SIGN.to_le_bytes());
+    public_key.extend_from_slice(&RSA1_MAGIC.to_le_bytes());
+    public_key.extend_from_slice(&512u32.to_le_bytes());
+    public_key.extend_from_slice(&65537u32.to_le_bytes());
+    public_key.extend(MODULUS.iter().rev());
+
+    let mut builder = ManagedPeBuilder::new(IMAGE_FILE_MACHINE_I386);
+    let body = builder.add_method_body(&[0x06, 0x2a]);
+
+    let unsigned = builder.clone().finish(&MetadataBuilder::new().finish());
+    let unsigned = ManagedPe::parse(&unsigned).unwrap();
+
+    assert_eq!(
+      Err(StrongNameError::NotSigned),
+      unsigned.verify_strong_name(&public_key)
+    );
+
+    builder.set_strong_name_signature_size(64);
+
+    let mut data = builder.finish(&MetadataBuilder::new().finish());
+    let (signature, signed, code) = {
+      let pe = ManagedPe::parse(&data).unwrap();
+
+      assert_eq!(
+        Err(StrongNameError::BadSignature),
+        pe.verify_strong_name(&public_key)
+      );
+
+      let hash = pe.strong_name_hash(AssemblyHashAlgorithm::SHA1).unwrap();
+      let scheme = Pkcs1v15Sign {
+        hash_len: Some(20),
+        prefix: digest_info_prefix(AssemblyHashAlgorithm::SHA1)
+          .unwrap()
+          .into(),
+      };
+      let mut signed = key.sign(scheme, &hash).unwrap();
+      signed.reverse();
+
+      let offset = |bytes: &[u8]| bytes.as_ptr() as usize - data.as_ptr() as usize;
+      let signature = offset(pe.strong_name_signature().unwrap());
+
+      (signature, signed, offset(pe.data_at(body).unwrap()))
+    };
+
+    data[signature..signature + 64].copy_from_slice(&signed);
+
+    let pe = ManagedPe::parse(&data).unwrap();
+
+    assert_eq!(Ok(()), pe.verify_strong_name(&public_key));
+    assert_eq!(
+      Err(StrongNameError::InvalidPublicKey),
+      pe.verify_strong_name(&ECMA_PUBLIC_KEY)
+    );
+
+    // The checksum is not covered by the hash, the code is.
+    let mut checksum = data.clone();
+    checksum[0x80 + 24 + 64] ^= 1;
+
+    assert_eq!(
+      Ok(()),
+      ManagedPe::parse(&checksum)
+        .unwrap()
+        .verify_strong_name(&public_key)
+    );
+
+    let mut patched = data.clone();
+    patched[code + 1] = 0x00;
+
+    assert_eq!(
+      Err(StrongNameError::BadSignature),
+      ManagedPe::parse(&patched)
+        .unwrap()
+        .verify_strong_name(&public_key)
+    );
+  }
+}
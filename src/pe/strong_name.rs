@@ -0,0 +1,357 @@
+//! Strong name signatures and public key tokens.
+//!
+//! A strong name signature is an RSA signature of a hash of the PE file, stored in the blob pointed
+//! to by [CliHeader::strong_name_signature](super::headers::CliHeader::strong_name_signature).  The
+//! public key used to check it is stored in the `public_key` column of the `Assembly` table, and
+//! assemblies referring to a strong named assembly usually store its public key token instead.
+
+#[cfg(feature = "crypto")]
+#[doc(inline)]
+pub use crypto::*;
+
+use crate::metadata::streams::tables::flags::AssemblyHashAlgorithm;
+
+/// The ECMA standard public key, which stands for the key of the platform the assembly runs on.
+pub const ECMA_PUBLIC_KEY: [u8; 16] = [0, 0, 0, 0, 0, 0, 0, 0, 4, 0, 0, 0, 0, 0, 0, 0];
+
+/// The `CALG_RSA_SIGN` algorithm id of strong name signatures.
+pub const CALG_RSA_SIGN: u32 = 0x2400;
+
+/// The magic signature of an `RSAPUBKEY` structure, `RSA1`.
+pub const RSA1_MAGIC: u32 = 0x31415352;
+
+/// A strong name public key, as stored in the `public_key` column of an `AssemblyRow`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct StrongNamePublicKey<'a> {
+  /// The signature algorithm, usually [CALG_RSA_SIGN].
+  pub signature_algorithm: u32,
+  /// The hash algorithm used to compute the strong name hash.
+  pub hash_algorithm: AssemblyHashAlgorithm,
+  /// The `PUBLICKEYBLOB` holding the key, or 4 bytes for the [ECMA_PUBLIC_KEY].
+  pub key: &'a [u8],
+}
+
+impl<'a> StrongNamePublicKey<'a> {
+  /// Returns the exponent and the little-endian modulus of the RSA key.
+  ///
+  /// Returns [None] if the key is not an RSA `PUBLICKEYBLOB`, such as the [ECMA_PUBLIC_KEY].
+  pub fn rsa(&self) -> Option<(u32, &'a [u8])> {
+    let u32_at = |offset: usize| {
+      let bytes = self.key.get(offset..offset + 4)?;
+
+      Some(u32::from_le_bytes(bytes.try_into().ok()?))
+    };
+
+    // BLOBHEADER { bType: PUBLICKEYBLOB, bVersion: 2, reserved, aiKeyAlg }
+    if self.key.get(..2)? != [0x06, 0x02] || u32_at(8)? != RSA1_MAGIC {
+      return None;
+    }
+
+    let bits = u32_at(12)? as usize;
+    let exponent = u32_at(16)?;
+    let modulus = self.key.get(20..20 + bits.div_ceil(8))?;
+
+    Some((exponent, modulus))
+  }
+}
+
+/// Returns the public key token of the given public key.
+///
+/// The token is the last 8 bytes of the SHA-1 hash of the public key blob in reverse order.
+pub fn public_key_token(public_key: &[u8]) -> [u8; 8] {
+  let mut sha1 = Sha1::new();
+  sha1.update(public_key);
+
+  let hash = sha1.finish();
+  let mut token = [0; 8];
+
+  token.copy_from_slice(&hash[12..]);
+  token.reverse();
+  token
+}
+
+/// A SHA-1 hasher, enough to compute public key tokens without pulling in a crypto library.
+#[derive(Clone)]
+pub(crate) struct Sha1 {
+  state: [u32; 5],
+  block: [u8; 64],
+  len: u64,
+}
+
+impl Sha1 {
+  pub(crate) fn new() -> Self {
+    Self {
+      state: [0x67452301, 0xefcdab89, 0x98badcfe, 0x10325476, 0xc3d2e1f0],
+      block: [0; 64],
+      len: 0,
+    }
+  }
+
+  pub(crate) fn update(&mut self, mut data: &[u8]) {
+    while !data.is_empty() {
+      let used = (self.len % 64) as usize;
+      let n = data.len().min(64 - used);
+
+      self.block[used..used + n].copy_from_slice(&data[..n]);
+      self.len += n as u64;
+      data = &data[n..];
+
+      if used + n == 64 {
+        self.compress();
+      }
+    }
+  }
+
+  pub(crate) fn finish(mut self) -> [u8; 20] {
+    let bits = self.len * 8;
+
+    self.update(&[0x80]);
+
+    while self.len % 64 != 56 {
+      self.update(&[0]);
+    }
+
+    self.update(&bits.to_be_bytes());
+
+    let mut hash = [0; 20];
+
+    for (bytes, word) in hash.chunks_exact_mut(4).zip(self.state) {
+      bytes.copy_from_slice(&word.to_be_bytes());
+    }
+
+    hash
+  }
+
+  fn compress(&mut self) {
+    let mut w = [0u32; 80];
+
+    for (i, bytes) in self.block.chunks_exact(4).enumerate() {
+      w[i] = u32::from_be_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]);
+    }
+
+    for i in 16..80 {
+      w[i] = (w[i - 3] ^ w[i - 8] ^ w[i - 14] ^ w[i - 16]).rotate_left(1);
+    }
+
+    let [mut a, mut b, mut c, mut d, mut e] = self.state;
+
+    for (i, w) in w.iter().enumerate() {
+      let (f, k) = match i {
+        0..=19 => ((b & c) | (!b & d), 0x5a827999),
+        20..=39 => (b ^ c ^ d, 0x6ed9eba1),
+        40..=59 => ((b & c) | (b & d) | (c & d), 0x8f1bbcdc),
+        _ => (b ^ c ^ d, 0xca62c1d6),
+      };
+
+      let t = a
+        .rotate_left(5)
+        .wrapping_add(f)
+        .wrapping_add(e)
+        .wrapping_add(k)
+        .wrapping_add(*w);
+
+      e = d;
+      d = c;
+      c = b.rotate_left(30);
+      b = a;
+      a = t;
+    }
+
+    for (state, value) in self.state.iter_mut().zip([a, b, c, d, e]) {
+      *state = state.wrapping_add(value);
+    }
+  }
+}
+
+#[cfg(feature = "read")]
+#[doc(hidden)]
+mod read {
+  use super::StrongNamePublicKey;
+  use crate::bytes::{ByteSliceExt, FromBytes};
+  use crate::metadata::streams::tables::flags::AssemblyHashAlgorithm;
+
+  impl<'a> FromBytes<'a> for StrongNamePublicKey<'a> {
+    fn from_bytes(buf: &'a [u8], offset: &mut usize, _: ()) -> Option<Self> {
+      let signature_algorithm = buf.read(offset)?;
+      let hash_algorithm = AssemblyHashAlgorithm::from_bits_retain(buf.read(offset)?);
+      let len = buf.read::<u32>(offset)? as usize;
+
+      Some(Self {
+        signature_algorithm,
+        hash_algorithm,
+        key: buf.read_with(offset, len)?,
+      })
+    }
+  }
+
+  impl<'a> StrongNamePublicKey<'a> {
+    /// Reads a [StrongNamePublicKey] from the given `public_key` blob.
+    pub fn parse(public_key: &'a [u8]) -> Option<Self> {
+      public_key.read(&mut 0)
+    }
+  }
+}
+
+#[cfg(feature = "crypto")]
+#[doc(hidden)]
+mod crypto {
+  use super::{Sha1, StrongNamePublicKey};
+  use crate::metadata::streams::tables::flags::AssemblyHashAlgorithm;
+  use crate::pe::errors::StrongNameError;
+  use rsa::{BigUint, Pkcs1v15Sign, RsaPublicKey};
+  use sha2::{Digest, Sha256, Sha384, Sha512};
+  use std::vec::Vec;
+
+  /// Computes a strong name hash with one of the supported [AssemblyHashAlgorithm]s.
+  ///
+  /// The data fed to the hasher is the PE file without its checksum, certificate table and
+  /// strong name signature, see `ManagedPeFile::strong_name_hash`.
+  #[derive(Clone)]
+  pub struct StrongNameHasher(Hasher);
+
+  #[derive(Clone)]
+  enum Hasher {
+    Sha1(Sha1),
+    Sha256(Sha256),
+    Sha384(Sha384),
+    Sha512(Sha512),
+  }
+
+  impl StrongNameHasher {
+    /// Returns a hasher for the given algorithm, or [None] if it is not supported.
+    pub fn new(algorithm: AssemblyHashAlgorithm) -> Option<Self> {
+      Some(Self(match algorithm {
+        AssemblyHashAlgorithm::SHA1 => Hasher::Sha1(Sha1::new()),
+        AssemblyHashAlgorithm::SHA256 => Hasher::Sha256(Sha256::new()),
+        AssemblyHashAlgorithm::SHA384 => Hasher::Sha384(Sha384::new()),
+        AssemblyHashAlgorithm::SHA512 => Hasher::Sha512(Sha512::new()),
+        _ => return None,
+      }))
+    }
+
+    /// Feeds the given data to the hasher.
+    pub fn update(&mut self, data: &[u8]) {
+      match &mut self.0 {
+        Hasher::Sha1(hasher) => hasher.update(data),
+        Hasher::Sha256(hasher) => hasher.update(data),
+        Hasher::Sha384(hasher) => hasher.update(data),
+        Hasher::Sha512(hasher) => hasher.update(data),
+      }
+    }
+
+    /// Returns the hash of all data fed to the hasher.
+    pub fn finish(self) -> Vec<u8> {
+      match self.0 {
+        Hasher::Sha1(hasher) => hasher.finish().to_vec(),
+        Hasher::Sha256(hasher) => hasher.finalize().to_vec(),
+        Hasher::Sha384(hasher) => hasher.finalize().to_vec(),
+        Hasher::Sha512(hasher) => hasher.finalize().to_vec(),
+      }
+    }
+  }
+
+  /// Returns the DER encoded `DigestInfo` prefix of a PKCS #1 v1.5 signature of the given hash.
+  pub(crate) fn digest_info_prefix(algorithm: AssemblyHashAlgorithm) -> Option<&'static [u8]> {
+    match algorithm {
+      AssemblyHashAlgorithm::SHA1 => Some(&[
+        0x30, 0x21, 0x30, 0x09, 0x06, 0x05, 0x2b, 0x0e, 0x03, 0x02, 0x1a, 0x05, 0x00, 0x04, 0x14,
+      ]),
+      AssemblyHashAlgorithm::SHA256 => Some(&[
+        0x30, 0x31, 0x30, 0x0d, 0x06, 0x09, 0x60, 0x86, 0x48, 0x01, 0x65, 0x03, 0x04, 0x02, 0x01,
+        0x05, 0x00, 0x04, 0x20,
+      ]),
+      AssemblyHashAlgorithm::SHA384 => Some(&[
+        0x30, 0x41, 0x30, 0x0d, 0x06, 0x09, 0x60, 0x86, 0x48, 0x01, 0x65, 0x03, 0x04, 0x02, 0x02,
+        0x05, 0x00, 0x04, 0x30,
+      ]),
+      AssemblyHashAlgorithm::SHA512 => Some(&[
+        0x30, 0x51, 0x30, 0x0d, 0x06, 0x09, 0x60, 0x86, 0x48, 0x01, 0x65, 0x03, 0x04, 0x02, 0x03,
+        0x05, 0x00, 0x04, 0x40,
+      ]),
+      _ => None,
+    }
+  }
+
+  impl<'a> StrongNamePublicKey<'a> {
+    /// Checks that the given strong name signature is a signature of the given hash by this key.
+    ///
+    /// The signature is stored little-endian, as it is in the PE file.
+    pub fn verify(&self, hash: &[u8], signature: &[u8]) -> Result<(), StrongNameError> {
+      let (exponent, modulus) = self.rsa().ok_or(StrongNameError::InvalidPublicKey)?;
+      let key = RsaPublicKey::new(BigUint::from_bytes_le(modulus), BigUint::from(exponent))
+        .map_err(|_| StrongNameError::InvalidPublicKey)?;
+
+      let prefix = digest_info_prefix(self.hash_algorithm).ok_or(
+        StrongNameError::UnsupportedHashAlgorithm(self.hash_algorithm),
+      )?;
+      let scheme = Pkcs1v15Sign {
+        hash_len: Some(hash.len()),
+        prefix: prefix.into(),
+      };
+
+      let mut signature = signature.to_vec();
+      signature.reverse();
+
+      key
+        .verify(scheme, hash, &signature)
+        .map_err(|_| StrongNameError::BadSignature)
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_sha1() {
+    let mut sha1 = Sha1::new();
+    sha1.update(b"abc");
+
+    assert_eq!(
+      [
+        0xa9, 0x99, 0x3e, 0x36, 0x47, 0x06, 0x81, 0x6a, 0xba, 0x3e, 0x25, 0x71, 0x78, 0x50, 0xc2,
+        0x6c, 0x9c, 0xd0, 0xd8, 0x9d
+      ],
+      sha1.finish()
+    );
+
+    // Spans more than one block.
+    let mut sha1 = Sha1::new();
+    sha1.update(b"abcdbcdecdefdefgefghfghighijhijkijkljklmklmnlmnomnopnopq");
+
+    assert_eq!(
+      [
+        0x84, 0x98, 0x3e, 0x44, 0x1c, 0x3b, 0xd2, 0x6e, 0xba, 0xae, 0x4a, 0xa1, 0xf9, 0x51, 0x29,
+        0xe5, 0xe5, 0x46, 0x70, 0xf1
+      ],
+      sha1.finish()
+    );
+  }
+
+  #[cfg(feature = "crypto")]
+  #[test]
+  fn test_strong_name_hasher() {
+    let mut hasher = StrongNameHasher::new(AssemblyHashAlgorithm::SHA256).unwrap();
+    hasher.update(b"a");
+    hasher.update(b"bc");
+
+    assert_eq!(
+      [
+        0xba, 0x78, 0x16, 0xbf, 0x8f, 0x01, 0xcf, 0xea, 0x41, 0x41, 0x40, 0xde, 0x5d, 0xae, 0x22,
+        0x23, 0xb0, 0x03, 0x61, 0xa3, 0x96, 0x17, 0x7a, 0x9c, 0xb4, 0x10, 0xff, 0x61, 0xf2, 0x00,
+        0x15, 0xad
+      ][..],
+      hasher.finish()
+    );
+    assert!(StrongNameHasher::new(AssemblyHashAlgorithm::MD5).is_none());
+  }
+
+  #[test]
+  fn test_public_key_token() {
+    assert_eq!(
+      [0xb7, 0x7a, 0x5c, 0x56, 0x19, 0x34, 0xe0, 0x89],
+      public_key_token(&ECMA_PUBLIC_KEY)
+    );
+  }
+}
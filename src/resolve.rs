@@ -0,0 +1,1119 @@
+//! Resolution of references to other assemblies and the types they define.
+//!
+//! An [AssemblyResolver] finds the assembly an `AssemblyRef` row refers to, and uses it to follow a
+//! `TypeRef` row to the `TypeDef` row of the type it refers to, wherever it is defined.  Following
+//! the base types and interfaces of a type in turn walks its hierarchy across assemblies.
+
+use crate::metadata::errors::{MetadataReadError, MetadataStreamReadError, ReadError};
+use crate::metadata::errors::{ReadErrorKind, ReadLocation};
+use crate::metadata::identity::{AssemblyIdentity, VersionPolicy};
+use crate::metadata::model::index::Indexes;
+use crate::metadata::model::names::FoundType;
+use crate::metadata::model::Metadata;
+use crate::metadata::streams::blobs::BlobsHeap;
+use crate::metadata::streams::strings::StringsHeap;
+use crate::metadata::streams::tables::id::{Implementation, ResolutionScope, RowId, TypeDefOrRef};
+use crate::metadata::streams::tables::rows::{AssemblyRefRow, TypeDefRow, TypeRefRow, TypeSpecRow};
+use crate::metadata::streams::tables::signatures::TypeSig;
+use crate::metadata::streams::tables::table::Row;
+use crate::metadata::streams::tables::TablesStream;
+use crate::pe::errors::ReadManagedPeError;
+use crate::pe::ManagedPe;
+use core::fmt;
+use std::collections::VecDeque;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+/// The number of type forwarders or enclosing types followed before giving up, in case they form a
+/// cycle.
+const MAX_DEPTH: usize = 16;
+
+/// A PE file containing CIL metadata, loaded into memory.
+///
+/// Either the manifest module of an assembly, which has an [AssemblyIdentity], or another module of
+/// a multi-module assembly.  The indexes built by lookups such as [Metadata::find_type] are kept
+/// with the module and shared by every [Metadata] it returns.
+pub struct LoadedModule {
+  data: Arc<[u8]>,
+  path: Option<PathBuf>,
+  identity: Option<AssemblyIdentity>,
+  mvid: Option<[u8; 16]>,
+  indexes: Arc<Indexes>,
+}
+
+impl LoadedModule {
+  /// Creates a [LoadedModule] from the bytes of a PE file.
+  ///
+  /// Checks that the file has readable metadata with a `#~` stream before returning the module.
+  pub fn new(data: Vec<u8>) -> Result<Self, LoadModuleError> {
+    let (identity, mvid, indexes) = {
+      let pe = ManagedPe::parse(&data)?;
+      let metadata = read_metadata(&pe)?;
+      let identity = match metadata.tables().assemblies().get(RowId::new(1)) {
+        Ok(row) => Some(AssemblyIdentity::from_assembly(
+          &row,
+          &metadata.strings(),
+          &metadata.blobs(),
+        )?),
+        Err(_) => None,
+      };
+      let mvid = match metadata.tables().modules().get(RowId::new(1)) {
+        Ok(row) => Some(metadata.guids().get(row.mvid())?),
+        Err(_) => None,
+      };
+
+      (identity, mvid, metadata.indexes().clone())
+    };
+
+    Ok(Self {
+      data: data.into(),
+      path: None,
+      identity,
+      mvid,
+      indexes,
+    })
+  }
+
+  /// Reads a [LoadedModule] from the file at the given path.
+  pub fn from_path(path: impl AsRef<Path>) -> Result<Self, LoadModuleError> {
+    let path = path.as_ref();
+
+    Ok(Self {
+      path: Some(path.into()),
+      ..Self::new(std::fs::read(path)?)?
+    })
+  }
+
+  /// Returns the bytes of the PE file.
+  pub fn data(&self) -> &[u8] {
+    &self.data
+  }
+
+  /// Returns the path the module was read from, if it was read from a file.
+  pub fn path(&self) -> Option<&Path> {
+    self.path.as_deref()
+  }
+
+  /// Returns the identity of the assembly, or [None] if this is not the manifest module of an
+  /// assembly.
+  pub fn identity(&self) -> Option<&AssemblyIdentity> {
+    self.identity.as_ref()
+  }
+
+  /// Returns the `Mvid` of the module, which identifies it among other modules and other builds of
+  /// the same module, or [None] if it has no `Module` row.
+  pub fn mvid(&self) -> Option<[u8; 16]> {
+    self.mvid
+  }
+
+  /// Returns `true` if both are the same module, either the same [LoadedModule] or modules with the
+  /// same `Mvid`.
+  pub fn is_same_module(&self, other: &Self) -> bool {
+    core::ptr::eq(self, other) || self.mvid.is_some() && self.mvid == other.mvid
+  }
+
+  /// Returns the [ManagedPe] of the module.
+  pub fn pe(&self) -> ManagedPe<'_> {
+    ManagedPe::parse(&self.data).expect("the file was parsed when it was loaded")
+  }
+
+  /// Returns the [Metadata] of the module.
+  pub fn metadata(&self) -> Metadata<'_> {
+    read_metadata(&self.pe())
+      .expect("the metadata was read when it was loaded")
+      .with_indexes(self.indexes.clone())
+  }
+
+  /// Returns the `#~` stream of the module.
+  pub fn tables(&self) -> TablesStream<'_> {
+    *self.metadata().tables()
+  }
+
+  /// Returns the `#Strings` heap of the module, empty if the module has none.
+  pub fn strings(&self) -> StringsHeap<'_> {
+    self.metadata().strings()
+  }
+
+  /// Returns the `#Blob` heap of the module, empty if the module has none.
+  pub fn blobs(&self) -> BlobsHeap<'_> {
+    self.metadata().blobs()
+  }
+}
+
+impl fmt::Debug for LoadedModule {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    f.debug_struct("LoadedModule")
+      .field("path", &self.path)
+      .field("identity", &self.identity)
+      .finish_non_exhaustive()
+  }
+}
+
+/// Reads the streams of the metadata of a module.
+fn read_metadata<'a>(pe: &ManagedPe<'a>) -> Result<Metadata<'a>, LoadModuleError> {
+  Metadata::from_reader(&pe.metadata()?).map_err(|err| match err {
+    MetadataStreamReadError::Read(err) => err.into(),
+    MetadataStreamReadError::MissingData { header } => ReadError::new(
+      ReadLocation::Root,
+      header.offset as usize,
+      ReadErrorKind::Truncated,
+    )
+    .into(),
+    MetadataStreamReadError::MissingStream { .. } => LoadModuleError::MissingTables,
+  })
+}
+
+/// A type definition found by resolving a reference.
+#[derive(Debug, Clone)]
+pub struct ResolvedType {
+  /// The module defining the type.
+  pub module: Arc<LoadedModule>,
+  /// The [TypeDefRow] of the type in the module.
+  pub type_def: RowId<TypeDefRow>,
+}
+
+impl ResolvedType {
+  /// Returns the [TypeDefRow] of the type.
+  pub fn row(&self) -> Result<TypeDefRow, ReadError> {
+    self.module.tables().type_defs().get(self.type_def)
+  }
+}
+
+impl PartialEq for ResolvedType {
+  /// Compares the modules by their `Mvid`, so that the same type found through modules loaded
+  /// more than once is equal.
+  fn eq(&self, other: &Self) -> bool {
+    self.module.is_same_module(&other.module) && self.type_def == other.type_def
+  }
+}
+
+impl Eq for ResolvedType {}
+
+/// Finds the assemblies and modules referred to by other assemblies.
+pub trait AssemblyResolver {
+  /// Returns the assembly satisfying the given reference, or [None] if it cannot be found.
+  fn resolve(&mut self, reference: &AssemblyIdentity) -> Option<Arc<LoadedModule>>;
+
+  /// Returns the module with the given file name belonging to the same assembly as the given
+  /// module, or [None] if it cannot be found.
+  ///
+  /// By default reads the file next to the given module, if it was read from a file.
+  fn resolve_module(&mut self, module: &LoadedModule, name: &str) -> Option<Arc<LoadedModule>> {
+    let path = module.path()?.parent()?.join(name);
+
+    LoadedModule::from_path(path).ok().map(Arc::new)
+  }
+
+  /// Returns the assembly referred to by the given [AssemblyRefRow] of the given module.
+  fn resolve_assembly_ref(
+    &mut self,
+    module: &LoadedModule,
+    assembly_ref: RowId<AssemblyRefRow>,
+  ) -> Result<Arc<LoadedModule>, ResolveError> {
+    let row = module.tables().assembly_refs().get(assembly_ref)?;
+    let identity = AssemblyIdentity::from_assembly_ref(&row, &module.strings(), &module.blobs())?;
+
+    self
+      .resolve(&identity)
+      .ok_or(ResolveError::AssemblyNotFound(identity))
+  }
+
+  /// Returns the type definition the given [TypeRefRow] of the given module refers to.
+  ///
+  /// Follows the resolution scope of the reference to the module or assembly defining the type, or
+  /// to the enclosing type of a nested type, and follows `ExportedType` rows to the module or
+  /// assembly a type is defined in or was forwarded to.
+  fn resolve_type_ref(
+    &mut self,
+    module: &Arc<LoadedModule>,
+    type_ref: RowId<TypeRefRow>,
+  ) -> Result<ResolvedType, ResolveError> {
+    resolve_type_ref(self, module, type_ref, 0)
+  }
+
+  /// Returns the type definition a `TypeDef`, `TypeRef` or `TypeSpec` row of the given module
+  /// refers to.
+  ///
+  /// A `TypeSpec` row instantiating a generic type resolves to the generic type, other type
+  /// specifications such as arrays do not refer to a type definition and fail.
+  fn resolve_type_def_or_ref(
+    &mut self,
+    module: &Arc<LoadedModule>,
+    ty: TypeDefOrRef,
+  ) -> Result<ResolvedType, ResolveError> {
+    let ty = match ty {
+      TypeDefOrRef::TypeSpec(_) => match module.metadata().type_def_or_ref_sig(ty)? {
+        TypeSig::Class(ty) | TypeSig::ValueType(ty) | TypeSig::GenericInst { generic: ty, .. } => {
+          ty
+        }
+        _ => ty,
+      },
+      ty => ty,
+    };
+
+    match ty {
+      TypeDefOrRef::TypeDef(id) => {
+        module.tables().type_defs().get(id)?;
+
+        Ok(ResolvedType {
+          module: module.clone(),
+          type_def: id,
+        })
+      }
+      TypeDefOrRef::TypeRef(id) => self.resolve_type_ref(module, id),
+      TypeDefOrRef::TypeSpec(_) => Err(invalid::<TypeSpecRow>(
+        module.tables().type_specs().offset(),
+      )),
+    }
+  }
+
+  /// Returns the chain of base types of the given type, starting with its direct base type, with
+  /// references to other assemblies resolved.
+  ///
+  /// Base types are resolved to their type definitions, without their type arguments.  Fails if a
+  /// base type cannot be resolved or the base types form a cycle.
+  fn resolve_base_types(&mut self, ty: &ResolvedType) -> Result<Vec<ResolvedType>, ResolveError> {
+    let mut bases = Vec::<ResolvedType>::new();
+
+    loop {
+      let derived = bases.last().unwrap_or(ty).clone();
+      let Some(base) = derived
+        .module
+        .metadata()
+        .type_def(derived.type_def)?
+        .base_type()
+      else {
+        return Ok(bases);
+      };
+      let base = self.resolve_type_def_or_ref(&derived.module, base)?;
+
+      if base == *ty || bases.contains(&base) {
+        return Err(invalid::<TypeDefRow>(
+          base.module.tables().type_defs().offset(),
+        ));
+      }
+
+      bases.push(base);
+    }
+  }
+
+  /// Returns the interfaces implemented by the given type, including the interfaces implemented by
+  /// its base types and inherited from other interfaces, with references to other assemblies
+  /// resolved.
+  ///
+  /// Interfaces are resolved to their type definitions, without their type arguments, and each is
+  /// returned once.
+  fn resolve_interfaces(&mut self, ty: &ResolvedType) -> Result<Vec<ResolvedType>, ResolveError> {
+    let mut interfaces = Vec::new();
+    let mut pending = VecDeque::from(self.resolve_base_types(ty)?);
+
+    pending.push_front(ty.clone());
+
+    while let Some(ty) = pending.pop_front() {
+      let metadata = ty.module.metadata();
+      let declared = metadata
+        .type_def(ty.type_def)?
+        .interfaces()
+        .collect::<Vec<_>>();
+
+      for interface in declared {
+        let interface = self.resolve_type_def_or_ref(&ty.module, interface)?;
+
+        if !interfaces.contains(&interface) {
+          interfaces.push(interface.clone());
+          pending.push_back(interface);
+        }
+      }
+    }
+
+    Ok(interfaces)
+  }
+
+  /// Returns the types defined by the given modules implementing the given interface, directly,
+  /// through their base types or through other interfaces.
+  ///
+  /// Types are compared by the `Mvid` of their module, so the modules need not be the ones this
+  /// resolver returns.
+  fn find_implementations(
+    &mut self,
+    modules: &[Arc<LoadedModule>],
+    interface: &ResolvedType,
+  ) -> Result<Vec<ResolvedType>, ResolveError> {
+    let mut types = Vec::new();
+
+    for module in modules {
+      let metadata = module.metadata();
+      let classes = metadata.type_defs().filter(|ty| !ty.is_interface());
+
+      for type_def in classes.map(|ty| ty.id()).collect::<Vec<_>>() {
+        let ty = ResolvedType {
+          module: module.clone(),
+          type_def,
+        };
+
+        if self.resolve_interfaces(&ty)?.contains(interface) {
+          types.push(ty);
+        }
+      }
+    }
+
+    Ok(types)
+  }
+}
+
+fn resolve_type_ref<R: AssemblyResolver + ?Sized>(
+  resolver: &mut R,
+  module: &Arc<LoadedModule>,
+  type_ref: RowId<TypeRefRow>,
+  depth: usize,
+) -> Result<ResolvedType, ResolveError> {
+  let metadata = module.metadata();
+  let row = metadata.tables().type_refs().get(type_ref)?;
+  let name = metadata.strings().get_str(row.name())?;
+  let namespace = metadata.strings().get_str(row.namespace())?;
+
+  match row.resolution_scope() {
+    ResolutionScope::Module(id) if id.index() != 0 => {
+      find_type_def(module, namespace, name)?.ok_or_else(|| not_found(namespace, name))
+    }
+    // A null scope refers to a type exported by this assembly.
+    ResolutionScope::Module(_) => find_type(resolver, module, namespace, name, depth),
+    ResolutionScope::ModuleRef(id) => {
+      let module_ref = metadata.tables().module_refs().get(id)?;
+      let file = metadata.strings().get_str(module_ref.name())?;
+      let target = resolver
+        .resolve_module(module, file)
+        .ok_or_else(|| ResolveError::ModuleNotFound(file.into()))?;
+
+      find_type_def(&target, namespace, name)?.ok_or_else(|| not_found(namespace, name))
+    }
+    ResolutionScope::AssemblyRef(id) => {
+      let target = resolver.resolve_assembly_ref(module, id)?;
+
+      find_type(resolver, &target, namespace, name, depth)
+    }
+    ResolutionScope::TypeRef(id) => {
+      if depth == MAX_DEPTH {
+        return Err(ResolveError::TooManyForwarders);
+      }
+
+      let enclosing = resolve_type_ref(resolver, module, id, depth + 1)?;
+
+      find_nested_type(&enclosing, name)?.ok_or_else(|| not_found(namespace, name))
+    }
+  }
+}
+
+/// Finds a top-level type defined or exported by the given module, following type forwarders.
+fn find_type<R: AssemblyResolver + ?Sized>(
+  resolver: &mut R,
+  module: &Arc<LoadedModule>,
+  namespace: &str,
+  name: &str,
+  depth: usize,
+) -> Result<ResolvedType, ResolveError> {
+  let metadata = module.metadata();
+  let row = match metadata.find_type(namespace, name)? {
+    Some(FoundType::Defined(ty)) => {
+      return Ok(ResolvedType {
+        module: module.clone(),
+        type_def: ty.id(),
+      })
+    }
+    Some(FoundType::Exported(row)) => row,
+    None => return Err(not_found(namespace, name)),
+  };
+
+  match row.implementation() {
+    // A type forwarder, usually marked with `TypeAttributes::IS_TYPE_FORWARDER`.
+    Implementation::AssemblyRef(id) => {
+      if depth == MAX_DEPTH {
+        return Err(ResolveError::TooManyForwarders);
+      }
+
+      let target = resolver.resolve_assembly_ref(module, id)?;
+
+      find_type(resolver, &target, namespace, name, depth + 1)
+    }
+    Implementation::File(id) => {
+      let file = metadata.tables().files().get(id)?;
+      let file = metadata.strings().get_str(file.name())?;
+      let target = resolver
+        .resolve_module(module, file)
+        .ok_or_else(|| ResolveError::ModuleNotFound(file.into()))?;
+
+      find_type_def(&target, namespace, name)?.ok_or_else(|| not_found(namespace, name))
+    }
+    // Nested types are found through their enclosing type, they are not top-level types.
+    Implementation::ExportedType(_) => Err(not_found(namespace, name)),
+  }
+}
+
+/// Finds a top-level type defined by the given module.
+fn find_type_def(
+  module: &Arc<LoadedModule>,
+  namespace: &str,
+  name: &str,
+) -> Result<Option<ResolvedType>, ResolveError> {
+  Ok(match module.metadata().find_type(namespace, name)? {
+    Some(FoundType::Defined(ty)) => Some(ResolvedType {
+      module: module.clone(),
+      type_def: ty.id(),
+    }),
+    _ => None,
+  })
+}
+
+/// Finds a type nested in the given type.
+fn find_nested_type(
+  enclosing: &ResolvedType,
+  name: &str,
+) -> Result<Option<ResolvedType>, ResolveError> {
+  let metadata = enclosing.module.metadata();
+  let nested = metadata.type_def(enclosing.type_def)?.nested_type(name)?;
+
+  Ok(nested.map(|nested| ResolvedType {
+    module: enclosing.module.clone(),
+    type_def: nested.id(),
+  }))
+}
+
+/// Returns the error for an invalid row of the table of the given row type at the given offset.
+fn invalid<R: Row>(offset: usize) -> ResolveError {
+  ReadError::new(
+    ReadLocation::Table { table: R::NAME },
+    offset,
+    ReadErrorKind::Invalid,
+  )
+  .into()
+}
+
+fn not_found(namespace: &str, name: &str) -> ResolveError {
+  ResolveError::TypeNotFound {
+    namespace: namespace.into(),
+    name: name.into(),
+  }
+}
+
+/// An [AssemblyResolver] that looks for assemblies in a list of directories.
+///
+/// An assembly named `Name` is looked for as `Name.dll` then `Name.exe` in each directory in
+/// order, and is only used if its identity satisfies the reference.  Loaded assemblies and modules
+/// are kept and reused for later references.
+#[derive(Debug)]
+pub struct DirectoryResolver {
+  directories: Vec<PathBuf>,
+  policy: VersionPolicy,
+  loaded: Vec<Arc<LoadedModule>>,
+}
+
+impl DirectoryResolver {
+  /// Creates a [DirectoryResolver] looking in the given directories, matching versions using
+  /// [VersionPolicy::Minimum].
+  pub fn new<P: Into<PathBuf>>(directories: impl IntoIterator<Item = P>) -> Self {
+    Self {
+      directories: directories.into_iter().map(Into::into).collect(),
+      policy: VersionPolicy::Minimum,
+      loaded: Vec::new(),
+    }
+  }
+
+  /// Sets the [VersionPolicy] used to match references to assemblies.
+  pub fn set_version_policy(&mut self, policy: VersionPolicy) {
+    self.policy = policy;
+  }
+
+  /// Adds an assembly that is already loaded, it is used before looking in the directories.
+  pub fn add(&mut self, assembly: Arc<LoadedModule>) {
+    self.loaded.push(assembly);
+  }
+
+  fn find_loaded(&self, reference: &AssemblyIdentity) -> Option<Arc<LoadedModule>> {
+    self
+      .loaded
+      .iter()
+      .find(|assembly| {
+        assembly
+          .identity()
+          .is_some_and(|identity| reference.matches(identity, self.policy))
+      })
+      .cloned()
+  }
+}
+
+impl AssemblyResolver for DirectoryResolver {
+  fn resolve(&mut self, reference: &AssemblyIdentity) -> Option<Arc<LoadedModule>> {
+    if let Some(assembly) = self.find_loaded(reference) {
+      return Some(assembly);
+    }
+
+    for directory in &self.directories {
+      for extension in ["dll", "exe"] {
+        let path = directory.join(format!("{}.{extension}", reference.name));
+        let Ok(assembly) = LoadedModule::from_path(path) else {
+          continue;
+        };
+
+        if assembly
+          .identity()
+          .is_some_and(|identity| reference.matches(identity, self.policy))
+        {
+          let assembly = Arc::new(assembly);
+
+          self.loaded.push(assembly.clone());
+
+          return Some(assembly);
+        }
+      }
+    }
+
+    None
+  }
+
+  fn resolve_module(&mut self, module: &LoadedModule, name: &str) -> Option<Arc<LoadedModule>> {
+    let path = module.path()?.parent()?.join(name);
+
+    if let Some(loaded) = self
+      .loaded
+      .iter()
+      .find(|loaded| loaded.path() == Some(&path))
+    {
+      return Some(loaded.clone());
+    }
+
+    let loaded = Arc::new(LoadedModule::from_path(path).ok()?);
+
+    self.loaded.push(loaded.clone());
+
+    Some(loaded)
+  }
+}
+
+/// An error that occurred while loading a module.
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum LoadModuleError {
+  /// The file could not be read.
+  Io(std::io::Error),
+  /// The file is not a managed PE file.
+  Pe(ReadManagedPeError),
+  /// The metadata root could not be read.
+  Metadata(MetadataReadError),
+  /// A metadata stream or the `Assembly` row could not be read.
+  Read(ReadError),
+  /// The metadata has no `#~` stream.
+  MissingTables,
+}
+
+impl fmt::Display for LoadModuleError {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match self {
+      Self::Io(err) => write!(f, "Failed to read the file: {err}"),
+      Self::Pe(err) => err.fmt(f),
+      Self::Metadata(err) => err.fmt(f),
+      Self::Read(err) => err.fmt(f),
+      Self::MissingTables => write!(f, "The metadata has no `#~` stream"),
+    }
+  }
+}
+
+impl std::error::Error for LoadModuleError {
+  fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+    match self {
+      Self::Io(err) => Some(err),
+      Self::Pe(err) => Some(err),
+      Self::Metadata(err) => Some(err),
+      Self::Read(err) => Some(err),
+      Self::MissingTables => None,
+    }
+  }
+}
+
+impl From<std::io::Error> for LoadModuleError {
+  fn from(err: std::io::Error) -> Self {
+    Self::Io(err)
+  }
+}
+
+impl From<ReadManagedPeError> for LoadModuleError {
+  fn from(err: ReadManagedPeError) -> Self {
+    Self::Pe(err)
+  }
+}
+
+impl From<MetadataReadError> for LoadModuleError {
+  fn from(err: MetadataReadError) -> Self {
+    Self::Metadata(err)
+  }
+}
+
+impl From<ReadError> for LoadModuleError {
+  fn from(err: ReadError) -> Self {
+    Self::Read(err)
+  }
+}
+
+/// An error that occurred while resolving a reference.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum ResolveError {
+  /// A row or heap entry could not be read.
+  Read(ReadError),
+  /// No assembly satisfying the reference was found.
+  AssemblyNotFound(AssemblyIdentity),
+  /// The module with the given file name was not found.
+  ModuleNotFound(String),
+  /// The assembly or module does not define or export the type.
+  TypeNotFound {
+    /// The namespace of the type, empty for nested types and types without a namespace.
+    namespace: String,
+    /// The name of the type.
+    name: String,
+  },
+  /// More type forwarders or enclosing types were followed than allowed, they probably form a
+  /// cycle.
+  TooManyForwarders,
+}
+
+impl fmt::Display for ResolveError {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match self {
+      Self::Read(err) => err.fmt(f),
+      Self::AssemblyNotFound(identity) => write!(f, "The assembly `{identity}` was not found"),
+      Self::ModuleNotFound(name) => write!(f, "The module `{name}` was not found"),
+      Self::TypeNotFound { namespace, name } if namespace.is_empty() => {
+        write!(f, "The type `{name}` was not found")
+      }
+      Self::TypeNotFound { namespace, name } => {
+        write!(f, "The type `{namespace}.{name}` was not found")
+      }
+      Self::TooManyForwarders => {
+        write!(
+          f,
+          "Too many type forwarders or enclosing types were followed"
+        )
+      }
+    }
+  }
+}
+
+impl std::error::Error for ResolveError {
+  fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+    match self {
+      Self::Read(err) => Some(err),
+      _ => None,
+    }
+  }
+}
+
+impl From<ReadError> for ResolveError {
+  fn from(err: ReadError) -> Self {
+    Self::Read(err)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  #[cfg(feature = "write")]
+  use crate::metadata::streams::tables::{id::RowId, rows::AssemblyRefRow};
+  #[cfg(feature = "write")]
+  use crate::metadata::MetadataBuilder;
+
+  #[cfg(feature = "write")]
+  fn module(name: &str, assembly: bool, build: impl FnOnce(&mut MetadataBuilder)) -> Vec<u8> {
+    use crate::metadata::streams::tables::flags::{
+      AssemblyFlags, AssemblyHashAlgorithm, TypeAttributes,
+    };
+    use crate::metadata::streams::tables::id::TypeDefOrRef;
+    use crate::metadata::streams::tables::rows::*;
+    use crate::pe::ManagedPeBuilder;
+    use object::pe::IMAGE_FILE_MACHINE_I386;
+
+    let mut md = MetadataBuilder::new();
+    let module_name = md.strings.add(&format!("{name}.dll"));
+    let mut mvid = [0; 16];
+
+    mvid[..name.len()].copy_from_slice(name.as_bytes());
+
+    let mvid = md.guids.add(mvid);
+
+    md.tables
+      .add_module(ModuleRow::new(0, module_name, mvid, mvid, mvid));
+    md.tables.add_type_def(TypeDefRow::new(
+      TypeAttributes::empty(),
+      md.strings.add("<Module>"),
+      md.strings.add(""),
+      TypeDefOrRef::TypeDef(RowId::new(0)),
+      RowId::new(1),
+      RowId::new(1),
+    ));
+
+    if assembly {
+      md.tables.add_assembly(AssemblyRow::new(
+        AssemblyHashAlgorithm::SHA1,
+        1,
+        0,
+        0,
+        0,
+        AssemblyFlags::empty(),
+        md.blobs.add(&[]),
+        md.strings.add(name),
+        md.strings.add(""),
+      ));
+    }
+
+    build(&mut md);
+
+    ManagedPeBuilder::new(IMAGE_FILE_MACHINE_I386).finish(&md.finish())
+  }
+
+  /// A directory in the temporary directory, removed when dropped, even if the test fails.
+  #[cfg(feature = "write")]
+  struct TempDir(std::path::PathBuf);
+
+  #[cfg(feature = "write")]
+  impl TempDir {
+    fn new(name: &str) -> Self {
+      let dir = std::env::temp_dir().join(format!("ecma335-{name}-{}", std::process::id()));
+
+      std::fs::create_dir_all(&dir).unwrap();
+
+      Self(dir)
+    }
+  }
+
+  #[cfg(feature = "write")]
+  impl Drop for TempDir {
+    fn drop(&mut self) {
+      let _ = std::fs::remove_dir_all(&self.0);
+    }
+  }
+
+  #[cfg(feature = "write")]
+  fn assembly_ref(md: &mut MetadataBuilder, name: &str) -> RowId<AssemblyRefRow> {
+    use crate::metadata::streams::tables::flags::AssemblyFlags;
+
+    md.tables.add_assembly_ref(AssemblyRefRow::new(
+      1,
+      0,
+      0,
+      0,
+      AssemblyFlags::empty(),
+      md.blobs.add(&[]),
+      md.strings.add(name),
+      md.strings.add(""),
+      md.blobs.add(&[]),
+    ))
+  }
+
+  #[test]
+  #[cfg(feature = "write")]
+  fn test_loaded_module_keeps_indexes() {
+    use super::*;
+
+    let module = LoadedModule::new(module("A", true, |_| {})).unwrap();
+
+    assert!(module.metadata().indexes().names.get().is_none());
+    assert!(module
+      .metadata()
+      .find_type("", "<Module>")
+      .unwrap()
+      .is_some());
+    assert!(module.metadata().indexes().names.get().is_some());
+  }
+
+  #[test]
+  #[cfg(feature = "write")]
+  fn test_resolve_type_ref() {
+    use super::*;
+    use crate::metadata::streams::tables::flags::TypeAttributes;
+    use crate::metadata::streams::tables::id::TypeDefOrRef;
+    use crate::metadata::streams::tables::rows::*;
+
+    fn type_def(md: &mut MetadataBuilder, flags: TypeAttributes, name: &str) -> RowId<TypeDefRow> {
+      md.tables.add_type_def(TypeDefRow::new(
+        flags,
+        md.strings.add(name),
+        md.strings.add(if flags == TypeAttributes::PUBLIC {
+          "N"
+        } else {
+          ""
+        }),
+        TypeDefOrRef::TypeDef(RowId::new(0)),
+        RowId::new(1),
+        RowId::new(1),
+      ))
+    }
+
+    // `C` defines `N.T` and its nested type `Inner`.
+    let c = module("C", true, |md| {
+      let outer = type_def(md, TypeAttributes::PUBLIC, "T");
+      let inner = type_def(md, TypeAttributes::NESTED_PUBLIC, "Inner");
+
+      md.tables
+        .add_nested_class(NestedClassRow::new(inner, outer));
+    });
+
+    // `B` forwards `N.T` to `C`.
+    let b = module("B", true, |md| {
+      let c = assembly_ref(md, "C");
+
+      md.tables.add_exported_type(ExportedTypeRow::new(
+        TypeAttributes::IS_TYPE_FORWARDER,
+        RowId::new(0),
+        md.strings.add("T"),
+        md.strings.add("N"),
+        Implementation::AssemblyRef(c),
+      ));
+    });
+
+    // `M.netmodule` is a module of `A` defining `N.InModule`.
+    let m = module("M", false, |md| {
+      type_def(md, TypeAttributes::PUBLIC, "InModule");
+    });
+
+    let a = module("A", true, |md| {
+      let b = assembly_ref(md, "B");
+      let d = assembly_ref(md, "D");
+      let m = md
+        .tables
+        .add_module_ref(ModuleRefRow::new(md.strings.add("M.netmodule")));
+      let mut type_ref = |scope, name, namespace| {
+        md.tables.add_type_ref(TypeRefRow::new(
+          scope,
+          md.strings.add(name),
+          md.strings.add(namespace),
+        ))
+      };
+
+      let t = type_ref(ResolutionScope::AssemblyRef(b), "T", "N");
+      type_ref(ResolutionScope::TypeRef(t), "Inner", "");
+      type_ref(ResolutionScope::Module(RowId::new(1)), "<Module>", "");
+      type_ref(ResolutionScope::ModuleRef(m), "InModule", "N");
+      type_ref(ResolutionScope::AssemblyRef(b), "Missing", "N");
+      type_ref(ResolutionScope::AssemblyRef(d), "T", "N");
+      // A nested type enclosed by itself.
+      type_ref(ResolutionScope::TypeRef(RowId::new(7)), "Cycle", "");
+    });
+
+    let dir = TempDir::new("resolve-type-ref");
+    let dir = &dir.0;
+
+    for (name, data) in [("A.dll", a), ("B.dll", b), ("C.exe", c), ("M.netmodule", m)] {
+      std::fs::write(dir.join(name), data).unwrap();
+    }
+
+    let a = Arc::new(LoadedModule::from_path(dir.join("A.dll")).unwrap());
+    let mut resolver = DirectoryResolver::new([dir]);
+    let mut resolve = |index| {
+      let resolved = resolver.resolve_type_ref(&a, RowId::new(index))?;
+      let row = resolved.row()?;
+      let strings = resolved.module.strings();
+
+      Ok::<_, ResolveError>((
+        resolved
+          .module
+          .identity()
+          .map(|identity| identity.name.clone()),
+        strings.get_str(row.name())?.to_owned(),
+      ))
+    };
+
+    assert_eq!(Ok((Some("C".into()), "T".into())), resolve(1));
+    assert_eq!(Ok((Some("C".into()), "Inner".into())), resolve(2));
+    assert_eq!(Ok((Some("A".into()), "<Module>".into())), resolve(3));
+    assert_eq!(Ok((None, "InModule".into())), resolve(4));
+    assert_eq!(
+      Err(ResolveError::TypeNotFound {
+        namespace: "N".into(),
+        name: "Missing".into()
+      }),
+      resolve(5)
+    );
+    assert!(matches!(
+      resolve(6),
+      Err(ResolveError::AssemblyNotFound(identity)) if identity.name == "D"
+    ));
+    assert_eq!(Err(ResolveError::TooManyForwarders), resolve(7));
+  }
+
+  #[test]
+  #[cfg(feature = "write")]
+  fn test_resolve_hierarchy() {
+    use super::*;
+    use crate::metadata::streams::tables::flags::TypeAttributes;
+    use crate::metadata::streams::tables::rows::*;
+
+    let interface = TypeAttributes::PUBLIC | TypeAttributes::INTERFACE | TypeAttributes::ABSTRACT;
+    let add_type = |md: &mut MetadataBuilder, flags, name, extends| {
+      md.tables.add_type_def(TypeDefRow::new(
+        flags,
+        md.strings.add(name),
+        md.strings.add("N"),
+        extends,
+        RowId::new(1),
+        RowId::new(1),
+      ))
+    };
+    let none = TypeDefOrRef::TypeDef(RowId::new(0));
+
+    // `B` defines `interface IFoo` and `class Base : IFoo`.
+    let b = module("B", true, |md| {
+      let foo = add_type(md, interface, "IFoo", none);
+      let base = add_type(md, TypeAttributes::PUBLIC, "Base", none);
+
+      md.tables
+        .add_interface_impl(InterfaceImplRow::new(base, TypeDefOrRef::TypeDef(foo)));
+    });
+
+    // `A` defines `interface IBar : IFoo`, `class Derived : Base`, `class Impl : IBar` and
+    // `class Other`.
+    let a = module("A", true, |md| {
+      let b = assembly_ref(md, "B");
+      let mut type_ref = |name| {
+        md.tables.add_type_ref(TypeRefRow::new(
+          ResolutionScope::AssemblyRef(b),
+          md.strings.add(name),
+          md.strings.add("N"),
+        ))
+      };
+      let foo = type_ref("IFoo");
+      let base = type_ref("Base");
+      let bar = add_type(md, interface, "IBar", none);
+
+      add_type(
+        md,
+        TypeAttributes::PUBLIC,
+        "Derived",
+        TypeDefOrRef::TypeRef(base),
+      );
+
+      let implementation = add_type(md, TypeAttributes::PUBLIC, "Impl", none);
+
+      add_type(md, TypeAttributes::PUBLIC, "Other", none);
+      md.tables
+        .add_interface_impl(InterfaceImplRow::new(bar, TypeDefOrRef::TypeRef(foo)));
+      md.tables.add_interface_impl(InterfaceImplRow::new(
+        implementation,
+        TypeDefOrRef::TypeDef(bar),
+      ));
+    });
+
+    let a = Arc::new(LoadedModule::new(a).unwrap());
+    let b = Arc::new(LoadedModule::new(b).unwrap());
+    let mut resolver = DirectoryResolver::new(Vec::<PathBuf>::new());
+    let ty = |module: &Arc<LoadedModule>, index| ResolvedType {
+      module: module.clone(),
+      type_def: RowId::new(index),
+    };
+
+    resolver.add(a.clone());
+    resolver.add(b.clone());
+
+    assert_eq!(
+      Ok(ty(&b, 2)),
+      resolver.resolve_type_def_or_ref(&a, TypeDefOrRef::TypeRef(RowId::new(1)))
+    );
+    assert_eq!(Ok(vec![ty(&b, 3)]), resolver.resolve_base_types(&ty(&a, 3)));
+    assert_eq!(Ok(vec![]), resolver.resolve_base_types(&ty(&a, 4)));
+    assert_eq!(
+      Ok(vec![ty(&a, 2), ty(&b, 2)]),
+      resolver.resolve_interfaces(&ty(&a, 4))
+    );
+    assert_eq!(
+      Ok(vec![ty(&a, 3), ty(&a, 4), ty(&b, 3)]),
+      resolver.find_implementations(&[a.clone(), b.clone()], &ty(&b, 2))
+    );
+    assert_eq!(
+      Ok(vec![ty(&a, 4)]),
+      resolver.find_implementations(&[a.clone(), b.clone()], &ty(&a, 2))
+    );
+  }
+
+  #[test]
+  #[cfg(feature = "write")]
+  fn test_resolve_hierarchy_across_modules() {
+    use super::*;
+    use crate::metadata::streams::tables::flags::TypeAttributes;
+    use crate::metadata::streams::tables::rows::*;
+
+    /// Uses the default [AssemblyResolver::resolve_module], which loads modules again each time.
+    struct NoAssemblies;
+
+    impl AssemblyResolver for NoAssemblies {
+      fn resolve(&mut self, _: &AssemblyIdentity) -> Option<Arc<LoadedModule>> {
+        None
+      }
+    }
+
+    let interface = TypeAttributes::PUBLIC | TypeAttributes::INTERFACE | TypeAttributes::ABSTRACT;
+    let add_type = |md: &mut MetadataBuilder, flags, name: &str, extends| {
+      md.tables.add_type_def(TypeDefRow::new(
+        flags,
+        md.strings.add(name),
+        md.strings.add("N"),
+        extends,
+        RowId::new(1),
+        RowId::new(1),
+      ))
+    };
+    let type_ref = |md: &mut MetadataBuilder, file: &str, name: &str| {
+      let scope = md
+        .tables
+        .add_module_ref(ModuleRefRow::new(md.strings.add(file)));
+
+      TypeDefOrRef::TypeRef(md.tables.add_type_ref(TypeRefRow::new(
+        ResolutionScope::ModuleRef(scope),
+        md.strings.add(name),
+        md.strings.add("N"),
+      )))
+    };
+    let none = TypeDefOrRef::TypeDef(RowId::new(0));
+
+    // `M.netmodule` defines `interface I`, `class Base : I` and `class Loop : A.Loop`.
+    let m = module("M", false, |md| {
+      let i = add_type(md, interface, "I", none);
+      let base = add_type(md, TypeAttributes::PUBLIC, "Base", none);
+      let a_loop = type_ref(md, "A.dll", "Loop");
+
+      add_type(md, TypeAttributes::PUBLIC, "Loop", a_loop);
+      md.tables
+        .add_interface_impl(InterfaceImplRow::new(base, TypeDefOrRef::TypeDef(i)));
+    });
+
+    // `A` defines `class Derived : M.Base, M.I` and `class Loop : M.Loop`.
+    let a = module("A", true, |md| {
+      let base = type_ref(md, "M.netmodule", "Base");
+      let i = type_ref(md, "M.netmodule", "I");
+      let m_loop = type_ref(md, "M.netmodule", "Loop");
+      let derived = add_type(md, TypeAttributes::PUBLIC, "Derived", base);
+
+      add_type(md, TypeAttributes::PUBLIC, "Loop", m_loop);
+      md.tables
+        .add_interface_impl(InterfaceImplRow::new(derived, i));
+    });
+
+    let dir = TempDir::new("resolve-hierarchy-across-modules");
+
+    for (name, data) in [("A.dll", a), ("M.netmodule", m)] {
+      std::fs::write(dir.0.join(name), data).unwrap();
+    }
+
+    let a = Arc::new(LoadedModule::from_path(dir.0.join("A.dll")).unwrap());
+    let m = Arc::new(LoadedModule::from_path(dir.0.join("M.netmodule")).unwrap());
+    let ty = |module: &Arc<LoadedModule>, index| ResolvedType {
+      module: module.clone(),
+      type_def: RowId::new(index),
+    };
+
+    assert_eq!(
+      Ok(vec![ty(&m, 2)]),
+      NoAssemblies.resolve_interfaces(&ty(&a, 2))
+    );
+    assert!(matches!(
+      NoAssemblies.resolve_base_types(&ty(&a, 3)),
+      Err(ResolveError::Read(_))
+    ));
+    assert!(ty(&a, 2) != ty(&m, 2));
+  }
+}
@@ -1,12 +1,14 @@
-use ecma335::pe::ManagedPeFile32;
+use ecma335::pe::ManagedPe;
 use std::fs;
 
 #[test]
 fn from_pe() {
   let pe = fs::read("data/empty.dll").unwrap();
-  let pe = ManagedPeFile32::from_data(&pe).unwrap();
+  let pe = ManagedPe::parse(&pe).unwrap();
   let md = pe.metadata().unwrap();
 
+  assert!(!pe.is_64());
+
   let strings = md
     .streams()
     .filter_map(Result::ok)
@@ -21,7 +23,479 @@ fn from_pe() {
     .next()
     .unwrap();
 
-  for td in tables.type_defs() {
-    println!("{:?}", strings.get(td.name()));
+  let type_defs = tables.type_defs();
+  let module = type_defs.into_iter().next().unwrap();
+
+  assert_eq!(type_defs.len(), type_defs.into_iter().count());
+  assert_eq!(Ok("<Module>"), strings.get_str(module.name()));
+
+  for td in type_defs {
+    println!("{:?}", strings.get_str(td.name()));
+  }
+}
+
+#[test]
+fn table_row_ids() {
+  use ecma335::metadata::errors::ReadErrorKind;
+  use ecma335::metadata::streams::tables::id::TypeDefOrRef;
+
+  let pe = fs::read("data/empty.dll").unwrap();
+  let pe = ManagedPe::parse(&pe).unwrap();
+  let md = pe.metadata().unwrap();
+
+  let tables = md
+    .streams()
+    .filter_map(Result::ok)
+    .filter_map(|s| s.as_tables())
+    .next()
+    .unwrap();
+
+  // Row ids are 1-based, the first row has the id `1` and `0` is a null reference.
+  let type_defs = tables.type_defs();
+  let module = type_defs.into_iter().next().unwrap();
+  let last = type_defs.into_iter().last().unwrap();
+  let TypeDefOrRef::TypeDef(null) = module.extends() else {
+    panic!("`<Module>` should have a null base type");
+  };
+  let len = type_defs.len();
+
+  assert_eq!(1, module.id().index());
+  assert_eq!(len, last.id().index());
+  assert_eq!(module.name(), type_defs.get(module.id()).unwrap().name());
+  assert_eq!(last.name(), type_defs.get(last.id()).unwrap().name());
+  assert_eq!(
+    ReadErrorKind::OutOfRange { index: 0, len },
+    type_defs.get(null).unwrap_err().kind()
+  );
+  assert_eq!(
+    ReadErrorKind::OutOfRange {
+      index: len + 1,
+      len
+    },
+    type_defs.get(last.id().next()).unwrap_err().kind()
+  );
+}
+
+#[test]
+fn method_body() {
+  let pe = fs::read("data/empty.dll").unwrap();
+  let pe = ManagedPe::parse(&pe).unwrap();
+  let md = pe.metadata().unwrap();
+
+  let tables = md
+    .streams()
+    .filter_map(Result::ok)
+    .filter_map(|s| s.as_tables())
+    .next()
+    .unwrap();
+
+  let main = tables.method_defs().into_iter().next().unwrap();
+  let body = pe.method_body(main.rva()).unwrap();
+
+  // ldstr "Hello world!"; call void [mscorlib]System.Console::WriteLine(string); ret
+  assert_eq!(0x72, body.code[0]);
+  assert_eq!(0x28, body.code[5]);
+  assert_eq!(Some(&0x2a), body.code.last());
+}
+
+#[test]
+fn debug_directory() {
+  let pe = fs::read("data/empty.dll").unwrap();
+  let pe = ManagedPe::parse(&pe).unwrap();
+
+  assert_eq!(0, pe.debug_directory().count());
+}
+
+#[test]
+#[cfg(feature = "std")]
+fn validate() {
+  use ecma335::metadata::errors::ReadLocation;
+  use ecma335::metadata::validate::{validate, Rule};
+
+  let pe = fs::read("data/empty.dll").unwrap();
+  let managed = ManagedPe::parse(&pe).unwrap();
+  let report = validate(&managed.metadata().unwrap());
+
+  assert!(report.findings().is_empty(), "{report}");
+
+  // Give the `<Module>` type nested visibility, the `#~` header is 52 bytes followed by a 10 byte
+  // `Module` row and a 6 byte `TypeRef` row.
+  let tables = managed
+    .metadata()
+    .unwrap()
+    .streams()
+    .filter_map(Result::ok)
+    .find_map(|s| s.as_tables())
+    .unwrap();
+
+  let flags = tables.bytes().as_ptr() as usize - pe.as_ptr() as usize + 68;
+  let mut pe = pe.clone();
+
+  pe[flags] = 0x02;
+
+  let managed = ManagedPe::parse(&pe).unwrap();
+  let report = validate(&managed.metadata().unwrap());
+  let errors = report.errors().collect::<Vec<_>>();
+
+  assert_eq!(1, errors.len(), "{report}");
+  assert_eq!(Rule::InvalidFlags, errors[0].rule);
+  assert_eq!(
+    ReadLocation::Row {
+      table: "TypeDefRow",
+      row: 1
+    },
+    errors[0].location
+  );
+}
+
+#[test]
+#[cfg(feature = "write")]
+fn tables_round_trip() {
+  use ecma335::metadata::streams::tables::TablesBuilder;
+
+  let pe = fs::read("data/empty.dll").unwrap();
+  let pe = ManagedPe::parse(&pe).unwrap();
+  let md = pe.metadata().unwrap();
+  let tables = md
+    .streams()
+    .filter_map(Result::ok)
+    .find_map(|s| s.as_tables())
+    .unwrap();
+
+  let mut builder = TablesBuilder::new();
+
+  builder.set_heap_sizes(tables.header().heap_sizes);
+  builder.set_sorted(tables.header().sorted);
+
+  tables.modules().into_iter().for_each(|row| {
+    builder.add_module(row);
+  });
+  tables.type_refs().into_iter().for_each(|row| {
+    builder.add_type_ref(row);
+  });
+  tables.type_defs().into_iter().for_each(|row| {
+    builder.add_type_def(row);
+  });
+  tables.method_defs().into_iter().for_each(|row| {
+    builder.add_method_def(row);
+  });
+  tables.member_refs().into_iter().for_each(|row| {
+    builder.add_member_ref(row);
+  });
+  tables.assemblies().into_iter().for_each(|row| {
+    builder.add_assembly(row);
+  });
+  tables.assembly_refs().into_iter().for_each(|row| {
+    builder.add_assembly_ref(row);
+  });
+
+  assert_eq!(tables.bytes(), &builder.finish()[..]);
+}
+
+#[test]
+#[cfg(all(feature = "write", feature = "object"))]
+fn pe_round_trip() {
+  use ecma335::pe::ManagedPeBuilder;
+  use object::pe::IMAGE_FILE_MACHINE_I386;
+
+  let data = fs::read("data/empty.dll").unwrap();
+  let pe = ManagedPe::parse(&data).unwrap();
+  let cli = pe.cli_header();
+  let metadata = pe.data_at(cli.metadata.virtual_address).unwrap();
+  let metadata = &metadata[..cli.metadata.size as usize];
+  let tables = pe
+    .metadata()
+    .unwrap()
+    .streams()
+    .filter_map(Result::ok)
+    .find_map(|s| s.as_tables())
+    .unwrap();
+
+  let mut builder = ManagedPeBuilder::new(IMAGE_FILE_MACHINE_I386);
+
+  builder.set_image_base(0x40_0000);
+  builder.set_flags(cli.flags);
+  builder.set_entry_point_token(cli.entry_point_token);
+
+  for method in tables.method_defs() {
+    // Every method of the file has a tiny body, which is its code prefixed with a single byte.
+    let body = pe.method_body(method.rva()).unwrap();
+    let body = &pe.data_at(method.rva()).unwrap()[..body.code.len() + 1];
+
+    assert_eq!(method.rva(), builder.add_method_body(body));
   }
+
+  let rebuilt = builder.finish(metadata);
+  let rebuilt = ManagedPe::parse(&rebuilt).unwrap();
+
+  assert!(!rebuilt.is_64());
+  assert_eq!(pe.data_at(0x2000), rebuilt.data_at(0x2000));
+  assert_eq!(pe.data_at(0x4000), rebuilt.data_at(0x4000));
+}
+
+#[test]
+#[cfg(feature = "write")]
+fn metadata_round_trip() {
+  use ecma335::metadata::MetadataBuilder;
+
+  let data = fs::read("data/empty.dll").unwrap();
+  let pe = ManagedPe::parse(&data).unwrap();
+  let cli = pe.cli_header();
+  let metadata = pe.data_at(cli.metadata.virtual_address).unwrap();
+  let metadata = &metadata[..cli.metadata.size as usize];
+  let rebuilt = MetadataBuilder::from_reader(&pe.metadata().unwrap())
+    .unwrap()
+    .finish();
+
+  assert_eq!(metadata, &rebuilt[..]);
+  assert_eq!(data, pe.with_metadata(&rebuilt).unwrap());
+}
+
+#[test]
+#[cfg(feature = "write")]
+fn modify_metadata() {
+  use ecma335::metadata::streams::tables::flags::TypeAttributes;
+  use ecma335::metadata::streams::tables::id::{CustomAttributeType, HasCustomAttribute, RowId};
+  use ecma335::metadata::streams::tables::rows::{AssemblyRow, CustomAttributeRow, TypeDefRow};
+  use ecma335::metadata::MetadataBuilder;
+
+  let data = fs::read("data/empty.dll").unwrap();
+  let pe = ManagedPe::parse(&data).unwrap();
+  let mut md = MetadataBuilder::from_reader(&pe.metadata().unwrap()).unwrap();
+
+  let assembly = md.tables.assemblies()[0];
+  let type_def = md.tables.type_defs()[0];
+  md.tables.set_assembly(
+    RowId::new(1),
+    AssemblyRow::new(
+      assembly.hash_alg(),
+      1,
+      2,
+      3,
+      4,
+      assembly.flags(),
+      assembly.public_key(),
+      assembly.name(),
+      assembly.culture(),
+    ),
+  );
+  md.tables.set_type_def(
+    RowId::new(1),
+    TypeDefRow::new(
+      type_def.flags() | TypeAttributes::SEALED,
+      type_def.name(),
+      type_def.namespace(),
+      type_def.extends(),
+      type_def.field_list(),
+      type_def.method_list(),
+    ),
+  );
+  md.tables.add_custom_attribute(CustomAttributeRow::new(
+    HasCustomAttribute::Assembly(RowId::new(1)),
+    CustomAttributeType::MemberRef(RowId::new(1)),
+    md.blobs.add(&[0x01, 0x00, 0x00, 0x00]),
+  ));
+
+  let modified = pe.with_metadata(&md.finish()).unwrap();
+  let modified = ManagedPe::parse(&modified).unwrap();
+  let md = modified.metadata().unwrap();
+  let tables = md
+    .streams()
+    .filter_map(Result::ok)
+    .find_map(|s| s.as_tables())
+    .unwrap();
+  let blobs = md
+    .streams()
+    .filter_map(Result::ok)
+    .find_map(|s| s.as_blobs())
+    .unwrap();
+
+  let assembly = tables.assemblies().get(RowId::new(1)).unwrap();
+  let type_def = tables.type_defs().get(RowId::new(1)).unwrap();
+  let attribute = tables.custom_attributes().get(RowId::new(1)).unwrap();
+
+  assert_eq!(1, assembly.major_version());
+  assert_eq!(4, assembly.revision_number());
+  assert!(type_def.flags().contains(TypeAttributes::SEALED));
+  assert_eq!(
+    HasCustomAttribute::Assembly(RowId::new(1)),
+    attribute.parent()
+  );
+  assert_eq!(
+    Ok(&[0x01, 0x00, 0x00, 0x00][..]),
+    blobs.get(attribute.value())
+  );
+
+  // The method bodies are untouched.
+  for method in tables.method_defs() {
+    let body = pe.method_body(method.rva()).unwrap();
+
+    assert_eq!(body.code, modified.method_body(method.rva()).unwrap().code);
+  }
+}
+
+#[test]
+#[cfg(feature = "write")]
+fn patch_in_place() {
+  use ecma335::metadata::errors::{PatchError, ReadLocation};
+  use ecma335::metadata::streams::tables::flags::TypeAttributes;
+  use ecma335::metadata::streams::tables::id::{RowId, TypeDefOrRef};
+  use ecma335::metadata::streams::tables::rows::{ModuleRow, TypeDefRow};
+  use ecma335::metadata::streams::tables::table::TableBytesMut;
+
+  let data = fs::read("data/empty.dll").unwrap();
+  let pe = ManagedPe::parse(&data).unwrap();
+  let md = pe.metadata().unwrap();
+  let tables = md
+    .streams()
+    .filter_map(Result::ok)
+    .find_map(|s| s.as_tables())
+    .unwrap();
+  let offset = tables.offset_in(&data).unwrap();
+
+  let module = tables.modules().get(RowId::new(1)).unwrap();
+  let type_def = tables.type_defs().get(RowId::new(1)).unwrap();
+
+  let mut patched = data.clone();
+  let mut type_defs = TableBytesMut::new(&mut patched[offset..], tables.type_defs()).unwrap();
+
+  let prev = type_defs
+    .set(
+      RowId::new(1),
+      TypeDefRow::new(
+        type_def.flags() | TypeAttributes::SEALED,
+        type_def.name(),
+        type_def.namespace(),
+        type_def.extends(),
+        type_def.field_list(),
+        type_def.method_list(),
+      ),
+    )
+    .unwrap();
+
+  assert_eq!(type_def, prev);
+
+  // A coded index with two tag bits does not fit in a two byte column past 0x3fff.
+  let err = type_defs
+    .set(
+      RowId::new(1),
+      TypeDefRow::new(
+        type_def.flags(),
+        type_def.name(),
+        type_def.namespace(),
+        TypeDefOrRef::TypeDef(RowId::new(0x4000)),
+        type_def.field_list(),
+        type_def.method_list(),
+      ),
+    )
+    .unwrap_err();
+
+  assert_eq!(
+    PatchError::TooWide {
+      location: ReadLocation::Column {
+        table: "TypeDefRow",
+        row: 1,
+        column: "extends"
+      },
+      offset: tables.type_defs().offset(),
+    },
+    err
+  );
+
+  let mut modules = TableBytesMut::new(&mut patched[offset..], tables.modules()).unwrap();
+
+  modules
+    .set(
+      RowId::new(1),
+      ModuleRow::new(
+        module.generation(),
+        type_def.name(),
+        module.mvid(),
+        module.enc_id(),
+        module.enc_base_id(),
+      ),
+    )
+    .unwrap();
+
+  // Only the flags and the module name changed.
+  let changed = data
+    .iter()
+    .zip(&patched)
+    .filter(|(old, new)| old != new)
+    .count();
+
+  assert!((1..=3).contains(&changed), "{changed} bytes changed");
+
+  let pe = ManagedPe::parse(&patched).unwrap();
+  let md = pe.metadata().unwrap();
+  let tables = md
+    .streams()
+    .filter_map(Result::ok)
+    .find_map(|s| s.as_tables())
+    .unwrap();
+
+  let module = tables.modules().get(RowId::new(1)).unwrap();
+  let patched = tables.type_defs().get(RowId::new(1)).unwrap();
+
+  assert_eq!(type_def.name(), module.name());
+  assert!(patched.flags().contains(TypeAttributes::SEALED));
+  assert_eq!(type_def.extends(), patched.extends());
+}
+
+#[test]
+#[cfg(feature = "std")]
+fn assembly_identity() {
+  use ecma335::metadata::identity::AssemblyIdentity;
+  use ecma335::metadata::streams::tables::id::RowId;
+
+  let data = fs::read("data/empty.dll").unwrap();
+  let pe = ManagedPe::parse(&data).unwrap();
+  let md = pe.metadata().unwrap();
+  let tables = md.streams().find_map(|s| s.ok()?.as_tables()).unwrap();
+  let strings = md.streams().find_map(|s| s.ok()?.as_strings()).unwrap();
+  let blobs = md.streams().find_map(|s| s.ok()?.as_blobs()).unwrap();
+
+  let assembly = tables.assemblies().get(RowId::new(1)).unwrap();
+  let assembly_ref = tables.assembly_refs().get(RowId::new(1)).unwrap();
+  let assembly = AssemblyIdentity::from_assembly(&assembly, &strings, &blobs).unwrap();
+  let assembly_ref = AssemblyIdentity::from_assembly_ref(&assembly_ref, &strings, &blobs).unwrap();
+
+  assert_eq!(
+    "empty, Version=0.0.0.0, Culture=neutral, PublicKeyToken=null",
+    assembly.to_string()
+  );
+  assert_eq!(
+    "mscorlib, Version=0.0.0.0, Culture=neutral, PublicKeyToken=null",
+    assembly_ref.to_string()
+  );
+  assert_eq!(Ok(assembly.clone()), assembly.to_string().parse());
+  assert_eq!(Ok(assembly_ref.clone()), assembly_ref.to_string().parse());
+}
+
+#[test]
+#[cfg(feature = "std")]
+fn type_definitions() {
+  use ecma335::metadata::model::Metadata;
+
+  let data = fs::read("data/empty.dll").unwrap();
+  let pe = ManagedPe::parse(&data).unwrap();
+  let md = pe.metadata().unwrap();
+  let metadata = Metadata::from_reader(&md).unwrap();
+  let types = metadata.type_defs().collect::<Vec<_>>();
+
+  assert_eq!(1, types.len());
+  assert_eq!(Ok("<Module>".into()), types[0].full_name());
+  assert_eq!(None, types[0].base_type());
+  assert_eq!(0, types[0].fields().count());
+  assert_eq!(1, types[0].methods().count());
+  assert_eq!(0, types[0].nested_types().unwrap().count());
+  assert_eq!(None, types[0].layout());
+
+  let main = types[0].methods().next().unwrap();
+  let signature = main.signature().unwrap();
+
+  assert_eq!(Ok("main"), main.name());
+  assert!(!signature.has_this());
+  assert_eq!(0, main.parameters().unwrap().len());
+  assert_eq!(Some(&0x2a), main.body(&pe).unwrap().code.last());
 }